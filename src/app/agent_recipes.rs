@@ -11,6 +11,10 @@
 //! trigger_events: VariableUpdate
 //! trigger_variables: deploy.request,ci.*
 //! tools: local
+//! budget: $2.00
+//! output_schema: schemas/repo-digest.json
+//! max_tool_loops: 12
+//! hosted_tools: web_search,code_interpreter
 //! persona: You are a repo digest agent.
 //! ---
 //! Summarize recent repository changes and propose next actions.
@@ -23,7 +27,10 @@ use std::path::{Path, PathBuf};
 use anyhow::{Context, Result, anyhow, bail};
 use directories::BaseDirs;
 
-use crate::constants::DEFAULT_AGENT_INTERVAL_SECS;
+use crate::constants::{DEFAULT_AGENT_INTERVAL_SECS, MAX_TOOL_LOOPS_CAP};
+
+use super::output_schema;
+use super::usage::Budget;
 
 #[derive(Clone, Debug)]
 pub struct AgentRecipe {
@@ -34,6 +41,16 @@ pub struct AgentRecipe {
     pub trigger_events: Vec<String>,
     pub trigger_variables: Vec<String>,
     pub tools: Vec<String>,
+    /// Daily token or dollar cap (`budget: 50k` / `budget: $2`).
+    pub budget: Option<Budget>,
+    /// JSON Schema for structured output (inline JSON or a path relative
+    /// to the recipe file).
+    pub output_schema: Option<serde_json::Value>,
+    /// Tool-call round-trips per run; `None` uses the global limit.
+    pub max_tool_loops: Option<usize>,
+    /// Provider-hosted tool specs (`web_search`, `code_interpreter`,
+    /// `file_search:<vector_store_ids>`).
+    pub hosted_tools: Vec<String>,
     pub persona: String,
     pub instructions: String,
     pub path: PathBuf,
@@ -287,6 +304,49 @@ fn parse_recipe_file(path: &Path, raw: &str) -> Result<AgentRecipe> {
         .map(|value| parse_csv(value))
         .unwrap_or_default();
 
+    let hosted_tools = front_matter
+        .get("hosted_tools")
+        .map(|value| crate::openai::parse_hosted_tools(&parse_csv(value)))
+        .transpose()
+        .context("invalid hosted_tools")?
+        .unwrap_or_default()
+        .iter()
+        .map(|tool| tool.spec())
+        .collect();
+
+    let budget = front_matter
+        .get("budget")
+        .filter(|value| !value.trim().is_empty())
+        .map(|value| {
+            Budget::parse(value).ok_or_else(|| {
+                anyhow!("invalid budget '{value}': use tokens (50000, 50k) or dollars ($2.50)")
+            })
+        })
+        .transpose()?;
+
+    let output_schema = front_matter
+        .get("output_schema")
+        .or_else(|| front_matter.get("schema"))
+        .filter(|value| !value.trim().is_empty())
+        .map(|value| {
+            let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+            output_schema::load_schema(value, base_dir)
+                .with_context(|| format!("invalid output_schema '{value}'"))
+        })
+        .transpose()?;
+
+    let max_tool_loops = front_matter
+        .get("max_tool_loops")
+        .or_else(|| front_matter.get("tool_loops"))
+        .filter(|value| !value.trim().is_empty())
+        .map(|value| match value.trim().parse::<usize>() {
+            Ok(limit) if (1..=MAX_TOOL_LOOPS_CAP).contains(&limit) => Ok(limit),
+            _ => Err(anyhow!(
+                "invalid max_tool_loops '{value}': use 1-{MAX_TOOL_LOOPS_CAP}"
+            )),
+        })
+        .transpose()?;
+
     let persona = front_matter.get("persona").cloned().unwrap_or_else(|| {
         format!(
             "You are a background autonomous agent named '{name}'. \
@@ -319,13 +379,17 @@ fn parse_recipe_file(path: &Path, raw: &str) -> Result<AgentRecipe> {
         trigger_events,
         trigger_variables,
         tools,
+        budget,
+        output_schema,
+        max_tool_loops,
+        hosted_tools,
         persona,
         instructions,
         path: path.to_path_buf(),
     })
 }
 
-fn split_front_matter(raw: &str) -> (std::collections::HashMap<String, String>, String) {
+pub(super) fn split_front_matter(raw: &str) -> (std::collections::HashMap<String, String>, String) {
     let mut map = std::collections::HashMap::new();
     let mut lines = raw.lines();
     let Some(first) = lines.next() else {
@@ -414,11 +478,11 @@ fn strip_quotes(raw: &str) -> &str {
     trimmed
 }
 
-fn yaml_quote(value: &str) -> String {
+pub(super) fn yaml_quote(value: &str) -> String {
     format!("\"{}\"", value.replace('\\', "\\\\").replace('\"', "\\\""))
 }
 
-fn sanitize_name(raw: &str) -> Result<String> {
+pub(super) fn sanitize_name(raw: &str) -> Result<String> {
     let candidate = raw
         .trim()
         .to_ascii_lowercase()
@@ -439,7 +503,7 @@ fn sanitize_name(raw: &str) -> Result<String> {
     Ok(candidate)
 }
 
-fn memini_home() -> PathBuf {
+pub(super) fn memini_home() -> PathBuf {
     if let Ok(value) = env::var("MEMINI_HOME") {
         if !value.trim().is_empty() {
             return PathBuf::from(value);
@@ -465,6 +529,10 @@ auto_start: true
 trigger_events: VariableUpdate,Commit
 trigger_variables: deploy.request,ci.*
 tools: workspace_read_file,workspace_run_command
+budget: $1.50
+output_schema: {"type": "object", "required": ["status"]}
+max_tool_loops: 20
+hosted_tools: web_search, file_search:vs_1
 persona: You are a repo agent.
 ---
 Check git status and summarize changes.
@@ -480,6 +548,13 @@ Check git status and summarize changes.
             parsed.tools,
             vec!["workspace_read_file", "workspace_run_command"]
         );
+        assert_eq!(parsed.budget, Some(Budget::Dollars(1.5)));
+        assert_eq!(
+            parsed.output_schema,
+            Some(serde_json::json!({"type": "object", "required": ["status"]}))
+        );
+        assert_eq!(parsed.max_tool_loops, Some(20));
+        assert_eq!(parsed.hosted_tools, vec!["web_search", "file_search:vs_1"]);
         assert_eq!(parsed.persona, "You are a repo agent.");
         assert_eq!(
             parsed.instructions,
@@ -492,6 +567,9 @@ Check git status and summarize changes.
         let raw = "Summarize unfinished tasks.";
         let parsed = parse_recipe_file(Path::new("quick-check.md"), raw).expect("parse recipe");
         assert_eq!(parsed.name, "quick-check");
+        assert_eq!(parsed.budget, None);
+        assert_eq!(parsed.output_schema, None);
+        assert_eq!(parsed.max_tool_loops, None);
         assert_eq!(parsed.instructions, "Summarize unfinished tasks.");
     }
 
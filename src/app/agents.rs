@@ -4,9 +4,19 @@
 //! injected into the system prompt.  Users can create custom agents on the
 //! fly with `/agent create <name> <description>` and switch between them
 //! with `/agent use <name>`.
+//!
+//! Custom agents may also pin their own model and fallback chain, reasoning
+//! effort, temperature, tool-loop limit, hosted tools (web search, code
+//! interpreter, file search), and the tools, MCP servers and skills they
+//! may use (`/agent set <field> <value>`).  Unset fields fall back to the
+//! global `/model` settings and "everything available".
 
+use anyhow::{Result, anyhow, bail};
 use serde::{Deserialize, Serialize};
 
+use crate::constants::MAX_TOOL_LOOPS_CAP;
+use crate::openai::{OpenAiClient, parse_hosted_tools, parse_reasoning_setting};
+
 /// An agent persona that shapes how the LLM responds.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Agent {
@@ -16,6 +26,39 @@ pub struct Agent {
     pub description: String,
     /// The personality/instructions injected into the system prompt.
     pub persona: String,
+    /// Model override (e.g. "gpt-5"); `None` uses the global model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Fallback models tried in order when `model` fails; empty uses the
+    /// global chain.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_models: Vec<String>,
+    /// Reasoning effort override: `low|medium|high`, or `off` to disable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<String>,
+    /// Sampling temperature override.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Tool-call round-trips per run; `None` uses the global limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tool_loops: Option<usize>,
+    /// Provider-hosted tool specs (`web_search`, `code_interpreter`,
+    /// `file_search:<vector_store_ids>`); empty offers none.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hosted_tools: Vec<String>,
+    /// Allowed built-in tool names; empty allows all.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+    /// Allowed MCP server ids; empty allows every connected server.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mcp_servers: Vec<String>,
+    /// Allowed imported skill names; empty allows all.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skills: Vec<String>,
+    /// RFC 3339 time of the last create, `/agent set` or import; the newer
+    /// side wins when persona files and Rice disagree.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub updated_at: String,
 }
 
 impl Default for Agent {
@@ -24,6 +67,321 @@ impl Default for Agent {
             name: "memini".to_string(),
             description: "Your personal CLI assistant with long-term memory".to_string(),
             persona: crate::prompts::default_memini_persona().to_string(),
+            model: None,
+            fallback_models: Vec::new(),
+            reasoning_effort: None,
+            temperature: None,
+            max_tool_loops: None,
+            hosted_tools: Vec::new(),
+            tools: Vec::new(),
+            mcp_servers: Vec::new(),
+            skills: Vec::new(),
+            updated_at: String::new(),
+        }
+    }
+}
+
+/// Fields accepted by `/agent set`.
+pub const AGENT_SETTING_FIELDS: &[&str] = &[
+    "model",
+    "fallback",
+    "reasoning",
+    "temperature",
+    "tool_loops",
+    "hosted",
+    "tools",
+    "mcp",
+    "skills",
+    "description",
+    "persona",
+];
+
+impl Agent {
+    /// A clone of `base` with this agent's model/reasoning/temperature
+    /// overrides applied.
+    pub fn apply_to(&self, base: &OpenAiClient) -> OpenAiClient {
+        let mut client = base.clone();
+        if let Some(model) = &self.model {
+            client.model = model.clone();
+        }
+        if !self.fallback_models.is_empty() {
+            client.fallback_models = self.fallback_models.clone();
+        }
+        if let Some(setting) = self
+            .reasoning_effort
+            .as_deref()
+            .and_then(parse_reasoning_setting)
+        {
+            client.reasoning_effort = setting;
+        }
+        if self.temperature.is_some() {
+            client.temperature = self.temperature;
+        }
+        if let Some(limit) = self.max_tool_loops {
+            client = client.with_max_tool_loops(limit);
         }
+        if let Ok(hosted) = parse_hosted_tools(&self.hosted_tools)
+            && !hosted.is_empty()
+        {
+            client = client.with_hosted_tools(hosted);
+        }
+        client
+    }
+
+    pub fn allows_tool(&self, name: &str) -> bool {
+        tool_allowed(&self.tools, name)
+    }
+
+    pub fn allows_mcp_server(&self, id: &str) -> bool {
+        allowed(&self.mcp_servers, id)
+    }
+
+    pub fn allows_skill(&self, name: &str) -> bool {
+        allowed(&self.skills, name)
+    }
+
+    /// Stamp the persona as changed now.
+    pub fn touch(&mut self) {
+        self.updated_at = chrono::Local::now().to_rfc3339();
+    }
+
+    /// Update one setting from `/agent set <field> <value>`.
+    ///
+    /// `default` (or `clear`) resets an override.  Returns a confirmation
+    /// line.
+    pub fn set_field(&mut self, field: &str, value: &str) -> Result<String> {
+        let value = value.trim();
+        let reset = matches!(value, "default" | "clear" | "none" | "all" | "");
+        let message = match field {
+            "model" => {
+                self.model = (!reset).then(|| value.to_string());
+                format!("model = {}", self.model.as_deref().unwrap_or("(global)"))
+            }
+            "fallback" | "fallbacks" | "fallback_models" => {
+                self.fallback_models = parse_list(value, reset);
+                format!(
+                    "fallback = {}",
+                    if self.fallback_models.is_empty() {
+                        "(global)".to_string()
+                    } else {
+                        self.fallback_models.join(" → ")
+                    }
+                )
+            }
+            "reasoning" | "thinking" | "effort" => {
+                if reset {
+                    self.reasoning_effort = None;
+                } else {
+                    let setting = parse_reasoning_setting(value)
+                        .ok_or_else(|| anyhow!("use on|off|low|medium|high"))?;
+                    self.reasoning_effort = Some(setting.unwrap_or_else(|| "off".to_string()));
+                }
+                format!(
+                    "reasoning = {}",
+                    self.reasoning_effort.as_deref().unwrap_or("(global)")
+                )
+            }
+            "temperature" | "temp" => {
+                if reset {
+                    self.temperature = None;
+                } else {
+                    let temperature: f32 = value
+                        .parse()
+                        .map_err(|_| anyhow!("temperature must be a number"))?;
+                    if !(0.0..=2.0).contains(&temperature) {
+                        bail!("temperature must be between 0 and 2");
+                    }
+                    self.temperature = Some(temperature);
+                }
+                format!(
+                    "temperature = {}",
+                    self.temperature
+                        .map(|t| t.to_string())
+                        .unwrap_or_else(|| "(default)".to_string())
+                )
+            }
+            "tool_loops" | "max_tool_loops" => {
+                if reset {
+                    self.max_tool_loops = None;
+                } else {
+                    let limit: usize = value
+                        .parse()
+                        .map_err(|_| anyhow!("tool_loops must be a whole number"))?;
+                    if !(1..=MAX_TOOL_LOOPS_CAP).contains(&limit) {
+                        bail!("tool_loops must be between 1 and {MAX_TOOL_LOOPS_CAP}");
+                    }
+                    self.max_tool_loops = Some(limit);
+                }
+                format!(
+                    "tool_loops = {}",
+                    self.max_tool_loops
+                        .map(|limit| limit.to_string())
+                        .unwrap_or_else(|| "(global)".to_string())
+                )
+            }
+            "hosted" | "hosted_tools" => {
+                let reset = reset || value == "off";
+                let hosted = parse_hosted_tools(&parse_list(value, reset))?;
+                self.hosted_tools = hosted.iter().map(|tool| tool.spec()).collect();
+                format!("hosted = {}", hosted_label(&self.hosted_tools))
+            }
+            "tools" => {
+                self.tools = parse_list(value, reset);
+                format!("tools = {}", list_label(&self.tools))
+            }
+            "mcp" | "mcp_servers" => {
+                self.mcp_servers = parse_list(value, reset);
+                format!("mcp = {}", list_label(&self.mcp_servers))
+            }
+            "skills" => {
+                self.skills = parse_list(value, reset);
+                format!("skills = {}", list_label(&self.skills))
+            }
+            "description" => {
+                if reset {
+                    bail!("description cannot be empty");
+                }
+                self.description = value.to_string();
+                format!("description = {value}")
+            }
+            "persona" => {
+                if reset {
+                    bail!("persona cannot be empty");
+                }
+                self.persona = value.to_string();
+                "persona updated".to_string()
+            }
+            other => bail!(
+                "unknown field '{other}' (expected {})",
+                AGENT_SETTING_FIELDS.join(", ")
+            ),
+        };
+        Ok(message)
+    }
+
+    /// Human-readable settings lines for `/agent info`.
+    pub fn settings_lines(&self) -> Vec<String> {
+        vec![
+            format!("   Model: {}", self.model.as_deref().unwrap_or("(global)")),
+            format!(
+                "   Fallback: {}",
+                if self.fallback_models.is_empty() {
+                    "(global)".to_string()
+                } else {
+                    self.fallback_models.join(" → ")
+                }
+            ),
+            format!(
+                "   Reasoning: {}",
+                self.reasoning_effort.as_deref().unwrap_or("(global)")
+            ),
+            format!(
+                "   Temperature: {}",
+                self.temperature
+                    .map(|t| t.to_string())
+                    .unwrap_or_else(|| "(default)".to_string())
+            ),
+            format!(
+                "   Tool loops: {}",
+                self.max_tool_loops
+                    .map(|limit| limit.to_string())
+                    .unwrap_or_else(|| "(global)".to_string())
+            ),
+            format!("   Hosted tools: {}", hosted_label(&self.hosted_tools)),
+            format!("   Tools: {}", list_label(&self.tools)),
+            format!("   MCP servers: {}", list_label(&self.mcp_servers)),
+            format!("   Skills: {}", list_label(&self.skills)),
+        ]
+    }
+}
+
+/// Whether a persona's `tools` allow-list admits `name`.
+pub fn tool_allowed(tools: &[String], name: &str) -> bool {
+    allowed(tools, name)
+}
+
+fn allowed(list: &[String], name: &str) -> bool {
+    list.is_empty() || list.iter().any(|item| item.eq_ignore_ascii_case(name))
+}
+
+fn parse_list(value: &str, reset: bool) -> Vec<String> {
+    if reset {
+        return Vec::new();
+    }
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn hosted_label(list: &[String]) -> String {
+    if list.is_empty() {
+        "(none)".to_string()
+    } else {
+        list.join(", ")
+    }
+}
+
+fn list_label(list: &[String]) -> String {
+    if list.is_empty() {
+        "(all)".to_string()
+    } else {
+        list.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Agent;
+    use crate::openai::{HostedTool, OpenAiClient};
+
+    #[test]
+    fn settings_override_the_global_client() {
+        let mut agent = Agent::default();
+        agent.set_field("model", "gpt-5").expect("model");
+        agent.set_field("reasoning", "off").expect("reasoning");
+        agent
+            .set_field("fallback", "gpt-4o, gpt-4.1")
+            .expect("fallback");
+        agent.set_field("temperature", "0.2").expect("temperature");
+        agent
+            .set_field("tools", "rice_memories, spawn_agent")
+            .expect("tools");
+        agent.set_field("tool_loops", "12").expect("tool loops");
+        agent
+            .set_field("hosted", "web_search, file_search:vs_1|vs_2")
+            .expect("hosted");
+        assert!(agent.set_field("hosted", "file_search").is_err());
+        assert!(agent.set_field("temperature", "9").is_err());
+        assert!(agent.set_field("tool_loops", "0").is_err());
+        assert!(agent.set_field("colour", "blue").is_err());
+
+        let mut base = OpenAiClient::new();
+        base.reasoning_effort = Some("high".to_string());
+        let client = agent.apply_to(&base);
+        assert_eq!(client.model, "gpt-5");
+        assert_eq!(client.reasoning_effort, None);
+        assert_eq!(client.temperature, Some(0.2));
+        assert!(!client.tool_loop_limit_reached(11));
+        assert!(client.tool_loop_limit_reached(12));
+        assert_eq!(client.model_chain(), vec!["gpt-5", "gpt-4o", "gpt-4.1"]);
+        assert_eq!(
+            client.hosted_tools,
+            vec![
+                HostedTool::WebSearch,
+                HostedTool::FileSearch {
+                    vector_store_ids: vec!["vs_1".to_string(), "vs_2".to_string()]
+                }
+            ]
+        );
+
+        assert!(agent.allows_tool("spawn_agent"));
+        assert!(!agent.allows_tool("collect_results"));
+        assert!(agent.allows_mcp_server("github"));
+
+        agent.set_field("model", "default").expect("reset");
+        assert_eq!(agent.apply_to(&base).model, base.model);
     }
 }
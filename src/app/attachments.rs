@@ -0,0 +1,426 @@
+//! Chat attachments — workspace files and images sent as Responses API
+//! input parts.
+//!
+//! Files are queued with `/attach <path>` or referenced inline as `@path`.
+//! Paths are confined to the workspace root (same rules as the workspace
+//! tools).  Text is sent as `input_text` parts, split into chunks when
+//! large; images are sent as base64 `input_image` data URLs.
+//!
+//! Only the turn that attaches a file carries its content.  The
+//! conversation thread keeps a short reference instead (path, hash and an
+//! excerpt), so a long session does not resend megabytes of base64 on
+//! every turn; the model or the user re-attaches the file when needed.
+
+use std::fs;
+
+use anyhow::{Context, Result, bail};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::constants::{
+    ATTACHMENT_CHUNK_CHARS, ATTACHMENT_EXCERPT_CHARS, ATTACHMENT_IMAGE_TOKEN_ESTIMATE,
+    ATTACHMENT_MAX_IMAGE_BYTES, ATTACHMENT_MAX_TEXT_CHARS,
+};
+use crate::local_tools::{resolve_workspace_path, to_workspace_relative};
+
+/// A file loaded for the next chat turn.
+#[derive(Clone, Debug)]
+pub struct Attachment {
+    /// Workspace-relative path, as shown to the user and the model.
+    pub path: String,
+    /// Hex SHA-256 of the file as it was attached.
+    pub sha256: String,
+    pub kind: AttachmentKind,
+}
+
+#[derive(Clone, Debug)]
+pub enum AttachmentKind {
+    Text {
+        chunks: Vec<String>,
+        total_chars: usize,
+        truncated: bool,
+    },
+    Image {
+        data_url: String,
+        bytes: u64,
+    },
+}
+
+impl Attachment {
+    /// One-line description for logs (`notes.md (3 chunks, 30k chars)`).
+    pub fn describe(&self) -> String {
+        match &self.kind {
+            AttachmentKind::Text {
+                chunks,
+                total_chars,
+                truncated,
+            } => {
+                let mut line = format!("{} ({total_chars} chars", self.path);
+                if chunks.len() > 1 {
+                    line.push_str(&format!(", {} chunks", chunks.len()));
+                }
+                if *truncated {
+                    line.push_str(&format!(", truncated to {ATTACHMENT_MAX_TEXT_CHARS}"));
+                }
+                line.push(')');
+                line
+            }
+            AttachmentKind::Image { bytes, .. } => {
+                format!("{} (image, {} KB)", self.path, bytes.div_ceil(1024))
+            }
+        }
+    }
+
+    /// Warning to show when the file was split or cut short.
+    pub fn size_warning(&self) -> Option<String> {
+        let AttachmentKind::Text {
+            chunks,
+            total_chars,
+            truncated,
+        } = &self.kind
+        else {
+            return None;
+        };
+        if *truncated {
+            Some(format!(
+                "{} is large ({total_chars} chars); only the first {ATTACHMENT_MAX_TEXT_CHARS} are sent, in {} chunks.",
+                self.path,
+                chunks.len()
+            ))
+        } else if chunks.len() > 1 {
+            Some(format!(
+                "{} is large ({total_chars} chars); sending it in {} chunks.",
+                self.path,
+                chunks.len()
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Responses API input parts for this attachment.
+    pub fn input_parts(&self) -> Vec<Value> {
+        match &self.kind {
+            AttachmentKind::Text { chunks, .. } => {
+                let count = chunks.len();
+                chunks
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, chunk)| {
+                        let label = if count > 1 {
+                            format!("Attached file `{}` (part {}/{count})", self.path, idx + 1)
+                        } else {
+                            format!("Attached file `{}`", self.path)
+                        };
+                        json!({
+                            "type": "input_text",
+                            "text": format!("{label}:\n```\n{chunk}\n```"),
+                        })
+                    })
+                    .collect()
+            }
+            AttachmentKind::Image { data_url, .. } => vec![
+                json!({
+                    "type": "input_text",
+                    "text": format!("Attached image `{}`:", self.path),
+                }),
+                json!({
+                    "type": "input_image",
+                    "image_url": data_url,
+                }),
+            ],
+        }
+    }
+
+    /// The part kept in the conversation thread in place of the content.
+    pub fn reference_part(&self) -> Value {
+        let hash = self.sha256.get(..12).unwrap_or(&self.sha256);
+        let text = match &self.kind {
+            AttachmentKind::Text {
+                chunks,
+                total_chars,
+                ..
+            } => {
+                let excerpt: String = chunks
+                    .first()
+                    .map(|chunk| chunk.chars().take(ATTACHMENT_EXCERPT_CHARS).collect())
+                    .unwrap_or_default();
+                format!(
+                    "Attached file `{}` earlier ({total_chars} chars, sha256 {hash}); its content \
+                     is not repeated here. Read it again with workspace_read_file, or ask the \
+                     user to re-attach @{}. It began:\n```\n{excerpt}\n```",
+                    self.path, self.path
+                )
+            }
+            AttachmentKind::Image { bytes, .. } => format!(
+                "Attached image `{}` earlier ({} KB, sha256 {hash}); it is not repeated here. \
+                 Ask the user to re-attach @{} to look at it again.",
+                self.path,
+                bytes.div_ceil(1024),
+                self.path
+            ),
+        };
+        json!({"type": "input_text", "text": text})
+    }
+}
+
+/// Load a workspace file as an attachment.
+pub fn load_attachment(raw: &str) -> Result<Attachment> {
+    let (workspace_root, path) = resolve_workspace_path(raw)?;
+    if !path.is_file() {
+        bail!("Not a file: {}", path.display());
+    }
+    let rel = to_workspace_relative(&path, &workspace_root);
+
+    if let Some(mime) = image_mime(&rel) {
+        let bytes = fs::metadata(&path)
+            .with_context(|| format!("Read {}", path.display()))?
+            .len();
+        if bytes > ATTACHMENT_MAX_IMAGE_BYTES {
+            bail!(
+                "{rel} is {} MB; images are limited to {} MB",
+                bytes / (1024 * 1024),
+                ATTACHMENT_MAX_IMAGE_BYTES / (1024 * 1024)
+            );
+        }
+        let data = fs::read(&path).with_context(|| format!("Read {}", path.display()))?;
+        return Ok(Attachment {
+            path: rel,
+            sha256: sha256_hex(&data),
+            kind: AttachmentKind::Image {
+                data_url: format!("data:{mime};base64,{}", STANDARD.encode(&data)),
+                bytes,
+            },
+        });
+    }
+
+    let data = fs::read(&path).with_context(|| format!("Read {}", path.display()))?;
+    if data.iter().take(8192).any(|byte| *byte == 0) {
+        bail!("{rel} looks like a binary file; only text and images can be attached");
+    }
+    let text = String::from_utf8_lossy(&data);
+    let total_chars = text.chars().count();
+    let truncated = total_chars > ATTACHMENT_MAX_TEXT_CHARS;
+    let kept: String = text.chars().take(ATTACHMENT_MAX_TEXT_CHARS).collect();
+    Ok(Attachment {
+        path: rel,
+        sha256: sha256_hex(&data),
+        kind: AttachmentKind::Text {
+            chunks: chunk_text(&kept, ATTACHMENT_CHUNK_CHARS),
+            total_chars,
+            truncated,
+        },
+    })
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn image_mime(path: &str) -> Option<&'static str> {
+    let ext = path.rsplit_once('.')?.1.to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Split text into chunks of at most `max_chars`, preferring line breaks.
+fn chunk_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_chars = 0usize;
+    for line in text.split_inclusive('\n') {
+        let line_chars = line.chars().count();
+        if current_chars + line_chars > max_chars && !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+            current_chars = 0;
+        }
+        if line_chars > max_chars {
+            // A single very long line: hard-split it.
+            let chars: Vec<char> = line.chars().collect();
+            for piece in chars.chunks(max_chars) {
+                chunks.push(piece.iter().collect());
+            }
+            continue;
+        }
+        current.push_str(line);
+        current_chars += line_chars;
+    }
+    if !current.is_empty() || chunks.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Inline `@path` references in a chat message.
+///
+/// A reference starts a word and must look like a path (contain `/` or `.`)
+/// so handles such as `@alice` are left alone.  Trailing punctuation is
+/// stripped.
+pub fn inline_references(message: &str) -> Vec<String> {
+    let mut refs: Vec<String> = Vec::new();
+    for word in message.split_whitespace() {
+        let Some(raw) = word.strip_prefix('@') else {
+            continue;
+        };
+        let path = raw.trim_end_matches([',', ';', ':', '!', '?', ')', '"', '\'', '`']);
+        let path = path.strip_suffix('.').unwrap_or(path);
+        if path.is_empty() || !(path.contains('/') || path.contains('.')) {
+            continue;
+        }
+        if !refs.iter().any(|existing| existing == path) {
+            refs.push(path.to_string());
+        }
+    }
+    refs
+}
+
+/// Build the user message content: plain text, or text plus attachment
+/// parts when there are any.
+pub fn user_content(message: &str, attachments: &[Attachment]) -> Value {
+    if attachments.is_empty() {
+        return Value::String(message.to_string());
+    }
+    let mut parts = vec![json!({"type": "input_text", "text": message})];
+    for attachment in attachments {
+        parts.extend(attachment.input_parts());
+    }
+    Value::Array(parts)
+}
+
+/// The same message as stored in the conversation thread, with each
+/// attachment replaced by its reference.
+pub fn thread_content(message: &str, attachments: &[Attachment]) -> Value {
+    if attachments.is_empty() {
+        return Value::String(message.to_string());
+    }
+    let mut parts = vec![json!({"type": "input_text", "text": message})];
+    parts.extend(attachments.iter().map(Attachment::reference_part));
+    Value::Array(parts)
+}
+
+// ── Image token estimate ─────────────────────────────────────────────
+
+/// Estimated input tokens for an `input_image` data URL, from the image's
+/// dimensions; the worst case when they cannot be read.
+pub fn image_url_tokens(image_url: &str) -> usize {
+    let Some((_, data)) = image_url.split_once(";base64,") else {
+        return ATTACHMENT_IMAGE_TOKEN_ESTIMATE;
+    };
+    // The header is near the start; JPEG may put EXIF data first.
+    let prefix = &data[..data.len().min(128 * 1024) / 4 * 4];
+    STANDARD
+        .decode(prefix)
+        .ok()
+        .and_then(|bytes| image_dimensions(&bytes))
+        .map_or(ATTACHMENT_IMAGE_TOKEN_ESTIMATE, |(width, height)| {
+            image_tokens(width, height)
+        })
+}
+
+/// High-detail vision cost: fit in 2048×2048, scale the short side down to
+/// 768, then 170 tokens per 512px tile plus 85.
+fn image_tokens(width: u32, height: u32) -> usize {
+    let (mut w, mut h) = (f64::from(width.max(1)), f64::from(height.max(1)));
+    let fit = (2048.0 / w.max(h)).min(1.0);
+    (w, h) = (w * fit, h * fit);
+    let short = (768.0 / w.min(h)).min(1.0);
+    (w, h) = (w * short, h * short);
+    let tiles = (w / 512.0).ceil() * (h / 512.0).ceil();
+    85 + 170 * tiles as usize
+}
+
+/// Width and height from a PNG, GIF, JPEG or WebP header.
+fn image_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |at: usize| {
+        Some(u32::from(u16::from_be_bytes(
+            bytes.get(at..at + 2)?.try_into().ok()?,
+        )))
+    };
+    let le16 = |at: usize| {
+        Some(u32::from(u16::from_le_bytes(
+            bytes.get(at..at + 2)?.try_into().ok()?,
+        )))
+    };
+    let be32 = |at: usize| Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+    let le24 = |at: usize| {
+        let b = bytes.get(at..at + 3)?;
+        Some(u32::from(b[0]) | u32::from(b[1]) << 8 | u32::from(b[2]) << 16)
+    };
+
+    if bytes.starts_with(b"\x89PNG") {
+        return Some((be32(16)?, be32(20)?));
+    }
+    if bytes.starts_with(b"GIF8") {
+        return Some((le16(6)?, le16(8)?));
+    }
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        return match bytes.get(12..16)? {
+            b"VP8X" => Some((le24(24)? + 1, le24(27)? + 1)),
+            b"VP8 " => Some((le16(26)? & 0x3fff, le16(28)? & 0x3fff)),
+            b"VP8L" => {
+                let bits = u32::from_le_bytes(bytes.get(21..25)?.try_into().ok()?);
+                Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+            }
+            _ => None,
+        };
+    }
+    if bytes.starts_with(&[0xff, 0xd8]) {
+        // Walk the JPEG segments to the first start-of-frame marker.
+        let mut at = 2;
+        while *bytes.get(at)? == 0xff {
+            let marker = *bytes.get(at + 1)?;
+            if matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+                return Some((be16(at + 7)?, be16(at + 5)?));
+            }
+            at += 2 + be16(at + 2)? as usize;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{chunk_text, image_dimensions, image_tokens, inline_references};
+
+    #[test]
+    fn finds_inline_path_references() {
+        let refs = inline_references(
+            "compare @src/main.rs and @README.md, ping @alice about @docs/notes.md.",
+        );
+        assert_eq!(refs, vec!["src/main.rs", "README.md", "docs/notes.md"]);
+        assert!(inline_references("email me at bob@example.com").is_empty());
+    }
+
+    #[test]
+    fn chunks_large_text_on_line_boundaries() {
+        let text = "aaaa\nbbbb\ncccc\n";
+        assert_eq!(chunk_text(text, 10), vec!["aaaa\nbbbb\n", "cccc\n"]);
+        assert_eq!(chunk_text("short", 10), vec!["short"]);
+        assert_eq!(chunk_text(&"x".repeat(25), 10).len(), 3);
+    }
+
+    #[test]
+    fn reads_image_sizes_for_the_token_estimate() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        png.extend(1024u32.to_be_bytes());
+        png.extend(768u32.to_be_bytes());
+        assert_eq!(image_dimensions(&png), Some((1024, 768)));
+        let gif = b"GIF89a\x40\x01\xf0\x00";
+        assert_eq!(image_dimensions(gif), Some((320, 240)));
+        assert_eq!(image_dimensions(b"not an image"), None);
+
+        assert_eq!(image_tokens(512, 512), 85 + 170);
+        // 4000x3000 fits to 2048x1536, then 1024x768: 2x2 tiles.
+        assert_eq!(image_tokens(4000, 3000), 85 + 170 * 4);
+    }
+}
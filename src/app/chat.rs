@@ -9,9 +9,11 @@ use crate::constants::OPENAI_KEY_VAR;
 use crate::mcp;
 
 use super::App;
+use super::attachments::{self, Attachment};
 use super::daemon;
 use super::log_src;
 use super::logging::{LogLevel, mask_key};
+use super::memory_tools;
 
 impl App {
     /// Launch a non-blocking chat turn.
@@ -32,13 +34,31 @@ impl App {
             }
         };
 
+        let attachments = self.take_chat_attachments(message);
+        let user_content = attachments::user_content(message, &attachments);
+        let thread_content = attachments::thread_content(message, &attachments);
+
         // Snapshot everything the background task needs (all Clone / Send).
-        let mcp_snapshots = self.build_mcp_snapshots(None);
-        let builtin_tools = Self::builtin_tool_defs();
+        // Persona settings narrow the MCP servers and tools for this turn.
+        let mcp_snapshots: Vec<daemon::McpServerSnapshot> = self
+            .build_mcp_snapshots(None)
+            .into_iter()
+            .filter(|snap| self.active_agent.allows_mcp_server(&snap.server.id))
+            .collect();
+        let builtin_tools: Vec<Value> = Self::builtin_tool_defs()
+            .into_iter()
+            .filter(|tool| {
+                tool.get("name")
+                    .and_then(Value::as_str)
+                    .is_none_or(|name| self.active_agent.allows_tool(name))
+            })
+            .collect();
 
         let params = daemon::ChatTaskParams {
             key,
             message: message.to_string(),
+            user_content,
+            thread_content,
             persona: self.active_agent.persona.clone(),
             agent_name: self.active_agent.name.clone(),
             skill_context: self.skills_prompt_context(message),
@@ -50,13 +70,43 @@ impl App {
         };
 
         let tx = self.daemon_tx.clone();
-        let openai = self.openai.clone();
-        let rice_handle = self.runtime.spawn(crate::rice::RiceStore::connect());
+        let openai = self.active_agent.apply_to(&self.openai);
+        let rice_handle = self.runtime.spawn(self.rice.connect_alike());
         let rt = self.runtime.handle().clone();
 
         daemon::spawn_chat_task(params, tx, openai, rice_handle, rt);
     }
 
+    /// Queued `/attach` files plus any inline `@path` references in the
+    /// message, loaded for this turn.
+    fn take_chat_attachments(&mut self, message: &str) -> Vec<Attachment> {
+        let mut loaded = std::mem::take(&mut self.pending_attachments);
+        for reference in attachments::inline_references(message) {
+            match attachments::load_attachment(&reference) {
+                Ok(attachment) => {
+                    if !loaded.iter().any(|a| a.path == attachment.path) {
+                        if let Some(warning) = attachment.size_warning() {
+                            self.log(LogLevel::Warn, warning);
+                        }
+                        loaded.push(attachment);
+                    }
+                }
+                Err(err) => {
+                    log_src!(
+                        self,
+                        LogLevel::Warn,
+                        format!("Could not attach @{reference}: {err:#}")
+                    );
+                }
+            }
+        }
+        if !loaded.is_empty() {
+            let names: Vec<String> = loaded.iter().map(Attachment::describe).collect();
+            self.log(LogLevel::Info, format!("📎 Attached: {}", names.join(", ")));
+        }
+        loaded
+    }
+
     /// Built-in tool definitions injected into every chat request.
     fn builtin_tool_defs() -> Vec<Value> {
         let spawn_tool = json!({
@@ -81,6 +131,18 @@ impl App {
                     "coordination_key": {
                         "type": "string",
                         "description": "A shared key to group parallel agents. Use the same key for agents whose results you want to collect together via collect_results."
+                    },
+                    "output_schema": {
+                        "type": "object",
+                        "description": "Optional JSON Schema (type object) for the agent's final answer. The agent replies in structured JSON, which is validated and stored as typed JSON for collect_results."
+                    },
+                    "output_schema_path": {
+                        "type": "string",
+                        "description": "Optional path to a JSON Schema file in the workspace; alternative to output_schema."
+                    },
+                    "max_tool_loops": {
+                        "type": "integer",
+                        "description": "Optional cap on tool-call round-trips for this agent (1-50). Raise it for long multi-step tasks; when it is hit the agent summarizes progress and waits to be continued."
                     }
                 },
                 "required": ["label", "prompt"]
@@ -89,13 +151,17 @@ impl App {
         let collect_tool = json!({
             "type": "function",
             "name": "collect_results",
-            "description": "Collect results from previously spawned agents that share a coordination_key. Returns all finished agent outputs stored in Rice state. Use this after spawning parallel agents to gather and synthesize their results.",
+            "description": "Collect results from previously spawned agents that share a coordination_key. Returns finished agent outputs stored in Rice state, plus the agents still `pending` and any that `failed`; `complete` is true once none are pending. Agents spawned with an output_schema return typed JSON in `output`. Use this after spawning parallel agents to gather and synthesize their results.",
             "parameters": {
                 "type": "object",
                 "properties": {
                     "coordination_key": {
                         "type": "string",
                         "description": "The coordination key that was passed to spawn_agent."
+                    },
+                    "wait_seconds": {
+                        "type": "integer",
+                        "description": "Optional: wait up to this many seconds (max 300) for pending agents to finish before returning."
                     }
                 },
                 "required": ["coordination_key"]
@@ -115,6 +181,10 @@ impl App {
                     "limit": {
                         "type": "integer",
                         "description": "Optional number of memories to return (default uses memory limit, max 50)."
+                    },
+                    "author": {
+                        "type": "string",
+                        "description": "Optional: only memories written by this user (name or user@host). In a shared workspace each memory records who wrote it."
                     }
                 }
             }
@@ -134,12 +204,10 @@ impl App {
                 "required": ["key"]
             }
         });
-        vec![
-            rice_memories_tool,
-            rice_state_get_tool,
-            spawn_tool,
-            collect_tool,
-        ]
+        let mut tools = vec![rice_memories_tool, rice_state_get_tool];
+        tools.extend(memory_tools::tool_defs());
+        tools.extend([spawn_tool, collect_tool]);
+        tools
     }
 
     /// Build [`McpServerSnapshot`]s from the currently active MCP connections,
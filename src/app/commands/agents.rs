@@ -1,10 +1,20 @@
 //! `/agent`, `/thread`, and `/memory` command handlers — persona
-//! management, conversation threads, and memory search.
+//! management, conversation threads, and memory search and curation.
+
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, TimeZone};
 
 use super::super::App;
-use super::super::agents::Agent;
+use super::super::agents::{AGENT_SETTING_FIELDS, Agent};
+use super::super::daemon;
 use super::super::log_src;
 use super::super::logging::LogLevel;
+use super::super::persona_files;
+use super::super::thread_summary;
+use super::super::transcript::{ExportRequest, ThreadExport, TranscriptTurn};
+use crate::constants::{
+    MEMORY_LIST_LIMIT, MEMORY_SCAN_LIMIT, THREAD_TRANSCRIPT_MAX_BYTES, THREAD_TRANSCRIPT_MAX_TURNS,
+};
+use crate::rice::{self, RiceStatus, StoredTrace, TransferCommand};
 
 // ── /agent ───────────────────────────────────────────────────────────
 
@@ -47,6 +57,50 @@ impl App {
                 }
             }
             "info" => self.show_agent_info(),
+            "export" => {
+                if let Some(name) = args.get(1) {
+                    self.export_agent(name, args.get(2).copied());
+                } else {
+                    log_src!(
+                        self,
+                        LogLevel::Warn,
+                        "Usage: /agent export <name> [path]".to_string()
+                    );
+                }
+            }
+            "import" => {
+                if let Some(source) = args.get(1) {
+                    self.import_agent(source);
+                } else {
+                    log_src!(
+                        self,
+                        LogLevel::Warn,
+                        "Usage: /agent import <path|url>".to_string()
+                    );
+                }
+            }
+            "reload" | "sync" => self.sync_persona_files(),
+            "dir" => {
+                let dir = persona_files::personas_dir();
+                self.log(
+                    LogLevel::Info,
+                    format!("Persona directory: {}", dir.display()),
+                );
+            }
+            "set" => {
+                if args.len() >= 3 {
+                    self.set_agent_field(args[1], &args[2..].join(" "));
+                } else {
+                    log_src!(
+                        self,
+                        LogLevel::Warn,
+                        format!(
+                            "Usage: /agent set <field> <value|default>  (fields: {})",
+                            AGENT_SETTING_FIELDS.join(", ")
+                        )
+                    );
+                }
+            }
             _ => self.list_agents(),
         }
     }
@@ -94,7 +148,12 @@ impl App {
 
         // Clear conversation thread when switching agents.
         self.conversation_thread.clear();
-        if let Err(err) = self.runtime.block_on(self.rice.clear_thread()) {
+        self.thread_transcript.clear();
+        self.wait_for_thread_save();
+        if let Err(err) = self
+            .runtime
+            .block_on(self.rice.clear_thread(&self.active_thread))
+        {
             log_src!(
                 self,
                 LogLevel::Warn,
@@ -121,6 +180,45 @@ impl App {
                 agent.name, agent.description
             ),
         );
+        let client = agent.apply_to(&self.openai);
+        let thinking = client.reasoning_effort.as_deref().unwrap_or("off");
+        self.log(
+            LogLevel::Info,
+            format!("   Model: {} | Thinking: {thinking}", client.model),
+        );
+    }
+
+    fn set_agent_field(&mut self, field: &str, value: &str) {
+        if self.active_agent.name == "memini" {
+            log_src!(
+                self,
+                LogLevel::Warn,
+                "The built-in 'memini' persona uses the global /model settings. Create a custom persona to override them.".to_string()
+            );
+            return;
+        }
+
+        let mut agent = self.active_agent.clone();
+        let message = match agent.set_field(&field.to_ascii_lowercase(), value) {
+            Ok(message) => message,
+            Err(err) => {
+                log_src!(self, LogLevel::Warn, format!("/agent set {field}: {err}"));
+                return;
+            }
+        };
+        agent.touch();
+
+        if let Some(slot) = self.custom_agents.iter_mut().find(|a| a.name == agent.name) {
+            *slot = agent.clone();
+        }
+        self.active_agent = agent;
+
+        self.persist_persona(&self.active_agent.clone());
+
+        self.log(
+            LogLevel::Info,
+            format!("Persona '{}': {message}", self.active_agent.name),
+        );
     }
 
     fn create_agent(&mut self, name: &str, description: &str) {
@@ -142,25 +240,16 @@ impl App {
         }
 
         let persona = crate::prompts::custom_persona(name, description);
-        let agent = Agent {
+        let mut agent = Agent {
             name: name.to_string(),
             description: description.to_string(),
             persona,
+            ..Agent::default()
         };
-        self.custom_agents.push(agent);
+        agent.touch();
+        self.custom_agents.push(agent.clone());
 
-        let agents_json =
-            serde_json::to_value(&self.custom_agents).unwrap_or(serde_json::Value::Array(vec![]));
-        if let Err(err) = self
-            .runtime
-            .block_on(self.rice.save_custom_agents(agents_json))
-        {
-            log_src!(
-                self,
-                LogLevel::Warn,
-                format!("Failed to save agents: {err:#}")
-            );
-        }
+        self.persist_persona(&agent);
 
         self.log(
             LogLevel::Info,
@@ -189,23 +278,28 @@ impl App {
         if self.active_agent.name == name {
             self.active_agent = Agent::default();
             self.conversation_thread.clear();
-            let _ = self.runtime.block_on(self.rice.clear_thread());
+            self.thread_transcript.clear();
+            self.wait_for_thread_save();
+            let _ = self
+                .runtime
+                .block_on(self.rice.clear_thread(&self.active_thread));
             let _ = self
                 .runtime
                 .block_on(self.rice.save_active_agent_name("memini"));
         }
 
-        let agents_json =
-            serde_json::to_value(&self.custom_agents).unwrap_or(serde_json::Value::Array(vec![]));
-        if let Err(err) = self
-            .runtime
-            .block_on(self.rice.save_custom_agents(agents_json))
-        {
-            log_src!(
+        self.save_custom_agents();
+        let mut tombstones = self.load_agent_tombstones();
+        tombstones.insert(name.to_string(), Local::now().to_rfc3339());
+        self.save_agent_tombstones(&tombstones);
+        match persona_files::remove_persona_file(name) {
+            Ok(Some(path)) => self.log(LogLevel::Info, format!("Removed {}", path.display())),
+            Ok(None) => {}
+            Err(err) => log_src!(
                 self,
                 LogLevel::Warn,
-                format!("Failed to save agents: {err:#}")
-            );
+                format!("Persona file removal failed: {err:#}")
+            ),
         }
 
         self.log(LogLevel::Info, format!("Persona '{name}' deleted."));
@@ -218,20 +312,140 @@ impl App {
         self.log(LogLevel::Info, format!("Active persona: {name}"));
         self.log(LogLevel::Info, format!("   Description: {description}"));
         self.log(LogLevel::Info, format!("   Thread: {thread_len} messages"));
+        for line in self.active_agent.settings_lines() {
+            self.log(LogLevel::Info, line);
+        }
     }
 }
 
 // ── /thread ──────────────────────────────────────────────────────────
 
+const THREAD_USAGE: &str = "Usage: /thread [info] | list | new <name> | switch <name> \
+     | rename [<old>] <new> | delete <name> | clear | export <path> [--format md|json|html] [--logs]";
+
 impl App {
     pub(crate) fn handle_thread_command(&mut self, args: Vec<&str>) {
+        // Clearing, moving or exporting must see the last turn's save.
+        self.wait_for_thread_save();
         if args.is_empty() {
             self.show_thread_info();
             return;
         }
-        match args[0] {
-            "clear" | "reset" => self.clear_thread(),
-            _ => self.show_thread_info(),
+        match (args[0], &args[1..]) {
+            ("info", _) => self.show_thread_info(),
+            ("clear" | "reset", _) => self.clear_thread(),
+            ("list" | "ls", _) => self.list_threads(),
+            ("new" | "create", [name]) => self.new_thread(name),
+            ("switch" | "use", [name]) => self.switch_thread(name),
+            ("rename", [new]) => {
+                let current = self.active_thread.clone();
+                self.rename_thread(&current, new);
+            }
+            ("rename", [old, new]) => self.rename_thread(old, new),
+            ("delete" | "rm", [name]) => self.delete_thread(name),
+            ("export", rest) => self.export_thread(rest),
+            _ => log_src!(self, LogLevel::Warn, THREAD_USAGE.to_string()),
+        }
+    }
+
+    /// Load the run-id's active thread into `conversation_thread` and
+    /// return its turn count.
+    pub(crate) fn load_active_thread(&mut self) -> anyhow::Result<usize> {
+        self.wait_for_thread_save();
+        let name = self.runtime.block_on(self.rice.active_thread_name())?;
+        let messages = self.runtime.block_on(self.rice.load_thread(&name))?;
+        self.thread_transcript = self.load_transcript(&name);
+        self.active_thread = name;
+        self.conversation_thread = messages;
+        Ok(self.conversation_thread.len() / 2)
+    }
+
+    /// Turns recorded for thread `name`; unreadable entries are skipped.
+    fn load_transcript(&mut self, name: &str) -> Vec<TranscriptTurn> {
+        match self.runtime.block_on(self.rice.load_transcript(name)) {
+            Ok(turns) => turns
+                .into_iter()
+                .filter_map(|turn| serde_json::from_value(turn).ok())
+                .collect(),
+            Err(err) => {
+                log_src!(
+                    self,
+                    LogLevel::Warn,
+                    format!("Transcript load skipped: {err:#}")
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    /// Append a finished turn to the active thread's transcript and
+    /// persist it in the background (best-effort).  The transcript keeps
+    /// at most [`THREAD_TRANSCRIPT_MAX_TURNS`] turns and
+    /// [`THREAD_TRANSCRIPT_MAX_BYTES`] of JSON, dropping the oldest first.
+    pub(crate) fn record_transcript_turn(&mut self, turn: TranscriptTurn) {
+        self.thread_transcript.push(turn);
+        let excess = self
+            .thread_transcript
+            .len()
+            .saturating_sub(THREAD_TRANSCRIPT_MAX_TURNS);
+        self.thread_transcript.drain(..excess);
+        let mut turns: Vec<serde_json::Value> = self
+            .thread_transcript
+            .iter()
+            .filter_map(|turn| serde_json::to_value(turn).ok())
+            .collect();
+        let sizes: Vec<usize> = turns.iter().map(|turn| turn.to_string().len()).collect();
+        let mut bytes: usize = sizes.iter().sum();
+        let mut dropped = 0;
+        while bytes > THREAD_TRANSCRIPT_MAX_BYTES && dropped + 1 < turns.len() {
+            bytes -= sizes[dropped];
+            dropped += 1;
+        }
+        turns.drain(..dropped);
+        self.thread_transcript
+            .drain(..dropped.min(self.thread_transcript.len()));
+
+        let thread = self.active_thread.clone();
+        self.queue_thread_save(move |mut rice| async move {
+            let _ = rice.save_transcript(&thread, &turns).await;
+        });
+    }
+
+    /// Persist `conversation_thread` under the active thread in the
+    /// background (best-effort).
+    pub(crate) fn save_thread(&mut self) {
+        let thread = self.active_thread.clone();
+        let messages = self.conversation_thread.clone();
+        let persona = self.active_agent.name.clone();
+        self.queue_thread_save(move |mut rice| async move {
+            let _ = rice.save_thread(&thread, &messages, &persona).await;
+        });
+    }
+
+    /// Run `save` on a background store once the previous thread save has
+    /// finished, so saves land in turn order without blocking the UI.
+    fn queue_thread_save<F, Fut>(&mut self, save: F)
+    where
+        F: FnOnce(rice::RiceStore) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let previous = self.thread_save.take();
+        let rice_handle = self.runtime.spawn(self.rice.connect_alike());
+        self.thread_save = Some(self.runtime.spawn(async move {
+            if let Some(previous) = previous {
+                let _ = previous.await;
+            }
+            if let Ok(rice) = rice_handle.await {
+                save(rice).await;
+            }
+        }));
+    }
+
+    /// Block until the last background thread or transcript save has
+    /// finished.
+    pub(crate) fn wait_for_thread_save(&mut self) {
+        if let Some(save) = self.thread_save.take() {
+            let _ = self.runtime.block_on(save);
         }
     }
 
@@ -241,10 +455,18 @@ impl App {
         self.log(
             LogLevel::Info,
             format!(
-                "Conversation: {count} messages ({turns} turns) | Persona: {}",
-                self.active_agent.name
+                "Thread '{}': {count} messages ({turns} turns) | Persona: {}",
+                self.active_thread, self.active_agent.name
             ),
         );
+        if let Some(summary) = thread_summary::pinned_summary(&self.conversation_thread) {
+            let words = summary.split_whitespace().count();
+            let tokens = thread_summary::estimate_tokens(&self.conversation_thread);
+            self.log(
+                LogLevel::Info,
+                format!("   Older turns condensed into a {words}-word summary (~{tokens} tokens in thread)."),
+            );
+        }
         if count == 0 {
             self.log(
                 LogLevel::Info,
@@ -255,7 +477,11 @@ impl App {
 
     fn clear_thread(&mut self) {
         self.conversation_thread.clear();
-        if let Err(err) = self.runtime.block_on(self.rice.clear_thread()) {
+        self.thread_transcript.clear();
+        if let Err(err) = self
+            .runtime
+            .block_on(self.rice.clear_thread(&self.active_thread))
+        {
             log_src!(
                 self,
                 LogLevel::Warn,
@@ -264,64 +490,1002 @@ impl App {
         }
         self.log(LogLevel::Info, "Conversation cleared.".to_string());
     }
+
+    fn list_threads(&mut self) {
+        let index = match self.runtime.block_on(self.rice.thread_index()) {
+            Ok(index) => index,
+            Err(err) => {
+                log_src!(self, LogLevel::Warn, format!("Thread list failed: {err:#}"));
+                return;
+            }
+        };
+        self.log(
+            LogLevel::Info,
+            format!("{} thread(s):", index.threads.len()),
+        );
+        for meta in index.by_last_used() {
+            let active = meta.name == self.active_thread;
+            // The active thread's count is live; others are as last saved.
+            let messages = if active {
+                self.conversation_thread.len()
+            } else {
+                meta.messages
+            };
+            let persona = if meta.persona.is_empty() {
+                "-"
+            } else {
+                meta.persona.as_str()
+            };
+            self.log(
+                LogLevel::Info,
+                format!(
+                    "{} {:<20} {:>4} msgs  {:<12} used {}  created {}",
+                    if active { "▸" } else { " " },
+                    meta.name,
+                    messages,
+                    persona,
+                    short_date(&meta.last_used),
+                    short_date(&meta.created_at),
+                ),
+            );
+        }
+    }
+
+    fn thread_switch_blocked(&mut self) -> bool {
+        if self.chat_busy {
+            log_src!(
+                self,
+                LogLevel::Warn,
+                "Wait for the current reply to finish before changing threads.".to_string()
+            );
+        }
+        self.chat_busy
+    }
+
+    fn new_thread(&mut self, name: &str) {
+        if self.thread_switch_blocked() {
+            return;
+        }
+        if let Err(err) = self
+            .runtime
+            .block_on(self.rice.create_thread(name, &self.active_agent.name))
+        {
+            log_src!(
+                self,
+                LogLevel::Warn,
+                format!("Could not create thread: {err:#}")
+            );
+            return;
+        }
+        self.active_thread = name.to_string();
+        self.conversation_thread.clear();
+        self.thread_transcript.clear();
+        self.log(
+            LogLevel::Info,
+            format!(
+                "Started thread '{name}'. The previous thread is kept; /thread list shows all."
+            ),
+        );
+    }
+
+    fn switch_thread(&mut self, name: &str) {
+        if name == self.active_thread {
+            self.log(LogLevel::Info, format!("Already on thread '{name}'."));
+            return;
+        }
+        if self.thread_switch_blocked() {
+            return;
+        }
+        let loaded = self.runtime.block_on(async {
+            let meta = self.rice.switch_thread(name).await?;
+            let messages = self.rice.load_thread(name).await?;
+            anyhow::Ok((meta, messages))
+        });
+        let (meta, messages) = match loaded {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                log_src!(
+                    self,
+                    LogLevel::Warn,
+                    format!("Could not switch thread: {err:#}")
+                );
+                return;
+            }
+        };
+        self.thread_transcript = self.load_transcript(name);
+        self.active_thread = name.to_string();
+        self.conversation_thread = messages;
+        self.log(
+            LogLevel::Info,
+            format!(
+                "Switched to thread '{name}' ({} turn(s)).",
+                self.conversation_thread.len() / 2
+            ),
+        );
+
+        // Pick the thread's persona back up, without clearing the thread
+        // the way /agent use does.
+        if meta.persona.is_empty() || meta.persona == self.active_agent.name {
+            return;
+        }
+        let agent = if meta.persona == "memini" {
+            Some(Agent::default())
+        } else {
+            self.custom_agents
+                .iter()
+                .find(|a| a.name == meta.persona)
+                .cloned()
+        };
+        match agent {
+            Some(agent) => {
+                self.active_agent = agent;
+                let _ = self
+                    .runtime
+                    .block_on(self.rice.save_active_agent_name(&meta.persona));
+                self.log(LogLevel::Info, format!("Persona: {}", meta.persona));
+            }
+            None => self.log(
+                LogLevel::Info,
+                format!(
+                    "This thread was last used with persona '{}', which no longer exists.",
+                    meta.persona
+                ),
+            ),
+        }
+    }
+
+    fn rename_thread(&mut self, old: &str, new: &str) {
+        if let Err(err) = self.runtime.block_on(self.rice.rename_thread(old, new)) {
+            log_src!(
+                self,
+                LogLevel::Warn,
+                format!("Could not rename thread: {err:#}")
+            );
+            return;
+        }
+        if self.active_thread == old {
+            self.active_thread = new.to_string();
+        }
+        self.log(
+            LogLevel::Info,
+            format!("Renamed thread '{old}' to '{new}'."),
+        );
+    }
+
+    fn delete_thread(&mut self, name: &str) {
+        if let Err(err) = self.runtime.block_on(self.rice.delete_thread(name)) {
+            log_src!(
+                self,
+                LogLevel::Warn,
+                format!("Could not delete thread: {err:#}")
+            );
+            return;
+        }
+        self.log(LogLevel::Info, format!("Deleted thread '{name}'."));
+    }
+
+    fn export_thread(&mut self, args: &[&str]) {
+        let request = match ExportRequest::parse(args) {
+            Ok(request) => request,
+            Err(err) => {
+                log_src!(self, LogLevel::Warn, format!("{err:#}"));
+                return;
+            }
+        };
+        let export = ThreadExport {
+            thread: &self.active_thread,
+            workspace: self.rice.shared_run_id.as_deref(),
+            turns: &self.thread_transcript,
+            messages: &self.conversation_thread,
+            logs: request.include_logs.then_some(self.logs.as_slice()),
+        };
+        let result = export.write(&request.path, request.format);
+        let (turns, messages) = (self.thread_transcript.len(), self.conversation_thread.len());
+        match result {
+            Ok(()) if turns > 0 => self.log(
+                LogLevel::Info,
+                format!(
+                    "Exported {turns} turn(s) of '{}' to {}.",
+                    self.active_thread,
+                    request.path.display()
+                ),
+            ),
+            Ok(()) => self.log(
+                LogLevel::Info,
+                format!(
+                    "Exported {messages} message(s) of '{}' to {} (no turn details were recorded for this thread yet).",
+                    self.active_thread,
+                    request.path.display()
+                ),
+            ),
+            Err(err) => log_src!(self, LogLevel::Warn, format!("Export failed: {err:#}")),
+        }
+    }
+}
+
+/// `YYYY-MM-DD HH:MM` from an RFC 3339 timestamp.
+fn short_date(at: &str) -> String {
+    DateTime::parse_from_rfc3339(at)
+        .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| "-".to_string())
 }
 
 // ── /memory ──────────────────────────────────────────────────────────
 
+const MEMORY_USAGE: &str = "Usage: /memory <query> [--author <user>] | list [--agent <name>] \
+     [--author <user>] [--since <date|7d>] [--limit <n>] | show <id> | forget <id|text> | pin <id> | unpin <id> | edit <id> [outcome] \
+     | export <path> [--workspace w] [--format jsonl|md] | import <path> [--dry-run] \
+     | compact [--dry-run]";
+
 impl App {
     pub(crate) fn handle_memory_command(&mut self, args: Vec<&str>) {
         if args.is_empty() {
-            self.log(LogLevel::Info, "Usage: /memory <search query>".to_string());
+            self.log(LogLevel::Info, MEMORY_USAGE.to_string());
             return;
         }
-        let query = args.join(" ");
-        self.search_memory(&query);
+        let rest = args[1..].join(" ");
+        match args[0] {
+            "list" | "ls" => self.list_memories(&args[1..]),
+            "show" => self.show_memory(&rest),
+            "forget" | "rm" => self.forget_memory(&rest),
+            "pin" => self.pin_memory(&rest),
+            "unpin" => self.unpin_memory(&rest),
+            "edit" => self.edit_memory(&rest),
+            "export" | "import" => self.transfer_memory(&args),
+            "compact" => self.compact_memory(&args[1..]),
+            _ => self.search_memory(&args),
+        }
     }
 
-    fn search_memory(&mut self, query: &str) {
-        let memories =
-            match self
+    fn search_memory(&mut self, args: &[&str]) {
+        let mut author = None;
+        let mut words = Vec::new();
+        let mut iter = args.iter();
+        while let Some(word) = iter.next() {
+            match (*word, author) {
+                ("--author", None) => match iter.next() {
+                    Some(value) => author = Some(*value),
+                    None => {
+                        log_src!(self, LogLevel::Warn, MEMORY_USAGE.to_string());
+                        return;
+                    }
+                },
+                _ => words.push(*word),
+            }
+        }
+        let query = words.join(" ");
+        let limit = self.memory_limit;
+        let recalled = match author {
+            Some(author) => self
                 .runtime
-                .block_on(self.rice.reminisce(vec![], self.memory_limit, query))
-            {
-                Ok(traces) => traces,
+                .block_on(self.rice.recall_by_author(author, limit, &query)),
+            None => self
+                .runtime
+                .block_on(self.rice.recall(vec![], limit, &query)),
+        };
+        let memories = match recalled {
+            Ok(traces) => traces,
+            Err(err) => {
+                log_src!(
+                    self,
+                    LogLevel::Warn,
+                    format!("Memory search failed: {err:#}")
+                );
+                return;
+            }
+        };
+
+        if memories.is_empty() {
+            self.log(LogLevel::Info, "No matching memories found.".to_string());
+            return;
+        }
+
+        self.log(
+            LogLevel::Info,
+            format!("Found {} memory(ies):", memories.len()),
+        );
+        for stored in &memories {
+            self.log_memory_line(stored);
+        }
+    }
+
+    fn list_memories(&mut self, args: &[&str]) {
+        let mut agent = None;
+        let mut author = None;
+        let mut since = None;
+        let mut limit = MEMORY_LIST_LIMIT;
+        let mut iter = args.iter();
+        while let Some(flag) = iter.next() {
+            let value = iter.next().copied();
+            match (*flag, value) {
+                ("--agent", Some(value)) => agent = Some(value.to_string()),
+                ("--author", Some(value)) => author = Some(value.to_string()),
+                ("--since", Some(value)) => match parse_since(value) {
+                    Some(parsed) => since = Some(parsed),
+                    None => {
+                        log_src!(
+                            self,
+                            LogLevel::Warn,
+                            format!("Bad --since '{value}'. Use YYYY-MM-DD or e.g. 7d / 12h.")
+                        );
+                        return;
+                    }
+                },
+                ("--limit", Some(value)) => match value.parse::<u64>() {
+                    Ok(parsed) if parsed > 0 => limit = parsed.min(MEMORY_SCAN_LIMIT),
+                    _ => {
+                        log_src!(self, LogLevel::Warn, format!("Bad --limit '{value}'."));
+                        return;
+                    }
+                },
+                _ => {
+                    log_src!(self, LogLevel::Warn, MEMORY_USAGE.to_string());
+                    return;
+                }
+            }
+        }
+
+        let memories = match self.runtime.block_on(self.rice.list_memories(
+            agent.as_deref(),
+            author.as_deref(),
+            since,
+            limit,
+        )) {
+            Ok(traces) => traces,
+            Err(err) => {
+                log_src!(self, LogLevel::Warn, format!("Memory list failed: {err:#}"));
+                return;
+            }
+        };
+        if memories.is_empty() {
+            self.log(LogLevel::Info, "No memories found.".to_string());
+            if since.is_some() && matches!(self.rice.status, RiceStatus::Connected) {
+                self.log(
+                    LogLevel::Info,
+                    "   Rice traces carry no timestamp, so --since only matches stamped memories."
+                        .to_string(),
+                );
+            }
+            return;
+        }
+        self.log(
+            LogLevel::Info,
+            format!("{} memory(ies), newest first:", memories.len()),
+        );
+        for stored in &memories {
+            self.log_memory_line(stored);
+        }
+    }
+
+    fn show_memory(&mut self, id: &str) {
+        let Some(stored) = self.resolve_memory(id, "show") else {
+            return;
+        };
+        let pinned = self
+            .runtime
+            .block_on(self.rice.is_memory_pinned(&stored.id))
+            .unwrap_or(false);
+        let trace = &stored.trace;
+        self.log(
+            LogLevel::Info,
+            format!(
+                "Memory {}{}",
+                stored.id,
+                if pinned { " (pinned)" } else { "" }
+            ),
+        );
+        let author = rice::trace_author(trace);
+        if !stored.created_at.is_empty() {
+            self.log(LogLevel::Info, format!("   When:    {}", stored.created_at));
+        } else if let Some(author) = &author {
+            self.log(LogLevel::Info, format!("   When:    {}", author.at));
+        }
+        if let Some(author) = &author {
+            self.log(LogLevel::Info, format!("   Author:  {}", author.label()));
+        }
+        for (label, value) in [
+            ("Agent:  ", trace.agent_id.as_str()),
+            ("Action: ", trace.action.as_str()),
+            ("Input:  ", trace.input.as_str()),
+            ("Outcome:", trace.outcome.as_str()),
+            ("Reasoning:", rice::strip_stamp(&trace.reasoning)),
+        ] {
+            if !value.trim().is_empty() {
+                self.log(LogLevel::Info, format!("   {label} {}", value.trim()));
+            }
+        }
+    }
+
+    fn forget_memory(&mut self, raw: &str) {
+        let Some((all, target)) = parse_forget_target(raw) else {
+            log_src!(
+                self,
+                LogLevel::Warn,
+                "Usage: /memory forget <id> | forget --id <id> | forget [--all] [--text] <text>"
+                    .to_string()
+            );
+            return;
+        };
+        let target = match target {
+            ForgetTarget::Id(id) => {
+                if let Some(stored) = self.resolve_memory(id, "forget") {
+                    self.forget_memories(&[stored]);
+                }
+                return;
+            }
+            ForgetTarget::Text(text) => text,
+        };
+        let targets = match self
+            .runtime
+            .block_on(self.rice.find_memories_containing(target))
+        {
+            Ok(found) => found,
+            Err(err) => {
+                log_src!(
+                    self,
+                    LogLevel::Warn,
+                    format!("Memory lookup failed: {err:#}")
+                );
+                return;
+            }
+        };
+        if targets.is_empty() {
+            self.log(LogLevel::Info, format!("No memory matches '{target}'."));
+            return;
+        }
+        if targets.len() > 1 && !all {
+            self.log(
+                LogLevel::Info,
+                format!("{} memories match '{target}':", targets.len()),
+            );
+            for stored in &targets {
+                self.log_memory_line(stored);
+            }
+            self.log(
+                LogLevel::Info,
+                format!("Forget one by id, or all of them with /memory forget --all {target}"),
+            );
+            return;
+        }
+        self.forget_memories(&targets);
+    }
+
+    fn forget_memories(&mut self, targets: &[StoredTrace]) {
+        let mut forgotten = 0usize;
+        for stored in targets {
+            match self.runtime.block_on(self.rice.forget_memory(stored)) {
+                Ok(()) => {
+                    forgotten += 1;
+                    self.log_memory_line(stored);
+                }
                 Err(err) => {
                     log_src!(
                         self,
                         LogLevel::Warn,
-                        format!("Memory search failed: {err:#}")
+                        format!("Failed to forget {}: {err:#}", stored.id)
                     );
-                    return;
                 }
-            };
+            }
+        }
+        self.log(LogLevel::Info, format!("Forgot {forgotten} memory(ies)."));
+    }
 
-        if memories.is_empty() {
-            self.log(LogLevel::Info, "No matching memories found.".to_string());
+    fn pin_memory(&mut self, id: &str) {
+        let Some(stored) = self.resolve_memory(id, "pin") else {
             return;
+        };
+        match self.runtime.block_on(self.rice.pin_memory(&stored)) {
+            Ok(true) => self.log(
+                LogLevel::Info,
+                format!(
+                    "📌 Pinned {} -- it is now included in every recall.",
+                    stored.id
+                ),
+            ),
+            Ok(false) => self.log(LogLevel::Info, format!("{} is already pinned.", stored.id)),
+            Err(err) => {
+                log_src!(self, LogLevel::Warn, format!("Pin failed: {err:#}"));
+            }
         }
+    }
 
+    fn unpin_memory(&mut self, id: &str) {
+        let Some(stored) = self.resolve_memory(id, "unpin") else {
+            return;
+        };
+        match self.runtime.block_on(self.rice.unpin_memory(&stored.id)) {
+            Ok(true) => self.log(LogLevel::Info, format!("Unpinned {}.", stored.id)),
+            Ok(false) => self.log(LogLevel::Info, format!("{} is not pinned.", stored.id)),
+            Err(err) => {
+                log_src!(self, LogLevel::Warn, format!("Unpin failed: {err:#}"));
+            }
+        }
+    }
+
+    /// `/memory edit <id>` pre-fills the input with the current outcome;
+    /// `/memory edit <id> <text>` saves `<text>` as the new outcome.
+    fn edit_memory(&mut self, args: &str) {
+        let (id, outcome) = match args.trim().split_once(char::is_whitespace) {
+            Some((id, outcome)) => (id, outcome.trim()),
+            None => (args.trim(), ""),
+        };
+        let Some(stored) = self.resolve_memory(id, "edit") else {
+            return;
+        };
+        if outcome.is_empty() {
+            self.input = format!("/memory edit {} {}", stored.id, stored.trace.outcome.trim());
+            self.cursor = self.input.len();
+            self.log(
+                LogLevel::Info,
+                "Edit the outcome in the input line and press Enter to save.".to_string(),
+            );
+            return;
+        }
+        match self
+            .runtime
+            .block_on(self.rice.edit_memory(&stored, outcome))
+        {
+            Ok(edited) => {
+                self.log(LogLevel::Info, format!("Updated memory {}:", stored.id));
+                self.log_memory_line(&edited);
+            }
+            Err(err) => {
+                log_src!(self, LogLevel::Warn, format!("Edit failed: {err:#}"));
+            }
+        }
+    }
+
+    /// `/memory export|import …` -- see `rice::transfer`.
+    fn transfer_memory(&mut self, args: &[&str]) {
+        let command = match TransferCommand::parse(args) {
+            Ok(command) => command,
+            Err(err) => {
+                log_src!(self, LogLevel::Warn, format!("{err:#}"));
+                return;
+            }
+        };
+        match self.runtime.block_on(self.rice.run_transfer(&command)) {
+            Ok(lines) => {
+                for line in lines {
+                    self.log(LogLevel::Info, line);
+                }
+            }
+            Err(err) => {
+                log_src!(
+                    self,
+                    LogLevel::Error,
+                    format!("Memory {} failed: {err:#}", args[0])
+                );
+            }
+        }
+    }
+
+    /// `/memory compact` runs the built-in compaction task in the
+    /// background; `--dry-run` only lists the clusters it would merge.
+    fn compact_memory(&mut self, args: &[&str]) {
+        if args.first() != Some(&"--dry-run") {
+            if let Some(def) = daemon::builtin_tasks()
+                .into_iter()
+                .find(|task| task.builtin == Some(daemon::BuiltinTask::MemoryCompact))
+            {
+                self.run_daemon_oneshot(def);
+            }
+            return;
+        }
+        let clusters = match self.runtime.block_on(self.rice.plan_compaction()) {
+            Ok(clusters) => clusters,
+            Err(err) => {
+                log_src!(self, LogLevel::Warn, format!("Compaction failed: {err:#}"));
+                return;
+            }
+        };
+        if clusters.is_empty() {
+            self.log(
+                LogLevel::Info,
+                "Memory is already compact; no near-duplicates found.".to_string(),
+            );
+            return;
+        }
+        let total: usize = clusters.iter().map(|cluster| cluster.members.len()).sum();
         self.log(
             LogLevel::Info,
-            format!("Found {} memory(ies):", memories.len()),
+            format!(
+                "Dry run: {total} memories in {} cluster(s) would be merged:",
+                clusters.len()
+            ),
         );
-        for trace in &memories {
-            let input = trace.input.trim();
-            let outcome = trace.outcome.trim();
-            let action = trace.action.trim();
-            if input.is_empty() && outcome.is_empty() {
-                continue;
-            }
-            if action.is_empty() {
-                self.log(
-                    LogLevel::Info,
-                    format!("  \u{21B3} {input} \u{2192} {outcome}"),
+        for (idx, cluster) in clusters.iter().enumerate() {
+            self.log(LogLevel::Info, format!("Cluster {}:", idx + 1));
+            for stored in &cluster.members {
+                self.log_memory_line(stored);
+            }
+        }
+    }
+
+    fn resolve_memory(&mut self, id: &str, verb: &str) -> Option<StoredTrace> {
+        if id.trim().is_empty() {
+            log_src!(self, LogLevel::Warn, format!("Usage: /memory {verb} <id>"));
+            return None;
+        }
+        match self.runtime.block_on(self.rice.find_memory(id)) {
+            Ok(stored) => Some(stored),
+            Err(err) => {
+                log_src!(self, LogLevel::Warn, format!("{err:#}"));
+                None
+            }
+        }
+    }
+
+    fn log_memory_line(&mut self, stored: &StoredTrace) {
+        let trace = &stored.trace;
+        let input = trace.input.trim();
+        let outcome = trace.outcome.trim();
+        let action = trace.action.trim();
+        if input.is_empty() && outcome.is_empty() {
+            return;
+        }
+        let by = rice::trace_author(trace)
+            .map(|author| format!(" \u{00B7} {}", author.label()))
+            .unwrap_or_default();
+        if action.is_empty() {
+            self.log(
+                LogLevel::Info,
+                format!("  {} \u{21B3} {input} \u{2192} {outcome}{by}", stored.id),
+            );
+        } else {
+            self.log(
+                LogLevel::Info,
+                format!(
+                    "  {} \u{21B3} [{action}] {input} \u{2192} {outcome}{by}",
+                    stored.id
+                ),
+            );
+        }
+    }
+}
+
+/// What `/memory forget` was asked to remove.
+#[derive(Debug, PartialEq, Eq)]
+enum ForgetTarget<'a> {
+    Id(&'a str),
+    Text(&'a str),
+}
+
+/// Parse `[--all] [--id <id> | --text <text> | <id|text>]`.  Without a
+/// flag only a full 8-hex-digit id, as `/memory list` shows it, is taken
+/// as an id, so a word such as `cafe` or `deadbeef1` is searched for.
+fn parse_forget_target(raw: &str) -> Option<(bool, ForgetTarget<'_>)> {
+    let mut rest = raw.trim();
+    let mut all = false;
+    if let Some(after) = rest.strip_prefix("--all") {
+        all = true;
+        rest = after.trim_start();
+    }
+    let (flag, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let target = if flag == "--id" {
+        ForgetTarget::Id(value.trim())
+    } else if flag == "--text" {
+        ForgetTarget::Text(value.trim())
+    } else if rest.len() == 8 && rest.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        ForgetTarget::Id(rest)
+    } else {
+        ForgetTarget::Text(rest)
+    };
+    match target {
+        ForgetTarget::Id("") | ForgetTarget::Text("") => None,
+        target => Some((all, target)),
+    }
+}
+
+/// `--since` accepts a date (`2026-10-01`), an RFC 3339 timestamp, or a
+/// relative span (`30m`, `12h`, `7d`, `2w`).
+fn parse_since(raw: &str) -> Option<DateTime<FixedOffset>> {
+    let raw = raw.trim();
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(raw) {
+        return Some(parsed);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(raw, "%Y-%m-%d") {
+        let midnight = date.and_hms_opt(0, 0, 0)?;
+        return Local
+            .from_local_datetime(&midnight)
+            .earliest()
+            .map(|local| local.fixed_offset());
+    }
+    let (split, _) = raw.char_indices().last()?;
+    let (amount, unit) = raw.split_at(split);
+    let amount: i64 = amount.parse().ok()?;
+    let span = match unit {
+        "m" => Duration::minutes(amount),
+        "h" => Duration::hours(amount),
+        "d" => Duration::days(amount),
+        "w" => Duration::weeks(amount),
+        _ => return None,
+    };
+    Some((Local::now() - span).fixed_offset())
+}
+
+// ── Persona files ────────────────────────────────────────────────────
+
+impl App {
+    /// Save the custom persona list to Rice (best-effort).
+    fn save_custom_agents(&mut self) {
+        let agents_json =
+            serde_json::to_value(&self.custom_agents).unwrap_or(serde_json::Value::Array(vec![]));
+        if let Err(err) = self
+            .runtime
+            .block_on(self.rice.save_custom_agents(agents_json))
+        {
+            log_src!(
+                self,
+                LogLevel::Warn,
+                format!("Failed to save agents: {err:#}")
+            );
+        }
+    }
+
+    /// Save one persona to Rice and to its file in `$MEMINI_HOME/personas`.
+    fn persist_persona(&mut self, agent: &Agent) {
+        self.save_custom_agents();
+        if let Err(err) = persona_files::write_persona_file(agent) {
+            log_src!(
+                self,
+                LogLevel::Warn,
+                format!("Persona file write failed: {err:#}")
+            );
+        }
+    }
+
+    /// Two-way sync between persona files and the Rice persona list.
+    ///
+    /// For each persona the newer side wins (see
+    /// [`persona_files::merge_personas`]); personas deleted since a file
+    /// was written have the file removed instead of being re-pushed.
+    pub(crate) fn sync_persona_files(&mut self) {
+        let (file_agents, errors) = match persona_files::load_persona_files() {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                log_src!(
+                    self,
+                    LogLevel::Warn,
+                    format!("Persona files load skipped: {err:#}")
                 );
-            } else {
-                self.log(
-                    LogLevel::Info,
-                    format!("  \u{21B3} [{action}] {input} \u{2192} {outcome}"),
+                return;
+            }
+        };
+        for (path, err) in errors {
+            log_src!(
+                self,
+                LogLevel::Warn,
+                format!("Skipped persona {}: {err}", path.display())
+            );
+        }
+
+        // Pick up changes made on other machines since startup.
+        if let Ok(Some(value)) = self.runtime.block_on(self.rice.load_custom_agents())
+            && let Ok(agents) = serde_json::from_value::<Vec<Agent>>(value)
+        {
+            self.custom_agents = agents;
+        }
+        let tombstones = self.load_agent_tombstones();
+        let from_files = file_agents.len();
+        let merge = persona_files::merge_personas(
+            file_agents,
+            std::mem::take(&mut self.custom_agents),
+            tombstones.clone(),
+        );
+        self.custom_agents = merge.agents;
+
+        for agent in &merge.write_files {
+            if let Err(err) = persona_files::write_persona_file(agent) {
+                log_src!(
+                    self,
+                    LogLevel::Warn,
+                    format!("Persona file write failed for '{}': {err:#}", agent.name)
                 );
             }
         }
+        for name in &merge.remove_files {
+            if let Err(err) = persona_files::remove_persona_file(name) {
+                log_src!(
+                    self,
+                    LogLevel::Warn,
+                    format!("Persona file removal failed for '{name}': {err:#}")
+                );
+            }
+        }
+
+        if merge.rice_changed {
+            self.save_custom_agents();
+        }
+        if merge.tombstones != tombstones {
+            self.save_agent_tombstones(&merge.tombstones);
+        }
+        if let Some(active) = self
+            .custom_agents
+            .iter()
+            .find(|a| a.name == self.active_agent.name)
+            .cloned()
+        {
+            self.active_agent = active;
+        }
+        if merge.rice_changed || !merge.write_files.is_empty() || !merge.remove_files.is_empty() {
+            self.log(
+                LogLevel::Info,
+                format!(
+                    "Personas synced: {from_files} file(s) read, {} written and {} removed in {}.",
+                    merge.write_files.len(),
+                    merge.remove_files.len(),
+                    persona_files::personas_dir().display()
+                ),
+            );
+        }
+    }
+
+    fn load_agent_tombstones(&mut self) -> persona_files::Tombstones {
+        match self.runtime.block_on(self.rice.load_agent_tombstones()) {
+            Ok(Some(value)) => serde_json::from_value(value).unwrap_or_default(),
+            Ok(None) => persona_files::Tombstones::new(),
+            Err(err) => {
+                log_src!(
+                    self,
+                    LogLevel::Warn,
+                    format!("Persona tombstones load skipped: {err:#}")
+                );
+                persona_files::Tombstones::new()
+            }
+        }
+    }
+
+    fn save_agent_tombstones(&mut self, tombstones: &persona_files::Tombstones) {
+        let value = serde_json::to_value(tombstones).unwrap_or_default();
+        if let Err(err) = self
+            .runtime
+            .block_on(self.rice.save_agent_tombstones(value))
+        {
+            log_src!(
+                self,
+                LogLevel::Warn,
+                format!("Failed to save persona deletions: {err:#}")
+            );
+        }
+    }
+
+    fn export_agent(&mut self, name: &str, target: Option<&str>) {
+        let agent = if name == "memini" {
+            Agent::default()
+        } else if let Some(agent) = self.custom_agents.iter().find(|a| a.name == name) {
+            agent.clone()
+        } else {
+            log_src!(self, LogLevel::Warn, format!("Agent '{name}' not found."));
+            return;
+        };
+
+        let path = match target {
+            Some(target) => std::path::PathBuf::from(target),
+            None => match persona_files::persona_file_path(&agent.name) {
+                Ok(path) => std::path::PathBuf::from(path.file_name().unwrap_or_default()),
+                Err(err) => {
+                    log_src!(self, LogLevel::Warn, format!("Export failed: {err:#}"));
+                    return;
+                }
+            },
+        };
+        match std::fs::write(&path, persona_files::render_persona(&agent)) {
+            Ok(()) => self.log(
+                LogLevel::Info,
+                format!("Exported persona '{}' to {}", agent.name, path.display()),
+            ),
+            Err(err) => log_src!(
+                self,
+                LogLevel::Error,
+                format!("Export to {} failed: {err}", path.display())
+            ),
+        }
+    }
+
+    fn import_agent(&mut self, source: &str) {
+        let is_url = source.starts_with("http://") || source.starts_with("https://");
+        let raw = if is_url {
+            self.log(LogLevel::Info, format!("Fetching persona from {source}..."));
+            self.runtime
+                .block_on(persona_files::fetch_persona_source(source))
+        } else {
+            std::fs::read_to_string(source).map_err(|err| anyhow::anyhow!("Read {source}: {err}"))
+        };
+        let parsed = raw.and_then(|raw| {
+            let name_hint = source
+                .rsplit('/')
+                .next()
+                .filter(|segment| !segment.is_empty())
+                .unwrap_or("persona");
+            persona_files::parse_persona(std::path::Path::new(name_hint), &raw)
+        });
+        let mut agent = match parsed {
+            Ok(agent) => agent,
+            Err(err) => {
+                log_src!(self, LogLevel::Error, format!("Import failed: {err:#}"));
+                return;
+            }
+        };
+        agent.touch();
+
+        let replaced = match self.custom_agents.iter_mut().find(|a| a.name == agent.name) {
+            Some(existing) => {
+                *existing = agent.clone();
+                true
+            }
+            None => {
+                self.custom_agents.push(agent.clone());
+                false
+            }
+        };
+        if self.active_agent.name == agent.name {
+            self.active_agent = agent.clone();
+        }
+        self.persist_persona(&agent);
+
+        let verb = if replaced { "updated" } else { "imported" };
+        self.log(
+            LogLevel::Info,
+            format!(
+                "Persona '{}' {verb}. Use /agent use {} to switch.",
+                agent.name, agent.name
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forget_takes_only_full_ids_unless_told() {
+        assert_eq!(
+            parse_forget_target("3fa2c81e"),
+            Some((false, ForgetTarget::Id("3fa2c81e")))
+        );
+        // Hex-looking words are searched for.
+        for word in ["cafe", "deadbeef1", "3fa2"] {
+            assert_eq!(
+                parse_forget_target(word),
+                Some((false, ForgetTarget::Text(word)))
+            );
+        }
+        assert_eq!(
+            parse_forget_target("--id 3fa2"),
+            Some((false, ForgetTarget::Id("3fa2")))
+        );
+        assert_eq!(
+            parse_forget_target("--all --text 3fa2c81e"),
+            Some((true, ForgetTarget::Text("3fa2c81e")))
+        );
+        assert_eq!(
+            parse_forget_target("--all old api"),
+            Some((true, ForgetTarget::Text("old api")))
+        );
+        assert_eq!(parse_forget_target("--all"), None);
+        assert_eq!(parse_forget_target("--id "), None);
+    }
+
+    #[test]
+    fn since_takes_dates_timestamps_and_spans() {
+        let at = parse_since("2026-10-01T08:00:00+02:00").unwrap();
+        assert_eq!(at.to_rfc3339(), "2026-10-01T08:00:00+02:00");
+        let midnight = parse_since("2026-10-01").unwrap();
+        assert_eq!(
+            midnight.naive_local(),
+            NaiveDate::from_ymd_opt(2026, 10, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        );
+        let week = parse_since("7d").unwrap();
+        let expected = Local::now() - Duration::days(7);
+        assert!((week.timestamp() - expected.timestamp()).abs() <= 5);
+        assert!(parse_since("2w").unwrap() < parse_since("12h").unwrap());
+        for bad in ["", "7", "7y", "d", "yesterday"] {
+            assert_eq!(parse_since(bad), None, "{bad}");
+        }
     }
 }
@@ -0,0 +1,79 @@
+//! `/attach` command handler — queue workspace files and images for the
+//! next chat turn.
+
+use super::super::App;
+use super::super::attachments;
+use super::super::log_src;
+use super::super::logging::LogLevel;
+
+impl App {
+    pub(crate) fn handle_attach_command(&mut self, args: Vec<&str>) {
+        if args.is_empty() {
+            self.show_pending_attachments();
+            return;
+        }
+
+        match args[0] {
+            "list" => self.show_pending_attachments(),
+            "clear" => {
+                let count = self.pending_attachments.len();
+                self.pending_attachments.clear();
+                self.log(
+                    LogLevel::Info,
+                    format!("Cleared {count} pending attachment(s)."),
+                );
+            }
+            _ => {
+                for raw in args {
+                    self.attach_file(raw.trim_start_matches('@'));
+                }
+            }
+        }
+    }
+
+    fn attach_file(&mut self, raw: &str) {
+        let attachment = match attachments::load_attachment(raw) {
+            Ok(attachment) => attachment,
+            Err(err) => {
+                log_src!(
+                    self,
+                    LogLevel::Warn,
+                    format!("Could not attach {raw}: {err:#}")
+                );
+                return;
+            }
+        };
+        if let Some(warning) = attachment.size_warning() {
+            self.log(LogLevel::Warn, warning);
+        }
+        self.pending_attachments
+            .retain(|existing| existing.path != attachment.path);
+        self.log(
+            LogLevel::Info,
+            format!("📎 Queued {} for your next message.", attachment.describe()),
+        );
+        self.pending_attachments.push(attachment);
+    }
+
+    fn show_pending_attachments(&mut self) {
+        if self.pending_attachments.is_empty() {
+            self.log(
+                LogLevel::Info,
+                "No pending attachments. Use /attach <path> or @path in a message.".to_string(),
+            );
+            return;
+        }
+        let lines: Vec<String> = self
+            .pending_attachments
+            .iter()
+            .map(|attachment| format!("  📎 {}", attachment.describe()))
+            .collect();
+        self.log(
+            LogLevel::Info,
+            "Attachments for your next message:".to_string(),
+        );
+        for line in lines {
+            self.log(LogLevel::Info, line);
+        }
+    }
+}
@@ -8,6 +8,8 @@ use super::super::agent_recipes;
 use super::super::daemon;
 use super::super::log_src;
 use super::super::logging::LogLevel;
+use super::super::memory_tools::MemoryScope;
+use super::super::usage::UsageScope;
 
 // ── /daemon ──────────────────────────────────────────────────────────
 
@@ -57,6 +59,7 @@ impl App {
                     );
                 }
             }
+            "resume" => self.resume_budget_paused(args.get(1).copied()),
             "stop" => {
                 if let Some(name) = args.get(1) {
                     self.stop_daemon(name);
@@ -216,6 +219,12 @@ impl App {
                     trigger_variables: Vec::new(),
                     tools: spec.tools,
                     paused: false,
+                    budget: None,
+                    output_schema: None,
+                    max_tool_loops: None,
+                    hosted_tools: Vec::new(),
+                    builtin: None,
+                    persona_tools: Vec::new(),
                 };
                 self.spawn_daemon_task(def);
             }
@@ -256,6 +265,12 @@ impl App {
             trigger_variables: recipe.trigger_variables.clone(),
             tools: recipe.tools.clone(),
             paused,
+            budget: recipe.budget,
+            output_schema: recipe.output_schema.clone(),
+            max_tool_loops: recipe.max_tool_loops,
+            hosted_tools: recipe.hosted_tools.clone(),
+            builtin: None,
+            persona_tools: Vec::new(),
         }
     }
 
@@ -322,7 +337,7 @@ impl App {
                 .daemon_handles
                 .iter()
                 .any(|h| h.def.name.eq_ignore_ascii_case(&builtin.name));
-            let status = if running { "running" } else { "available" };
+            let status = self.daemon_status(running, &builtin.name);
             self.log(
                 LogLevel::Info,
                 format!(
@@ -349,7 +364,7 @@ impl App {
                 .daemon_handles
                 .iter()
                 .any(|h| h.def.name.eq_ignore_ascii_case(&recipe.name));
-            let status = if running { "running" } else { "available" };
+            let status = self.daemon_status(running, &recipe.name);
             let preview: String = if recipe.description.trim().is_empty() {
                 recipe.instructions.chars().take(72).collect()
             } else {
@@ -393,6 +408,16 @@ impl App {
         }
     }
 
+    fn daemon_status(&self, running: bool, name: &str) -> &'static str {
+        if running {
+            "running"
+        } else if self.budget_paused.iter().any(|def| def.name == name) {
+            "paused: budget"
+        } else {
+            "available"
+        }
+    }
+
     fn run_daemon_now(&mut self, name: &str) {
         for handle in &self.daemon_handles {
             if handle.def.name.eq_ignore_ascii_case(name) {
@@ -540,6 +565,12 @@ impl App {
                     trigger_variables: Vec::new(),
                     tools: spec.tools,
                     paused: false,
+                    budget: None,
+                    output_schema: None,
+                    max_tool_loops: None,
+                    hosted_tools: Vec::new(),
+                    builtin: None,
+                    persona_tools: Vec::new(),
                 };
                 self.spawn_daemon_task(def);
                 self.log(
@@ -784,22 +815,37 @@ impl App {
             skill_context: self.skills_prompt_context(prompt),
             mcp_snapshots: Vec::new(),
             coordination_key: String::new(),
+            output_schema: None,
+            max_tool_loops: None,
+            last_response_id: None,
         };
         self.agent_windows.push(window);
 
         // Spawn the background task.
         let tx = self.daemon_tx.clone();
-        let openai = self.openai.clone();
+        let openai = self.budget_gated(
+            &self.active_agent.apply_to(&self.openai),
+            UsageScope::Window(window_id),
+        );
         let key = self.openai_key.clone();
-        let rice_handle = self.runtime.spawn(crate::rice::RiceStore::connect());
+        let rice_handle = self.runtime.spawn(self.rice.connect_alike());
         let persona = self.active_agent.persona.clone();
         let skill_context = self.skills_prompt_context(prompt);
 
-        daemon::spawn_agent_window(
+        let run = daemon::AgentWindowRun {
             window_id,
+            coordination_key: String::new(),
+            output_schema: None,
+            previous_response_id: None,
             persona,
-            prompt.to_string(),
+            memory_scope: MemoryScope::persona(&self.active_agent.name),
+            prompt: prompt.to_string(),
             skill_context,
+            tools: self.active_agent.tools.clone(),
+        };
+
+        daemon::spawn_agent_window(
+            run,
             tx,
             openai,
             key,
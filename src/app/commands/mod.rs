@@ -8,16 +8,20 @@
 //! | `mcp`     | `/mcp` – connect, auth, tools, call   |
 //! | `openai`  | `/openai`, `/model`, `/key`, `/rice`, bootstrap |
 //! | `agents`  | `/agent`, `/thread`, `/memory`        |
+//! | `attach`  | `/attach` — files & images for chat   |
 //! | `daemons` | `/daemon`, `/auto`, `/spawn`, `/reply`|
 //! | `share`   | `/share`                              |
 //! | `skills`  | `/skills` — import/list skill packs   |
+//! | `usage`   | `/usage` — token/cost totals, budgets |
 
 mod agents;
+mod attach;
 mod daemons;
 mod mcp;
 mod openai;
 mod share;
 mod skills;
+mod usage;
 
 use super::App;
 use super::log_src;
@@ -42,12 +46,14 @@ impl App {
             "/rice" => self.handle_rice_command(parts.collect()),
             "/agent" => self.handle_agent_command(parts.collect()),
             "/thread" => self.handle_thread_command(parts.collect()),
+            "/attach" => self.handle_attach_command(parts.collect()),
             "/memory" | "/mem" => self.handle_memory_command(parts.collect()),
             "/daemon" | "/d" | "/auto" => self.handle_daemon_command(parts.collect()),
             "/spawn" => self.handle_spawn_command(parts.collect()),
             "/reply" | "/r" => self.handle_reply_command(parts.collect()),
             "/share" => self.handle_share_command(parts.collect()),
             "/skills" => self.handle_skills_command(parts.collect()),
+            "/usage" => self.handle_usage_command(parts.collect()),
             "/panel" => {
                 self.show_side_panel = !self.show_side_panel;
                 let state = if self.show_side_panel {
@@ -79,8 +85,25 @@ impl App {
             "Chat & Memory",
             "  (just type)             Talk to your AI — it recalls past chats",
             "  /memory <query>         Search your saved memories",
+            "  /memory list [--agent a] [--author u] [--since 7d]  Recent memories with ids",
+            "  /memory show <id>       Show one memory in full",
+            "  /memory forget <id|text> Delete a wrong or stale memory",
+            "  /memory pin|unpin <id>  Always (or no longer) inject a memory",
+            "  /memory edit <id> [text] Correct a memory's outcome",
+            "  /memory export <path>   Back up memory (--format jsonl|md, --workspace w)",
+            "  /memory import <path>   Load an export (--dry-run, --overwrite, --workspace w)",
+            "  /memory compact         Merge near-duplicate memories (--dry-run to preview)",
             "  /thread                 Show current conversation info",
             "  /thread clear           Start a fresh conversation",
+            "  /thread list            List named conversation threads",
+            "  /thread new <name>      Start a new thread, keeping the current one",
+            "  /thread switch <name>   Resume another thread (and its persona)",
+            "  /thread rename [old] <new> Rename a thread (the active one by default)",
+            "  /thread delete <name>   Delete a thread other than the active one",
+            "  /thread export <path>   Save the thread with tool calls (--format md|json|html, --logs)",
+            "  /attach <path>          Attach a workspace file or image to your next message",
+            "  /attach [list|clear]    Show or drop pending attachments",
+            "  @path/to/file           Attach a file inline while typing",
             "",
             "Personas",
             "  /agent                  See available personas",
@@ -88,12 +111,17 @@ impl App {
             "  /agent create <n> <d>   Create a custom persona",
             "  /agent delete <name>    Remove a custom persona",
             "  /agent info             Current persona details",
+            "  /agent set <f> <v>      Persona setting: model, fallback, reasoning, temperature, tool_loops, hosted, tools, mcp, skills",
+            "  /agent export <n> [p]   Write a persona to Markdown (default ./<name>.md)",
+            "  /agent import <p|url>   Import a persona Markdown file",
+            "  /agent reload           Re-sync personas with ~/Memini/personas",
             "",
             "Autopilot (Background Tasks)",
             "  /auto                   See available background tasks",
             "  /auto run <name>        Run a task right now",
             "  /auto start <name>      Start a recurring task",
             "  /auto stop <name>       Stop a running task",
+            "  /auto resume [name]     Restart tasks paused by a budget",
             "  /auto create <n> <s> <p> Create + start recipe task (saved to ~/Memini/agents)",
             "  /auto add <n> <s> <p>   Alias for /auto create",
             "  /auto templates         List built-in recipe templates",
@@ -103,6 +131,7 @@ impl App {
             "  /auto remove <name>     Remove a task",
             "  /auto results [name]    See recent task outputs",
             "  recipe triggers         front matter: trigger_events + trigger_variables",
+            "  recipe budgets          front matter: budget: 50k | $2.50 (daily, pauses task)",
             "",
             "Agents (Multi-Instance)",
             "  /spawn <prompt>         Spin up a live agent window",
@@ -129,8 +158,13 @@ impl App {
             "",
             "Shared Workspaces (Team Memory)",
             "  /share                  Show current workspace status",
-            "  /share join <name>      Join a shared workspace (team members use same name)",
+            "  /share create <name> [--secret <s>] Create a shared workspace (you become admin)",
+            "  /share join <name> [token] [--secret <s>] Join a shared workspace",
             "  /share leave            Return to your private memory",
+            "  /share members          List members, roles and pending invites",
+            "  /share invite [role]    Create a single-use invite (reader|writer|admin)",
+            "  /share role <user> <r>  Change a member's role (admins only)",
+            "  /share remove <user>    Remove a member (admins only)",
             "",
             "Skills (Agent Workflows)",
             "  /skills                 List imported skills",
@@ -144,9 +178,16 @@ impl App {
             "  /model list             Show model picking guidance",
             "  /model set <name>       Set active model",
             "  /model thinking <mode>  Set thinking: on|off|low|medium|high",
+            "  /model fallback <a,b>   Fallback models on failure (clear to disable)",
+            "  /model chain <on|off>   Chain requests with previous_response_id",
             "  /key <key>              Quick set OpenAI key",
+            "  /usage                  Token + cost totals (turn, windows, recipes, days)",
+            "  /usage budget <b|off>   Global daily budget (50k tokens or $2.50)",
+            "  /usage price <m> <i> <o> Override model price (USD per 1M tokens)",
+            "  /usage prices           Show the price table",
             "  /rice                   Show Rice memory connection status",
             "  /rice setup             Interactive Rice environment wizard",
+            "  /rice migrate           Copy offline local memory into Rice",
             "  /clear                  Clear the screen",
             "  /quit                   Exit Memini by AG\\I",
         ];
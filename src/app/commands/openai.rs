@@ -7,11 +7,12 @@ use anyhow::{Context, Result};
 use serde_json::Value;
 
 use crate::constants::{
-    ACTIVE_MCP_VAR, OPENAI_KEY_VAR, OPENAI_MODEL_VAR, OPENAI_REASONING_EFFORT_VAR,
+    ACTIVE_MCP_VAR, OPENAI_CHAIN_RESPONSES_VAR, OPENAI_FALLBACK_MODELS_VAR, OPENAI_KEY_VAR,
+    OPENAI_MODEL_VAR, OPENAI_REASONING_EFFORT_VAR,
 };
 use crate::mcp::config::McpServer;
-use crate::openai::parse_reasoning_setting;
-use crate::rice::RiceStatus;
+use crate::openai::{parse_model_list, parse_reasoning_setting, parse_toggle};
+use crate::rice::{RicePool, RiceStatus};
 
 use super::super::App;
 use super::super::log_src;
@@ -130,6 +131,31 @@ impl App {
                     );
                 }
             }
+            "fallback" | "fallbacks" => {
+                if args.len() > 1 {
+                    self.persist_fallback_models(&args[1..].join(" "));
+                } else {
+                    self.show_model_status();
+                    self.log(
+                        LogLevel::Info,
+                        "Usage: /model fallback <model,model,...|clear>".to_string(),
+                    );
+                }
+            }
+            "chain" => match args.get(1).and_then(|raw| parse_toggle(raw)) {
+                Some(enabled) => self.persist_chain_setting(enabled),
+                None => self.log(
+                    LogLevel::Info,
+                    format!(
+                        "Response chaining: {}. Usage: /model chain <on|off>",
+                        if self.openai.chain_responses {
+                            "on"
+                        } else {
+                            "off"
+                        }
+                    ),
+                ),
+            },
             "help" => self.show_model_help(),
             maybe_model => {
                 // Shortcut: `/model gpt-5-mini`
@@ -152,9 +178,38 @@ impl App {
                 self.openai.model.as_str()
             ),
         );
+        if !self.openai.fallback_models.is_empty() {
+            self.log(
+                LogLevel::Info,
+                format!("Fallback: {}", self.openai.fallback_models.join(" → ")),
+            );
+        }
+        if self.openai.chain_responses {
+            self.log(
+                LogLevel::Info,
+                "Response chaining: on (previous_response_id)".to_string(),
+            );
+        }
+        let client = self.active_agent.apply_to(&self.openai);
+        if client.model != self.openai.model
+            || client.reasoning_effort != self.openai.reasoning_effort
+            || client.temperature.is_some()
+            || client.fallback_models != self.openai.fallback_models
+        {
+            let thinking = client.reasoning_effort.as_deref().unwrap_or("off");
+            self.log(
+                LogLevel::Info,
+                format!(
+                    "Persona '{}' overrides: {} | Thinking: {thinking}",
+                    self.active_agent.name,
+                    client.model_chain().join(" → ")
+                ),
+            );
+        }
         self.log(
             LogLevel::Info,
-            "Use /model list for guidance, /model set <name>, /model thinking <mode>.".to_string(),
+            "Use /model list for guidance, /model set <name>, /model thinking <mode>, /model fallback <models>."
+                .to_string(),
         );
     }
 
@@ -198,12 +253,76 @@ impl App {
             LogLevel::Info,
             "  /model thinking <on|off|low|medium|high>".to_string(),
         );
+        self.log(
+            LogLevel::Info,
+            "  /model fallback <model,model,...|clear>".to_string(),
+        );
+        self.log(LogLevel::Info, "  /model chain <on|off>".to_string());
         self.log(
             LogLevel::Info,
             "  /model <name>   (shortcut for set)".to_string(),
         );
     }
 
+    fn persist_chain_setting(&mut self, enabled: bool) {
+        if let Err(err) = self.runtime.block_on(self.rice.set_variable(
+            OPENAI_CHAIN_RESPONSES_VAR,
+            Value::String(if enabled { "on" } else { "off" }.to_string()),
+            "explicit",
+        )) {
+            log_src!(
+                self,
+                LogLevel::Error,
+                format!("Failed to store chaining setting: {err:#}")
+            );
+            return;
+        }
+        self.openai.chain_responses = enabled;
+        let message = if enabled {
+            "Response chaining on: tool loops and /reply continuations send only new input."
+        } else {
+            "Response chaining off: every request resends the full input."
+        };
+        self.log(LogLevel::Info, message.to_string());
+    }
+
+    fn persist_fallback_models(&mut self, raw: &str) {
+        let models = if matches!(raw.trim(), "clear" | "off" | "none") {
+            Vec::new()
+        } else {
+            parse_model_list(raw)
+        };
+
+        let result = if models.is_empty() {
+            self.runtime
+                .block_on(self.rice.delete_variable(OPENAI_FALLBACK_MODELS_VAR))
+        } else {
+            self.runtime.block_on(self.rice.set_variable(
+                OPENAI_FALLBACK_MODELS_VAR,
+                Value::String(models.join(",")),
+                "explicit",
+            ))
+        };
+        if let Err(err) = result {
+            log_src!(
+                self,
+                LogLevel::Error,
+                format!("Failed to store fallback models: {err:#}")
+            );
+            return;
+        }
+
+        self.openai.fallback_models = models;
+        if self.openai.fallback_models.is_empty() {
+            self.log(LogLevel::Info, "Model fallback disabled.".to_string());
+        } else {
+            self.log(
+                LogLevel::Info,
+                format!("Fallback chain: {}", self.openai.model_chain().join(" → ")),
+            );
+        }
+    }
+
     fn persist_openai_model(&mut self, model: &str) {
         let model = model.trim();
         if model.is_empty() {
@@ -293,14 +412,39 @@ impl App {
             self.start_rice_setup();
             return;
         }
+        if !args.is_empty() && args[0] == "migrate" {
+            self.migrate_local_memory();
+            return;
+        }
 
-        match &self.rice.status {
+        match self.rice.status.clone() {
             RiceStatus::Connected => {
                 self.log(LogLevel::Info, "🟢 Rice is connected.".to_string());
                 self.log(
                     LogLevel::Info,
                     format!("   Run ID: {}", self.rice.active_run_id()),
                 );
+                let (open, size) = RicePool::shared().stats();
+                self.log(
+                    LogLevel::Info,
+                    format!("   Connections: {open}/{size} open (shared by all tasks)"),
+                );
+            }
+            RiceStatus::Local(dir) => {
+                self.log(
+                    LogLevel::Info,
+                    "🟡 Rice is not configured -- using local memory.".to_string(),
+                );
+                self.log(LogLevel::Info, format!("   Stored in: {dir}"));
+                self.log(
+                    LogLevel::Info,
+                    format!("   Run ID: {}", self.rice.active_run_id()),
+                );
+                self.log(
+                    LogLevel::Info,
+                    "Run /rice setup to connect, then /rice migrate to copy it into Rice."
+                        .to_string(),
+                );
             }
             RiceStatus::Disabled(reason) => {
                 log_src!(self, LogLevel::Warn, format!("Rice disabled: {reason}"));
@@ -310,6 +454,43 @@ impl App {
                 );
             }
         };
+        let queued = crate::rice::outbox_depth();
+        if queued > 0 {
+            self.log(
+                LogLevel::Info,
+                format!("   Outbox: {queued} write(s) queued, replayed once Rice is reachable"),
+            );
+        }
+    }
+
+    /// `/rice migrate` -- copy the local offline store into Rice.
+    fn migrate_local_memory(&mut self) {
+        if !matches!(self.rice.status, RiceStatus::Connected) {
+            log_src!(
+                self,
+                LogLevel::Warn,
+                "Connect to Rice first (/rice setup), then run /rice migrate.".to_string()
+            );
+            return;
+        }
+        match self.runtime.block_on(self.rice.migrate_local_memory()) {
+            Ok(Some((variables, traces, archived))) => {
+                self.log(
+                    LogLevel::Info,
+                    format!("Migrated {variables} variable(s) and {traces} trace(s) into Rice."),
+                );
+                self.log(
+                    LogLevel::Info,
+                    format!("Local store moved to {}", archived.display()),
+                );
+            }
+            Ok(None) => {
+                self.log(LogLevel::Info, "No local memory to migrate.".to_string());
+            }
+            Err(err) => {
+                log_src!(self, LogLevel::Error, format!("Migration failed: {err:#}"));
+            }
+        }
     }
 }
 
@@ -367,6 +548,27 @@ impl App {
             }
         }
 
+        let fallback_value = self
+            .runtime
+            .block_on(self.rice.get_variable(OPENAI_FALLBACK_MODELS_VAR))?;
+        if let Some(Value::String(raw)) = fallback_value {
+            let models = parse_model_list(&raw);
+            if !models.is_empty() {
+                self.openai.fallback_models = models;
+            }
+        }
+
+        let chain_value = self
+            .runtime
+            .block_on(self.rice.get_variable(OPENAI_CHAIN_RESPONSES_VAR))?;
+        if let Some(enabled) = chain_value
+            .as_ref()
+            .and_then(Value::as_str)
+            .and_then(parse_toggle)
+        {
+            self.openai.chain_responses = enabled;
+        }
+
         let thinking_value = self
             .runtime
             .block_on(self.rice.get_variable(OPENAI_REASONING_EFFORT_VAR))?;
@@ -1,11 +1,15 @@
 //! `/share` command handlers — shared workspace (team memory) management.
 
-use crate::rice::RiceStatus;
+use crate::constants::WORKSPACE_INVITE_TTL_HOURS;
+use crate::rice::{Author, JoinKind, JoinMode, RiceStatus, Role};
 
 use super::super::App;
 use super::super::log_src;
 use super::super::logging::LogLevel;
 
+const SHARE_USAGE: &str = "Usage: /share [status] | create <name> [--secret <s>] \
+     | join <name> [invite-token] [--secret <s>] | leave | members | invite [reader|writer|admin] | role <user> <role> | remove <user>";
+
 impl App {
     pub(crate) fn handle_share_command(&mut self, args: Vec<&str>) {
         if args.is_empty() {
@@ -13,30 +17,73 @@ impl App {
             return;
         }
         match args[0] {
-            "join" => {
-                if let Some(name) = args.get(1) {
-                    self.join_shared_workspace(name);
-                } else {
-                    log_src!(
-                        self,
-                        LogLevel::Warn,
-                        "Usage: /share join <workspace-name>".to_string()
-                    );
-                }
-            }
+            "create" => self.parse_share_join(JoinMode::Create, &args[1..]),
+            "join" => self.parse_share_join(JoinMode::Join, &args[1..]),
             "leave" => self.leave_shared_workspace(),
             "status" => self.show_share_status(),
+            "members" => self.show_share_members(),
+            "invite" => self.create_share_invite(args.get(1).copied()),
+            "role" => match (args.get(1), args.get(2).and_then(|raw| Role::parse(raw))) {
+                (Some(user), Some(role)) => self.set_share_role(user, role),
+                _ => log_src!(
+                    self,
+                    LogLevel::Warn,
+                    "Usage: /share role <user> <reader|writer|admin>".to_string()
+                ),
+            },
+            "remove" => match args.get(1) {
+                Some(user) => self.remove_share_member(user),
+                None => log_src!(
+                    self,
+                    LogLevel::Warn,
+                    "Usage: /share remove <user>".to_string()
+                ),
+            },
             other => {
                 log_src!(
                     self,
                     LogLevel::Warn,
                     format!("Unknown /share command: {other}")
                 );
+                self.log(LogLevel::Info, SHARE_USAGE.to_string());
             }
         }
     }
 
+    fn parse_share_join(&mut self, mode: JoinMode, args: &[&str]) {
+        let mut name = None;
+        let mut invite = None;
+        let mut secret = None;
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match *arg {
+                "--secret" => secret = iter.next().copied(),
+                "--invite" => invite = iter.next().copied(),
+                value if name.is_none() => name = Some(value),
+                value if invite.is_none() && mode == JoinMode::Join => invite = Some(value),
+                _ => {
+                    name = None;
+                    break;
+                }
+            }
+        }
+        match (name, mode) {
+            (Some(name), _) => self.join_shared_workspace(name, mode, invite, secret),
+            (None, JoinMode::Create) => log_src!(
+                self,
+                LogLevel::Warn,
+                "Usage: /share create <name> [--secret <shared secret>]".to_string()
+            ),
+            (None, _) => log_src!(
+                self,
+                LogLevel::Warn,
+                "Usage: /share join <name> [invite-token] [--secret <shared secret>]".to_string()
+            ),
+        }
+    }
+
     fn show_share_status(&mut self) {
+        let author = Author::current().label();
         match &self.rice.shared_run_id {
             Some(name) => {
                 self.log(
@@ -45,6 +92,26 @@ impl App {
                         "Shared workspace: {name} (all memory is shared with anyone on this workspace)"
                     ),
                 );
+                if let Some(role) = self.rice.workspace_role() {
+                    self.log(
+                        LogLevel::Info,
+                        format!(
+                            "Your role: {}{}",
+                            role.label(),
+                            if self.rice.workspace_encrypted() {
+                                " -- memories are encrypted"
+                            } else {
+                                ""
+                            }
+                        ),
+                    );
+                }
+                self.log(
+                    LogLevel::Info,
+                    format!(
+                        "Your memories are attributed to {author} (set MEMINI_USER to change)."
+                    ),
+                );
                 self.log(
                     LogLevel::Info,
                     "Use /share leave to return to your private memory.".to_string(),
@@ -57,13 +124,19 @@ impl App {
                 );
                 self.log(
                     LogLevel::Info,
-                    "Use /share join <name> to join a shared workspace with your team.".to_string(),
+                    "Use /share create <name> to start a shared workspace, or /share join <name> <token> to join one.".to_string(),
                 );
             }
         }
     }
 
-    fn join_shared_workspace(&mut self, name: &str) {
+    fn join_shared_workspace(
+        &mut self,
+        name: &str,
+        mode: JoinMode,
+        invite: Option<&str>,
+        secret: Option<&str>,
+    ) {
         if matches!(&self.rice.status, RiceStatus::Disabled(_)) {
             log_src!(
                 self,
@@ -73,11 +146,25 @@ impl App {
             return;
         }
 
+        let join = match self
+            .runtime
+            .block_on(self.rice.open_workspace(name, mode, invite, secret))
+        {
+            Ok(join) => join,
+            Err(err) => {
+                log_src!(
+                    self,
+                    LogLevel::Warn,
+                    format!("Could not join {name}: {err:#}")
+                );
+                return;
+            }
+        };
+
         // Clear the local conversation thread since it belongs to the
         // old workspace context.
         self.conversation_thread.clear();
-
-        self.rice.join_workspace(name);
+        self.thread_transcript.clear();
 
         // Persist the choice so it's restored on next launch.
         if let Err(err) = self.runtime.block_on(self.rice.save_shared_workspace()) {
@@ -88,24 +175,55 @@ impl App {
             );
         }
 
-        self.log(LogLevel::Info, format!("Joined shared workspace: {name}"));
         self.log(
             LogLevel::Info,
-            "Memories you create are now visible to everyone in this workspace.".to_string(),
+            format!(
+                "Joined shared workspace: {name} as {}{}",
+                join.role.label(),
+                match join.kind {
+                    JoinKind::Created => " (new workspace -- invite others with /share invite)",
+                    JoinKind::Invited => " (invite accepted)",
+                    JoinKind::Member | JoinKind::Unmanaged => "",
+                }
+            ),
         );
+        if join.encrypted {
+            self.log(
+                LogLevel::Info,
+                "Memory content in this workspace is encrypted with its shared secret.".to_string(),
+            );
+        } else if secret.is_some() {
+            log_src!(
+                self,
+                LogLevel::Warn,
+                "This workspace was created without encryption; --secret was ignored.".to_string()
+            );
+        }
+        if join.role.can_write() {
+            self.log(
+                LogLevel::Info,
+                "Memories you create are now visible to everyone in this workspace.".to_string(),
+            );
+        } else {
+            self.log(
+                LogLevel::Info,
+                "You have read-only access: recall works, new memories are not saved.".to_string(),
+            );
+        }
         self.log(
             LogLevel::Info,
             "Memories from other members will appear when relevant to your questions.".to_string(),
         );
 
         // Try to load the shared conversation thread.
-        match self.runtime.block_on(self.rice.load_thread()) {
-            Ok(thread) if !thread.is_empty() => {
-                let turns = thread.len() / 2;
-                self.conversation_thread = thread;
+        match self.load_active_thread() {
+            Ok(turns) if turns > 0 => {
                 self.log(
                     LogLevel::Info,
-                    format!("Picked up {turns} shared conversation turn(s)."),
+                    format!(
+                        "Picked up {turns} shared conversation turn(s) in '{}'.",
+                        self.active_thread
+                    ),
                 );
             }
             _ => {}
@@ -124,6 +242,7 @@ impl App {
 
         let old_name = self.rice.shared_run_id.clone().unwrap_or_default();
         self.conversation_thread.clear();
+        self.thread_transcript.clear();
         self.rice.leave_workspace();
 
         if let Err(err) = self.runtime.block_on(self.rice.save_shared_workspace()) {
@@ -141,17 +260,137 @@ impl App {
         );
 
         // Restore personal conversation thread.
-        match self.runtime.block_on(self.rice.load_thread()) {
-            Ok(thread) if !thread.is_empty() => {
-                let turns = thread.len() / 2;
-                self.conversation_thread = thread;
+        match self.load_active_thread() {
+            Ok(turns) if turns > 0 => {
                 self.log(
                     LogLevel::Info,
-                    format!("Restored {turns} personal conversation turn(s)."),
+                    format!(
+                        "Restored {turns} personal conversation turn(s) in '{}'.",
+                        self.active_thread
+                    ),
                 );
             }
             _ => {}
         }
         self.restart_rice_trigger_listener();
     }
+
+    fn show_share_members(&mut self) {
+        let manifest = match self.runtime.block_on(self.rice.workspace_manifest()) {
+            Ok(Some(manifest)) => manifest,
+            Ok(None) => {
+                self.log(
+                    LogLevel::Info,
+                    "Not in a shared workspace with members. Use /share join <name>.".to_string(),
+                );
+                return;
+            }
+            Err(err) => {
+                log_src!(
+                    self,
+                    LogLevel::Warn,
+                    format!("Could not load members: {err:#}")
+                );
+                return;
+            }
+        };
+        let me = Author::current().user;
+        self.log(
+            LogLevel::Info,
+            format!(
+                "{} member(s) of {}{}:",
+                manifest.members.len(),
+                manifest.name,
+                if manifest.is_encrypted() {
+                    " (encrypted)"
+                } else {
+                    ""
+                }
+            ),
+        );
+        for member in &manifest.members {
+            let added = member.added_at.get(..10).unwrap_or(&member.added_at);
+            self.log(
+                LogLevel::Info,
+                format!(
+                    "  {:<16} {:<7} added {added} by {}{}",
+                    member.user,
+                    member.role.label(),
+                    member.added_by,
+                    if member.user.eq_ignore_ascii_case(&me) {
+                        "  (you)"
+                    } else {
+                        ""
+                    }
+                ),
+            );
+        }
+        let invites = manifest.pending_invites();
+        if invites > 0 {
+            self.log(
+                LogLevel::Info,
+                format!("  {invites} unused invite(s) outstanding."),
+            );
+        }
+    }
+
+    fn create_share_invite(&mut self, role: Option<&str>) {
+        let role = match role.map(Role::parse) {
+            None => Role::Writer,
+            Some(Some(role)) => role,
+            Some(None) => {
+                log_src!(
+                    self,
+                    LogLevel::Warn,
+                    "Usage: /share invite [reader|writer|admin]".to_string()
+                );
+                return;
+            }
+        };
+        match self.runtime.block_on(self.rice.create_invite(role)) {
+            Ok(token) => {
+                let name = self.rice.shared_run_id.clone().unwrap_or_default();
+                self.log(
+                    LogLevel::Info,
+                    format!(
+                        "Invite for one {} (valid {WORKSPACE_INVITE_TTL_HOURS}h, single use):",
+                        role.label()
+                    ),
+                );
+                let secret_hint = if self.rice.workspace_encrypted() {
+                    " --secret <shared secret>"
+                } else {
+                    ""
+                };
+                self.log(
+                    LogLevel::Info,
+                    format!("  /share join {name} {token}{secret_hint}"),
+                );
+            }
+            Err(err) => {
+                log_src!(self, LogLevel::Warn, format!("Invite failed: {err:#}"));
+            }
+        }
+    }
+
+    fn set_share_role(&mut self, user: &str, role: Role) {
+        match self.runtime.block_on(self.rice.set_member_role(user, role)) {
+            Ok(()) => self.log(LogLevel::Info, format!("{user} is now a {}.", role.label())),
+            Err(err) => {
+                log_src!(self, LogLevel::Warn, format!("Role change failed: {err:#}"));
+            }
+        }
+    }
+
+    fn remove_share_member(&mut self, user: &str) {
+        match self.runtime.block_on(self.rice.remove_member(user)) {
+            Ok(()) => self.log(
+                LogLevel::Info,
+                format!("Removed {user} from the workspace."),
+            ),
+            Err(err) => {
+                log_src!(self, LogLevel::Warn, format!("Remove failed: {err:#}"));
+            }
+        }
+    }
 }
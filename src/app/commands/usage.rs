@@ -0,0 +1,408 @@
+//! `/usage` command handler, plus usage recording and budget enforcement
+//! for background recipes.
+
+use anyhow::Result;
+
+use std::time::Instant;
+
+use crate::constants::USAGE_LEDGER_VAR;
+use crate::openai::{OpenAiClient, TokenUsage};
+
+use super::super::App;
+use super::super::agent_recipes;
+use super::super::daemon::DaemonTaskDef;
+use super::super::log_src;
+use super::super::logging::LogLevel;
+use super::super::usage::{self, Budget, ModelPrice, UsageLedger, UsageScope};
+
+// ── /usage ───────────────────────────────────────────────────────────
+
+impl App {
+    pub(crate) fn handle_usage_command(&mut self, args: Vec<&str>) {
+        if args.is_empty() {
+            self.show_usage();
+            return;
+        }
+
+        match args[0] {
+            "budget" => match args.get(1..) {
+                Some(rest) if !rest.is_empty() => self.set_global_budget(&rest.join(" ")),
+                _ => self.show_global_budget(),
+            },
+            "price" => {
+                if args.len() == 4 {
+                    self.set_model_price(args[1], args[2], args[3]);
+                } else {
+                    log_src!(
+                        self,
+                        LogLevel::Warn,
+                        "Usage: /usage price <model> <input $/1M> <output $/1M>".to_string()
+                    );
+                }
+            }
+            "prices" => self.show_price_table(),
+            other => {
+                log_src!(
+                    self,
+                    LogLevel::Warn,
+                    format!("Unknown /usage command: {other}")
+                );
+            }
+        }
+    }
+
+    fn show_usage(&mut self) {
+        let today = self.usage.today();
+        self.log(LogLevel::Info, format!("Today: {}", today.summary()));
+        self.show_global_budget();
+        if self.usage.last_turn.requests > 0 {
+            let line = format!("Last chat turn: {}", self.usage.last_turn.summary());
+            self.log(LogLevel::Info, line);
+        }
+        if self.usage.session.requests > 0 {
+            let line = format!(
+                "This session (since launch, not saved): {}",
+                self.usage.session.summary()
+            );
+            self.log(LogLevel::Info, line);
+        }
+
+        let recipe_budgets: Vec<(String, Option<Budget>)> = {
+            let mut budgets: Vec<(String, Option<Budget>)> = self
+                .daemon_handles
+                .iter()
+                .map(|handle| (handle.def.name.clone(), handle.def.budget))
+                .collect();
+            for recipe in agent_recipes::load_agent_recipes().unwrap_or_default() {
+                if !budgets.iter().any(|(name, _)| *name == recipe.name) {
+                    budgets.push((recipe.name, recipe.budget));
+                }
+            }
+            budgets
+        };
+        let mut recipe_lines = Vec::new();
+        for name in self.usage.recipes.keys() {
+            let totals = self.usage.recipe_today(name);
+            if totals.requests == 0 {
+                continue;
+            }
+            let budget = recipe_budgets
+                .iter()
+                .find(|(recipe, _)| recipe == name)
+                .and_then(|(_, budget)| *budget)
+                .map(|budget| {
+                    format!(
+                        " [budget {}, {:.0}% used]",
+                        budget.label(),
+                        budget.fraction_used(&totals) * 100.0
+                    )
+                })
+                .unwrap_or_default();
+            recipe_lines.push(format!("  {name} -- {}{budget}", totals.summary()));
+        }
+        if !recipe_lines.is_empty() {
+            self.log(LogLevel::Info, "Recipes today:".to_string());
+            for line in recipe_lines {
+                self.log(LogLevel::Info, line);
+            }
+        }
+
+        let window_lines: Vec<String> = self
+            .usage
+            .windows
+            .iter()
+            .map(|(id, totals)| {
+                let label = self
+                    .agent_windows
+                    .iter()
+                    .find(|w| w.id == *id)
+                    .map(|w| w.label.clone())
+                    .unwrap_or_else(|| format!("agent-{id}"));
+                format!("  #{id} {label} -- {}", totals.summary())
+            })
+            .collect();
+        let windows_today = self.usage.windows_today();
+        if windows_today.requests > 0 {
+            let line = format!("Agent windows today: {}", windows_today.summary());
+            self.log(LogLevel::Info, line);
+        }
+        if !window_lines.is_empty() {
+            self.log(
+                LogLevel::Info,
+                "Per window (this session only, not saved):".to_string(),
+            );
+            for line in window_lines {
+                self.log(LogLevel::Info, line);
+            }
+        }
+
+        let history: Vec<String> = self
+            .usage
+            .days
+            .iter()
+            .rev()
+            .skip(1)
+            .take(6)
+            .map(|(day, totals)| format!("  {day} -- {}", totals.summary()))
+            .collect();
+        if !history.is_empty() {
+            self.log(LogLevel::Info, "Previous days:".to_string());
+            for line in history {
+                self.log(LogLevel::Info, line);
+            }
+        }
+    }
+
+    fn show_global_budget(&mut self) {
+        match self.usage.global_budget() {
+            Some(budget) => {
+                let used = budget.fraction_used(&self.usage.today()) * 100.0;
+                self.log(
+                    LogLevel::Info,
+                    format!("Global budget: {} ({used:.0}% used today).", budget.label()),
+                );
+            }
+            None => self.log(
+                LogLevel::Info,
+                "Global budget: none. Set one with /usage budget <50k|$2.50>.".to_string(),
+            ),
+        }
+    }
+
+    fn set_global_budget(&mut self, raw: &str) {
+        if matches!(raw.trim(), "off" | "none" | "clear") {
+            self.usage.budget = None;
+            self.usage.dirty = true;
+            self.budget_gate.refresh(&self.usage);
+            self.log(LogLevel::Info, "Global budget cleared.".to_string());
+            return;
+        }
+        let Some(budget) = Budget::parse(raw) else {
+            log_src!(
+                self,
+                LogLevel::Warn,
+                format!("Invalid budget '{raw}'. Use tokens (50000, 50k) or dollars ($2.50).")
+            );
+            return;
+        };
+        self.usage.budget = Some(budget);
+        self.usage.dirty = true;
+        self.budget_gate.refresh(&self.usage);
+        self.log(
+            LogLevel::Info,
+            format!("Global budget set to {}.", budget.label()),
+        );
+    }
+
+    fn set_model_price(&mut self, model: &str, input: &str, output: &str) {
+        let (Ok(input), Ok(output)) = (input.parse::<f64>(), output.parse::<f64>()) else {
+            log_src!(
+                self,
+                LogLevel::Warn,
+                "Prices must be numbers (USD per 1M tokens).".to_string()
+            );
+            return;
+        };
+        let model = model.trim().to_ascii_lowercase();
+        self.usage.prices.insert(
+            model.clone(),
+            ModelPrice {
+                input_per_mtok: input,
+                output_per_mtok: output,
+            },
+        );
+        self.usage.dirty = true;
+        self.log(
+            LogLevel::Info,
+            format!("Price for '{model}' set to ${input}/1M input, ${output}/1M output."),
+        );
+    }
+
+    fn show_price_table(&mut self) {
+        self.log(
+            LogLevel::Info,
+            "Model prices (USD per 1M tokens, prefix match):".to_string(),
+        );
+        for (model, price, source) in self.usage.price_table() {
+            self.log(
+                LogLevel::Info,
+                format!(
+                    "  {model} -- in ${} / out ${} [{source}]",
+                    price.input_per_mtok, price.output_per_mtok
+                ),
+            );
+        }
+    }
+}
+
+// ── Recording & budgets ──────────────────────────────────────────────
+
+impl App {
+    /// Restore the persisted usage ledger from Rice.
+    pub(crate) fn load_usage_from_rice(&mut self) -> Result<()> {
+        let value = self
+            .runtime
+            .block_on(self.rice.get_variable(USAGE_LEDGER_VAR))?;
+        self.usage = UsageLedger::from_persisted(value);
+        Ok(())
+    }
+
+    /// Save the ledger to Rice (best-effort).  Called at most every
+    /// `USAGE_SAVE_INTERVAL_SECS` while busy, and on exit.
+    pub(crate) fn persist_usage(&mut self) {
+        self.usage.dirty = false;
+        self.usage_saved_at = Instant::now();
+        let Ok(value) = serde_json::to_value(&self.usage) else {
+            return;
+        };
+        let _ = self
+            .runtime
+            .block_on(self.rice.set_variable(USAGE_LEDGER_VAR, value, "usage"));
+    }
+
+    /// A clone of `openai` for a background run in `scope` that stops
+    /// before any request once its budget is spent.
+    pub(crate) fn budget_gated(&self, openai: &OpenAiClient, scope: UsageScope) -> OpenAiClient {
+        if let UsageScope::Recipe(name) = &scope
+            && let Some(budget) = self.recipe_budget(name)
+        {
+            self.budget_gate.watch(name, budget);
+        }
+        self.budget_gate.refresh(&self.usage);
+        openai.with_request_gate(self.budget_gate.request_gate(scope))
+    }
+
+    fn recipe_budget(&self, name: &str) -> Option<Budget> {
+        self.daemon_handles
+            .iter()
+            .map(|handle| &handle.def)
+            .chain(self.budget_paused.iter())
+            .find(|def| def.name == name)
+            .and_then(|def| def.budget)
+            .or_else(|| self.budget_gate.budget(name))
+            .or_else(|| {
+                agent_recipes::load_agent_recipes()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .find(|recipe| recipe.name == name)
+                    .and_then(|recipe| recipe.budget)
+            })
+    }
+
+    /// Fold one response's usage into the ledger and enforce budgets.
+    pub(crate) fn record_usage(&mut self, scope: UsageScope, model: &str, tokens: &TokenUsage) {
+        self.usage.record(&scope, model, tokens);
+        self.budget_gate.refresh(&self.usage);
+
+        if let UsageScope::Recipe(name) = &scope
+            && let Some(budget) = self.budget_gate.budget(name)
+        {
+            let spent = self.usage.recipe_today(name);
+            if budget.exceeded_by(&spent) {
+                let reason = format!(
+                    "daily budget {} reached ({} tokens, {} today)",
+                    budget.label(),
+                    usage::format_tokens(spent.tokens.total()),
+                    usage::format_usd(spent.cost_usd)
+                );
+                self.pause_daemon_for_budget(&name.clone(), &reason);
+            }
+        }
+
+        if self.usage.global_budget_exceeded() {
+            let today = usage::today();
+            if self.usage.budget_warned_on.as_deref() != Some(today.as_str()) {
+                self.usage.budget_warned_on = Some(today);
+                let label = self
+                    .usage
+                    .global_budget()
+                    .map(|budget| budget.label())
+                    .unwrap_or_default();
+                log_src!(
+                    self,
+                    LogLevel::Warn,
+                    format!(
+                        "⚠ Global budget {label} reached. Background tasks are paused until tomorrow (or raise it with /usage budget)."
+                    )
+                );
+            }
+            let names: Vec<String> = self
+                .daemon_handles
+                .iter()
+                .map(|handle| handle.def.name.clone())
+                .collect();
+            for name in names {
+                self.pause_daemon_for_budget(&name, "global daily budget reached");
+            }
+        }
+    }
+
+    /// Why a daemon task may not run right now, if it is over budget.
+    pub(crate) fn daemon_budget_block(&self, def: &DaemonTaskDef) -> Option<String> {
+        if self.usage.global_budget_exceeded() {
+            return Some("global daily budget reached (see /usage)".to_string());
+        }
+        let budget = def.budget?;
+        if budget.exceeded_by(&self.usage.recipe_today(&def.name)) {
+            return Some(format!(
+                "daily budget {} reached (see /usage)",
+                budget.label()
+            ));
+        }
+        None
+    }
+
+    /// Stop a running task and keep its definition for `/daemon resume`.
+    fn pause_daemon_for_budget(&mut self, name: &str, reason: &str) {
+        let Some(pos) = self
+            .daemon_handles
+            .iter()
+            .position(|handle| handle.def.name == name)
+        else {
+            return;
+        };
+        let handle = self.daemon_handles.remove(pos);
+        handle.abort.abort();
+        log_src!(
+            self,
+            LogLevel::Warn,
+            format!(
+                "⚠ Paused task '{}': {reason}. Resume with /daemon resume {} once budget allows.",
+                handle.def.name, handle.def.name
+            )
+        );
+        self.budget_paused.retain(|def| def.name != name);
+        self.budget_paused.push(handle.def);
+    }
+
+    /// `/daemon resume [name]`: restart tasks paused by a budget, all of
+    /// them without a name.  Tasks still over budget stay paused.
+    pub(crate) fn resume_budget_paused(&mut self, name: Option<&str>) {
+        let (resume, keep): (Vec<DaemonTaskDef>, Vec<DaemonTaskDef>) =
+            std::mem::take(&mut self.budget_paused)
+                .into_iter()
+                .partition(|def| name.is_none_or(|name| def.name.eq_ignore_ascii_case(name)));
+        self.budget_paused = keep;
+        if resume.is_empty() {
+            let message = match name {
+                Some(name) => format!("Task '{name}' is not paused by a budget."),
+                None => "No tasks are paused by a budget.".to_string(),
+            };
+            log_src!(self, LogLevel::Warn, message);
+            return;
+        }
+        self.budget_gate.refresh(&self.usage);
+        for def in resume {
+            if let Some(reason) = self.daemon_budget_block(&def) {
+                log_src!(
+                    self,
+                    LogLevel::Warn,
+                    format!("Task '{}' is still paused: {reason}", def.name)
+                );
+                self.budget_paused.push(def);
+                continue;
+            }
+            self.spawn_daemon_task(def);
+        }
+    }
+}
@@ -0,0 +1,364 @@
+//! Coordination groups: the manifest behind `collect_results`.
+//!
+//! Every `spawn_agent` call with a `coordination_key` adds the new window
+//! to the `coordination_group:<key>` variable.  Each member records its
+//! status, start and finish times, and the `agent_result:<key>:<id>`
+//! variable its output lands in, so `collect_results` can tell finished
+//! agents from running ones (and an unknown key from an empty group)
+//! without probing window ids.
+//!
+//! Window ids restart at 1 in every Memini session, so a manifest also
+//! records the session that wrote it.  The first update from a new session
+//! starts the group over instead of merging into the old members.
+
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::sync::Mutex;
+
+use crate::constants::{COLLECT_RESULTS_MAX_WAIT_SECS, COLLECT_RESULTS_POLL_SECS};
+use crate::rice::RiceStore;
+
+/// Serializes manifest updates from windows finishing at the same time.
+///
+/// The lock is process-local only.  Two Memini processes updating the same
+/// group (e.g. teammates in a shared workspace) can still race, and the
+/// later read-modify-write wins.
+static MANIFEST_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Identifies this process's window ids in manifests.
+static SESSION_ID: LazyLock<String> =
+    LazyLock::new(|| format!("{}-{:x}", std::process::id(), rand::random::<u64>()));
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemberStatus {
+    #[default]
+    Running,
+    Done,
+    Truncated,
+    Failed,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GroupMember {
+    pub window_id: usize,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub status: MemberStatus,
+    #[serde(default)]
+    pub started_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+    #[serde(default)]
+    pub result_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GroupManifest {
+    pub key: String,
+    /// Session whose window ids `members` refer to.
+    #[serde(default)]
+    pub session: String,
+    #[serde(default)]
+    pub members: Vec<GroupMember>,
+}
+
+impl GroupManifest {
+    fn member_mut(&mut self, window_id: usize) -> &mut GroupMember {
+        let idx = match self.members.iter().position(|m| m.window_id == window_id) {
+            Some(idx) => idx,
+            None => {
+                self.members.push(GroupMember {
+                    window_id,
+                    result_key: result_var(&self.key, window_id),
+                    ..GroupMember::default()
+                });
+                self.members.len() - 1
+            }
+        };
+        &mut self.members[idx]
+    }
+
+    fn pending(&self) -> impl Iterator<Item = &GroupMember> {
+        self.members
+            .iter()
+            .filter(|m| m.status == MemberStatus::Running)
+    }
+}
+
+/// Variable holding the manifest for `key`.
+pub fn manifest_var(key: &str) -> String {
+    format!("coordination_group:{key}")
+}
+
+/// Variable a member's final result is written to.
+pub fn result_var(key: &str, window_id: usize) -> String {
+    format!("agent_result:{key}:{window_id}")
+}
+
+async fn load(rice: &mut RiceStore, key: &str) -> Result<Option<GroupManifest>> {
+    Ok(rice
+        .get_variable(&manifest_var(key))
+        .await?
+        .and_then(|value| serde_json::from_value(value).ok()))
+}
+
+/// Read-modify-write the manifest for `key` under the process-wide lock.
+/// A manifest left by another session is replaced by an empty group.
+async fn update(
+    rice: &mut RiceStore,
+    key: &str,
+    session: &str,
+    apply: impl FnOnce(&mut GroupManifest),
+) -> Result<()> {
+    let _guard = MANIFEST_LOCK.lock().await;
+    let mut manifest = load(rice, key)
+        .await?
+        .filter(|manifest| manifest.session == session)
+        .unwrap_or_else(|| GroupManifest {
+            key: key.to_string(),
+            session: session.to_string(),
+            members: Vec::new(),
+        });
+    apply(&mut manifest);
+    rice.set_variable(
+        &manifest_var(key),
+        serde_json::to_value(&manifest)?,
+        "agent-coordination",
+    )
+    .await
+}
+
+/// Mark `window_id` as running in its group.  An empty `label` keeps the
+/// one recorded at spawn time (re-runs after `/reply` or a trigger).
+pub async fn start_member(
+    rice: &mut RiceStore,
+    key: &str,
+    window_id: usize,
+    label: &str,
+) -> Result<()> {
+    start_member_in(rice, key, &SESSION_ID, window_id, label).await
+}
+
+async fn start_member_in(
+    rice: &mut RiceStore,
+    key: &str,
+    session: &str,
+    window_id: usize,
+    label: &str,
+) -> Result<()> {
+    update(rice, key, session, |manifest| {
+        let member = manifest.member_mut(window_id);
+        if !label.is_empty() {
+            member.label = label.to_string();
+        }
+        member.status = MemberStatus::Running;
+        member.started_at = Local::now().to_rfc3339();
+        member.finished_at = None;
+        member.error = None;
+    })
+    .await
+}
+
+/// Record how a member's run ended.
+pub async fn finish_member(
+    rice: &mut RiceStore,
+    key: &str,
+    window_id: usize,
+    status: MemberStatus,
+    error: Option<String>,
+) -> Result<()> {
+    update(rice, key, &SESSION_ID, |manifest| {
+        let member = manifest.member_mut(window_id);
+        member.status = status;
+        member.finished_at = Some(Local::now().to_rfc3339());
+        member.error = error;
+    })
+    .await
+}
+
+/// The `collect_results` tool output for `key`, waiting up to `wait` for
+/// running members to finish.
+pub async fn collect(rice: &mut RiceStore, key: &str, wait: Duration) -> Value {
+    let wait = wait.min(Duration::from_secs(COLLECT_RESULTS_MAX_WAIT_SECS));
+    let started = Instant::now();
+    let manifest = loop {
+        let manifest = match load(rice, key).await {
+            Ok(Some(manifest)) => manifest,
+            Ok(None) => {
+                return json!({
+                    "coordination_key": key,
+                    "error": "no agents were spawned with this coordination_key",
+                });
+            }
+            Err(err) => return json!({ "error": format!("{err:#}") }),
+        };
+        if manifest.pending().next().is_none() || started.elapsed() >= wait {
+            break manifest;
+        }
+        tokio::time::sleep(Duration::from_secs(COLLECT_RESULTS_POLL_SECS)).await;
+    };
+
+    let mut results = Vec::new();
+    let mut failed = Vec::new();
+    for member in &manifest.members {
+        match member.status {
+            MemberStatus::Running => {}
+            MemberStatus::Failed => failed.push(json!({
+                "window_id": member.window_id,
+                "label": member.label,
+                "finished_at": member.finished_at,
+                "error": member.error,
+            })),
+            MemberStatus::Done | MemberStatus::Truncated => {
+                if let Ok(Some(value)) = rice.get_variable(&member.result_key).await {
+                    results.push(value);
+                }
+            }
+        }
+    }
+    let pending: Vec<Value> = manifest
+        .pending()
+        .map(|member| {
+            json!({
+                "window_id": member.window_id,
+                "label": member.label,
+                "started_at": member.started_at,
+            })
+        })
+        .collect();
+
+    json!({
+        "coordination_key": key,
+        "agent_count": manifest.members.len(),
+        "complete": pending.is_empty(),
+        "results": results,
+        "pending": pending,
+        "failed": failed,
+        "waited_seconds": started.elapsed().as_secs(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_reports_results_pending_and_failed_members() {
+        let dir = std::env::temp_dir().join(format!(
+            "memini-coordination-{}-{:x}",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let mut rice = RiceStore::local_at(&dir).unwrap();
+            let unknown = collect(&mut rice, "research", Duration::ZERO).await;
+            assert!(unknown["error"].is_string(), "{unknown}");
+
+            // Window ids well past the old 1..50 probe range.
+            for (id, label) in [(71, "docs"), (72, "tests"), (73, "bench")] {
+                start_member(&mut rice, "research", id, label)
+                    .await
+                    .unwrap();
+            }
+            rice.set_variable(
+                &result_var("research", 71),
+                json!({"window_id": 71, "output": "done"}),
+                "test",
+            )
+            .await
+            .unwrap();
+            finish_member(&mut rice, "research", 71, MemberStatus::Done, None)
+                .await
+                .unwrap();
+            finish_member(
+                &mut rice,
+                "research",
+                73,
+                MemberStatus::Failed,
+                Some("No OpenAI key.".to_string()),
+            )
+            .await
+            .unwrap();
+
+            let report = collect(&mut rice, "research", Duration::ZERO).await;
+            assert_eq!(report["agent_count"], 3);
+            assert_eq!(report["complete"], false);
+            assert_eq!(report["results"][0]["window_id"], 71);
+            assert_eq!(report["pending"][0]["label"], "tests");
+            assert_eq!(report["failed"][0]["error"], "No OpenAI key.");
+
+            // A re-run keeps the spawn label and goes back to running.
+            start_member(&mut rice, "research", 71, "").await.unwrap();
+            let report = collect(&mut rice, "research", Duration::ZERO).await;
+            assert_eq!(report["pending"].as_array().unwrap().len(), 2);
+            assert_eq!(report["pending"][0]["label"], "docs");
+        });
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reused_key_in_a_new_session_starts_a_fresh_group() {
+        let dir = std::env::temp_dir().join(format!(
+            "memini-coordination-reuse-{}-{:x}",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let mut rice = RiceStore::local_at(&dir).unwrap();
+            // An earlier session spawned windows 1 and 2 under the same key.
+            for (id, label) in [(1, "old-docs"), (2, "old-tests")] {
+                start_member_in(&mut rice, "research", "earlier", id, label)
+                    .await
+                    .unwrap();
+            }
+            rice.set_variable(
+                &result_var("research", 1),
+                json!({"window_id": 1, "output": "stale"}),
+                "test",
+            )
+            .await
+            .unwrap();
+
+            // This session's window 1 reuses the key and the window id.
+            start_member(&mut rice, "research", 1, "docs")
+                .await
+                .unwrap();
+            let report = collect(&mut rice, "research", Duration::ZERO).await;
+            assert_eq!(report["agent_count"], 1, "{report}");
+            assert_eq!(report["pending"][0]["label"], "docs");
+            assert!(report["results"].as_array().unwrap().is_empty());
+
+            rice.set_variable(
+                &result_var("research", 1),
+                json!({"window_id": 1, "output": "fresh"}),
+                "test",
+            )
+            .await
+            .unwrap();
+            finish_member(&mut rice, "research", 1, MemberStatus::Done, None)
+                .await
+                .unwrap();
+            let report = collect(&mut rice, "research", Duration::ZERO).await;
+            assert_eq!(report["agent_count"], 1);
+            assert_eq!(report["results"][0]["output"], "fresh");
+        });
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
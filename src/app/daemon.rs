@@ -13,15 +13,26 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
 
+use anyhow::{Result, bail};
 use chrono::Local;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use tokio::sync::{Notify, mpsc};
 
+use crate::constants::{MEMORY_COMPACT_MAX_CLUSTERS, TOOL_CALL_CONCURRENCY};
 use crate::mcp;
 use crate::mcp::config::McpServer;
-use crate::openai::{self, OpenAiClient};
+use crate::openai::{self, OpenAiClient, ResponseChain};
 use crate::rice::{self, RiceStore};
+use crate::util::join_ordered;
+
+use super::coordination::{self, MemberStatus};
+use super::logging::REASONING_PREFIX;
+use super::memory_tools::{self, MemoryScope};
+use super::output_schema;
+use super::thread_summary::{self, Eviction};
+use super::transcript::{ToolRecord, TranscriptTurn};
+use super::usage::{Budget, UsageScope};
 
 // ── Public types ─────────────────────────────────────────────────────
 
@@ -40,6 +51,18 @@ pub enum AgentEvent {
     },
     /// Agent needs user input to continue.
     NeedsInput { window_id: usize, question: String },
+    /// Id of a window run's final stored response (chained continuations).
+    ResponseId {
+        window_id: usize,
+        response_id: Option<String>,
+    },
+    /// A coordinated window stopped before it had a Rice store to report
+    /// through; the TUI marks it failed in the group manifest instead.
+    CoordinationFailed {
+        coordination_key: String,
+        window_id: usize,
+        error: String,
+    },
     /// Legacy: a simple result from a periodic daemon task.
     DaemonResult {
         task_name: String,
@@ -52,6 +75,8 @@ pub enum AgentEvent {
     ChatProgress { line: String, level: ChatLogLevel },
     /// Markdown output from the main chat LLM.
     ChatMarkdown { label: String, body: String },
+    /// A reasoning summary for the activity log (shown collapsed).
+    ChatReasoning { label: String, body: String },
     /// The main chat turn finished — update thread + commit to Rice.
     #[allow(dead_code)]
     ChatFinished {
@@ -59,6 +84,8 @@ pub enum AgentEvent {
         output_text: String,
         agent_name: String,
         thread_entries: Vec<Value>,
+        /// The turn as recorded for `/thread export`; `None` if it failed.
+        transcript: Option<TranscriptTurn>,
     },
     /// The LLM wants to spawn a sub-agent (from the background chat task).
     ChatSpawnAgent {
@@ -67,9 +94,22 @@ pub enum AgentEvent {
         prompt: String,
         mcp_snapshots: Vec<McpServerSnapshot>,
         coordination_key: String,
+        output_schema: Option<Value>,
+        max_tool_loops: Option<usize>,
         persona: String,
         skill_context: String,
     },
+    /// A rolling thread summary finished (or failed) for `evicted` turns.
+    ThreadSummary {
+        evicted: Vec<Value>,
+        summary: Result<String, String>,
+    },
+    /// Token usage reported by one `/responses` call.
+    Usage {
+        scope: UsageScope,
+        model: String,
+        usage: openai::TokenUsage,
+    },
     /// Rice pub-sub event observed for the active run/workspace.
     RiceStateEvent {
         run_id: String,
@@ -77,6 +117,8 @@ pub enum AgentEvent {
         agent_id: String,
         payload: String,
     },
+    /// A replay of the offline Rice outbox finished.
+    OutboxReplayed { lines: Vec<String>, warn: bool },
 }
 
 /// Log level for ChatProgress events.
@@ -113,6 +155,12 @@ pub struct AgentWindow {
     pub mcp_snapshots: Vec<McpServerSnapshot>,
     /// Optional coordination key used by orchestrated windows.
     pub coordination_key: String,
+    /// JSON Schema the window's result must satisfy, if any.
+    pub output_schema: Option<Value>,
+    /// Tool-loop limit requested via `spawn_agent`; `None` uses the persona's.
+    pub max_tool_loops: Option<usize>,
+    /// Last stored response id, used to chain `/reply` continuations.
+    pub last_response_id: Option<String>,
 }
 
 /// Status of an agent window.
@@ -140,6 +188,32 @@ pub struct DaemonTaskDef {
     #[serde(default)]
     pub tools: Vec<String>,
     pub paused: bool,
+    /// Optional daily token/dollar cap; the task pauses once it is spent.
+    #[serde(default)]
+    pub budget: Option<Budget>,
+    /// JSON Schema the task's output must satisfy; valid results are stored
+    /// as typed JSON in `recipe_output:<name>`.
+    #[serde(default)]
+    pub output_schema: Option<Value>,
+    /// Tool-call round-trips per run; `None` uses the global limit.
+    #[serde(default)]
+    pub max_tool_loops: Option<usize>,
+    /// Provider-hosted tool specs offered to the task.
+    #[serde(default)]
+    pub hosted_tools: Vec<String>,
+    /// Set only on definitions from `builtin_tasks()`; never read from a
+    /// recipe, so a user task cannot take over built-in behaviour by name.
+    #[serde(skip)]
+    pub builtin: Option<BuiltinTask>,
+    /// `tools` allow-list of the persona active when the task was started.
+    #[serde(skip)]
+    pub persona_tools: Vec<String>,
+}
+
+/// Built-in tasks that run code instead of a prompt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuiltinTask {
+    MemoryCompact,
 }
 
 /// Runtime handle for a running daemon task.
@@ -153,6 +227,10 @@ pub struct DaemonHandle {
 
 // ── Built-in task definitions ────────────────────────────────────────
 
+/// Built-in maintenance task that consolidates near-duplicate memories
+/// instead of running a prompt.
+pub const MEMORY_COMPACT_TASK: &str = "compact";
+
 /// Return the set of built-in daemon tasks that ship with Memini.
 pub fn builtin_tasks() -> Vec<DaemonTaskDef> {
     vec![
@@ -165,6 +243,12 @@ pub fn builtin_tasks() -> Vec<DaemonTaskDef> {
             trigger_variables: Vec::new(),
             tools: vec!["local".to_string()],
             paused: true, // off by default, user enables
+            budget: None,
+            output_schema: None,
+            max_tool_loops: None,
+            hosted_tools: Vec::new(),
+            builtin: None,
+            persona_tools: Vec::new(),
         },
         DaemonTaskDef {
             name: "digest".to_string(),
@@ -175,6 +259,28 @@ pub fn builtin_tasks() -> Vec<DaemonTaskDef> {
             trigger_variables: Vec::new(),
             tools: vec!["local".to_string()],
             paused: true,
+            budget: None,
+            output_schema: None,
+            max_tool_loops: None,
+            hosted_tools: Vec::new(),
+            builtin: None,
+            persona_tools: Vec::new(),
+        },
+        DaemonTaskDef {
+            name: MEMORY_COMPACT_TASK.to_string(),
+            persona: crate::prompts::memory_compact_prompt(),
+            prompt: "Merge near-duplicate memories into consolidated facts.".to_string(),
+            interval_secs: 86_400, // daily
+            trigger_events: Vec::new(),
+            trigger_variables: Vec::new(),
+            tools: vec!["none".to_string()],
+            paused: true,
+            budget: None,
+            output_schema: None,
+            max_tool_loops: None,
+            hosted_tools: Vec::new(),
+            builtin: Some(BuiltinTask::MemoryCompact),
+            persona_tools: Vec::new(),
         },
     ]
 }
@@ -257,6 +363,23 @@ pub fn spawn_task(
                 continue;
             }
 
+            if def_clone.builtin == Some(BuiltinTask::MemoryCompact) {
+                let message = run_memory_compaction(
+                    &def_clone,
+                    &openai,
+                    openai_key.as_deref(),
+                    &mut rice,
+                    &tx,
+                )
+                .await;
+                let _ = tx.send(AgentEvent::DaemonResult {
+                    task_name: def_clone.name.clone(),
+                    message,
+                    timestamp: Local::now().format("%H:%M:%S").to_string(),
+                });
+                continue;
+            }
+
             let Some(key) = &openai_key else {
                 let _ = tx.send(AgentEvent::DaemonResult {
                     task_name: def_clone.name.clone(),
@@ -266,14 +389,26 @@ pub fn spawn_task(
                 continue;
             };
 
-            let output_text = run_daemon_task_once(&def_clone, &openai, key, &mut rice).await;
+            let mut reasoning = Vec::new();
+            let mut served_model = openai.model.clone();
+            let output_text = run_daemon_task_once(
+                &def_clone,
+                &openai,
+                key,
+                &mut rice,
+                &tx,
+                &mut reasoning,
+                &mut served_model,
+            )
+            .await;
 
             // Commit to Rice memory.
             let _ = rice
                 .commit_trace(
                     &def_clone.prompt,
                     &output_text,
-                    &format!("daemon:{}", def_clone.name),
+                    &format!("daemon:{} (model: {served_model})", def_clone.name),
+                    &reasoning.join("\n\n"),
                     vec![],
                     &format!("memini:{}", def_clone.name),
                 )
@@ -311,6 +446,18 @@ pub fn spawn_oneshot(
             Err(_) => return,
         };
 
+        if def_clone.builtin == Some(BuiltinTask::MemoryCompact) {
+            let message =
+                run_memory_compaction(&def_clone, &openai, openai_key.as_deref(), &mut rice, &tx)
+                    .await;
+            let _ = tx.send(AgentEvent::DaemonResult {
+                task_name: def_clone.name.clone(),
+                message,
+                timestamp: Local::now().format("%H:%M:%S").to_string(),
+            });
+            return;
+        }
+
         let Some(key) = &openai_key else {
             let _ = tx.send(AgentEvent::DaemonResult {
                 task_name: def_clone.name.clone(),
@@ -320,13 +467,25 @@ pub fn spawn_oneshot(
             return;
         };
 
-        let output_text = run_daemon_task_once(&def_clone, &openai, key, &mut rice).await;
+        let mut reasoning = Vec::new();
+        let mut served_model = openai.model.clone();
+        let output_text = run_daemon_task_once(
+            &def_clone,
+            &openai,
+            key,
+            &mut rice,
+            &tx,
+            &mut reasoning,
+            &mut served_model,
+        )
+        .await;
 
         let _ = rice
             .commit_trace(
                 &def_clone.prompt,
                 &output_text,
-                &format!("daemon:{}", def_clone.name),
+                &format!("daemon:{} (model: {served_model})", def_clone.name),
+                &reasoning.join("\n\n"),
                 vec![],
                 &format!("memini:{}", def_clone.name),
             )
@@ -392,6 +551,10 @@ fn rice_memory_tool_defs() -> Vec<Value> {
                     "limit": {
                         "type": "integer",
                         "description": "Optional number of memories to return (default 6, max 50)."
+                    },
+                    "author": {
+                        "type": "string",
+                        "description": "Optional: only memories written by this user (name or user@host). In a shared workspace each memory records who wrote it."
                     }
                 }
             }
@@ -412,6 +575,9 @@ fn rice_memory_tool_defs() -> Vec<Value> {
             }
         }),
     ]
+    .into_iter()
+    .chain(memory_tools::tool_defs())
+    .collect()
 }
 
 fn with_rice_priority_tools(mut tools: Vec<Value>) -> Vec<Value> {
@@ -420,10 +586,199 @@ fn with_rice_priority_tools(mut tools: Vec<Value>) -> Vec<Value> {
     prioritized
 }
 
+/// Narrow a tool set to a persona's `tools` allow-list (empty keeps all).
+/// MCP tools pass through: personas narrow those per server (`mcp`).
+fn retain_persona_tools(tools: &mut Vec<Value>, allowed: &[String]) {
+    tools.retain(|tool| {
+        tool.get("name").and_then(Value::as_str).is_none_or(|name| {
+            mcp::split_namespaced_tool_name(name).is_some()
+                || super::agents::tool_allowed(allowed, name)
+        })
+    });
+}
+
 fn is_rice_memory_state_tool(name: &str) -> bool {
     name == "rice_memories" || name == "rice_state_get"
 }
 
+/// Apply the Rice-first rule in call order: for memory/state questions,
+/// workspace and delegation calls before any Rice lookup are blocked.
+fn rice_first_blocks(
+    calls: &[openai::ToolCall],
+    memory_or_state_query: bool,
+    rice_first_satisfied: &mut bool,
+) -> Vec<bool> {
+    calls
+        .iter()
+        .map(|call| {
+            let blocked = memory_or_state_query
+                && !*rice_first_satisfied
+                && is_workspace_or_delegation_tool(&call.name);
+            if is_rice_memory_state_tool(&call.name) {
+                *rice_first_satisfied = true;
+            }
+            blocked
+        })
+        .collect()
+}
+
+/// Whether a call only reads state, so it may overlap with its neighbours.
+///
+/// Calls run one at a time unless known to be reads: workspace reads and
+/// MCP tools annotated `readOnlyHint: true`.  Unannotated MCP tools, Rice,
+/// delegation, write and command tools never qualify.
+fn tool_runs_in_parallel(call: &openai::ToolCall, connections: &[mcp::McpConnection]) -> bool {
+    match call.name.as_str() {
+        "workspace_list_files" | "workspace_read_file" => return true,
+        _ => {}
+    }
+    let Some((server_id, tool_name)) = mcp::split_namespaced_tool_name(&call.name) else {
+        return false;
+    };
+    connections
+        .iter()
+        .find(|c| c.server.id == server_id)
+        .is_some_and(|conn| mcp_tool_is_read_only(&conn.tool_cache, tool_name))
+}
+
+/// Whether the server annotated `tool_name` with `readOnlyHint: true`.
+fn mcp_tool_is_read_only(tools: &[rmcp::model::Tool], tool_name: &str) -> bool {
+    tools
+        .iter()
+        .find(|tool| tool.name == tool_name)
+        .and_then(|tool| tool.annotations.as_ref())
+        .and_then(|annotations| annotations.read_only_hint)
+        == Some(true)
+}
+
+/// Run a read-only call: a workspace read or an MCP tool.
+async fn run_parallel_tool(call: &openai::ToolCall, connections: &[mcp::McpConnection]) -> String {
+    if let Some(output) = crate::local_tools::handle_tool_call(call).await {
+        return output;
+    }
+    let Some((server_id, tool_name)) = mcp::split_namespaced_tool_name(&call.name) else {
+        return format!(r#"{{"error":"Unknown tool '{}'"}}"#, call.name);
+    };
+    let Some(conn) = connections.iter().find(|c| c.server.id == server_id) else {
+        return format!(r#"{{"error":"No MCP connection for server '{server_id}'"}}"#);
+    };
+    match mcp::call_tool(conn, tool_name, call.arguments.clone()).await {
+        Ok(value) => serde_json::to_string(&value).unwrap_or_else(|_| "{}".to_string()),
+        Err(err) => format!(r#"{{"error":"{err}"}}"#),
+    }
+}
+
+/// A step of one loop iteration's tool calls: a lone call, or a run of
+/// consecutive read-only calls that may execute together.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ToolSegment {
+    Serial(usize),
+    Parallel(std::ops::Range<usize>),
+}
+
+/// Split calls into segments.  Runs of calls marked in `parallel` become one
+/// concurrent segment; every other call stands alone, so writes and
+/// `spawn_agent` keep the model's order relative to everything around them.
+fn tool_segments(parallel: &[bool]) -> Vec<ToolSegment> {
+    let mut segments = Vec::new();
+    let mut idx = 0usize;
+    while idx < parallel.len() {
+        let run = parallel[idx..].iter().take_while(|p| **p).count();
+        if run == 0 {
+            segments.push(ToolSegment::Serial(idx));
+            idx += 1;
+        } else {
+            segments.push(ToolSegment::Parallel(idx..idx + run));
+            idx += run;
+        }
+    }
+    segments
+}
+
+/// Run a batch of read-only calls concurrently (at most
+/// [`TOOL_CALL_CONCURRENCY`] at once), returning outputs in call order.
+async fn run_parallel_tools(
+    batch: &[openai::ToolCall],
+    connections: &[mcp::McpConnection],
+) -> Vec<String> {
+    let futures = batch
+        .iter()
+        .map(|call| run_parallel_tool(call, connections))
+        .collect();
+    join_ordered(futures, TOOL_CALL_CONCURRENCY).await
+}
+
+/// Append `function_call_output` items for `calls`, in call order.
+fn push_tool_outputs(input: &mut Vec<Value>, calls: &[openai::ToolCall], outputs: Vec<String>) {
+    for (call, output) in calls.iter().zip(outputs) {
+        input.push(json!({
+            "type": "function_call_output",
+            "call_id": call.call_id,
+            "output": output
+        }));
+    }
+}
+
+/// Progress label for a concurrent batch of tool calls.
+fn batch_label(batch: &[openai::ToolCall]) -> String {
+    let names: Vec<&str> = batch.iter().map(|call| call.name.as_str()).collect();
+    if names.len() == 1 {
+        format!("Calling tool: {}", names[0])
+    } else {
+        format!(
+            "Calling {} tools in parallel: {}",
+            names.len(),
+            names.join(", ")
+        )
+    }
+}
+
+/// Wrap up a run that hit its tool-loop limit.
+///
+/// Answers the pending calls with an error (the Responses API requires an
+/// output for every call), then asks the model for one tool-free reply
+/// summarizing progress and what is left.
+async fn summarize_truncated_run(
+    openai: &OpenAiClient,
+    key: &str,
+    input: &mut Vec<Value>,
+    pending: &[openai::ToolCall],
+    chain: &mut ResponseChain,
+) -> anyhow::Result<Value> {
+    let outputs = pending
+        .iter()
+        .map(|_| r#"{"error":"Tool loop limit reached; call not executed."}"#.to_string())
+        .collect();
+    push_tool_outputs(input, pending, outputs);
+    input.push(json!({
+        "role": "system",
+        "content": crate::prompts::tool_loop_exhausted_prompt()
+    }));
+    openai.response_chained(key, input, None, chain).await
+}
+
+/// Ask for a window's final answer in its output schema.
+///
+/// Tool-loop requests go out without `text.format`, so the window can still
+/// ask a `[NEEDS_INPUT]` question in prose; this one tool-free call restates
+/// the finished answer as JSON.
+async fn structured_final_answer(
+    openai: &OpenAiClient,
+    key: &str,
+    input: &mut Vec<Value>,
+    schema: &Value,
+    chain: &mut ResponseChain,
+) -> anyhow::Result<Value> {
+    input.push(json!({
+        "role": "system",
+        "content": crate::prompts::structured_answer_prompt()
+    }));
+    openai
+        .with_text_format(output_schema::text_format(schema))
+        .response_chained(key, input, None, chain)
+        .await
+}
+
 fn is_workspace_or_delegation_tool(name: &str) -> bool {
     if name == "spawn_agent" || name == "collect_results" {
         return true;
@@ -442,12 +797,137 @@ fn rice_first_tool_error() -> String {
         .to_string()
 }
 
+/// Clone of `openai` whose retry notices go to the main activity log.
+fn with_chat_retry_log(
+    openai: &OpenAiClient,
+    tx: &mpsc::UnboundedSender<AgentEvent>,
+    prefix: &str,
+) -> OpenAiClient {
+    let tx = tx.clone();
+    let prefix = prefix.to_string();
+    openai.with_retry_reporter(move |line| {
+        let _ = tx.send(AgentEvent::ChatProgress {
+            line: format!("{prefix}{line}"),
+            level: ChatLogLevel::Warn,
+        });
+    })
+}
+
+/// Clone of `openai` whose retry notices stream into an agent window.
+fn with_window_retry_log(
+    openai: &OpenAiClient,
+    tx: &mpsc::UnboundedSender<AgentEvent>,
+    window_id: usize,
+) -> OpenAiClient {
+    let tx = tx.clone();
+    openai.with_retry_reporter(move |line| {
+        let _ = tx.send(AgentEvent::Progress { window_id, line });
+    })
+}
+
+/// Forward the `usage` block of a response to the TUI for accounting, and
+/// show its reasoning summaries and hosted-tool activity (searches,
+/// citations, generated files).  Reasoning summaries are also appended to
+/// `reasoning` for the run's trace.  Returns the model that served the
+/// response (after any fallback).
+fn report_response(
+    tx: &mpsc::UnboundedSender<AgentEvent>,
+    scope: UsageScope,
+    openai: &OpenAiClient,
+    response: &Value,
+    reasoning: &mut Vec<String>,
+) -> String {
+    let items = openai::extract_output_items(response);
+    for summary in openai::extract_reasoning_summaries(&items) {
+        report_reasoning(tx, &scope, &summary);
+        reasoning.push(summary);
+    }
+    report_hosted_output(tx, &scope, response);
+    let model = openai::response_model(response, &openai.model);
+    let usage = openai::extract_usage(response);
+    if !usage.is_empty() {
+        let _ = tx.send(AgentEvent::Usage {
+            scope,
+            model: model.clone(),
+            usage,
+        });
+    }
+    model
+}
+
+/// Show a reasoning summary as a collapsible block: prefixed lines in an
+/// agent window, a `ChatReasoning` entry in the activity log otherwise.
+fn report_reasoning(tx: &mpsc::UnboundedSender<AgentEvent>, scope: &UsageScope, summary: &str) {
+    let label = match scope {
+        UsageScope::Window(window_id) => {
+            for line in summary.lines() {
+                let _ = tx.send(AgentEvent::Progress {
+                    window_id: *window_id,
+                    line: format!("{REASONING_PREFIX}{line}"),
+                });
+            }
+            return;
+        }
+        UsageScope::Chat => "reasoning".to_string(),
+        UsageScope::Recipe(name) => format!("[{name}] reasoning"),
+    };
+    let _ = tx.send(AgentEvent::ChatReasoning {
+        label,
+        body: summary.to_string(),
+    });
+}
+
+/// Log hosted-tool output items: into the agent window for window runs,
+/// otherwise into the activity log.
+fn report_hosted_output(
+    tx: &mpsc::UnboundedSender<AgentEvent>,
+    scope: &UsageScope,
+    response: &Value,
+) {
+    let lines = openai::describe_hosted_output(&openai::extract_output_items(response));
+    for line in lines {
+        let _ = match scope {
+            UsageScope::Window(window_id) => tx.send(AgentEvent::Progress {
+                window_id: *window_id,
+                line,
+            }),
+            UsageScope::Chat => tx.send(AgentEvent::ChatProgress {
+                line,
+                level: ChatLogLevel::Info,
+            }),
+            UsageScope::Recipe(name) => tx.send(AgentEvent::ChatProgress {
+                line: format!("[{name}] {line}"),
+                level: ChatLogLevel::Info,
+            }),
+        };
+    }
+}
+
+/// Run one pass of a task and return its output.  `served_model` is left
+/// on the model that answered last, for the trace.
 async fn run_daemon_task_once(
     def: &DaemonTaskDef,
     openai: &OpenAiClient,
     key: &str,
     rice: &mut RiceStore,
+    tx: &mpsc::UnboundedSender<AgentEvent>,
+    reasoning: &mut Vec<String>,
+    served_model: &mut String,
 ) -> String {
+    let mut openai = with_chat_retry_log(openai, tx, &format!("[{}] ", def.name));
+    if let Some(schema) = &def.output_schema {
+        openai = openai.with_text_format(output_schema::text_format(schema));
+    }
+    if let Some(limit) = def.max_tool_loops {
+        openai = openai.with_max_tool_loops(limit);
+    }
+    if !def.hosted_tools.is_empty() {
+        match openai::parse_hosted_tools(&def.hosted_tools) {
+            Ok(hosted) => openai = openai.with_hosted_tools(hosted),
+            Err(err) => return format!("Error: invalid hosted_tools: {err:#}"),
+        }
+    }
+    let openai = &openai;
     let memory_or_state_query = message_requests_memory_or_state(&def.prompt);
     let mut rice_first_satisfied = !memory_or_state_query;
 
@@ -458,7 +938,8 @@ async fn run_daemon_task_once(
 
     let memory_ctx = crate::rice::format_memories(&memories);
     let now = Local::now().format("%A, %B %e, %Y at %H:%M");
-    let all_tools = with_rice_priority_tools(selected_local_tools(&def.tools));
+    let mut all_tools = with_rice_priority_tools(selected_local_tools(&def.tools));
+    retain_persona_tools(&mut all_tools, &def.persona_tools);
 
     let system_prompt =
         crate::prompts::worker_system_prompt(&def.persona, &now.to_string(), !all_tools.is_empty());
@@ -480,11 +961,23 @@ async fn run_daemon_task_once(
         Some(&all_tools)
     };
 
-    let mut response = match openai.response(key, &input, tools_opt).await {
+    let mut chain = ResponseChain::default();
+
+    let mut response = match openai
+        .response_chained(key, &input, tools_opt, &mut chain)
+        .await
+    {
         Ok(value) => value,
         Err(err) => return format!("Error: {err:#}"),
     };
 
+    *served_model = report_response(
+        tx,
+        UsageScope::Recipe(def.name.clone()),
+        openai,
+        &response,
+        reasoning,
+    );
     let mut output_items = openai::extract_output_items(&response);
     if !output_items.is_empty() {
         input.extend(output_items.clone());
@@ -492,25 +985,60 @@ async fn run_daemon_task_once(
     let mut output_text = openai::extract_output_text(&output_items);
     let mut tool_calls = openai::extract_tool_calls(&output_items);
     let mut tool_loops = 0usize;
+    let mut truncated = false;
 
     while !tool_calls.is_empty() {
-        if openai::tool_loop_limit_reached(tool_loops) {
+        if openai.tool_loop_limit_reached(tool_loops) {
+            truncated = true;
+            response =
+                match summarize_truncated_run(openai, key, &mut input, &tool_calls, &mut chain)
+                    .await
+                {
+                    Ok(value) => value,
+                    Err(err) => return format!("Error: {err:#}"),
+                };
+            *served_model = report_response(
+                tx,
+                UsageScope::Recipe(def.name.clone()),
+                openai,
+                &response,
+                reasoning,
+            );
+            output_text = openai::extract_output_text(&openai::extract_output_items(&response));
             break;
         }
         tool_loops += 1;
 
-        for call in &tool_calls {
-            let tool_output = if memory_or_state_query
-                && !rice_first_satisfied
-                && is_workspace_or_delegation_tool(&call.name)
-            {
+        let blocked = rice_first_blocks(
+            &tool_calls,
+            memory_or_state_query,
+            &mut rice_first_satisfied,
+        );
+        let parallel: Vec<bool> = tool_calls
+            .iter()
+            .zip(&blocked)
+            .map(|(call, blocked)| !blocked && tool_runs_in_parallel(call, &[]))
+            .collect();
+        let mut outputs = Vec::with_capacity(tool_calls.len());
+        for segment in tool_segments(&parallel) {
+            let idx = match segment {
+                ToolSegment::Parallel(range) => {
+                    outputs.extend(run_parallel_tools(&tool_calls[range], &[]).await);
+                    continue;
+                }
+                ToolSegment::Serial(idx) => idx,
+            };
+            let call = &tool_calls[idx];
+            let tool_output = if blocked[idx] {
                 rice_first_tool_error()
             } else if call.name == "rice_memories" {
-                rice_first_satisfied = true;
                 handle_rice_memories_bg(call, rice, 6).await
             } else if call.name == "rice_state_get" {
-                rice_first_satisfied = true;
                 handle_rice_state_get_bg(call, rice).await
+            } else if let Some(output) =
+                memory_tools::handle_tool_call(call, rice, &MemoryScope::recipe(&def.name)).await
+            {
+                output
             } else if let Some(output) = crate::local_tools::handle_tool_call(call).await {
                 output
             } else {
@@ -519,18 +1047,24 @@ async fn run_daemon_task_once(
                     call.name
                 )
             };
-
-            input.push(json!({
-                "type": "function_call_output",
-                "call_id": call.call_id,
-                "output": tool_output
-            }));
+            outputs.push(tool_output);
         }
+        push_tool_outputs(&mut input, &tool_calls, outputs);
 
-        response = match openai.response(key, &input, tools_opt).await {
+        response = match openai
+            .response_chained(key, &input, tools_opt, &mut chain)
+            .await
+        {
             Ok(value) => value,
             Err(err) => return format!("Error: {err:#}"),
         };
+        *served_model = report_response(
+            tx,
+            UsageScope::Recipe(def.name.clone()),
+            openai,
+            &response,
+            reasoning,
+        );
         output_items = openai::extract_output_items(&response);
         if !output_items.is_empty() {
             input.extend(output_items.clone());
@@ -539,36 +1073,176 @@ async fn run_daemon_task_once(
         tool_calls = openai::extract_tool_calls(&output_items);
     }
 
+    if truncated {
+        // A partial run never satisfies the schema; keep it out of
+        // `recipe_output:<name>` and flag it for the next run.
+        let _ = tx.send(AgentEvent::ChatProgress {
+            line: format!(
+                "[{}] Stopped after {tool_loops} tool loops (limit reached).",
+                def.name
+            ),
+            level: ChatLogLevel::Warn,
+        });
+        return format!(
+            "(stopped after {tool_loops} tool loops; unfinished)\n{}",
+            output_text.trim()
+        );
+    }
+
     if output_text.trim().is_empty() {
-        "(no output)".to_string()
-    } else {
-        output_text
+        return "(no output)".to_string();
+    }
+
+    let Some(schema) = &def.output_schema else {
+        return output_text;
+    };
+    match output_schema::parse_structured_output(&output_text, schema) {
+        Ok(value) => {
+            let _ = rice
+                .set_variable(
+                    &format!("recipe_output:{}", def.name),
+                    value.clone(),
+                    "structured-output",
+                )
+                .await;
+            serde_json::to_string_pretty(&value).unwrap_or(output_text)
+        }
+        Err(errors) => {
+            let _ = tx.send(AgentEvent::ChatProgress {
+                line: format!(
+                    "[{}] Output does not match output_schema: {}",
+                    def.name,
+                    errors.join("; ")
+                ),
+                level: ChatLogLevel::Warn,
+            });
+            output_text
+        }
     }
 }
 
+/// Build the `agent_result:<key>:<id>` value for a finished window.
+///
+/// With a schema, `output` holds the parsed JSON when it validates;
+/// otherwise it stays the raw text and `schema_errors` explains why.
+/// Runs cut off by the tool-loop limit report `status: "truncated"`.
+fn agent_result_value(
+    window_id: usize,
+    output_text: &str,
+    schema: Option<&Value>,
+    truncated: bool,
+) -> Value {
+    let mut result = json!({
+        "window_id": window_id,
+        "status": if truncated { "truncated" } else { "done" },
+        "output": output_text,
+        "timestamp": Local::now().format("%H:%M:%S").to_string(),
+    });
+    if let Some(schema) = schema {
+        match output_schema::parse_structured_output(output_text, schema) {
+            Ok(value) => {
+                result["output"] = value;
+                result["structured"] = json!(true);
+            }
+            Err(errors) => {
+                result["structured"] = json!(false);
+                result["schema_errors"] = json!(errors);
+            }
+        }
+    }
+    result
+}
+
+fn report_schema_errors(tx: &mpsc::UnboundedSender<AgentEvent>, window_id: usize, result: &Value) {
+    let Some(errors) = result.get("schema_errors").and_then(Value::as_array) else {
+        return;
+    };
+    let errors: Vec<&str> = errors.iter().filter_map(Value::as_str).collect();
+    let _ = tx.send(AgentEvent::Progress {
+        window_id,
+        line: format!("Output does not match output_schema: {}", errors.join("; ")),
+    });
+}
+
+/// Record a window run that ended before producing a result.  Every
+/// early exit of a coordinated run must call this (or send
+/// `CoordinationFailed` when it has no store), or `collect_results` keeps
+/// waiting on it.
+async fn fail_coordination_member(
+    rice: &mut RiceStore,
+    coordination_key: &str,
+    window_id: usize,
+    error: &str,
+) {
+    if coordination_key.is_empty() {
+        return;
+    }
+    let _ = coordination::finish_member(
+        rice,
+        coordination_key,
+        window_id,
+        MemberStatus::Failed,
+        Some(error.to_string()),
+    )
+    .await;
+}
+
 // ── Spawn an agent window (streaming, interactive) ───────────────────
 
+/// What one agent-window run works on.
+pub struct AgentWindowRun {
+    pub window_id: usize,
+    /// Empty when the window is not part of a coordination group.
+    pub coordination_key: String,
+    /// JSON Schema for the final answer, requested once the tool loop ends.
+    pub output_schema: Option<Value>,
+    /// Set on `/reply` runs, which chain onto the previous exchange.
+    pub previous_response_id: Option<String>,
+    pub persona: String,
+    pub memory_scope: MemoryScope,
+    pub prompt: String,
+    pub skill_context: String,
+    /// `tools` allow-list of the persona the window runs as.
+    pub tools: Vec<String>,
+}
+
 /// Spawn a one-shot agent that streams progress into an [`AgentWindow`].
 ///
 /// Sends `Started`, then `Progress` lines as it works, then `Finished`.
 /// The window_id must already be allocated by the caller.
 pub fn spawn_agent_window(
-    window_id: usize,
-    persona: String,
-    prompt: String,
-    skill_context: String,
+    run: AgentWindowRun,
     tx: mpsc::UnboundedSender<AgentEvent>,
     openai: OpenAiClient,
     openai_key: Option<String>,
     rice_future: tokio::task::JoinHandle<RiceStore>,
     rt: tokio::runtime::Handle,
 ) {
+    let AgentWindowRun {
+        window_id,
+        coordination_key,
+        output_schema,
+        previous_response_id,
+        persona,
+        memory_scope,
+        prompt,
+        skill_context,
+        tools,
+    } = run;
     rt.spawn(async move {
         let _ = tx.send(AgentEvent::Started { window_id });
+        let openai = with_window_retry_log(&openai, &tx, window_id);
 
         let mut rice = match rice_future.await {
             Ok(r) => r,
             Err(_) => {
+                if !coordination_key.is_empty() {
+                    let _ = tx.send(AgentEvent::CoordinationFailed {
+                        coordination_key: coordination_key.clone(),
+                        window_id,
+                        error: "Could not connect to Rice.".to_string(),
+                    });
+                }
                 let _ = tx.send(AgentEvent::Progress {
                     window_id,
                     line: "[error] Could not connect to Rice.".to_string(),
@@ -582,7 +1256,13 @@ pub fn spawn_agent_window(
             }
         };
 
+        if !coordination_key.is_empty() {
+            let _ = coordination::start_member(&mut rice, &coordination_key, window_id, "").await;
+        }
+
         let Some(key) = &openai_key else {
+            fail_coordination_member(&mut rice, &coordination_key, window_id, "No OpenAI key.")
+                .await;
             let _ = tx.send(AgentEvent::Progress {
                 window_id,
                 line: "[error] No OpenAI key configured.".to_string(),
@@ -624,7 +1304,8 @@ pub fn spawn_agent_window(
             line: "Thinking...".to_string(),
         });
 
-        let all_tools = with_rice_priority_tools(crate::local_tools::tool_defs());
+        let mut all_tools = with_rice_priority_tools(crate::local_tools::tool_defs());
+        retain_persona_tools(&mut all_tools, &tools);
         let system_prompt =
             crate::prompts::worker_system_prompt(&persona, &now.to_string(), !all_tools.is_empty());
         let mut input = vec![json!({"role": "system", "content": system_prompt})];
@@ -648,10 +1329,19 @@ pub fn spawn_agent_window(
             Some(&all_tools)
         };
 
-        let mut response = match openai.response(key, &input, tools_opt).await {
+        // Continuation runs (after /reply) chain onto the window's last
+        // response, so only the new user message is sent.
+        let mut chain = ResponseChain::resume(previous_response_id, input.len() - 1);
+        let mut reasoning = Vec::new();
+
+        let mut response = match openai
+            .response_chained(key, &input, tools_opt, &mut chain)
+            .await
+        {
             Ok(r) => r,
             Err(err) => {
                 let msg = format!("Error: {err:#}");
+                fail_coordination_member(&mut rice, &coordination_key, window_id, &msg).await;
                 let _ = tx.send(AgentEvent::Progress {
                     window_id,
                     line: msg.clone(),
@@ -665,6 +1355,8 @@ pub fn spawn_agent_window(
             }
         };
 
+        let mut served_model =
+            report_response(&tx, UsageScope::Window(window_id), &openai, &response, &mut reasoning);
         let mut output_items = openai::extract_output_items(&response);
         if !output_items.is_empty() {
             input.extend(output_items.clone());
@@ -672,34 +1364,85 @@ pub fn spawn_agent_window(
         let mut output_text = openai::extract_output_text(&output_items);
         let mut tool_calls = openai::extract_tool_calls(&output_items);
         let mut tool_loops = 0usize;
+        let mut truncated = false;
 
         while !tool_calls.is_empty() {
-            if openai::tool_loop_limit_reached(tool_loops) {
+            if openai.tool_loop_limit_reached(tool_loops) {
                 let _ = tx.send(AgentEvent::Progress {
                     window_id,
-                    line: "Tool loop limit reached.".to_string(),
+                    line: format!("Tool loop limit ({tool_loops}) reached; summarizing progress."),
                 });
+                match summarize_truncated_run(&openai, key, &mut input, &tool_calls, &mut chain)
+                    .await
+                {
+                    Ok(r) => {
+                        served_model = report_response(
+                            &tx,
+                            UsageScope::Window(window_id),
+                            &openai,
+                            &r,
+                            &mut reasoning,
+                        );
+                        output_text =
+                            openai::extract_output_text(&openai::extract_output_items(&r));
+                        tool_calls.clear();
+                    }
+                    Err(err) => {
+                        let _ = tx.send(AgentEvent::Progress {
+                            window_id,
+                            line: format!("LLM error: {err:#}"),
+                        });
+                    }
+                }
+                truncated = true;
                 break;
             }
             tool_loops += 1;
 
-            for call in &tool_calls {
+            let blocked = rice_first_blocks(
+                &tool_calls,
+                memory_or_state_query,
+                &mut rice_first_satisfied,
+            );
+            let parallel: Vec<bool> = tool_calls
+                .iter()
+                .zip(&blocked)
+                .map(|(call, blocked)| !blocked && tool_runs_in_parallel(call, &[]))
+                .collect();
+            let mut outputs = Vec::with_capacity(tool_calls.len());
+            for segment in tool_segments(&parallel) {
+                let idx = match segment {
+                    ToolSegment::Parallel(range) => {
+                        let batch = &tool_calls[range];
+                        let _ = tx.send(AgentEvent::Progress {
+                            window_id,
+                            line: batch_label(batch),
+                        });
+                        outputs.extend(run_parallel_tools(batch, &[]).await);
+                        let _ = tx.send(AgentEvent::Progress {
+                            window_id,
+                            line: format!("{} tool(s) returned.", batch.len()),
+                        });
+                        continue;
+                    }
+                    ToolSegment::Serial(idx) => idx,
+                };
+                let call = &tool_calls[idx];
                 let _ = tx.send(AgentEvent::Progress {
                     window_id,
                     line: format!("Calling tool: {}", call.name),
                 });
 
-                let tool_output = if memory_or_state_query
-                    && !rice_first_satisfied
-                    && is_workspace_or_delegation_tool(&call.name)
-                {
+                let tool_output = if blocked[idx] {
                     rice_first_tool_error()
                 } else if call.name == "rice_memories" {
-                    rice_first_satisfied = true;
                     handle_rice_memories_bg(call, &mut rice, 6).await
                 } else if call.name == "rice_state_get" {
-                    rice_first_satisfied = true;
                     handle_rice_state_get_bg(call, &mut rice).await
+                } else if let Some(output) =
+                    memory_tools::handle_tool_call(call, &mut rice, &memory_scope).await
+                {
+                    output
                 } else if let Some(output) = crate::local_tools::handle_tool_call(call).await {
                     output
                 } else {
@@ -710,15 +1453,14 @@ pub fn spawn_agent_window(
                     window_id,
                     line: format!("Tool {} returned.", call.name),
                 });
-
-                input.push(json!({
-                    "type": "function_call_output",
-                    "call_id": call.call_id,
-                    "output": tool_output
-                }));
+                outputs.push(tool_output);
             }
+            push_tool_outputs(&mut input, &tool_calls, outputs);
 
-            response = match openai.response(key, &input, tools_opt).await {
+            response = match openai
+                .response_chained(key, &input, tools_opt, &mut chain)
+                .await
+            {
                 Ok(r) => r,
                 Err(err) => {
                     let _ = tx.send(AgentEvent::Progress {
@@ -728,6 +1470,13 @@ pub fn spawn_agent_window(
                     break;
                 }
             };
+            served_model = report_response(
+                &tx,
+                UsageScope::Window(window_id),
+                &openai,
+                &response,
+                &mut reasoning,
+            );
             output_items = openai::extract_output_items(&response);
             if !output_items.is_empty() {
                 input.extend(output_items.clone());
@@ -736,6 +1485,31 @@ pub fn spawn_agent_window(
             tool_calls = openai::extract_tool_calls(&output_items);
         }
 
+        if let Some(schema) = &output_schema
+            && tool_calls.is_empty()
+            && !truncated
+            && !output_text.contains("[NEEDS_INPUT]")
+        {
+            match structured_final_answer(&openai, key, &mut input, schema, &mut chain).await {
+                Ok(r) => {
+                    served_model = report_response(
+                        &tx,
+                        UsageScope::Window(window_id),
+                        &openai,
+                        &r,
+                        &mut reasoning,
+                    );
+                    output_text = openai::extract_output_text(&openai::extract_output_items(&r));
+                }
+                Err(err) => {
+                    let _ = tx.send(AgentEvent::Progress {
+                        window_id,
+                        line: format!("Structured answer failed: {err:#}"),
+                    });
+                }
+            }
+        }
+
         // -- Step 3: Stream output line by line
         for line in output_text.lines() {
             let _ = tx.send(AgentEvent::Progress {
@@ -756,14 +1530,52 @@ pub fn spawn_agent_window(
             .commit_trace(
                 &prompt,
                 &output_text,
-                &format!("agent-window:{window_id}"),
+                &format!("agent-window:{window_id} (model: {served_model})"),
+                &reasoning.join("\n\n"),
                 vec![],
                 &format!("memini:agent-{window_id}"),
             )
             .await;
 
+        // Write result to coordination variable so the orchestrator can collect it.
+        if !coordination_key.is_empty() {
+            let coord_var = coordination::result_var(&coordination_key, window_id);
+            let result_value =
+                agent_result_value(window_id, &output_text, output_schema.as_ref(), truncated);
+            report_schema_errors(&tx, window_id, &result_value);
+            let _ = rice
+                .set_variable(&coord_var, result_value, "agent-coordination")
+                .await;
+            let status = if truncated {
+                MemberStatus::Truncated
+            } else {
+                MemberStatus::Done
+            };
+            let _ =
+                coordination::finish_member(&mut rice, &coordination_key, window_id, status, None)
+                    .await;
+        }
+
+        // Remember where this run ended so a /reply can chain onto it.
+        // A run cut off mid tool call cannot be continued that way.
+        let _ = tx.send(AgentEvent::ResponseId {
+            window_id,
+            response_id: if tool_calls.is_empty() {
+                chain.last_response_id().map(str::to_string)
+            } else {
+                None
+            },
+        });
+
         // -- Step 5: Check if agent needs user input
-        if output_text.contains("[NEEDS_INPUT]") {
+        if truncated {
+            let _ = tx.send(AgentEvent::NeedsInput {
+                window_id,
+                question: format!(
+                    "Stopped after {tool_loops} tool loops (limit reached). Reply to let me continue."
+                ),
+            });
+        } else if output_text.contains("[NEEDS_INPUT]") {
             let question = output_text
                 .split("[NEEDS_INPUT]")
                 .nth(1)
@@ -803,11 +1615,7 @@ pub struct McpServerSnapshot {
 /// `agent_result:<coordination_key>:<window_id>` so the orchestrator (or
 /// another agent) can collect them.
 pub fn spawn_agent_window_with_mcp(
-    window_id: usize,
-    coordination_key: String,
-    persona: String,
-    prompt: String,
-    skill_context: String,
+    run: AgentWindowRun,
     mcp_snapshots: Vec<McpServerSnapshot>,
     tx: mpsc::UnboundedSender<AgentEvent>,
     openai: OpenAiClient,
@@ -815,12 +1623,31 @@ pub fn spawn_agent_window_with_mcp(
     rice_future: tokio::task::JoinHandle<RiceStore>,
     rt: tokio::runtime::Handle,
 ) {
+    let AgentWindowRun {
+        window_id,
+        coordination_key,
+        output_schema,
+        previous_response_id,
+        persona,
+        memory_scope,
+        prompt,
+        skill_context,
+        tools,
+    } = run;
     rt.spawn(async move {
         let _ = tx.send(AgentEvent::Started { window_id });
+        let openai = with_window_retry_log(&openai, &tx, window_id);
 
         let mut rice = match rice_future.await {
             Ok(r) => r,
             Err(_) => {
+                if !coordination_key.is_empty() {
+                    let _ = tx.send(AgentEvent::CoordinationFailed {
+                        coordination_key: coordination_key.clone(),
+                        window_id,
+                        error: "Could not connect to Rice.".to_string(),
+                    });
+                }
                 let _ = tx.send(AgentEvent::Progress {
                     window_id,
                     line: "[error] Could not connect to Rice.".to_string(),
@@ -834,7 +1661,13 @@ pub fn spawn_agent_window_with_mcp(
             }
         };
 
+        if !coordination_key.is_empty() {
+            let _ = coordination::start_member(&mut rice, &coordination_key, window_id, "").await;
+        }
+
         let Some(key) = &openai_key else {
+            fail_coordination_member(&mut rice, &coordination_key, window_id, "No OpenAI key.")
+                .await;
             let _ = tx.send(AgentEvent::Progress {
                 window_id,
                 line: "[error] No OpenAI key configured.".to_string(),
@@ -906,6 +1739,7 @@ pub fn spawn_agent_window_with_mcp(
         }
         all_tools.extend(crate::local_tools::tool_defs());
         all_tools = with_rice_priority_tools(all_tools);
+        retain_persona_tools(&mut all_tools, &tools);
 
         // -- Step 2: Recall memories
         let _ = tx.send(AgentEvent::Progress {
@@ -959,10 +1793,19 @@ pub fn spawn_agent_window_with_mcp(
             Some(&all_tools)
         };
 
-        let mut response = match openai.response(key, &input, tools_opt).await {
+        // Continuation runs (after /reply) chain onto the window's last
+        // response, so only the new user message is sent.
+        let mut chain = ResponseChain::resume(previous_response_id, input.len() - 1);
+        let mut reasoning = Vec::new();
+
+        let mut response = match openai
+            .response_chained(key, &input, tools_opt, &mut chain)
+            .await
+        {
             Ok(r) => r,
             Err(err) => {
                 let msg = format!("Error: {err:#}");
+                fail_coordination_member(&mut rice, &coordination_key, window_id, &msg).await;
                 let _ = tx.send(AgentEvent::Progress {
                     window_id,
                     line: msg.clone(),
@@ -976,6 +1819,8 @@ pub fn spawn_agent_window_with_mcp(
             }
         };
 
+        let mut served_model =
+            report_response(&tx, UsageScope::Window(window_id), &openai, &response, &mut reasoning);
         let mut output_items = openai::extract_output_items(&response);
         if !output_items.is_empty() {
             input.extend(output_items.clone());
@@ -983,34 +1828,85 @@ pub fn spawn_agent_window_with_mcp(
         let mut output_text = openai::extract_output_text(&output_items);
         let mut tool_calls = openai::extract_tool_calls(&output_items);
         let mut tool_loops = 0usize;
+        let mut truncated = false;
 
         while !tool_calls.is_empty() {
-            if openai::tool_loop_limit_reached(tool_loops) {
+            if openai.tool_loop_limit_reached(tool_loops) {
                 let _ = tx.send(AgentEvent::Progress {
                     window_id,
-                    line: "Tool loop limit reached.".to_string(),
+                    line: format!("Tool loop limit ({tool_loops}) reached; summarizing progress."),
                 });
+                match summarize_truncated_run(&openai, key, &mut input, &tool_calls, &mut chain)
+                    .await
+                {
+                    Ok(r) => {
+                        served_model = report_response(
+                            &tx,
+                            UsageScope::Window(window_id),
+                            &openai,
+                            &r,
+                            &mut reasoning,
+                        );
+                        output_text =
+                            openai::extract_output_text(&openai::extract_output_items(&r));
+                        tool_calls.clear();
+                    }
+                    Err(err) => {
+                        let _ = tx.send(AgentEvent::Progress {
+                            window_id,
+                            line: format!("LLM error: {err:#}"),
+                        });
+                    }
+                }
+                truncated = true;
                 break;
             }
             tool_loops += 1;
 
-            for call in &tool_calls {
+            let blocked = rice_first_blocks(
+                &tool_calls,
+                memory_or_state_query,
+                &mut rice_first_satisfied,
+            );
+            let parallel: Vec<bool> = tool_calls
+                .iter()
+                .zip(&blocked)
+                .map(|(call, blocked)| !blocked && tool_runs_in_parallel(call, &connections))
+                .collect();
+            let mut outputs = Vec::with_capacity(tool_calls.len());
+            for segment in tool_segments(&parallel) {
+                let idx = match segment {
+                    ToolSegment::Parallel(range) => {
+                        let batch = &tool_calls[range];
+                        let _ = tx.send(AgentEvent::Progress {
+                            window_id,
+                            line: batch_label(batch),
+                        });
+                        outputs.extend(run_parallel_tools(batch, &connections).await);
+                        let _ = tx.send(AgentEvent::Progress {
+                            window_id,
+                            line: format!("{} tool(s) returned.", batch.len()),
+                        });
+                        continue;
+                    }
+                    ToolSegment::Serial(idx) => idx,
+                };
+                let call = &tool_calls[idx];
                 let _ = tx.send(AgentEvent::Progress {
                     window_id,
                     line: format!("Calling tool: {}", call.name),
                 });
 
-                let tool_output = if memory_or_state_query
-                    && !rice_first_satisfied
-                    && is_workspace_or_delegation_tool(&call.name)
-                {
+                let tool_output = if blocked[idx] {
                     rice_first_tool_error()
                 } else if call.name == "rice_memories" {
-                    rice_first_satisfied = true;
                     handle_rice_memories_bg(call, &mut rice, 6).await
                 } else if call.name == "rice_state_get" {
-                    rice_first_satisfied = true;
                     handle_rice_state_get_bg(call, &mut rice).await
+                } else if let Some(output) =
+                    memory_tools::handle_tool_call(call, &mut rice, &memory_scope).await
+                {
+                    output
                 } else if let Some(output) = crate::local_tools::handle_tool_call(call).await {
                     output
                 } else if let Some((server_id, tool_name)) =
@@ -1034,15 +1930,14 @@ pub fn spawn_agent_window_with_mcp(
                     window_id,
                     line: format!("Tool {} returned.", call.name),
                 });
-
-                input.push(json!({
-                    "type": "function_call_output",
-                    "call_id": call.call_id,
-                    "output": tool_output
-                }));
+                outputs.push(tool_output);
             }
+            push_tool_outputs(&mut input, &tool_calls, outputs);
 
-            response = match openai.response(key, &input, tools_opt).await {
+            response = match openai
+                .response_chained(key, &input, tools_opt, &mut chain)
+                .await
+            {
                 Ok(r) => r,
                 Err(err) => {
                     let _ = tx.send(AgentEvent::Progress {
@@ -1052,6 +1947,13 @@ pub fn spawn_agent_window_with_mcp(
                     break;
                 }
             };
+            served_model = report_response(
+                &tx,
+                UsageScope::Window(window_id),
+                &openai,
+                &response,
+                &mut reasoning,
+            );
             output_items = openai::extract_output_items(&response);
             if !output_items.is_empty() {
                 input.extend(output_items.clone());
@@ -1060,6 +1962,31 @@ pub fn spawn_agent_window_with_mcp(
             tool_calls = openai::extract_tool_calls(&output_items);
         }
 
+        if let Some(schema) = &output_schema
+            && tool_calls.is_empty()
+            && !truncated
+            && !output_text.contains("[NEEDS_INPUT]")
+        {
+            match structured_final_answer(&openai, key, &mut input, schema, &mut chain).await {
+                Ok(r) => {
+                    served_model = report_response(
+                        &tx,
+                        UsageScope::Window(window_id),
+                        &openai,
+                        &r,
+                        &mut reasoning,
+                    );
+                    output_text = openai::extract_output_text(&openai::extract_output_items(&r));
+                }
+                Err(err) => {
+                    let _ = tx.send(AgentEvent::Progress {
+                        window_id,
+                        line: format!("Structured answer failed: {err:#}"),
+                    });
+                }
+            }
+        }
+
         // -- Step 4: Stream output
         for line in output_text.lines() {
             let _ = tx.send(AgentEvent::Progress {
@@ -1079,7 +2006,8 @@ pub fn spawn_agent_window_with_mcp(
             .commit_trace(
                 &prompt,
                 &output_text,
-                &format!("agent-window:{window_id}"),
+                &format!("agent-window:{window_id} (model: {served_model})"),
+                &reasoning.join("\n\n"),
                 vec![],
                 &format!("memini:agent-{window_id}"),
             )
@@ -1087,20 +2015,43 @@ pub fn spawn_agent_window_with_mcp(
 
         // Write result to coordination variable so the orchestrator can collect it.
         if !coordination_key.is_empty() {
-            let coord_var = format!("agent_result:{coordination_key}:{window_id}");
-            let result_value = json!({
-                "window_id": window_id,
-                "status": "done",
-                "output": output_text,
-                "timestamp": Local::now().format("%H:%M:%S").to_string(),
-            });
+            let coord_var = coordination::result_var(&coordination_key, window_id);
+            let result_value =
+                agent_result_value(window_id, &output_text, output_schema.as_ref(), truncated);
+            report_schema_errors(&tx, window_id, &result_value);
             let _ = rice
                 .set_variable(&coord_var, result_value, "agent-coordination")
                 .await;
+            let status = if truncated {
+                MemberStatus::Truncated
+            } else {
+                MemberStatus::Done
+            };
+            let _ =
+                coordination::finish_member(&mut rice, &coordination_key, window_id, status, None)
+                    .await;
         }
 
+        // Remember where this run ended so a /reply can chain onto it.
+        // A run cut off mid tool call cannot be continued that way.
+        let _ = tx.send(AgentEvent::ResponseId {
+            window_id,
+            response_id: if tool_calls.is_empty() {
+                chain.last_response_id().map(str::to_string)
+            } else {
+                None
+            },
+        });
+
         // -- Step 6: Check if agent needs user input
-        if output_text.contains("[NEEDS_INPUT]") {
+        if truncated {
+            let _ = tx.send(AgentEvent::NeedsInput {
+                window_id,
+                question: format!(
+                    "Stopped after {tool_loops} tool loops (limit reached). Reply to let me continue."
+                ),
+            });
+        } else if output_text.contains("[NEEDS_INPUT]") {
             let question = output_text
                 .split("[NEEDS_INPUT]")
                 .nth(1)
@@ -1127,6 +2078,12 @@ pub fn spawn_agent_window_with_mcp(
 pub struct ChatTaskParams {
     pub key: String,
     pub message: String,
+    /// User message content sent to the model: the message text, or an
+    /// array of input parts when files or images are attached.
+    pub user_content: Value,
+    /// The same message as kept in the conversation thread: attachments
+    /// are replaced by short references.
+    pub thread_content: Value,
     pub persona: String,
     pub agent_name: String,
     pub skill_context: String,
@@ -1152,6 +2109,8 @@ pub fn spawn_chat_task(
         let ChatTaskParams {
             key,
             message,
+            user_content,
+            thread_content,
             persona,
             agent_name,
             skill_context,
@@ -1161,6 +2120,8 @@ pub fn spawn_chat_task(
             builtin_tools,
             next_window_id,
         } = params;
+        let openai = with_chat_retry_log(&openai, &tx, "");
+        let started_at = Local::now().to_rfc3339();
 
         let mut rice = match rice_future.await {
             Ok(r) => r,
@@ -1174,6 +2135,7 @@ pub fn spawn_chat_task(
                     output_text: String::new(),
                     agent_name,
                     thread_entries: Vec::new(),
+                    transcript: None,
                 });
                 return;
             }
@@ -1289,7 +2251,7 @@ pub fn spawn_chat_task(
         for msg in &conversation_thread {
             input.push(msg.clone());
         }
-        input.push(json!({"role": "user", "content": message}));
+        input.push(json!({"role": "user", "content": user_content.clone()}));
 
         let tools_opt: Option<&[Value]> = if all_tools.is_empty() {
             None
@@ -1303,7 +2265,13 @@ pub fn spawn_chat_task(
             level: ChatLogLevel::Info,
         });
 
-        let mut response = match openai.response(&key, &input, tools_opt).await {
+        let mut chain = ResponseChain::default();
+        let mut reasoning = Vec::new();
+
+        let mut response = match openai
+            .response_chained(&key, &input, tools_opt, &mut chain)
+            .await
+        {
             Ok(r) => r,
             Err(err) => {
                 let _ = tx.send(AgentEvent::ChatProgress {
@@ -1315,11 +2283,14 @@ pub fn spawn_chat_task(
                     output_text: String::new(),
                     agent_name,
                     thread_entries: Vec::new(),
+                    transcript: None,
                 });
                 return;
             }
         };
 
+        let mut served_model =
+            report_response(&tx, UsageScope::Chat, &openai, &response, &mut reasoning);
         let mut output_items = openai::extract_output_items(&response);
         if !output_items.is_empty() {
             input.extend(output_items.clone());
@@ -1329,38 +2300,84 @@ pub fn spawn_chat_task(
         let mut tool_loops = 0usize;
         let mut rice_first_satisfied = !memory_or_state_query;
         let mut spawned_windows: Vec<(usize, String)> = Vec::new();
+        let mut tool_records = Vec::new();
 
         // ── Step 6: Tool-call loop ───────────────────────────────────
         while !tool_calls.is_empty() {
-            if openai::tool_loop_limit_reached(tool_loops) {
+            if openai.tool_loop_limit_reached(tool_loops) {
                 let _ = tx.send(AgentEvent::ChatProgress {
-                    line: "Tool loop limit reached.".to_string(),
+                    line: format!(
+                        "Tool loop limit ({tool_loops}) reached; summarizing progress. Reply \"continue\" to pick up where it stopped."
+                    ),
                     level: ChatLogLevel::Warn,
                 });
+                match summarize_truncated_run(&openai, &key, &mut input, &tool_calls, &mut chain)
+                    .await
+                {
+                    Ok(r) => {
+                        served_model =
+                            report_response(&tx, UsageScope::Chat, &openai, &r, &mut reasoning);
+                        output_text =
+                            openai::extract_output_text(&openai::extract_output_items(&r));
+                    }
+                    Err(err) => {
+                        let _ = tx.send(AgentEvent::ChatProgress {
+                            line: format!("OpenAI request failed: {err:#}"),
+                            level: ChatLogLevel::Error,
+                        });
+                    }
+                }
                 break;
             }
             tool_loops += 1;
 
-            for call in &tool_calls {
+            let blocked = rice_first_blocks(
+                &tool_calls,
+                memory_or_state_query,
+                &mut rice_first_satisfied,
+            );
+            let parallel: Vec<bool> = tool_calls
+                .iter()
+                .zip(&blocked)
+                .map(|(call, blocked)| !blocked && tool_runs_in_parallel(call, &connections))
+                .collect();
+            let mut outputs = Vec::with_capacity(tool_calls.len());
+            for segment in tool_segments(&parallel) {
+                let idx = match segment {
+                    ToolSegment::Parallel(range) => {
+                        let batch = &tool_calls[range];
+                        let _ = tx.send(AgentEvent::ChatProgress {
+                            line: format!("⚙ {}", batch_label(batch)),
+                            level: ChatLogLevel::Info,
+                        });
+                        outputs.extend(run_parallel_tools(batch, &connections).await);
+                        let _ = tx.send(AgentEvent::ChatProgress {
+                            line: format!("✓ {} tool(s) returned.", batch.len()),
+                            level: ChatLogLevel::Info,
+                        });
+                        continue;
+                    }
+                    ToolSegment::Serial(idx) => idx,
+                };
+                let call = &tool_calls[idx];
                 let _ = tx.send(AgentEvent::ChatProgress {
                     line: format!("⚙ Calling tool: {}", call.name),
                     level: ChatLogLevel::Info,
                 });
 
-                let tool_output = if memory_or_state_query
-                    && !rice_first_satisfied
-                    && is_workspace_or_delegation_tool(&call.name)
-                {
+                let tool_output = if blocked[idx] {
                     rice_first_tool_error()
                 } else if call.name == "spawn_agent" {
                     let spawn_result = handle_spawn_agent_bg(
                         call,
+                        &mut rice,
                         &mcp_snapshots,
                         &next_window_id,
                         &tx,
                         &persona,
                         &skill_context,
-                    );
+                    )
+                    .await;
                     if let Some((window_id, label)) = spawn_result.spawned_window {
                         spawned_windows.push((window_id, label));
                     }
@@ -1371,6 +2388,14 @@ pub fn spawn_chat_task(
                     handle_rice_memories_bg(call, &mut rice, memory_limit).await
                 } else if call.name == "rice_state_get" {
                     handle_rice_state_get_bg(call, &mut rice).await
+                } else if let Some(output) = memory_tools::handle_tool_call(
+                    call,
+                    &mut rice,
+                    &MemoryScope::persona(&agent_name),
+                )
+                .await
+                {
+                    output
                 } else {
                     // MCP tool call.
                     if let Some((server_id, tool_name)) =
@@ -1395,23 +2420,25 @@ pub fn spawn_chat_task(
                         format!(r#"{{"error":"Unknown tool '{}'"}}"#, call.name)
                     }
                 };
-                if is_rice_memory_state_tool(&call.name) {
-                    rice_first_satisfied = true;
-                }
-
-                input.push(json!({
-                    "type": "function_call_output",
-                    "call_id": call.call_id,
-                    "output": tool_output
-                }));
+                outputs.push(tool_output);
             }
+            tool_records.extend(
+                tool_calls
+                    .iter()
+                    .zip(&outputs)
+                    .map(|(call, output)| ToolRecord::new(call, output)),
+            );
+            push_tool_outputs(&mut input, &tool_calls, outputs);
 
             let _ = tx.send(AgentEvent::ChatProgress {
                 line: "⟳ Thinking…".to_string(),
                 level: ChatLogLevel::Info,
             });
 
-            response = match openai.response(&key, &input, tools_opt).await {
+            response = match openai
+                .response_chained(&key, &input, tools_opt, &mut chain)
+                .await
+            {
                 Ok(r) => r,
                 Err(err) => {
                     let _ = tx.send(AgentEvent::ChatProgress {
@@ -1421,6 +2448,8 @@ pub fn spawn_chat_task(
                     break;
                 }
             };
+            served_model =
+                report_response(&tx, UsageScope::Chat, &openai, &response, &mut reasoning);
             output_items = openai::extract_output_items(&response);
             if !output_items.is_empty() {
                 input.extend(output_items.clone());
@@ -1448,32 +2477,247 @@ pub fn spawn_chat_task(
             });
         } else {
             let _ = tx.send(AgentEvent::ChatMarkdown {
-                label: agent_name.clone(),
+                label: format!("{agent_name} · {served_model}"),
                 body: output_text.clone(),
             });
         }
 
         // ── Step 8: Commit to Rice ───────────────────────────────────
         let mut thread_entries = Vec::new();
-        thread_entries.push(json!({"role": "user", "content": message}));
+        // Only references to attachments stay in the thread.
+        thread_entries.push(json!({"role": "user", "content": thread_content}));
         if !output_text.is_empty() {
             thread_entries.push(json!({"role": "assistant", "content": output_text.clone()}));
         }
 
         let aid = rice::agent_id_for(&agent_name);
         let _ = rice
-            .commit_trace(&message, &output_text, "chat", vec![], &aid)
+            .commit_trace(
+                &message,
+                &output_text,
+                &format!("chat (model: {served_model})"),
+                &reasoning.join("\n\n"),
+                vec![],
+                &aid,
+            )
             .await;
 
+        let transcript = TranscriptTurn {
+            at: started_at,
+            finished_at: Local::now().to_rfc3339(),
+            persona: agent_name.clone(),
+            model: served_model,
+            user: message.clone(),
+            tools: tool_records,
+            assistant: output_text.clone(),
+        };
         let _ = tx.send(AgentEvent::ChatFinished {
             user_message: message,
             output_text,
             agent_name,
             thread_entries,
+            transcript: Some(transcript),
         });
     });
 }
 
+// ── Memory compaction ────────────────────────────────────────────────
+
+/// Merge each cluster of near-duplicate memories into one consolidated
+/// fact written by the model, and retire the originals.  Without a key,
+/// or when the model fails, a cluster is left untouched and reported:
+/// nothing is retired that was not merged.  Returns a report of what
+/// changed.  Compaction runs are not committed as traces.
+async fn run_memory_compaction(
+    def: &DaemonTaskDef,
+    openai: &OpenAiClient,
+    key: Option<&str>,
+    rice: &mut RiceStore,
+    tx: &mpsc::UnboundedSender<AgentEvent>,
+) -> String {
+    let clusters = match rice.plan_compaction().await {
+        Ok(clusters) => clusters,
+        Err(err) => return format!("Compaction failed: {err:#}"),
+    };
+    if clusters.is_empty() {
+        return "Memory is already compact; no near-duplicates found.".to_string();
+    }
+
+    let Some(key) = key else {
+        return format!(
+            "No OpenAI key -- left {} cluster(s) of near-duplicates unmerged.",
+            clusters.len()
+        );
+    };
+
+    let total = clusters.len();
+    let mut lines = Vec::new();
+    let mut retired = 0usize;
+    let mut merged = 0usize;
+    let mut skipped = 0usize;
+    for cluster in clusters.iter().take(MEMORY_COMPACT_MAX_CLUSTERS) {
+        let fact = match merge_memory_cluster(def, openai, key, cluster, tx).await {
+            Ok(fact) => fact,
+            Err(err) => {
+                skipped += 1;
+                lines.push(format!("- skipped {}: {err:#}", member_ids(cluster)));
+                continue;
+            }
+        };
+        if let Err(err) = rice.apply_compaction(cluster, &fact).await {
+            lines.push(format!(
+                "- failed to merge {}: {err:#}",
+                member_ids(cluster)
+            ));
+            continue;
+        }
+        merged += 1;
+        retired += cluster.members.len();
+        let preview: String = fact.chars().take(100).collect();
+        lines.push(format!("- {} → {preview}", member_ids(cluster)));
+    }
+
+    let mut report = format!("Merged {retired} memories into {merged} consolidated fact(s).");
+    if skipped > 0 {
+        report.push_str(&format!(
+            " Skipped {skipped} cluster(s); they were kept as-is."
+        ));
+    }
+    if total > MEMORY_COMPACT_MAX_CLUSTERS {
+        report.push_str(&format!(
+            " {} more cluster(s) left for the next run.",
+            total - MEMORY_COMPACT_MAX_CLUSTERS
+        ));
+    }
+    for line in lines {
+        report.push('\n');
+        report.push_str(&line);
+    }
+    report
+}
+
+/// Ask the model for one merged fact.
+async fn merge_memory_cluster(
+    def: &DaemonTaskDef,
+    openai: &OpenAiClient,
+    key: &str,
+    cluster: &rice::MemoryCluster,
+    tx: &mpsc::UnboundedSender<AgentEvent>,
+) -> Result<String> {
+    let input = vec![
+        json!({"role": "system", "content": def.persona}),
+        json!({"role": "user", "content": cluster.describe()}),
+    ];
+    let response = openai.response(key, &input, None).await?;
+    report_response(
+        tx,
+        UsageScope::Recipe(def.name.clone()),
+        openai,
+        &response,
+        &mut Vec::new(),
+    );
+    let items = openai::extract_output_items(&response);
+    let text = openai::extract_output_text(&items);
+    let text = text.trim();
+    if text.is_empty() {
+        bail!("the model returned no merged fact");
+    }
+    Ok(text.to_string())
+}
+
+fn member_ids(cluster: &rice::MemoryCluster) -> String {
+    cluster
+        .members
+        .iter()
+        .map(|stored| stored.id.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// ── Rolling thread summary ───────────────────────────────────────────
+
+/// Condense evicted chat turns (plus the previous summary) into an updated
+/// running summary, commit it to Rice as a trace, and report back with
+/// `ThreadSummary`.
+pub fn spawn_thread_summary(
+    eviction: Eviction,
+    agent_name: String,
+    key: String,
+    tx: mpsc::UnboundedSender<AgentEvent>,
+    openai: OpenAiClient,
+    rice_future: tokio::task::JoinHandle<RiceStore>,
+    rt: tokio::runtime::Handle,
+) {
+    rt.spawn(async move {
+        let openai = with_chat_retry_log(&openai, &tx, "[summary] ");
+        let Eviction {
+            previous_summary,
+            evicted,
+        } = eviction;
+
+        let mut request = String::new();
+        if let Some(previous) = &previous_summary {
+            request.push_str(&format!("Previous summary:\n{previous}\n\n"));
+        }
+        request.push_str(&format!(
+            "Transcript to fold in:\n{}",
+            thread_summary::render_transcript(&evicted)
+        ));
+        let input = vec![
+            json!({"role": "system", "content": crate::prompts::thread_summary_prompt()}),
+            json!({"role": "user", "content": request}),
+        ];
+
+        let summary = match openai.response(&key, &input, None).await {
+            Ok(response) => {
+                report_response(&tx, UsageScope::Chat, &openai, &response, &mut Vec::new());
+                let items = openai::extract_output_items(&response);
+                let text = openai::extract_output_text(&items);
+                if text.trim().is_empty() {
+                    Err("empty summary".to_string())
+                } else {
+                    Ok(text.trim().to_string())
+                }
+            }
+            Err(err) => Err(format!("{err:#}")),
+        };
+
+        if let (Ok(summary), Ok(mut rice)) = (&summary, rice_future.await) {
+            let _ = rice
+                .commit_trace(
+                    &format!("Conversation summary of {} message(s)", evicted.len()),
+                    summary,
+                    "thread-summary",
+                    "",
+                    vec![],
+                    &rice::agent_id_for(&agent_name),
+                )
+                .await;
+        }
+
+        let _ = tx.send(AgentEvent::ThreadSummary { evicted, summary });
+    });
+}
+
+// ── Offline outbox replay ────────────────────────────────────────────
+
+/// Reconnect to Rice and replay queued writes, reporting back with
+/// `OutboxReplayed`.
+pub fn spawn_outbox_replay(tx: mpsc::UnboundedSender<AgentEvent>, rt: tokio::runtime::Handle) {
+    rt.spawn(async move {
+        let mut rice = RiceStore::connect().await;
+        let (lines, warn) = match rice.replay_outbox().await {
+            Ok(report) if report.is_empty() => (Vec::new(), false),
+            Ok(report) => (
+                report.lines(),
+                report.failed > 0 || !report.conflicts.is_empty(),
+            ),
+            Err(err) => (vec![format!("Outbox replay failed: {err:#}")], true),
+        };
+        let _ = tx.send(AgentEvent::OutboxReplayed { lines, warn });
+    });
+}
+
 fn message_requests_memory_or_state(message: &str) -> bool {
     let text = message.to_ascii_lowercase();
     let direct_markers = [
@@ -1528,8 +2772,9 @@ struct SpawnAgentResult {
     spawned_window: Option<(usize, String)>,
 }
 
-fn handle_spawn_agent_bg(
+async fn handle_spawn_agent_bg(
     call: &openai::ToolCall,
+    rice: &mut RiceStore,
     mcp_snapshots: &[McpServerSnapshot],
     next_window_id: &Arc<AtomicUsize>,
     tx: &mpsc::UnboundedSender<AgentEvent>,
@@ -1567,6 +2812,22 @@ fn handle_spawn_agent_bg(
         };
     }
 
+    let output_schema = match spawn_output_schema(&call.arguments) {
+        Ok(schema) => schema,
+        Err(err) => {
+            return SpawnAgentResult {
+                tool_output: json!({ "error": format!("{err:#}") }).to_string(),
+                spawned_window: None,
+            };
+        }
+    };
+    let structured = output_schema.is_some();
+    let max_tool_loops = call
+        .arguments
+        .get("max_tool_loops")
+        .and_then(Value::as_u64)
+        .map(|limit| openai::clamp_tool_loops(limit as usize));
+
     let window_id = next_window_id.fetch_add(1, Ordering::SeqCst);
 
     // Filter snapshots if a specific server was requested.
@@ -1582,6 +2843,12 @@ fn handle_spawn_agent_bg(
 
     let has_mcp = !filtered.is_empty();
 
+    // Join the coordination group before the window exists, so a
+    // collect_results right after this call already sees it as pending.
+    if !coordination_key.is_empty() {
+        let _ = coordination::start_member(rice, &coordination_key, window_id, &label).await;
+    }
+
     // Send event to main thread to create the window + spawn the sub-agent.
     if tx
         .send(AgentEvent::ChatSpawnAgent {
@@ -1590,6 +2857,8 @@ fn handle_spawn_agent_bg(
             prompt: prompt.clone(),
             mcp_snapshots: filtered,
             coordination_key: coordination_key.clone(),
+            output_schema,
+            max_tool_loops,
             persona: persona.to_string(),
             skill_context: skill_context.to_string(),
         })
@@ -1613,13 +2882,37 @@ fn handle_spawn_agent_bg(
             "window_id": window_id,
             "label": label,
             "has_mcp": has_mcp,
-            "coordination_key": coordination_key
+            "coordination_key": coordination_key,
+            "structured_output": structured
         }))
         .unwrap_or_else(|_| r#"{"status":"spawned"}"#.to_string()),
         spawned_window: Some((window_id, spawned_label)),
     }
 }
 
+/// Read `output_schema` (inline object) or `output_schema_path` (file in
+/// the workspace) from `spawn_agent` arguments.
+fn spawn_output_schema(arguments: &Value) -> anyhow::Result<Option<Value>> {
+    if let Some(schema) = arguments.get("output_schema").filter(|v| !v.is_null()) {
+        let schema = match schema {
+            Value::String(raw) => serde_json::from_str(raw)
+                .map_err(|err| anyhow::anyhow!("output_schema is not valid JSON: {err}"))?,
+            other => other.clone(),
+        };
+        output_schema::check_schema(&schema)?;
+        return Ok(Some(schema));
+    }
+    let Some(raw_path) = arguments
+        .get("output_schema_path")
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.trim().is_empty())
+    else {
+        return Ok(None);
+    };
+    let (root, path) = crate::local_tools::resolve_workspace_path(raw_path)?;
+    output_schema::load_schema(&path.to_string_lossy(), &root).map(Some)
+}
+
 fn output_claims_agent_spawn(output: &str) -> bool {
     let text = output.to_ascii_lowercase();
     let explicitly_negative = [
@@ -1678,7 +2971,17 @@ async fn handle_rice_memories_bg(
         .unwrap_or(default_limit)
         .clamp(1, 50);
 
-    let traces = match rice.reminisce(vec![], limit, query).await {
+    let author = call
+        .arguments
+        .get("author")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|author| !author.is_empty());
+    let recalled = match author {
+        Some(author) => rice.recall_by_author(author, limit, query).await,
+        None => rice.recall(vec![], limit, query).await,
+    };
+    let traces = match recalled {
         Ok(value) => value,
         Err(err) => {
             return format!(
@@ -1691,12 +2994,17 @@ async fn handle_rice_memories_bg(
 
     let memories: Vec<Value> = traces
         .into_iter()
-        .map(|trace| {
-            json!({
-                "input": trace.input,
-                "action": trace.action,
-                "outcome": trace.outcome,
-            })
+        .map(|stored| {
+            let mut memory = json!({
+                "input": stored.trace.input,
+                "action": stored.trace.action,
+                "outcome": stored.trace.outcome,
+            });
+            if let Some(author) = rice::trace_author(&stored.trace) {
+                memory["author"] = json!(author.label());
+                memory["written_at"] = json!(author.at);
+            }
+            memory
         })
         .collect();
 
@@ -1751,22 +3059,13 @@ async fn handle_collect_results_bg(call: &openai::ToolCall, rice: &mut RiceStore
     if coordination_key.is_empty() {
         return r#"{"error":"coordination_key is required"}"#.to_string();
     }
+    let wait = call
+        .arguments
+        .get("wait_seconds")
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
 
-    // Scan Rice variables for this coordination key (window IDs 1..50).
-    let mut results: Vec<Value> = Vec::new();
-    for wid in 1..50usize {
-        let coord_var = format!("agent_result:{coordination_key}:{wid}");
-        if let Ok(Some(value)) = rice.get_variable(&coord_var).await {
-            results.push(value);
-        }
-    }
-
-    let summary = json!({
-        "coordination_key": coordination_key,
-        "agent_count": results.len(),
-        "results": results,
-    });
-
+    let summary = coordination::collect(rice, &coordination_key, Duration::from_secs(wait)).await;
     serde_json::to_string(&summary)
         .unwrap_or_else(|_| r#"{"error":"serialize failed"}"#.to_string())
 }
@@ -1774,9 +3073,13 @@ async fn handle_collect_results_bg(call: &openai::ToolCall, rice: &mut RiceStore
 #[cfg(test)]
 mod tests {
     use super::{
-        DaemonTaskDef, is_rice_memory_state_tool, is_workspace_or_delegation_tool,
-        message_requests_memory_or_state, output_claims_agent_spawn, trigger_matches,
+        BuiltinTask, DaemonTaskDef, ToolSegment, builtin_tasks, is_rice_memory_state_tool,
+        is_workspace_or_delegation_tool, mcp_tool_is_read_only, message_requests_memory_or_state,
+        output_claims_agent_spawn, retain_persona_tools, run_memory_compaction, tool_segments,
+        trigger_matches, with_rice_priority_tools,
     };
+    use crate::openai::OpenAiClient;
+    use crate::rice::RiceStore;
 
     #[test]
     fn detects_memory_queries() {
@@ -1812,6 +3115,32 @@ mod tests {
         assert!(!is_rice_memory_state_tool("workspace_list_files"));
     }
 
+    #[test]
+    fn persona_tool_list_narrows_local_and_rice_tools() {
+        let names = |tools: &[serde_json::Value]| -> Vec<String> {
+            tools
+                .iter()
+                .filter_map(|tool| tool.get("name").and_then(|v| v.as_str()))
+                .map(str::to_string)
+                .collect()
+        };
+        let mut tools = with_rice_priority_tools(crate::local_tools::tool_defs());
+        let everything = names(&tools);
+        retain_persona_tools(&mut tools, &[]);
+        assert_eq!(names(&tools), everything);
+
+        tools.push(serde_json::json!({"type": "function", "name": "github__search"}));
+        let allowed = vec![
+            "rice_memories".to_string(),
+            "Workspace_Read_File".to_string(),
+        ];
+        retain_persona_tools(&mut tools, &allowed);
+        assert_eq!(
+            names(&tools),
+            ["rice_memories", "workspace_read_file", "github__search"]
+        );
+    }
+
     #[test]
     fn detects_workspace_and_delegation_tool_names() {
         assert!(is_workspace_or_delegation_tool("spawn_agent"));
@@ -1835,6 +3164,12 @@ mod tests {
             trigger_variables: vec!["deploy.request".to_string(), "ci.*".to_string()],
             tools: vec![],
             paused: false,
+            budget: None,
+            output_schema: None,
+            max_tool_loops: None,
+            hosted_tools: Vec::new(),
+            builtin: None,
+            persona_tools: Vec::new(),
         };
         assert!(trigger_matches(
             &def,
@@ -1863,4 +3198,88 @@ mod tests {
         ));
         assert!(!output_claims_agent_spawn("I did not spawn any worker."));
     }
+
+    #[test]
+    fn read_only_runs_share_a_segment() {
+        let plan = tool_segments(&[true, true, false, true, false, false]);
+        assert_eq!(
+            plan,
+            vec![
+                ToolSegment::Parallel(0..2),
+                ToolSegment::Serial(2),
+                ToolSegment::Parallel(3..4),
+                ToolSegment::Serial(4),
+                ToolSegment::Serial(5),
+            ]
+        );
+        assert!(tool_segments(&[]).is_empty());
+    }
+
+    #[test]
+    fn only_annotated_reads_run_in_parallel() {
+        use rmcp::model::{Tool, ToolAnnotations};
+        let tool = |name: &'static str| Tool::new(name, "", serde_json::Map::new());
+        let tools = vec![
+            tool("list_meetings").annotate(ToolAnnotations::new().read_only(true)),
+            tool("get_page").annotate(ToolAnnotations::new().read_only(false)),
+            tool("get_or_create_issue"),
+            tool("search_and_delete").annotate(ToolAnnotations::new()),
+        ];
+        assert!(mcp_tool_is_read_only(&tools, "list_meetings"));
+        for serial in [
+            "get_page",
+            "get_or_create_issue",
+            "search_and_delete",
+            "missing",
+        ] {
+            assert!(!mcp_tool_is_read_only(&tools, serial), "{serial}");
+        }
+    }
+
+    #[test]
+    fn compaction_without_a_model_retires_nothing() {
+        let dir = std::env::temp_dir().join(format!(
+            "memini-daemon-compact-{}-{:x}",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let mut rice = RiceStore::local_at(&dir).unwrap();
+            for outcome in ["Deploys go to fly.io", "Deploys go to fly.io daily"] {
+                rice.commit_trace("deploy target", outcome, "chat", "", vec![], "memini:coder")
+                    .await
+                    .unwrap();
+            }
+            let def = builtin_tasks()
+                .into_iter()
+                .find(|task| task.builtin == Some(BuiltinTask::MemoryCompact))
+                .unwrap();
+            let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+            let report =
+                run_memory_compaction(&def, &OpenAiClient::new(), None, &mut rice, &tx).await;
+            assert!(report.contains("unmerged"), "{report}");
+            assert_eq!(
+                rice.list_memories(None, None, None, 10)
+                    .await
+                    .unwrap()
+                    .len(),
+                2
+            );
+
+            // A user task that happens to share the name is an ordinary task.
+            let custom: DaemonTaskDef = serde_json::from_value(serde_json::json!({
+                "name": def.name,
+                "persona": "x",
+                "prompt": "x",
+                "interval_secs": 60,
+                "paused": false,
+            }))
+            .unwrap();
+            assert_eq!(custom.builtin, None);
+        });
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
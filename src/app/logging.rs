@@ -1,10 +1,12 @@
 //! Logging primitives for the activity panel.
 //!
-//! Log entries come in two flavours:
+//! Log entries come in three flavours:
 //!
 //! - **Plain** – timestamped single-line messages (system info, warnings, etc.)
 //! - **Markdown** – multi-line rich content from LLM responses, rendered with
 //!   `tui-markdown` for proper headings, bold, code blocks, lists, etc.
+//! - **Reasoning** – reasoning summaries from thinking models, dimmed and
+//!   collapsed to one line until expanded with Ctrl+R.
 
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span, Text};
@@ -41,6 +43,10 @@ impl LogLevel {
 
 // ── Log content ──────────────────────────────────────────────────────
 
+/// Marks reasoning-summary lines in agent window output so they can be
+/// dimmed and collapsed.
+pub const REASONING_PREFIX: &str = "💭 ";
+
 /// The body of a log entry — either a single plain string or pre-parsed
 /// markdown lines ready for rendering.
 #[derive(Clone, Debug)]
@@ -51,6 +57,9 @@ pub enum LogContent {
     /// "memini") is stored separately so it can be rendered as a
     /// header line before the markdown body.
     Markdown { label: String, body: String },
+    /// A model's reasoning summary, rendered dimmed and collapsed to its
+    /// header unless reasoning is expanded (Ctrl+R).
+    Reasoning { label: String, body: String },
 }
 
 // ── Log line ─────────────────────────────────────────────────────────
@@ -70,8 +79,9 @@ impl LogLine {
     ///   `[HH:MM:SS] INFO  message text`
     ///
     /// Markdown entries produce a coloured header line followed by
-    /// tui-markdown–rendered lines for the body.
-    pub fn render(&self) -> Vec<Line<'_>> {
+    /// tui-markdown–rendered lines for the body.  Reasoning entries show
+    /// only their header unless `expand_reasoning` is set.
+    pub fn render(&self, expand_reasoning: bool) -> Vec<Line<'_>> {
         match &self.content {
             LogContent::Plain(msg) => {
                 vec![Line::from(vec![
@@ -115,10 +125,44 @@ impl LogLine {
                 lines.push(Line::raw(""));
                 lines
             }
+            LogContent::Reasoning { label, body } => {
+                let mut lines = vec![Line::from(vec![
+                    Span::styled(
+                        format!("[{}] ", self.timestamp),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(
+                        reasoning_header(label, body.lines().count(), expand_reasoning),
+                        reasoning_style(),
+                    ),
+                ])];
+                if expand_reasoning {
+                    lines.extend(body.lines().map(|line| {
+                        Line::from(Span::styled(format!("  {line}"), reasoning_style()))
+                    }));
+                }
+                lines
+            }
         }
     }
 }
 
+/// Header line for a reasoning block: `▸ label (N lines)` when collapsed.
+pub fn reasoning_header(label: &str, line_count: usize, expanded: bool) -> String {
+    if expanded {
+        format!("▾ {label}")
+    } else {
+        format!("▸ {label} ({line_count} line(s), Ctrl+R to expand)")
+    }
+}
+
+/// Dimmed italic style for reasoning text.
+pub fn reasoning_style() -> Style {
+    Style::default()
+        .fg(Color::DarkGray)
+        .add_modifier(Modifier::ITALIC)
+}
+
 // ── Helpers ──────────────────────────────────────────────────────────
 
 /// Mask a secret key, keeping only the last 4 characters visible.
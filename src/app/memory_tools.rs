@@ -0,0 +1,321 @@
+//! Model-writable memory: the `remember`, `rice_state_set` and
+//! `rice_state_delete` tools.
+//!
+//! Every write is confined to the caller's namespace — `persona:<name>:`
+//! for chat and agent windows, `recipe:<name>:` for auto-agents — so a
+//! model can never overwrite app state such as `openai_api_key` or
+//! `conversation_thread`.  Reads (`rice_state_get`) stay unrestricted.
+
+use serde_json::{Value, json};
+
+use crate::openai;
+use crate::rice::{self, RiceStore};
+
+/// Longest key (after the namespace prefix) a tool may write.
+const MAX_KEY_LEN: usize = 128;
+/// Most tags kept on a `remember` trace.
+const MAX_TAGS: usize = 8;
+
+/// Whose namespace a run writes into.
+#[derive(Clone, Debug)]
+pub struct MemoryScope {
+    kind: &'static str,
+    name: String,
+}
+
+impl MemoryScope {
+    pub fn persona(name: &str) -> Self {
+        MemoryScope {
+            kind: "persona",
+            name: name.to_string(),
+        }
+    }
+
+    pub fn recipe(name: &str) -> Self {
+        MemoryScope {
+            kind: "recipe",
+            name: name.to_string(),
+        }
+    }
+
+    /// Prefix every written key gets, e.g. `persona:coder:`.
+    pub fn prefix(&self) -> String {
+        let slug: String = self
+            .name
+            .trim()
+            .to_ascii_lowercase()
+            .chars()
+            .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '-' })
+            .collect();
+        format!("{}:{slug}:", self.kind)
+    }
+
+    /// Agent id that `remember` traces are committed under.
+    fn agent_id(&self) -> String {
+        rice::agent_id_for(&self.name)
+    }
+
+    /// Resolve a tool-supplied key to the full, namespaced variable name.
+    fn scoped_key(&self, raw: &str) -> Result<String, String> {
+        let prefix = self.prefix();
+        let key = raw.trim();
+        let key = key.strip_prefix(prefix.as_str()).unwrap_or(key);
+        if key.is_empty() {
+            return Err("key is required".to_string());
+        }
+        if key.len() > MAX_KEY_LEN {
+            return Err(format!("key is longer than {MAX_KEY_LEN} characters"));
+        }
+        if !key
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-' | '.' | ':' | '/'))
+        {
+            return Err("key may only contain letters, digits and _ - . : /".to_string());
+        }
+        Ok(format!("{prefix}{key}"))
+    }
+}
+
+pub fn tool_defs() -> Vec<Value> {
+    vec![
+        json!({
+            "type": "function",
+            "name": "remember",
+            "description": "Save an explicit fact to long-term memory so it is recalled in later conversations. Use for durable facts the user states or confirms (preferences, decisions, names, configuration), not for transient chatter.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "fact": {
+                        "type": "string",
+                        "description": "The fact to remember, as one self-contained sentence."
+                    },
+                    "subject": {
+                        "type": "string",
+                        "description": "Optional short topic the fact is about (e.g. 'deploy target'); helps recall."
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Optional tags such as 'preference' or 'infra'."
+                    }
+                },
+                "required": ["fact"]
+            }
+        }),
+        json!({
+            "type": "function",
+            "name": "rice_state_set",
+            "description": "Write a Rice state variable. Keys are stored under your own namespace prefix (returned as `key`); read them back with rice_state_get using that full key.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "key": {
+                        "type": "string",
+                        "description": "Variable name within your namespace (letters, digits, _ - . : /)."
+                    },
+                    "value": {
+                        "description": "Any JSON value to store."
+                    }
+                },
+                "required": ["key", "value"]
+            }
+        }),
+        json!({
+            "type": "function",
+            "name": "rice_state_delete",
+            "description": "Delete a Rice state variable from your own namespace.",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "key": {
+                        "type": "string",
+                        "description": "Variable name within your namespace."
+                    }
+                },
+                "required": ["key"]
+            }
+        }),
+    ]
+}
+
+/// Handle a memory write tool.  Returns `None` for any other tool.
+pub async fn handle_tool_call(
+    call: &openai::ToolCall,
+    rice: &mut RiceStore,
+    scope: &MemoryScope,
+) -> Option<String> {
+    let output = match call.name.as_str() {
+        "remember" => remember(call, rice, scope).await,
+        "rice_state_set" => state_set(call, rice, scope).await,
+        "rice_state_delete" => state_delete(call, rice, scope).await,
+        _ => return None,
+    };
+    Some(output.unwrap_or_else(|error| json!({ "error": error }).to_string()))
+}
+
+async fn remember(
+    call: &openai::ToolCall,
+    rice: &mut RiceStore,
+    scope: &MemoryScope,
+) -> Result<String, String> {
+    let fact = string_arg(call, "fact");
+    if fact.is_empty() {
+        return Err("fact is required".to_string());
+    }
+    let subject = string_arg(call, "subject");
+    let tags: Vec<String> = call
+        .arguments
+        .get("tags")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(|tag| {
+            tag.trim()
+                .trim_start_matches('#')
+                .replace(char::is_whitespace, "-")
+        })
+        .filter(|tag| !tag.is_empty())
+        .take(MAX_TAGS)
+        .collect();
+
+    let mut action = "remember".to_string();
+    for tag in &tags {
+        action.push_str(&format!(" #{tag}"));
+    }
+    let input = if subject.is_empty() {
+        fact.clone()
+    } else {
+        subject
+    };
+    rice.commit_trace(&input, &fact, &action, "", vec![], &scope.agent_id())
+        .await
+        .map_err(|err| format!("remember failed: {err:#}"))?;
+    Ok(json!({ "remembered": fact, "tags": tags }).to_string())
+}
+
+async fn state_set(
+    call: &openai::ToolCall,
+    rice: &mut RiceStore,
+    scope: &MemoryScope,
+) -> Result<String, String> {
+    let key = scope.scoped_key(&string_arg(call, "key"))?;
+    let Some(value) = call.arguments.get("value").cloned() else {
+        return Err("value is required".to_string());
+    };
+    rice.set_variable(&key, value, &format!("tool:{}", scope.prefix()))
+        .await
+        .map_err(|err| format!("rice_state_set failed: {err:#}"))?;
+    Ok(json!({ "key": key, "saved": true }).to_string())
+}
+
+async fn state_delete(
+    call: &openai::ToolCall,
+    rice: &mut RiceStore,
+    scope: &MemoryScope,
+) -> Result<String, String> {
+    let key = scope.scoped_key(&string_arg(call, "key"))?;
+    rice.delete_variable(&key)
+        .await
+        .map_err(|err| format!("rice_state_delete failed: {err:#}"))?;
+    Ok(json!({ "key": key, "deleted": true }).to_string())
+}
+
+fn string_arg(call: &openai::ToolCall, name: &str) -> String {
+    call.arguments
+        .get(name)
+        .and_then(Value::as_str)
+        .unwrap_or("")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_confined_to_the_scope_namespace() {
+        let scope = MemoryScope::persona("Code Reviewer");
+        assert_eq!(scope.prefix(), "persona:code-reviewer:");
+        assert_eq!(
+            scope.scoped_key("openai_api_key").unwrap(),
+            "persona:code-reviewer:openai_api_key"
+        );
+        // Already-prefixed keys are not prefixed twice.
+        assert_eq!(
+            scope.scoped_key("persona:code-reviewer:todo").unwrap(),
+            "persona:code-reviewer:todo"
+        );
+        assert_eq!(
+            MemoryScope::recipe("nightly")
+                .scoped_key("last run")
+                .unwrap_err(),
+            "key may only contain letters, digits and _ - . : /"
+        );
+        assert!(scope.scoped_key("  ").is_err());
+    }
+
+    #[test]
+    fn writes_land_in_the_namespace_and_remember_commits_a_trace() {
+        let dir = std::env::temp_dir().join(format!(
+            "memini-memory-tools-{}-{:x}",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        let call = |name: &str, arguments: Value| openai::ToolCall {
+            name: name.to_string(),
+            arguments,
+            call_id: String::new(),
+        };
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let mut rice = RiceStore::local_at(&dir).unwrap();
+            let scope = MemoryScope::recipe("nightly");
+            rice.set_variable("openai_api_key", json!("sk-keep"), "test")
+                .await
+                .unwrap();
+
+            let set = call(
+                "rice_state_set",
+                json!({"key": "openai_api_key", "value": "clobbered"}),
+            );
+            let output = handle_tool_call(&set, &mut rice, &scope).await.unwrap();
+            assert!(output.contains("recipe:nightly:openai_api_key"), "{output}");
+            assert_eq!(
+                rice.get_variable("openai_api_key").await.unwrap(),
+                Some(json!("sk-keep"))
+            );
+
+            let delete = call("rice_state_delete", json!({"key": "openai_api_key"}));
+            handle_tool_call(&delete, &mut rice, &scope).await.unwrap();
+            assert_eq!(
+                rice.get_variable("recipe:nightly:openai_api_key")
+                    .await
+                    .unwrap(),
+                None
+            );
+
+            let remember = call(
+                "remember",
+                json!({"fact": "Deploys go to fly.io", "tags": ["infra"]}),
+            );
+            handle_tool_call(&remember, &mut rice, &scope)
+                .await
+                .unwrap();
+            let memories = rice.list_memories(None, None, None, 10).await.unwrap();
+            assert_eq!(memories.len(), 1);
+            assert_eq!(memories[0].trace.action, "remember #infra");
+            assert_eq!(memories[0].trace.agent_id, "memini:nightly");
+
+            assert!(
+                handle_tool_call(&call("rice_memories", json!({})), &mut rice, &scope)
+                    .await
+                    .is_none()
+            );
+        });
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
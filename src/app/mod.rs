@@ -4,24 +4,40 @@
 //! for the rest of the binary.  Heavy concerns are delegated to focused
 //! submodules:
 //!
-//! | Module       | Responsibility                            |
-//! |--------------|-------------------------------------------|
-//! | `chat`       | AI chat flow & tool loops                 |
-//! | `commands`   | Slash-command dispatch & handlers          |
-//! | `input`      | Text-input editing (cursor, insert, etc.) |
-//! | `logging`    | `LogLevel`, `LogLine`, `mask_key`         |
-//! | `store`      | Local on-disk MCP credential cache        |
-//! | `ui`         | TUI rendering & status-bar helpers        |
+//! | Module           | Responsibility                            |
+//! |------------------|-------------------------------------------|
+//! | `attachments`    | Files & images attached to chat turns     |
+//! | `chat`           | AI chat flow & tool loops                 |
+//! | `commands`       | Slash-command dispatch & handlers         |
+//! | `coordination`   | Group manifests for `collect_results`     |
+//! | `input`          | Text-input editing (cursor, insert, etc.) |
+//! | `logging`        | `LogLevel`, `LogLine`, `mask_key`         |
+//! | `memory_tools`   | Namespaced `remember` / state-write tools |
+//! | `output_schema`  | Structured-output schemas & validation    |
+//! | `persona_files`  | Persona Markdown files in `~/Memini`      |
+//! | `store`          | Local on-disk MCP credential cache        |
+//! | `thread_summary` | Rolling summary of older chat turns       |
+//! | `transcript`     | Turn transcripts & `/thread export`       |
+//! | `ui`             | TUI rendering & status-bar helpers        |
+//! | `usage`          | Token usage, cost accounting & budgets    |
 
 mod agent_recipes;
 mod agents;
+mod attachments;
 mod chat;
 mod commands;
+mod coordination;
 mod daemon;
 mod input;
 mod logging;
+mod memory_tools;
+mod output_schema;
+mod persona_files;
 mod store;
+mod thread_summary;
+mod transcript;
 mod ui;
+mod usage;
 
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
@@ -34,18 +50,23 @@ use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, Mouse
 use tokio::runtime::Runtime;
 use tokio::sync::mpsc;
 
-use crate::constants::{DEFAULT_MEMORY_LIMIT, MAX_DAEMON_RESULTS, MAX_LOGS};
+use crate::constants::{
+    DEFAULT_MEMORY_LIMIT, DEFAULT_THREAD_NAME, MAX_DAEMON_RESULTS, MAX_LOGS, OUTBOX_RETRY_SECS,
+    USAGE_SAVE_INTERVAL_SECS,
+};
 use crate::mcp::McpConnection;
 use crate::mcp::config::{McpConfig, McpServer, McpSource};
 use crate::mcp::oauth::PendingOAuth;
 use crate::openai::OpenAiClient;
-use crate::rice::{RiceStatus, RiceStore};
+use crate::rice::{JoinKind, JoinMode, RicePool, RiceStatus, RiceStore};
 use crate::util::env_first;
 
 use self::agents::Agent;
 use self::daemon::{AgentEvent, AgentWindow, AgentWindowStatus, ChatLogLevel, DaemonHandle};
 use self::logging::{LogContent, LogLevel, LogLine};
+use self::memory_tools::MemoryScope;
 use self::store::{LocalMcpStore, load_local_mcp_store};
+use self::usage::{BudgetGate, UsageLedger, UsageScope};
 
 // ── View modes ───────────────────────────────────────────────────────
 
@@ -160,6 +181,21 @@ pub struct App {
     pub(crate) custom_agents: Vec<Agent>,
     pub(crate) imported_skills: Vec<crate::skills::LoadedSkill>,
     pub(crate) conversation_thread: Vec<serde_json::Value>,
+    // Name of the thread `conversation_thread` is saved under.
+    pub(crate) active_thread: String,
+    // What each turn of the active thread did, for `/thread export`.
+    pub(crate) thread_transcript: Vec<transcript::TranscriptTurn>,
+    // Background save of the transcript; each save waits for the previous.
+    pub(crate) thread_save: Option<tokio::task::JoinHandle<()>>,
+    // A rolling thread summary is being generated in the background.
+    pub(crate) summarizing_thread: bool,
+    // Backoff after failed thread summaries.
+    pub(crate) summary_retry: thread_summary::SummaryRetry,
+    // A replay of the offline Rice outbox is in flight / next attempt.
+    pub(crate) outbox_replaying: bool,
+    pub(crate) outbox_retry_at: Instant,
+    // Files queued with /attach for the next chat turn.
+    pub(crate) pending_attachments: Vec<attachments::Attachment>,
     pub(crate) openai_key_hint: Option<String>,
     pub(crate) openai_key: Option<String>,
     pub(crate) openai: OpenAiClient,
@@ -168,6 +204,8 @@ pub struct App {
     pub(crate) scroll_offset: u16,
     pub(crate) should_quit: bool,
     pub(crate) show_side_panel: bool,
+    /// Expand reasoning-summary blocks (toggled with Ctrl+R).
+    pub(crate) show_reasoning: bool,
     // Input history (up/down arrow cycling)
     pub(crate) input_history: Vec<String>,
     pub(crate) history_index: Option<usize>,
@@ -176,7 +214,13 @@ pub struct App {
     pub(crate) daemon_tx: mpsc::UnboundedSender<AgentEvent>,
     pub(crate) daemon_rx: mpsc::UnboundedReceiver<AgentEvent>,
     pub(crate) daemon_handles: Vec<DaemonHandle>,
+    // Tasks stopped by a spent budget, kept for `/daemon resume`.
+    pub(crate) budget_paused: Vec<daemon::DaemonTaskDef>,
     pub(crate) daemon_results: Vec<(String, String, String)>, // (task_name, message, timestamp)
+    // Token usage ledger (per turn / window / recipe / day)
+    pub(crate) usage: UsageLedger,
+    pub(crate) usage_saved_at: Instant,
+    pub(crate) budget_gate: BudgetGate,
     pub(crate) rice_trigger_listener: Option<tokio::task::JoinHandle<()>>,
     pub(crate) trigger_last_run: HashMap<String, Instant>,
     pub(crate) window_active_runs: HashMap<usize, usize>,
@@ -231,6 +275,14 @@ impl App {
             custom_agents: Vec::new(),
             imported_skills: Vec::new(),
             conversation_thread: Vec::new(),
+            active_thread: DEFAULT_THREAD_NAME.to_string(),
+            thread_transcript: Vec::new(),
+            thread_save: None,
+            summarizing_thread: false,
+            summary_retry: thread_summary::SummaryRetry::default(),
+            outbox_replaying: false,
+            outbox_retry_at: Instant::now(),
+            pending_attachments: Vec::new(),
             openai_key_hint: None,
             openai_key: None,
             openai: OpenAiClient::new(),
@@ -239,13 +291,18 @@ impl App {
             scroll_offset: 0,
             should_quit: false,
             show_side_panel: false,
+            show_reasoning: false,
             input_history: Vec::new(),
             history_index: None,
             history_stash: String::new(),
             daemon_tx,
             daemon_rx,
             daemon_handles: Vec::new(),
+            budget_paused: Vec::new(),
             daemon_results: Vec::new(),
+            usage: UsageLedger::from_persisted(None),
+            usage_saved_at: Instant::now(),
+            budget_gate: BudgetGate::default(),
             rice_trigger_listener: None,
             trigger_last_run: HashMap::new(),
             window_active_runs: HashMap::new(),
@@ -312,6 +369,14 @@ impl App {
             );
         }
 
+        if let Err(err) = self.load_usage_from_rice() {
+            log_src!(
+                self,
+                LogLevel::Warn,
+                format!("Usage ledger load skipped: {err}")
+            );
+        }
+
         // Auto-connect MCP servers we already have tokens for.
         self.autoconnect_saved_mcps();
 
@@ -331,6 +396,8 @@ impl App {
             }
             _ => {}
         }
+        // Merge personas from $MEMINI_HOME/personas (two-way).
+        self.sync_persona_files();
 
         // Restore active agent.
         match self.runtime.block_on(self.rice.load_active_agent_name()) {
@@ -351,13 +418,14 @@ impl App {
         }
 
         // Restore conversation thread.
-        match self.runtime.block_on(self.rice.load_thread()) {
-            Ok(thread) if !thread.is_empty() => {
-                let turns = thread.len() / 2;
-                self.conversation_thread = thread;
+        match self.load_active_thread() {
+            Ok(turns) if turns > 0 => {
                 self.log(
                     LogLevel::Info,
-                    format!("Picked up where you left off ({turns} turn(s) from Rice)."),
+                    format!(
+                        "Picked up where you left off ({turns} turn(s) of '{}' from Rice).",
+                        self.active_thread
+                    ),
                 );
             }
             Err(err) => {
@@ -368,10 +436,38 @@ impl App {
 
         // Restore shared workspace.
         match self.runtime.block_on(self.rice.load_shared_workspace()) {
-            Ok(Some(name)) => {
-                self.rice.join_workspace(&name);
-                self.log(LogLevel::Info, format!("Rejoined shared workspace: {name}"));
-            }
+            Ok(Some(name)) => match self.runtime.block_on(self.rice.open_workspace(
+                &name,
+                JoinMode::Rejoin,
+                None,
+                None,
+            )) {
+                Ok(join) if join.kind == JoinKind::Unmanaged => {
+                    self.log(
+                        LogLevel::Info,
+                        format!("Rejoined shared workspace: {name} (no member list)"),
+                    );
+                    self.log(
+                        LogLevel::Info,
+                        format!(
+                            "Anyone can join {name}; /share create {name} adds a member list with you as admin."
+                        ),
+                    );
+                }
+                Ok(join) => {
+                    self.log(
+                        LogLevel::Info,
+                        format!("Rejoined shared workspace: {name} ({})", join.role.label()),
+                    );
+                }
+                Err(err) => {
+                    log_src!(
+                        self,
+                        LogLevel::Warn,
+                        format!("Could not rejoin {name}, staying private: {err:#}")
+                    );
+                }
+            },
             Err(err) => {
                 log_src!(
                     self,
@@ -409,6 +505,15 @@ impl App {
         self.should_quit
     }
 
+    /// Save what is written lazily (the usage ledger, a pending
+    /// transcript) before exiting.
+    pub fn flush(&mut self) {
+        if self.usage.dirty {
+            self.persist_usage();
+        }
+        self.wait_for_thread_save();
+    }
+
     fn is_window_waiting(&self, window_id: usize) -> bool {
         self.agent_windows
             .iter()
@@ -513,7 +618,16 @@ impl App {
     }
 
     pub(crate) fn skills_prompt_context(&self, query: &str) -> String {
-        crate::skills::build_prompt_context(&self.imported_skills, query)
+        if self.active_agent.skills.is_empty() {
+            return crate::skills::build_prompt_context(&self.imported_skills, query);
+        }
+        let allowed: Vec<crate::skills::LoadedSkill> = self
+            .imported_skills
+            .iter()
+            .filter(|skill| self.active_agent.allows_skill(&skill.meta.name))
+            .cloned()
+            .collect();
+        crate::skills::build_prompt_context(&allowed, query)
     }
 }
 
@@ -525,6 +639,21 @@ impl App {
     pub fn tick(&mut self) {
         self.tick_count = self.tick_count.wrapping_add(1);
         self.drain_daemon_events();
+        self.maybe_replay_outbox();
+    }
+
+    /// Replay queued Rice writes in the background every
+    /// `OUTBOX_RETRY_SECS` while the outbox is non-empty.
+    fn maybe_replay_outbox(&mut self) {
+        if self.outbox_replaying
+            || Instant::now() < self.outbox_retry_at
+            || crate::rice::outbox_depth() == 0
+        {
+            return;
+        }
+        self.outbox_replaying = true;
+        self.outbox_retry_at = Instant::now() + Duration::from_secs(OUTBOX_RETRY_SECS);
+        daemon::spawn_outbox_replay(self.daemon_tx.clone(), self.runtime.handle().clone());
     }
 
     /// Route a terminal event to the appropriate handler.
@@ -552,6 +681,12 @@ impl App {
                 ..
             } => self.logs.clear(),
 
+            KeyEvent {
+                code: KeyCode::Char('r'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => self.show_reasoning = !self.show_reasoning,
+
             // Ctrl+1 through Ctrl+9: jump straight into an agent session.
             KeyEvent {
                 code: KeyCode::Char(ch @ '1'..='9'),
@@ -858,6 +993,20 @@ impl App {
         }
     }
 
+    /// Append a reasoning summary (rendered collapsed) to the activity log.
+    pub(crate) fn log_reasoning(&mut self, label: String, body: String) {
+        let timestamp = Local::now().format("%H:%M:%S").to_string();
+        self.logs.push(LogLine {
+            timestamp,
+            level: LogLevel::Info,
+            content: LogContent::Reasoning { label, body },
+        });
+        if self.logs.len() > MAX_LOGS {
+            let overflow = self.logs.len() - MAX_LOGS;
+            self.logs.drain(0..overflow);
+        }
+    }
+
     /// Append a message with a source location suffix (debug-logs builds only).
     #[cfg(feature = "debug-logs")]
     pub(crate) fn log_with_src(&mut self, level: LogLevel, message: String, src: &str) {
@@ -929,6 +1078,14 @@ impl App {
                         .unwrap_or_else(|| format!("agent-{window_id}"));
                     self.log_markdown(label, message);
                 }
+                AgentEvent::ResponseId {
+                    window_id,
+                    response_id,
+                } => {
+                    if let Some(win) = self.agent_windows.iter_mut().find(|w| w.id == window_id) {
+                        win.last_response_id = response_id;
+                    }
+                }
                 AgentEvent::NeedsInput {
                     window_id,
                     question,
@@ -988,25 +1145,29 @@ impl App {
                 AgentEvent::ChatMarkdown { label, body } => {
                     self.log_markdown(label, body);
                 }
+                AgentEvent::ChatReasoning { label, body } => {
+                    self.log_reasoning(label, body);
+                }
                 AgentEvent::ChatFinished {
                     user_message: _,
                     output_text: _,
                     agent_name: _,
                     thread_entries,
+                    transcript,
                 } => {
                     // Update conversation thread with this turn.
                     for entry in thread_entries {
                         self.conversation_thread.push(entry);
                     }
-                    // Trim thread if over limit.
-                    let max = crate::constants::MAX_THREAD_MESSAGES;
-                    while self.conversation_thread.len() > max {
-                        self.conversation_thread.drain(0..2);
+                    if let Some(turn) = transcript {
+                        self.record_transcript_turn(turn);
                     }
+                    // Fold the oldest turns into the running summary once
+                    // the thread is over its token budget.
+                    self.maybe_summarize_thread();
                     // Persist thread to Rice (best-effort).
-                    let _ = self
-                        .runtime
-                        .block_on(self.rice.save_thread(&self.conversation_thread));
+                    self.save_thread();
+                    self.usage.finish_turn();
                     self.chat_busy = false;
                 }
                 AgentEvent::ChatSpawnAgent {
@@ -1015,6 +1176,8 @@ impl App {
                     prompt,
                     mcp_snapshots,
                     coordination_key,
+                    output_schema,
+                    max_tool_loops,
                     persona,
                     skill_context,
                 } => {
@@ -1031,6 +1194,9 @@ impl App {
                         skill_context: skill_context.clone(),
                         mcp_snapshots: mcp_snapshots.clone(),
                         coordination_key: coordination_key.clone(),
+                        output_schema: output_schema.clone(),
+                        max_tool_loops,
+                        last_response_id: None,
                     };
                     self.agent_windows.push(window);
                     let idx = self.agent_windows.len().saturating_sub(1);
@@ -1038,19 +1204,30 @@ impl App {
 
                     // Spawn the sub-agent background task.
                     let tx = self.daemon_tx.clone();
-                    let openai = self.openai.clone();
+                    let mut openai = self.budget_gated(
+                        &self.active_agent.apply_to(&self.openai),
+                        UsageScope::Window(window_id),
+                    );
+                    if let Some(limit) = max_tool_loops {
+                        openai = openai.with_max_tool_loops(limit);
+                    }
                     let key = self.openai_key.clone();
-                    let rice_handle = self.runtime.spawn(crate::rice::RiceStore::connect());
-                    let has_mcp = !mcp_snapshots.is_empty();
+                    let rice_handle = self.runtime.spawn(self.rice.connect_alike());
+                    let run = daemon::AgentWindowRun {
+                        window_id,
+                        coordination_key,
+                        output_schema,
+                        previous_response_id: None,
+                        persona,
+                        memory_scope: MemoryScope::persona(&self.active_agent.name),
+                        prompt,
+                        skill_context,
+                        tools: self.active_agent.tools.clone(),
+                    };
 
-                    if has_mcp {
-                        daemon::spawn_agent_window_with_mcp(
-                            window_id,
-                            coordination_key,
-                            persona,
-                            prompt,
-                            skill_context,
-                            mcp_snapshots,
+                    if mcp_snapshots.is_empty() {
+                        daemon::spawn_agent_window(
+                            run,
                             tx,
                             openai,
                             key,
@@ -1058,11 +1235,9 @@ impl App {
                             self.runtime.handle().clone(),
                         );
                     } else {
-                        daemon::spawn_agent_window(
-                            window_id,
-                            persona,
-                            prompt,
-                            skill_context,
+                        daemon::spawn_agent_window_with_mcp(
+                            run,
+                            mcp_snapshots,
                             tx,
                             openai,
                             key,
@@ -1071,6 +1246,16 @@ impl App {
                         );
                     }
                 }
+                AgentEvent::ThreadSummary { evicted, summary } => {
+                    self.finish_thread_summary(evicted, summary);
+                }
+                AgentEvent::Usage {
+                    scope,
+                    model,
+                    usage,
+                } => {
+                    self.record_usage(scope, &model, &usage);
+                }
                 AgentEvent::RiceStateEvent {
                     run_id,
                     event_type,
@@ -1079,8 +1264,40 @@ impl App {
                 } => {
                     self.handle_rice_trigger_event(run_id, event_type, agent_id, payload);
                 }
+                AgentEvent::CoordinationFailed {
+                    coordination_key,
+                    window_id,
+                    error,
+                } => {
+                    let failed = coordination::finish_member(
+                        &mut self.rice,
+                        &coordination_key,
+                        window_id,
+                        coordination::MemberStatus::Failed,
+                        Some(error),
+                    );
+                    if let Err(err) = self.runtime.block_on(failed) {
+                        log_src!(
+                            self,
+                            LogLevel::Warn,
+                            format!("Could not mark agent #{window_id} as failed: {err:#}")
+                        );
+                    }
+                }
+                AgentEvent::OutboxReplayed { lines, warn } => {
+                    self.outbox_replaying = false;
+                    let level = if warn { LogLevel::Warn } else { LogLevel::Info };
+                    for line in lines {
+                        log_src!(self, level.clone(), line);
+                    }
+                }
             }
         }
+        if self.usage.dirty
+            && self.usage_saved_at.elapsed() >= Duration::from_secs(USAGE_SAVE_INTERVAL_SECS)
+        {
+            self.persist_usage();
+        }
     }
 
     /// Focus an agent window by its id — opens the session view.
@@ -1278,6 +1495,7 @@ impl App {
 
         // Reconnect Rice.
         self.log(LogLevel::Info, "⟳ Reconnecting to Rice…".to_string());
+        self.runtime.block_on(RicePool::shared().reset());
         self.rice = self.runtime.block_on(RiceStore::connect());
         match &self.rice.status {
             crate::rice::RiceStatus::Connected => {
@@ -1286,6 +1504,12 @@ impl App {
                     "🎉 Rice connected successfully! You're all set.".to_string(),
                 );
             }
+            crate::rice::RiceStatus::Local(dir) => {
+                self.log(
+                    LogLevel::Warn,
+                    format!("No Rice State URL set -- using local memory in {dir}."),
+                );
+            }
             crate::rice::RiceStatus::Disabled(reason) => {
                 self.log(LogLevel::Warn, format!("Rice connection failed: {reason}"));
                 self.log(
@@ -1304,7 +1528,15 @@ impl App {
         status_line: Option<String>,
         clear_waiting_input: bool,
     ) -> bool {
-        let Some((persona, skill_context, mcp_snapshots, coordination_key)) = self
+        let Some((
+            persona,
+            skill_context,
+            mcp_snapshots,
+            coordination_key,
+            output_schema,
+            max_tool_loops,
+            last_response_id,
+        )) = self
             .agent_windows
             .iter()
             .find(|w| w.id == window_id)
@@ -1314,11 +1546,17 @@ impl App {
                     w.skill_context.clone(),
                     w.mcp_snapshots.clone(),
                     w.coordination_key.clone(),
+                    w.output_schema.clone(),
+                    w.max_tool_loops,
+                    w.last_response_id.clone(),
                 )
             })
         else {
             return false;
         };
+        // Only replies continue the previous exchange; state-triggered runs
+        // start fresh and may run in parallel.
+        let previous_response_id = last_response_id.filter(|_| clear_waiting_input);
 
         if let Some(win) = self.agent_windows.iter_mut().find(|w| w.id == window_id) {
             if let Some(line) = status_line {
@@ -1336,16 +1574,30 @@ impl App {
         }
 
         let tx = self.daemon_tx.clone();
-        let openai = self.openai.clone();
+        let mut openai = self.budget_gated(
+            &self.active_agent.apply_to(&self.openai),
+            UsageScope::Window(window_id),
+        );
+        if let Some(limit) = max_tool_loops {
+            openai = openai.with_max_tool_loops(limit);
+        }
         let key = self.openai_key.clone();
-        let rice_handle = self.runtime.spawn(RiceStore::connect());
+        let rice_handle = self.runtime.spawn(self.rice.connect_alike());
+        let run = daemon::AgentWindowRun {
+            window_id,
+            coordination_key,
+            output_schema,
+            previous_response_id,
+            persona,
+            memory_scope: MemoryScope::persona(&self.active_agent.name),
+            prompt,
+            skill_context,
+            tools: self.active_agent.tools.clone(),
+        };
 
         if mcp_snapshots.is_empty() {
             daemon::spawn_agent_window(
-                window_id,
-                persona,
-                prompt,
-                skill_context,
+                run,
                 tx,
                 openai,
                 key,
@@ -1354,11 +1606,7 @@ impl App {
             );
         } else {
             daemon::spawn_agent_window_with_mcp(
-                window_id,
-                coordination_key,
-                persona,
-                prompt,
-                skill_context,
+                run,
                 mcp_snapshots,
                 tx,
                 openai,
@@ -1471,13 +1719,22 @@ impl App {
     }
 
     /// Spawn a background daemon task, connecting it to the shared channel.
-    pub(crate) fn spawn_daemon_task(&mut self, def: daemon::DaemonTaskDef) {
+    pub(crate) fn spawn_daemon_task(&mut self, mut def: daemon::DaemonTaskDef) {
+        if let Some(reason) = self.daemon_budget_block(&def) {
+            log_src!(
+                self,
+                LogLevel::Warn,
+                format!("Task '{}' not started: {reason}", def.name)
+            );
+            return;
+        }
+        def.persona_tools = self.active_agent.tools.clone();
         let tx = self.daemon_tx.clone();
-        let openai = self.openai.clone();
+        let openai = self.budget_gated(&self.openai, UsageScope::Recipe(def.name.clone()));
         let key = self.openai_key.clone();
 
         // Each daemon task gets its own Rice connection (async).
-        let rice_handle = self.runtime.spawn(RiceStore::connect());
+        let rice_handle = self.runtime.spawn(self.rice.connect_alike());
 
         let handle = daemon::spawn_task(
             def,
@@ -1494,15 +1751,26 @@ impl App {
                 handle.def.name, handle.def.interval_secs
             ),
         );
+        self.budget_paused
+            .retain(|paused| paused.name != handle.def.name);
         self.daemon_handles.push(handle);
     }
 
     /// Fire a one-shot background run of a daemon task definition.
-    pub(crate) fn run_daemon_oneshot(&mut self, def: daemon::DaemonTaskDef) {
+    pub(crate) fn run_daemon_oneshot(&mut self, mut def: daemon::DaemonTaskDef) {
+        if let Some(reason) = self.daemon_budget_block(&def) {
+            log_src!(
+                self,
+                LogLevel::Warn,
+                format!("Skipped run of '{}': {reason}", def.name)
+            );
+            return;
+        }
+        def.persona_tools = self.active_agent.tools.clone();
         let tx = self.daemon_tx.clone();
-        let openai = self.openai.clone();
+        let openai = self.budget_gated(&self.openai, UsageScope::Recipe(def.name.clone()));
         let key = self.openai_key.clone();
-        let rice_handle = self.runtime.spawn(RiceStore::connect());
+        let rice_handle = self.runtime.spawn(self.rice.connect_alike());
 
         self.log(LogLevel::Info, format!("Running '{}' now...", def.name));
         daemon::spawn_oneshot(
@@ -1660,6 +1928,12 @@ impl App {
                 trigger_variables: recipe.trigger_variables.clone(),
                 tools: recipe.tools.clone(),
                 paused: true,
+                budget: recipe.budget,
+                output_schema: recipe.output_schema.clone(),
+                max_tool_loops: recipe.max_tool_loops,
+                hosted_tools: recipe.hosted_tools.clone(),
+                builtin: None,
+                persona_tools: Vec::new(),
             };
             self.run_daemon_oneshot(def);
             started_spawned += 1;
@@ -0,0 +1,336 @@
+//! Structured outputs — JSON Schema loading, Responses API text format, and
+//! a small validator for the schema subset recipes and sub-agents use.
+//!
+//! Schemas come from recipe front matter (`output_schema`) or `spawn_agent`
+//! arguments.  The model is asked for JSON via the Responses API
+//! `text.format` option; the reply is then parsed and checked here so only
+//! well-formed values are stored in Rice as typed JSON.
+
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use serde_json::{Value, json};
+
+/// Resolve an `output_schema` value: inline JSON (`{...}`) or a file path,
+/// relative paths resolved against `base_dir`.
+pub fn load_schema(raw: &str, base_dir: &Path) -> Result<Value> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        bail!("output_schema is empty");
+    }
+    let schema: Value = if raw.starts_with('{') {
+        serde_json::from_str(raw).context("output_schema is not valid JSON")?
+    } else {
+        let path = Path::new(raw);
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            base_dir.join(path)
+        };
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read schema {}", path.display()))?;
+        serde_json::from_str(&text)
+            .with_context(|| format!("schema {} is not valid JSON", path.display()))?
+    };
+    check_schema(&schema)?;
+    Ok(schema)
+}
+
+/// Reject values that cannot act as a top-level output schema.
+pub fn check_schema(schema: &Value) -> Result<()> {
+    let Some(obj) = schema.as_object() else {
+        bail!("output_schema must be a JSON object");
+    };
+    match obj.get("type").and_then(Value::as_str) {
+        Some("object") | None => Ok(()),
+        Some(other) => bail!("output_schema must describe an object (got type '{other}')"),
+    }
+}
+
+/// Build the Responses API `text.format` block for a schema.
+///
+/// `strict` stays off: strict mode rejects common schemas (optional
+/// properties, open objects), and we validate locally anyway.
+pub fn text_format(schema: &Value) -> Value {
+    let name = schema
+        .get("title")
+        .and_then(Value::as_str)
+        .map(sanitize_format_name)
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "output".to_string());
+    json!({
+        "type": "json_schema",
+        "name": name,
+        "schema": schema,
+        "strict": false,
+    })
+}
+
+fn sanitize_format_name(raw: &str) -> String {
+    raw.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(64)
+        .collect()
+}
+
+/// Parse model output as JSON and validate it against `schema`.
+///
+/// Tolerates a surrounding Markdown code fence.  On failure returns the
+/// list of problems (parse error or schema violations).
+pub fn parse_structured_output(text: &str, schema: &Value) -> Result<Value, Vec<String>> {
+    let body = strip_code_fence(text.trim());
+    let value: Value =
+        serde_json::from_str(body).map_err(|err| vec![format!("invalid JSON: {err}")])?;
+    let errors = validate(&value, schema);
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        Err(errors)
+    }
+}
+
+fn strip_code_fence(text: &str) -> &str {
+    let Some(rest) = text.strip_prefix("```") else {
+        return text;
+    };
+    let rest = rest.split_once('\n').map(|(_, body)| body).unwrap_or("");
+    rest.trim_end().strip_suffix("```").unwrap_or(rest).trim()
+}
+
+/// Validate `value` against a JSON Schema, returning human-readable errors.
+///
+/// Supports `type`, `enum`, `const`, `properties`, `required`,
+/// `additionalProperties`, `items`, `minItems`/`maxItems`,
+/// `minLength`/`maxLength`, `minimum`/`maximum`, `anyOf` and `oneOf`.
+/// Unknown keywords are ignored.
+pub fn validate(value: &Value, schema: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_at(value, schema, "$", &mut errors);
+    errors
+}
+
+fn validate_at(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        // `true` / `{}` accept anything; `false` accepts nothing.
+        if schema == &Value::Bool(false) {
+            errors.push(format!("{path}: no value is allowed here"));
+        }
+        return;
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|name| type_matches(value, name)) {
+            errors.push(format!(
+                "{path}: expected {}, got {}",
+                allowed.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(options) = schema.get("enum").and_then(Value::as_array)
+        && !options.contains(value)
+    {
+        errors.push(format!(
+            "{path}: value {value} is not one of {}",
+            Value::Array(options.clone())
+        ));
+    }
+    if let Some(expected) = schema.get("const")
+        && expected != value
+    {
+        errors.push(format!("{path}: expected constant {expected}"));
+    }
+
+    for (keyword, exactly_one) in [("anyOf", false), ("oneOf", true)] {
+        let Some(branches) = schema.get(keyword).and_then(Value::as_array) else {
+            continue;
+        };
+        let matching = branches
+            .iter()
+            .filter(|branch| validate(value, branch).is_empty())
+            .count();
+        if matching == 0 {
+            errors.push(format!("{path}: does not match any {keyword} branch"));
+        } else if exactly_one && matching > 1 {
+            errors.push(format!(
+                "{path}: matches {matching} oneOf branches, expected 1"
+            ));
+        }
+    }
+
+    match value {
+        Value::Object(map) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for key in required.iter().filter_map(Value::as_str) {
+                    if !map.contains_key(key) {
+                        errors.push(format!("{path}: missing required property '{key}'"));
+                    }
+                }
+            }
+            for (key, child) in map {
+                let child_path = format!("{path}.{key}");
+                if let Some(child_schema) = properties.and_then(|props| props.get(key)) {
+                    validate_at(child, child_schema, &child_path, errors);
+                    continue;
+                }
+                match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        errors.push(format!("{path}: unexpected property '{key}'"));
+                    }
+                    Some(extra @ Value::Object(_)) => {
+                        validate_at(child, extra, &child_path, errors);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64)
+                && (items.len() as u64) < min
+            {
+                errors.push(format!("{path}: expected at least {min} item(s)"));
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64)
+                && (items.len() as u64) > max
+            {
+                errors.push(format!("{path}: expected at most {max} item(s)"));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (idx, item) in items.iter().enumerate() {
+                    validate_at(item, item_schema, &format!("{path}[{idx}]"), errors);
+                }
+            }
+        }
+        Value::String(text) => {
+            let len = text.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64)
+                && len < min
+            {
+                errors.push(format!("{path}: shorter than {min} character(s)"));
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64)
+                && len > max
+            {
+                errors.push(format!("{path}: longer than {max} character(s)"));
+            }
+        }
+        Value::Number(number) => {
+            let Some(number) = number.as_f64() else {
+                return;
+            };
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64)
+                && number < min
+            {
+                errors.push(format!("{path}: {number} is below minimum {min}"));
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64)
+                && number > max
+            {
+                errors.push(format!("{path}: {number} is above maximum {max}"));
+            }
+        }
+        Value::Bool(_) | Value::Null => {}
+    }
+}
+
+fn type_matches(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::String(_) => "string",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::Bool(_) => "boolean",
+        Value::Null => "null",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{parse_structured_output, text_format, validate};
+
+    fn report_schema() -> serde_json::Value {
+        json!({
+            "title": "Repo Report",
+            "type": "object",
+            "properties": {
+                "status": {"type": "string", "enum": ["ok", "failing"]},
+                "failures": {"type": "array", "items": {"type": "string"}},
+                "score": {"type": "integer", "minimum": 0, "maximum": 100}
+            },
+            "required": ["status"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn validates_typed_output() {
+        let schema = report_schema();
+        let ok = json!({"status": "ok", "failures": [], "score": 90});
+        assert!(validate(&ok, &schema).is_empty());
+
+        let bad = json!({"status": "meh", "failures": [1], "score": 120, "extra": true});
+        let errors = validate(&bad, &schema);
+        assert_eq!(errors.len(), 4, "{errors:?}");
+        assert!(errors.iter().any(|e| e.starts_with("$.failures[0]:")));
+        assert!(
+            errors
+                .iter()
+                .any(|e| e.contains("unexpected property 'extra'"))
+        );
+
+        let missing = validate(&json!({}), &schema);
+        assert_eq!(
+            missing,
+            vec!["$: missing required property 'status'".to_string()]
+        );
+    }
+
+    #[test]
+    fn parses_fenced_json_and_reports_errors() {
+        let schema = report_schema();
+        let value = parse_structured_output("```json\n{\"status\": \"failing\"}\n```", &schema)
+            .expect("fenced JSON should parse");
+        assert_eq!(value["status"], "failing");
+
+        let errors = parse_structured_output("all good!", &schema).unwrap_err();
+        assert!(errors[0].starts_with("invalid JSON"));
+    }
+
+    #[test]
+    fn text_format_uses_sanitized_title() {
+        let format = text_format(&report_schema());
+        assert_eq!(format["type"], "json_schema");
+        assert_eq!(format["name"], "Repo_Report");
+        assert_eq!(format["strict"], false);
+    }
+}
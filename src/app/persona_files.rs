@@ -0,0 +1,419 @@
+//! File-backed persona definitions in `$MEMINI_HOME/personas`.
+//!
+//! Persona files are Markdown with the same front matter style as agent
+//! recipes; the body is the persona text:
+//!
+//! ```text
+//! ---
+//! name: coder
+//! description: focused pair programmer
+//! model: gpt-5
+//! fallback_models: gpt-5-mini,gpt-4.1
+//! reasoning: high
+//! temperature: 0.2
+//! max_tool_loops: 12
+//! hosted_tools: web_search,file_search:vs_123
+//! tools: rice_memories,spawn_agent,collect_results
+//! mcp_servers: github
+//! skills: code-review
+//! updated_at: 2026-10-18T09:30:00+02:00
+//! ---
+//! You are a senior engineer. Prefer small, verified changes.
+//! ```
+//!
+//! Files are kept in sync with the `custom_agents` list in Rice so personas
+//! can be reviewed in git and shared across a team.  Each side carries the
+//! persona's `updated_at`, and the newer one wins.  Deleted personas leave
+//! a tombstone in Rice, so a stale file on another machine is removed
+//! instead of bringing the persona back.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, FixedOffset};
+
+use super::agent_recipes::{memini_home, sanitize_name, split_front_matter, yaml_quote};
+use super::agents::Agent;
+
+/// Persona files that failed to load, as `(path, error)`.
+pub type PersonaFileErrors = Vec<(PathBuf, String)>;
+
+/// Load every `*.md` persona file.  Files that fail to parse are reported
+/// instead of aborting the whole load.
+pub fn load_persona_files() -> Result<(Vec<Agent>, PersonaFileErrors)> {
+    let dir = ensure_personas_dir()?;
+    let mut agents: Vec<Agent> = Vec::new();
+    let mut errors = Vec::new();
+
+    for entry in fs::read_dir(&dir).with_context(|| format!("Read {}", dir.display()))? {
+        let path = entry?.path();
+        let is_md = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.eq_ignore_ascii_case("md"))
+            .unwrap_or(false);
+        if !is_md {
+            continue;
+        }
+        let parsed = fs::read_to_string(&path)
+            .with_context(|| format!("Read {}", path.display()))
+            .and_then(|raw| parse_persona(&path, &raw));
+        match parsed {
+            Ok(agent) if agents.iter().any(|a| a.name == agent.name) => {
+                errors.push((path, format!("duplicate persona name '{}'", agent.name)));
+            }
+            Ok(agent) => agents.push(agent),
+            Err(err) => errors.push((path, format!("{err:#}"))),
+        }
+    }
+
+    agents.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok((agents, errors))
+}
+
+/// Parse a persona file; `source` supplies the fallback name (file stem).
+pub fn parse_persona(source: &Path, raw: &str) -> Result<Agent> {
+    let (front_matter, body) = split_front_matter(raw);
+
+    let fallback_name = source
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or("persona");
+    let name = front_matter
+        .get("name")
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| fallback_name.to_string());
+    if name == "memini" {
+        bail!("'memini' is the built-in persona and cannot be redefined");
+    }
+
+    let description = front_matter
+        .get("description")
+        .map(|value| value.trim().to_string())
+        .unwrap_or_default();
+
+    let persona = if !body.trim().is_empty() {
+        body.trim().to_string()
+    } else if let Some(value) = front_matter.get("persona") {
+        value.trim().to_string()
+    } else {
+        crate::prompts::custom_persona(&name, &description)
+    };
+
+    let mut agent = Agent {
+        name,
+        description,
+        persona,
+        updated_at: front_matter
+            .get("updated_at")
+            .map(|value| value.trim().to_string())
+            .unwrap_or_default(),
+        ..Agent::default()
+    };
+
+    // Reuse `/agent set` parsing so files and commands accept the same values.
+    let settings = [
+        ("model", &["model"][..]),
+        ("fallback", &["fallback", "fallback_models"][..]),
+        (
+            "reasoning",
+            &["reasoning", "reasoning_effort", "thinking"][..],
+        ),
+        ("temperature", &["temperature"][..]),
+        ("tool_loops", &["max_tool_loops", "tool_loops"][..]),
+        ("hosted", &["hosted_tools", "hosted"][..]),
+        ("tools", &["tools"][..]),
+        ("mcp", &["mcp_servers", "mcp"][..]),
+        ("skills", &["skills"][..]),
+    ];
+    for (field, keys) in settings {
+        let Some(value) = keys.iter().find_map(|key| front_matter.get(*key)) else {
+            continue;
+        };
+        if value.trim().is_empty() {
+            continue;
+        }
+        agent
+            .set_field(field, value)
+            .map_err(|err| anyhow!("invalid {field}: {err}"))?;
+    }
+    Ok(agent)
+}
+
+/// Render a persona as Markdown with front matter.
+pub fn render_persona(agent: &Agent) -> String {
+    let mut out = String::from("---\n");
+    out.push_str(&format!("name: {}\n", agent.name));
+    out.push_str(&format!(
+        "description: {}\n",
+        yaml_quote(&agent.description)
+    ));
+    if let Some(model) = &agent.model {
+        out.push_str(&format!("model: {model}\n"));
+    }
+    if !agent.fallback_models.is_empty() {
+        out.push_str(&format!(
+            "fallback_models: {}\n",
+            agent.fallback_models.join(",")
+        ));
+    }
+    if let Some(effort) = &agent.reasoning_effort {
+        out.push_str(&format!("reasoning: {effort}\n"));
+    }
+    if let Some(temperature) = agent.temperature {
+        out.push_str(&format!("temperature: {temperature}\n"));
+    }
+    if let Some(limit) = agent.max_tool_loops {
+        out.push_str(&format!("max_tool_loops: {limit}\n"));
+    }
+    if !agent.hosted_tools.is_empty() {
+        out.push_str(&format!("hosted_tools: {}\n", agent.hosted_tools.join(",")));
+    }
+    if !agent.tools.is_empty() {
+        out.push_str(&format!("tools: {}\n", agent.tools.join(",")));
+    }
+    if !agent.mcp_servers.is_empty() {
+        out.push_str(&format!("mcp_servers: {}\n", agent.mcp_servers.join(",")));
+    }
+    if !agent.skills.is_empty() {
+        out.push_str(&format!("skills: {}\n", agent.skills.join(",")));
+    }
+    if !agent.updated_at.is_empty() {
+        out.push_str(&format!("updated_at: {}\n", agent.updated_at));
+    }
+    out.push_str("---\n");
+    out.push_str(agent.persona.trim());
+    out.push('\n');
+    out
+}
+
+/// Deleted persona names and when they were deleted (RFC 3339).
+pub type Tombstones = BTreeMap<String, String>;
+
+/// Outcome of [`merge_personas`].
+#[derive(Debug, Default)]
+pub struct PersonaMerge {
+    /// The merged persona list for Rice.
+    pub agents: Vec<Agent>,
+    /// Whether `agents` differs from the Rice list it started from.
+    pub rice_changed: bool,
+    /// Personas whose file is missing or older than Rice's copy.
+    pub write_files: Vec<Agent>,
+    /// Files of personas deleted after their last update.
+    pub remove_files: Vec<String>,
+    pub tombstones: Tombstones,
+}
+
+fn stamp(raw: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(raw.trim()).ok()
+}
+
+/// Merge persona files with the Rice list.  For each name the newest of
+/// the file, the Rice entry and a tombstone wins; unstamped personas count
+/// as oldest, and a tie between differing copies goes to the file.
+pub fn merge_personas(files: Vec<Agent>, rice: Vec<Agent>, tombstones: Tombstones) -> PersonaMerge {
+    let mut names: Vec<String> = files
+        .iter()
+        .chain(&rice)
+        .map(|agent| agent.name.clone())
+        .collect();
+    names.sort();
+    names.dedup();
+
+    let same = |a: &Agent, b: &Agent| serde_json::to_value(a).ok() == serde_json::to_value(b).ok();
+    let mut merge = PersonaMerge {
+        tombstones,
+        ..PersonaMerge::default()
+    };
+    for name in names {
+        let file = files.iter().find(|agent| agent.name == name);
+        let stored = rice.iter().find(|agent| agent.name == name);
+        let newest = match (file, stored) {
+            (Some(file), Some(stored)) if stamp(&stored.updated_at) > stamp(&file.updated_at) => {
+                stored
+            }
+            (Some(file), _) => file,
+            (None, Some(stored)) => stored,
+            (None, None) => continue,
+        };
+        let deleted = merge
+            .tombstones
+            .get(&name)
+            .is_some_and(|deleted_at| stamp(deleted_at) >= stamp(&newest.updated_at));
+        if deleted {
+            merge.rice_changed |= stored.is_some();
+            if file.is_some() {
+                merge.remove_files.push(name);
+            }
+            continue;
+        }
+        merge.tombstones.remove(&name);
+        if file.is_none_or(|file| !same(file, newest)) {
+            merge.write_files.push(newest.clone());
+        }
+        merge.rice_changed |= stored.is_none_or(|stored| !same(stored, newest));
+        merge.agents.push(newest.clone());
+    }
+    // Keep the Rice order for personas that were already there.
+    merge.agents.sort_by_key(|agent| {
+        rice.iter()
+            .position(|stored| stored.name == agent.name)
+            .unwrap_or(usize::MAX)
+    });
+    merge
+}
+
+/// Write (or overwrite) the file for `agent` in the personas directory.
+pub fn write_persona_file(agent: &Agent) -> Result<PathBuf> {
+    let path = persona_file_path(&agent.name)?;
+    fs::create_dir_all(personas_dir())
+        .with_context(|| format!("Create {}", personas_dir().display()))?;
+    fs::write(&path, render_persona(agent)).with_context(|| format!("Write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Remove the file for persona `name`, if present.
+pub fn remove_persona_file(name: &str) -> Result<Option<PathBuf>> {
+    let path = persona_file_path(name)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    fs::remove_file(&path).with_context(|| format!("Remove {}", path.display()))?;
+    Ok(Some(path))
+}
+
+pub fn persona_file_path(name: &str) -> Result<PathBuf> {
+    Ok(personas_dir().join(format!("{}.md", sanitize_name(name)?)))
+}
+
+pub fn ensure_personas_dir() -> Result<PathBuf> {
+    let dir = personas_dir();
+    fs::create_dir_all(&dir).with_context(|| format!("Create {}", dir.display()))?;
+    Ok(dir)
+}
+
+pub fn personas_dir() -> PathBuf {
+    memini_home().join("personas")
+}
+
+/// Fetch persona Markdown from a URL (GitHub `blob` links are rewritten to
+/// their raw form).
+pub async fn fetch_persona_source(url: &str) -> Result<String> {
+    let url = url
+        .strip_prefix("https://github.com/")
+        .and_then(|rest| rest.split_once("/blob/"))
+        .map(|(repo, path)| format!("https://raw.githubusercontent.com/{repo}/{path}"))
+        .unwrap_or_else(|| url.to_string());
+    let response = reqwest::Client::new()
+        .get(&url)
+        .timeout(std::time::Duration::from_secs(30))
+        .send()
+        .await
+        .with_context(|| format!("GET {url}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        bail!("GET {url} returned {status}");
+    }
+    response.text().await.context("Read persona body")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn persona_round_trips_through_markdown() {
+        let raw = r#"---
+name: coder
+description: focused pair programmer
+model: gpt-5
+reasoning: high
+temperature: 0.2
+hosted_tools: web_search,code_interpreter
+tools: rice_memories,spawn_agent
+mcp_servers: github
+---
+You are a senior engineer.
+"#;
+        let agent = parse_persona(Path::new("coder.md"), raw).expect("parse persona");
+        assert_eq!(agent.name, "coder");
+        assert_eq!(agent.model.as_deref(), Some("gpt-5"));
+        assert_eq!(agent.reasoning_effort.as_deref(), Some("high"));
+        assert_eq!(agent.temperature, Some(0.2));
+        assert_eq!(agent.tools, vec!["rice_memories", "spawn_agent"]);
+        assert_eq!(agent.mcp_servers, vec!["github"]);
+        assert_eq!(agent.persona, "You are a senior engineer.");
+
+        let again = parse_persona(Path::new("x.md"), &render_persona(&agent)).expect("reparse");
+        assert_eq!(again.name, "coder");
+        assert_eq!(again.description, "focused pair programmer");
+        assert_eq!(again.tools, agent.tools);
+        assert_eq!(again.hosted_tools, vec!["web_search", "code_interpreter"]);
+        assert_eq!(again.persona, agent.persona);
+    }
+
+    #[test]
+    fn persona_without_body_uses_generated_persona() {
+        let raw = "---\ndescription: quick notes\n---\n";
+        let agent = parse_persona(Path::new("notes.md"), raw).expect("parse persona");
+        assert_eq!(agent.name, "notes");
+        assert!(agent.persona.contains("notes"));
+        assert!(parse_persona(Path::new("memini.md"), raw).is_err());
+    }
+
+    fn stamped(name: &str, persona: &str, updated_at: &str) -> Agent {
+        Agent {
+            name: name.to_string(),
+            persona: persona.to_string(),
+            updated_at: updated_at.to_string(),
+            ..Agent::default()
+        }
+    }
+
+    #[test]
+    fn newer_side_wins_and_deletions_stick() {
+        let t1 = "2026-10-01T09:00:00+00:00";
+        let t2 = "2026-10-02T09:00:00+00:00";
+        let t3 = "2026-10-03T09:00:00+00:00";
+
+        // Deleted elsewhere after this machine's file was last written.
+        let tombstones = Tombstones::from([("coder".to_string(), t2.to_string())]);
+        let merge = merge_personas(vec![stamped("coder", "old", t1)], Vec::new(), tombstones);
+        assert!(merge.agents.is_empty());
+        assert_eq!(merge.remove_files, vec!["coder"]);
+        assert!(merge.write_files.is_empty());
+        assert!(!merge.rice_changed);
+
+        // Recreated after the deletion: it comes back and the tombstone goes.
+        let tombstones = Tombstones::from([("coder".to_string(), t2.to_string())]);
+        let merge = merge_personas(Vec::new(), vec![stamped("coder", "new", t3)], tombstones);
+        assert_eq!(merge.agents.len(), 1);
+        assert_eq!(merge.write_files[0].persona, "new");
+        assert!(merge.tombstones.is_empty());
+
+        // An `/agent set` elsewhere beats this machine's older file.
+        let merge = merge_personas(
+            vec![stamped("coder", "stale", t1), stamped("notes", "mine", "")],
+            vec![stamped("coder", "fresh", t2)],
+            Tombstones::new(),
+        );
+        let names: Vec<&str> = merge.agents.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["coder", "notes"]);
+        assert_eq!(merge.agents[0].persona, "fresh");
+        assert_eq!(merge.write_files.len(), 1);
+        assert_eq!(merge.write_files[0].persona, "fresh");
+        assert!(merge.rice_changed);
+
+        // A newer (or hand-edited, same-stamp) file updates Rice.
+        let merge = merge_personas(
+            vec![stamped("coder", "edited", t2)],
+            vec![stamped("coder", "fresh", t2)],
+            Tombstones::new(),
+        );
+        assert_eq!(merge.agents[0].persona, "edited");
+        assert!(merge.rice_changed && merge.write_files.is_empty());
+    }
+}
@@ -0,0 +1,374 @@
+//! Rolling conversation summary.
+//!
+//! When the chat thread grows past [`THREAD_SUMMARY_TRIGGER_TOKENS`]
+//! (estimated), the oldest turns are handed to a background task that folds
+//! them into a running summary.  The summary lives at the front of
+//! `conversation_thread` as a pinned system message, so the model keeps the
+//! gist of long sessions instead of silently losing it.
+//!
+//! A failed summary keeps its turns and is retried on later turns, backing
+//! off after repeated failures.  Only a thread past
+//! [`THREAD_HARD_CAP_TOKENS`] has its oldest turns dropped unsummarized.
+
+use serde_json::{Value, json};
+
+use crate::constants::{
+    THREAD_HARD_CAP_TOKENS, THREAD_KEEP_RECENT_MESSAGES, THREAD_SUMMARY_MAX_RETRY_WAIT,
+    THREAD_SUMMARY_PREFIX, THREAD_SUMMARY_TRIGGER_TOKENS,
+};
+
+use super::App;
+use super::attachments;
+use super::daemon;
+use super::log_src;
+use super::logging::LogLevel;
+
+/// Oldest turns selected for summarization.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Eviction {
+    pub previous_summary: Option<String>,
+    pub evicted: Vec<Value>,
+}
+
+/// Backoff between summary attempts after failures, counted in turns.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SummaryRetry {
+    failures: u32,
+    wait_turns: u32,
+}
+
+impl SummaryRetry {
+    /// Whether a summary may be attempted this turn; counts down the wait.
+    pub fn ready(&mut self) -> bool {
+        if self.wait_turns == 0 {
+            return true;
+        }
+        self.wait_turns -= 1;
+        false
+    }
+
+    /// Record a failure: retry next turn, then skip 1, 3, 7… turns.
+    pub fn failed(&mut self) {
+        self.failures = self.failures.saturating_add(1);
+        self.wait_turns = (1u32 << (self.failures - 1).min(8)).saturating_sub(1);
+        self.wait_turns = self.wait_turns.min(THREAD_SUMMARY_MAX_RETRY_WAIT);
+    }
+
+    pub fn succeeded(&mut self) {
+        *self = SummaryRetry::default();
+    }
+}
+
+/// Rough token estimate for thread messages (~4 characters per token plus
+/// a small per-message overhead).
+pub fn estimate_tokens(messages: &[Value]) -> usize {
+    messages
+        .iter()
+        .map(|msg| match msg.get("content") {
+            Some(Value::String(text)) => text.chars().count().div_ceil(4) + 4,
+            Some(Value::Array(parts)) => {
+                // Attachment parts: count text, size images by their dimensions.
+                let part_tokens: usize = parts
+                    .iter()
+                    .map(|part| {
+                        if let Some(text) = part.get("text").and_then(Value::as_str) {
+                            text.chars().count().div_ceil(4)
+                        } else if let Some(url) = part.get("image_url").and_then(Value::as_str) {
+                            attachments::image_url_tokens(url)
+                        } else {
+                            part.to_string().chars().count().div_ceil(4)
+                        }
+                    })
+                    .sum();
+                part_tokens + 4
+            }
+            Some(other) => other.to_string().chars().count().div_ceil(4) + 4,
+            None => 4,
+        })
+        .sum()
+}
+
+/// The pinned summary text, if the thread starts with one.
+pub fn pinned_summary(thread: &[Value]) -> Option<&str> {
+    thread
+        .first()
+        .filter(|msg| msg.get("role").and_then(Value::as_str) == Some("system"))
+        .and_then(|msg| msg.get("content").and_then(Value::as_str))
+        .and_then(|content| content.strip_prefix(THREAD_SUMMARY_PREFIX))
+        .map(str::trim)
+}
+
+fn summary_message(summary: &str) -> Value {
+    json!({
+        "role": "system",
+        "content": format!("{THREAD_SUMMARY_PREFIX}\n{}", summary.trim()),
+    })
+}
+
+/// Decide which messages to fold into the summary, if the thread is over
+/// budget.  The kept tail always starts at a user message so turns stay
+/// intact.
+pub fn plan_eviction(
+    thread: &[Value],
+    trigger_tokens: usize,
+    keep_recent: usize,
+) -> Option<Eviction> {
+    if estimate_tokens(thread) <= trigger_tokens {
+        return None;
+    }
+    let previous_summary = pinned_summary(thread).map(str::to_string);
+    let start = usize::from(previous_summary.is_some());
+    let mut end = thread.len().saturating_sub(keep_recent).max(start);
+    while end > start
+        && end < thread.len()
+        && thread[end].get("role").and_then(Value::as_str) != Some("user")
+    {
+        end -= 1;
+    }
+    if end <= start {
+        return None;
+    }
+    Some(Eviction {
+        previous_summary,
+        evicted: thread[start..end].to_vec(),
+    })
+}
+
+/// Replace `evicted` (which must still sit right after the pinned summary)
+/// with `summary`, or simply drop it when `summary` is `None`.
+///
+/// Returns `false` if the thread changed underneath us (cleared, switched
+/// agent, imported) and nothing was applied.
+pub fn apply_summary(thread: &mut Vec<Value>, evicted: &[Value], summary: Option<&str>) -> bool {
+    let start = usize::from(pinned_summary(thread).is_some());
+    let end = start + evicted.len();
+    if evicted.is_empty() || thread.get(start..end) != Some(evicted) {
+        return false;
+    }
+    thread.drain(start..end);
+    if let Some(summary) = summary {
+        if start == 1 {
+            thread[0] = summary_message(summary);
+        } else {
+            thread.insert(0, summary_message(summary));
+        }
+    }
+    true
+}
+
+/// Last resort after a failed summary: drop `evicted` unsummarized, but
+/// only once the thread is past `hard_cap`.  Returns whether it was dropped.
+pub fn drop_past_cap(thread: &mut Vec<Value>, evicted: &[Value], hard_cap: usize) -> bool {
+    estimate_tokens(thread) > hard_cap && apply_summary(thread, evicted, None)
+}
+
+/// Plain-text transcript of thread messages for the summarizer prompt.
+pub fn render_transcript(messages: &[Value]) -> String {
+    messages
+        .iter()
+        .map(|msg| {
+            let role = msg.get("role").and_then(Value::as_str).unwrap_or("message");
+            let content = match msg.get("content") {
+                Some(Value::String(text)) => text.clone(),
+                Some(Value::Array(parts)) => parts
+                    .iter()
+                    .map(|part| match part.get("text").and_then(Value::as_str) {
+                        Some(text) => text.to_string(),
+                        None => "[image]".to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                Some(other) => other.to_string(),
+                None => String::new(),
+            };
+            format!("{role}: {content}")
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+impl App {
+    /// Start a background summary of the oldest turns once the thread is
+    /// over its token budget, unless backing off after a failure.
+    pub(crate) fn maybe_summarize_thread(&mut self) {
+        if self.summarizing_thread {
+            return;
+        }
+        let Some(eviction) = plan_eviction(
+            &self.conversation_thread,
+            THREAD_SUMMARY_TRIGGER_TOKENS,
+            THREAD_KEEP_RECENT_MESSAGES,
+        ) else {
+            return;
+        };
+        if !self.summary_retry.ready() {
+            self.drop_thread_past_cap(&eviction.evicted);
+            return;
+        }
+
+        let Some(key) = self.openai_key.clone() else {
+            self.finish_thread_summary(eviction.evicted, Err("no OpenAI key".to_string()));
+            return;
+        };
+
+        self.summarizing_thread = true;
+        self.log(
+            LogLevel::Info,
+            format!(
+                "Summarizing {} older message(s) to keep the thread compact…",
+                eviction.evicted.len()
+            ),
+        );
+        let rice_handle = self.runtime.spawn(self.rice.connect_alike());
+        daemon::spawn_thread_summary(
+            eviction,
+            self.active_agent.name.clone(),
+            key,
+            self.daemon_tx.clone(),
+            self.openai.clone(),
+            rice_handle,
+            self.runtime.handle().clone(),
+        );
+    }
+
+    /// Fold a finished summary into the thread and persist it.  A failed
+    /// summary keeps the turns for a later retry.
+    pub(crate) fn finish_thread_summary(
+        &mut self,
+        evicted: Vec<Value>,
+        summary: Result<String, String>,
+    ) {
+        self.summarizing_thread = false;
+        let summary = match summary {
+            Ok(summary) => summary,
+            Err(err) => {
+                self.summary_retry.failed();
+                log_src!(
+                    self,
+                    LogLevel::Warn,
+                    format!(
+                        "Thread summary failed ({err}); keeping {} older message(s) and retrying on a later turn.",
+                        evicted.len()
+                    )
+                );
+                self.drop_thread_past_cap(&evicted);
+                return;
+            }
+        };
+        self.summary_retry.succeeded();
+        if !apply_summary(&mut self.conversation_thread, &evicted, Some(&summary)) {
+            return;
+        }
+        self.save_thread();
+        // The thread may still be over budget after a long burst of turns.
+        self.maybe_summarize_thread();
+    }
+
+    fn drop_thread_past_cap(&mut self, evicted: &[Value]) {
+        if drop_past_cap(
+            &mut self.conversation_thread,
+            evicted,
+            THREAD_HARD_CAP_TOKENS,
+        ) {
+            log_src!(
+                self,
+                LogLevel::Warn,
+                format!(
+                    "Thread is past its size cap with no summary; dropped {} oldest message(s).",
+                    evicted.len()
+                )
+            );
+            self.save_thread();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{
+        SummaryRetry, apply_summary, drop_past_cap, estimate_tokens, pinned_summary, plan_eviction,
+    };
+
+    fn turn(user: &str, assistant: &str) -> Vec<serde_json::Value> {
+        vec![
+            json!({"role": "user", "content": user}),
+            json!({"role": "assistant", "content": assistant}),
+        ]
+    }
+
+    #[test]
+    fn plans_eviction_on_token_budget_and_keeps_turns_whole() {
+        let mut thread = Vec::new();
+        for i in 0..6 {
+            thread.extend(turn(&format!("question {i} {}", "x".repeat(200)), "answer"));
+        }
+        assert!(plan_eviction(&thread, estimate_tokens(&thread), 4).is_none());
+
+        let plan = plan_eviction(&thread, 100, 3).expect("over budget");
+        assert_eq!(plan.previous_summary, None);
+        // Keeping 3 would split a turn; the kept tail grows to start at a user message.
+        assert_eq!(plan.evicted.len(), 8);
+        assert_eq!(plan.evicted[0]["content"], thread[0]["content"]);
+    }
+
+    #[test]
+    fn applies_rolling_summary_as_pinned_message() {
+        let mut thread = Vec::new();
+        for i in 0..4 {
+            thread.extend(turn(&format!("q{i}"), &format!("a{i}")));
+        }
+        let evicted = thread[..4].to_vec();
+        assert!(apply_summary(&mut thread, &evicted, Some("first summary")));
+        assert_eq!(thread.len(), 5);
+        assert_eq!(pinned_summary(&thread), Some("first summary"));
+
+        let plan = plan_eviction(&thread, 0, 2).expect("over budget");
+        assert_eq!(plan.previous_summary.as_deref(), Some("first summary"));
+        assert!(apply_summary(
+            &mut thread,
+            &plan.evicted,
+            Some("second summary")
+        ));
+        assert_eq!(thread.len(), 3);
+        assert_eq!(pinned_summary(&thread), Some("second summary"));
+        assert_eq!(thread[1]["content"], "q3");
+
+        // A thread that changed underneath the summarizer is left alone.
+        assert!(!apply_summary(&mut thread, &evicted, Some("stale")));
+    }
+
+    #[test]
+    fn failed_summaries_keep_turns_and_back_off() {
+        let mut thread = Vec::new();
+        for i in 0..6 {
+            thread.extend(turn(&format!("q{i} {}", "x".repeat(400)), "answer"));
+        }
+        let plan = plan_eviction(&thread, 100, 2).expect("over budget");
+        let before = thread.clone();
+        // Under the hard cap a failure changes nothing.
+        let cap = estimate_tokens(&thread);
+        assert!(!drop_past_cap(&mut thread, &plan.evicted, cap));
+        assert_eq!(thread, before);
+        // Past it, the oldest turns go as a last resort.
+        assert!(drop_past_cap(&mut thread, &plan.evicted, 100));
+        assert_eq!(thread.len(), 2);
+
+        let mut retry = SummaryRetry::default();
+        assert!(retry.ready());
+        retry.failed();
+        assert!(retry.ready(), "first retry is on the next turn");
+        retry.failed();
+        assert!(!retry.ready());
+        assert!(retry.ready());
+        retry.failed();
+        assert_eq!((0..4).filter(|_| retry.ready()).count(), 1);
+        for _ in 0..20 {
+            retry.failed();
+        }
+        assert_eq!((0..10).filter(|_| !retry.ready()).count(), 8);
+        retry.succeeded();
+        assert!(retry.ready());
+    }
+}
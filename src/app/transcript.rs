@@ -0,0 +1,518 @@
+//! Chat transcripts and `/thread export`.
+//!
+//! `conversation_thread` holds only what the model is sent (and older turns
+//! get folded into a summary), so each finished chat turn is also recorded
+//! as a [`TranscriptTurn`]: when it ran, the persona and model, every tool
+//! call with its output, and the reply.  `/thread export` renders those
+//! turns, optionally followed by the activity log, as Markdown, JSON or
+//! HTML.  Threads saved before transcripts existed export their messages
+//! without the metadata.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::Local;
+use pulldown_cmark::{Event, Parser, Tag, TagEnd, html};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::constants::TRANSCRIPT_TOOL_OUTPUT_MAX_CHARS;
+use crate::openai::ToolCall;
+
+use super::logging::{LogContent, LogLine};
+use super::thread_summary;
+
+pub const EXPORT_USAGE: &str = "Usage: /thread export <path> [--format md|json|html] [--logs]";
+
+/// One tool call made during a turn.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolRecord {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Value,
+    #[serde(default)]
+    pub output: String,
+}
+
+impl ToolRecord {
+    /// Record `call` and its output, truncating very long outputs.
+    pub fn new(call: &ToolCall, output: &str) -> Self {
+        let output = match output.char_indices().nth(TRANSCRIPT_TOOL_OUTPUT_MAX_CHARS) {
+            Some((cut, _)) => format!(
+                "{}\n… [truncated, {} chars total]",
+                &output[..cut],
+                output.chars().count()
+            ),
+            None => output.to_string(),
+        };
+        ToolRecord {
+            name: call.name.clone(),
+            arguments: call.arguments.clone(),
+            output,
+        }
+    }
+}
+
+/// One finished chat turn.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptTurn {
+    /// RFC 3339 time the message was sent.
+    pub at: String,
+    /// RFC 3339 time the reply finished.
+    #[serde(default)]
+    pub finished_at: String,
+    #[serde(default)]
+    pub persona: String,
+    #[serde(default)]
+    pub model: String,
+    pub user: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<ToolRecord>,
+    #[serde(default)]
+    pub assistant: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+    Html,
+}
+
+impl ExportFormat {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Some(ExportFormat::Markdown),
+            "json" => Some(ExportFormat::Json),
+            "html" | "htm" => Some(ExportFormat::Html),
+            _ => None,
+        }
+    }
+
+    /// Format implied by `path`'s extension, Markdown otherwise.
+    fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(ExportFormat::parse)
+            .unwrap_or(ExportFormat::Markdown)
+    }
+}
+
+/// Parsed `/thread export` arguments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExportRequest {
+    pub path: PathBuf,
+    pub format: ExportFormat,
+    pub include_logs: bool,
+}
+
+impl ExportRequest {
+    /// Parse the arguments after `/thread export`.
+    pub fn parse(args: &[&str]) -> Result<Self> {
+        let mut path = None;
+        let mut format = None;
+        let mut include_logs = false;
+        let mut iter = args.iter();
+        while let Some(&arg) = iter.next() {
+            match arg {
+                "--format" | "-f" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| anyhow!("--format needs a value"))?;
+                    format =
+                        Some(ExportFormat::parse(value).ok_or_else(|| {
+                            anyhow!("unknown format '{value}' (md, json or html)")
+                        })?);
+                }
+                "--logs" => include_logs = true,
+                flag if flag.starts_with("--") => bail!("unknown option {flag}\n{EXPORT_USAGE}"),
+                value if path.is_none() => path = Some(PathBuf::from(value)),
+                value => bail!("unexpected argument '{value}'\n{EXPORT_USAGE}"),
+            }
+        }
+        let path = path.ok_or_else(|| anyhow!("{EXPORT_USAGE}"))?;
+        Ok(ExportRequest {
+            format: format.unwrap_or_else(|| ExportFormat::from_path(&path)),
+            path,
+            include_logs,
+        })
+    }
+}
+
+/// Everything `/thread export` writes.
+pub struct ThreadExport<'a> {
+    pub thread: &'a str,
+    pub workspace: Option<&'a str>,
+    pub turns: &'a [TranscriptTurn],
+    /// Model-facing messages, exported when there are no recorded turns.
+    pub messages: &'a [Value],
+    pub logs: Option<&'a [LogLine]>,
+}
+
+impl ThreadExport<'_> {
+    /// Render in `format` and write to `path`.
+    pub fn write(&self, path: &Path, format: ExportFormat) -> Result<()> {
+        let body = match format {
+            ExportFormat::Markdown => self.render_markdown(),
+            ExportFormat::Json => {
+                serde_json::to_string_pretty(&self.to_json()).context("serialize thread export")?
+            }
+            ExportFormat::Html => self.render_html(),
+        };
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent).with_context(|| format!("create {}", parent.display()))?;
+        }
+        fs::write(path, body).with_context(|| format!("write {}", path.display()))
+    }
+
+    fn title(&self) -> String {
+        match self.workspace {
+            Some(workspace) => format!("Memini thread: {} ({workspace})", self.thread),
+            None => format!("Memini thread: {}", self.thread),
+        }
+    }
+
+    pub fn render_markdown(&self) -> String {
+        let mut out = format!(
+            "# {}\n\nExported {}.\n",
+            self.title(),
+            Local::now().format("%Y-%m-%d %H:%M")
+        );
+        for turn in self.turns {
+            out.push_str(&format!(
+                "\n---\n\n## {}\n\n_{}{}_\n\n### You\n\n{}\n",
+                short_time(&turn.at),
+                turn.persona,
+                if turn.model.is_empty() {
+                    String::new()
+                } else {
+                    format!(" · {}", turn.model)
+                },
+                turn.user.trim()
+            ));
+            for tool in &turn.tools {
+                let arguments = pretty_json(&tool.arguments);
+                let arguments_fence = fence_for(&arguments);
+                let output_fence = fence_for(&tool.output);
+                out.push_str(&format!(
+                    "\n<details>\n<summary>Tool: {}</summary>\n\n{arguments_fence}json\n{arguments}\n{arguments_fence}\n\n{output_fence}\n{}\n{output_fence}\n\n</details>\n",
+                    tool.name, tool.output
+                ));
+            }
+            out.push_str(&format!(
+                "\n### {}\n\n{}\n",
+                turn.persona,
+                turn.assistant.trim()
+            ));
+        }
+        if self.turns.is_empty() {
+            for (role, content) in self.messages.iter().map(message_text) {
+                out.push_str(&format!("\n---\n\n### {role}\n\n{}\n", content.trim()));
+            }
+        }
+        if let Some(logs) = self.logs {
+            out.push_str("\n---\n\n## Activity log\n");
+            for line in logs {
+                match &line.content {
+                    LogContent::Plain(text) => out.push_str(&format!(
+                        "\n`{} {}` {}\n",
+                        line.timestamp,
+                        line.level.label(),
+                        text
+                    )),
+                    LogContent::Markdown { label, body }
+                    | LogContent::Reasoning { label, body } => out.push_str(&format!(
+                        "\n#### {label} ({})\n\n{}\n",
+                        line.timestamp,
+                        body.trim()
+                    )),
+                }
+            }
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut export = json!({
+            "thread": self.thread,
+            "workspace": self.workspace,
+            "exported_at": Local::now().to_rfc3339(),
+            "turns": self.turns,
+        });
+        if self.turns.is_empty() {
+            export["messages"] = Value::Array(self.messages.to_vec());
+        }
+        if let Some(logs) = self.logs {
+            export["logs"] = logs
+                .iter()
+                .map(|line| {
+                    let (kind, label, text) = match &line.content {
+                        LogContent::Plain(text) => ("plain", None, text),
+                        LogContent::Markdown { label, body } => ("markdown", Some(label), body),
+                        LogContent::Reasoning { label, body } => ("reasoning", Some(label), body),
+                    };
+                    json!({
+                        "timestamp": line.timestamp,
+                        "level": line.level.label(),
+                        "kind": kind,
+                        "label": label,
+                        "text": text,
+                    })
+                })
+                .collect();
+        }
+        export
+    }
+
+    pub fn render_html(&self) -> String {
+        let title = escape_html(&self.title());
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{title}</title>\n<style>{HTML_STYLE}</style></head>\n<body>\n<h1>{title}</h1>\n"
+        );
+        for turn in self.turns {
+            out.push_str(&format!(
+                "<section class=\"turn\">\n<p class=\"meta\">{} · {}{}</p>\n<div class=\"user\"><h3>You</h3>{}</div>\n",
+                escape_html(&short_time(&turn.at)),
+                escape_html(&turn.persona),
+                if turn.model.is_empty() {
+                    String::new()
+                } else {
+                    format!(" · {}", escape_html(&turn.model))
+                },
+                markdown_html(&turn.user)
+            ));
+            for tool in &turn.tools {
+                out.push_str(&format!(
+                    "<details class=\"tool\"><summary>Tool: {}</summary><pre>{}</pre><pre>{}</pre></details>\n",
+                    escape_html(&tool.name),
+                    escape_html(&pretty_json(&tool.arguments)),
+                    escape_html(&tool.output)
+                ));
+            }
+            out.push_str(&format!(
+                "<div class=\"assistant\"><h3>{}</h3>{}</div>\n</section>\n",
+                escape_html(&turn.persona),
+                markdown_html(&turn.assistant)
+            ));
+        }
+        if self.turns.is_empty() {
+            for (role, content) in self.messages.iter().map(message_text) {
+                out.push_str(&format!(
+                    "<section class=\"turn\"><h3>{}</h3>{}</section>\n",
+                    escape_html(&role),
+                    markdown_html(&content)
+                ));
+            }
+        }
+        if let Some(logs) = self.logs {
+            out.push_str("<h2>Activity log</h2>\n<div class=\"logs\">\n");
+            for line in logs {
+                match &line.content {
+                    LogContent::Plain(text) => out.push_str(&format!(
+                        "<div class=\"log\"><code>{} {}</code> {}</div>\n",
+                        escape_html(&line.timestamp),
+                        line.level.label(),
+                        escape_html(text)
+                    )),
+                    LogContent::Markdown { label, body }
+                    | LogContent::Reasoning { label, body } => out.push_str(&format!(
+                        "<div class=\"log\"><h4>{} ({})</h4>{}</div>\n",
+                        escape_html(label),
+                        escape_html(&line.timestamp),
+                        markdown_html(body)
+                    )),
+                }
+            }
+            out.push_str("</div>\n");
+        }
+        out.push_str("</body></html>\n");
+        out
+    }
+}
+
+const HTML_STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:52rem;margin:2rem auto;padding:0 1rem;line-height:1.5}\
+.turn{border-top:1px solid #ddd;padding:1rem 0}.meta{color:#777;font-size:.9em}\
+pre{background:#f6f8fa;padding:.75rem;overflow-x:auto;white-space:pre-wrap}\
+.tool summary{cursor:pointer;color:#555}.log{margin:.25rem 0}";
+
+/// Role and plain text of a thread message.
+fn message_text(msg: &Value) -> (String, String) {
+    let role = msg
+        .get("role")
+        .and_then(Value::as_str)
+        .unwrap_or("message")
+        .to_string();
+    let content = thread_summary::render_transcript(std::slice::from_ref(msg));
+    let content = content
+        .strip_prefix(&format!("{role}: "))
+        .unwrap_or(&content)
+        .to_string();
+    (role, content)
+}
+
+/// `YYYY-MM-DD HH:MM:SS` from an RFC 3339 timestamp.
+fn short_time(at: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(at)
+        .map(|at| at.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|_| at.to_string())
+}
+
+fn pretty_json(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+}
+
+/// A backtick fence longer than any backtick run in `text`, so the text
+/// can sit inside it unchanged.
+fn fence_for(text: &str) -> String {
+    let longest = text.split(|ch| ch != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Markdown to HTML, with raw HTML in the source shown as text.  Links
+/// keep only http(s) and mailto targets (others are reduced to their
+/// text), and images become plain links, so opening an export runs no
+/// script and loads nothing remote.
+fn markdown_html(markdown: &str) -> String {
+    // Whether each open link/image was rewritten to plain text.
+    let mut unlinked = Vec::new();
+    let events = Parser::new(markdown).filter_map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Some(Event::Text(raw)),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        })
+        | Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => {
+            let safe = safe_link(&dest_url);
+            unlinked.push(!safe);
+            safe.then_some(Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }))
+        }
+        Event::End(TagEnd::Link | TagEnd::Image) => {
+            (!unlinked.pop().unwrap_or(false)).then_some(Event::End(TagEnd::Link))
+        }
+        other => Some(other),
+    });
+    let mut out = String::new();
+    html::push_html(&mut out, events);
+    out
+}
+
+/// Whether a link target is safe to keep in an exported page.
+fn safe_link(url: &str) -> bool {
+    let url = url.trim().to_ascii_lowercase();
+    ["http://", "https://", "mailto:"]
+        .iter()
+        .any(|scheme| url.starts_with(scheme))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::logging::LogLevel;
+
+    #[test]
+    fn exports_turns_tools_and_logs_in_each_format() {
+        let request = ExportRequest::parse(&["notes/incident.html", "--logs"]).unwrap();
+        assert_eq!(request.format, ExportFormat::Html);
+        assert!(request.include_logs);
+        let request = ExportRequest::parse(&["out.txt", "--format", "json"]).unwrap();
+        assert_eq!(request.format, ExportFormat::Json);
+        assert!(ExportRequest::parse(&["--logs"]).is_err());
+
+        let call = ToolCall {
+            name: "rice_memories".to_string(),
+            arguments: json!({"query": "deploy"}),
+            call_id: "c1".to_string(),
+        };
+        let long = "x".repeat(TRANSCRIPT_TOOL_OUTPUT_MAX_CHARS + 10);
+        assert!(ToolRecord::new(&call, &long).output.contains("[truncated"));
+        let turns = vec![TranscriptTurn {
+            at: "2026-10-18T09:30:00+02:00".to_string(),
+            finished_at: "2026-10-18T09:30:05+02:00".to_string(),
+            persona: "coder".to_string(),
+            model: "gpt-5".to_string(),
+            user: "Where do we deploy?".to_string(),
+            tools: vec![ToolRecord::new(&call, "[\"fly.io\"]")],
+            assistant: "We deploy to **fly.io**.".to_string(),
+        }];
+        let logs = vec![LogLine {
+            timestamp: "09:30:05".to_string(),
+            level: LogLevel::Info,
+            content: LogContent::Markdown {
+                label: "coder".to_string(),
+                body: "# Plan\n- ship <it>".to_string(),
+            },
+        }];
+        let export = ThreadExport {
+            thread: "main",
+            workspace: None,
+            turns: &turns,
+            messages: &[],
+            logs: Some(&logs),
+        };
+
+        let md = export.render_markdown();
+        assert!(md.contains("2026-10-18 09:30:00"), "{md}");
+        assert!(md.contains("coder · gpt-5"));
+        assert!(md.contains("Tool: rice_memories"));
+        assert!(md.contains("# Plan\n- ship <it>"));
+        // Outputs are fenced with a longer fence than they contain, unchanged.
+        assert_eq!(fence_for("plain"), "```");
+        assert_eq!(fence_for("a ```rust``` b ````"), "`````");
+
+        let value = export.to_json();
+        assert_eq!(value["turns"][0]["tools"][0]["output"], "[\"fly.io\"]");
+        assert_eq!(value["logs"][0]["kind"], "markdown");
+
+        let html = export.render_html();
+        assert!(html.contains("<strong>fly.io</strong>"));
+        assert!(html.contains("<h1>Plan</h1>"));
+        assert!(!html.contains("<it>"));
+        let links = markdown_html(
+            "[docs](https://example.com) [x](javascript:alert(1)) ![pixel](https://t.example/p.png) <mailto:a@b.c>",
+        );
+        assert!(
+            links.contains(r#"<a href="https://example.com">docs</a>"#),
+            "{links}"
+        );
+        assert!(!links.contains("javascript"), "{links}");
+        assert!(links.contains("</a> x "), "{links}");
+        assert!(!links.contains("<img"), "{links}");
+        assert!(
+            links.contains(r#"<a href="https://t.example/p.png">pixel</a>"#),
+            "{links}"
+        );
+        assert!(links.contains(r#"href="mailto:a@b.c""#), "{links}");
+
+        // Threads without recorded turns fall back to their messages.
+        let messages = vec![json!({"role": "user", "content": "hello"})];
+        let legacy = ThreadExport {
+            turns: &[],
+            messages: &messages,
+            logs: None,
+            ..export
+        };
+        assert!(legacy.render_markdown().contains("### user\n\nhello"));
+    }
+}
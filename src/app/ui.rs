@@ -12,6 +12,8 @@ use super::App;
 use super::RiceSetupStep;
 use super::ViewMode;
 use super::daemon::AgentWindowStatus;
+use super::logging::{REASONING_PREFIX, reasoning_header, reasoning_style};
+use super::usage::{format_tokens, format_usd};
 
 /// Animated spinner frames for the thinking indicator.
 const SPINNER: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
@@ -416,31 +418,9 @@ impl App {
             );
 
             let inner_height = rows[1].height.saturating_sub(2) as usize;
-            let display_lines: Vec<Line> = window
-                .output_lines
-                .iter()
-                .rev()
-                .take(inner_height.max(1))
-                .collect::<Vec<_>>()
-                .into_iter()
-                .rev()
-                .map(|s| {
-                    let color = if s.starts_with(">>") {
-                        Color::Rgb(255, 105, 180)
-                    } else if s.starts_with("--") {
-                        Color::Rgb(80, 80, 80)
-                    } else if s.starts_with("Thinking")
-                        || s.starts_with("Recalling")
-                        || s.starts_with("Saving")
-                        || s.starts_with("Found")
-                    {
-                        Color::Yellow
-                    } else {
-                        Color::White
-                    };
-                    Line::from(Span::styled(format!(" {s}"), Style::default().fg(color)))
-                })
-                .collect();
+            let mut display_lines = agent_output_lines(&window.output_lines, self.show_reasoning);
+            let overflow = display_lines.len().saturating_sub(inner_height.max(1));
+            display_lines.drain(..overflow);
 
             let panel = Paragraph::new(Text::from(display_lines))
                 .block(
@@ -562,11 +542,34 @@ impl App {
                 self.rice.status_label(),
                 Style::default().fg(self.rice_status_color()),
             ),
-            Span::styled(
-                format!("  ↩ {thread_turns}"),
-                Style::default().fg(Color::Rgb(100, 100, 100)),
-            ),
         ]);
+        let queued = crate::rice::outbox_depth();
+        if queued > 0 {
+            spans.push(Span::styled(
+                format!("  ⇡ {queued} queued"),
+                Style::default().fg(Color::Rgb(255, 165, 0)),
+            ));
+        }
+        spans.push(Span::styled(
+            format!("  ↩ {} {thread_turns}", self.active_thread),
+            Style::default().fg(Color::Rgb(100, 100, 100)),
+        ));
+        let usage_today = self.usage.today();
+        if usage_today.requests > 0 {
+            let color = if self.usage.global_budget_exceeded() {
+                Color::Rgb(220, 20, 60)
+            } else {
+                Color::Rgb(100, 100, 100)
+            };
+            spans.push(Span::styled(
+                format!(
+                    "  Σ {} {}",
+                    format_tokens(usage_today.tokens.total()),
+                    format_usd(usage_today.cost_usd)
+                ),
+                Style::default().fg(color),
+            ));
+        }
         if daemon_count > 0 {
             spans.push(Span::styled(
                 format!("  ⚙ {daemon_count}"),
@@ -604,7 +607,11 @@ impl App {
         let inner_width = area.width.saturating_sub(2);
         let inner_height = area.height.saturating_sub(2) as usize;
 
-        let log_lines: Vec<Line> = self.logs.iter().flat_map(|l| l.render()).collect();
+        let log_lines: Vec<Line> = self
+            .logs
+            .iter()
+            .flat_map(|l| l.render(self.show_reasoning))
+            .collect();
         let log_paragraph = Paragraph::new(Text::from(log_lines)).wrap(Wrap { trim: false });
 
         let total_visual = log_paragraph.line_count(inner_width);
@@ -700,6 +707,7 @@ impl App {
     fn rice_status_color(&self) -> Color {
         match self.rice.status {
             RiceStatus::Connected => Color::Rgb(0, 255, 136),
+            RiceStatus::Local(_) => Color::Rgb(255, 200, 0),
             RiceStatus::Disabled(_) => Color::Rgb(80, 80, 80),
         }
     }
@@ -768,3 +776,50 @@ impl App {
         frame.render_widget(Paragraph::new(Line::from(keys)), area);
     }
 }
+
+/// Style agent window output, folding each run of reasoning-summary lines
+/// into one dimmed header unless `expand_reasoning` is set.
+fn agent_output_lines(output: &[String], expand_reasoning: bool) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut idx = 0;
+    while idx < output.len() {
+        let s = &output[idx];
+        if s.starts_with(REASONING_PREFIX) {
+            let run = output[idx..]
+                .iter()
+                .take_while(|line| line.starts_with(REASONING_PREFIX))
+                .count();
+            lines.push(Line::from(Span::styled(
+                format!(" {}", reasoning_header("reasoning", run, expand_reasoning)),
+                reasoning_style(),
+            )));
+            if expand_reasoning {
+                lines.extend(output[idx..idx + run].iter().map(|line| {
+                    let text = line.trim_start_matches(REASONING_PREFIX);
+                    Line::from(Span::styled(format!("   {text}"), reasoning_style()))
+                }));
+            }
+            idx += run;
+            continue;
+        }
+        let color = if s.starts_with(">>") {
+            Color::Rgb(255, 105, 180)
+        } else if s.starts_with("--") {
+            Color::Rgb(80, 80, 80)
+        } else if s.starts_with("Thinking")
+            || s.starts_with("Recalling")
+            || s.starts_with("Saving")
+            || s.starts_with("Found")
+        {
+            Color::Yellow
+        } else {
+            Color::White
+        };
+        lines.push(Line::from(Span::styled(
+            format!(" {s}"),
+            Style::default().fg(color),
+        )));
+        idx += 1;
+    }
+    lines
+}
@@ -0,0 +1,526 @@
+//! Token usage and cost accounting with daily budgets.
+//!
+//! Every `/responses` reply carries a `usage` block.  Background tasks
+//! forward it to the TUI as an `AgentEvent::Usage`, and the app folds it
+//! into a [`UsageLedger`] that tracks totals per chat turn, agent window,
+//! recipe, and day.  The persistent parts (daily totals, per-recipe
+//! totals, price overrides, global budget) are stored in Rice.
+//!
+//! Budgets are daily caps expressed either in tokens (`50000`, `50k`,
+//! `2M tokens`) or in dollars (`$2.50`, `2.5usd`).  Dollar costs are
+//! computed from a price table (USD per 1M tokens) that can be overridden
+//! with `MEMINI_PRICES="gpt-4o=2.5/10,gpt-4o-mini=0.15/0.6"` or `/usage price`.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::constants::USAGE_HISTORY_DAYS;
+use crate::openai::{RequestGate, TokenUsage};
+use crate::util::env_first;
+
+/// Built-in prices (USD per 1M input / output tokens), matched by prefix.
+const DEFAULT_PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("gpt-5-nano", 0.05, 0.40),
+    ("gpt-5-mini", 0.25, 2.00),
+    ("gpt-5", 1.25, 10.00),
+    ("o4-mini", 1.10, 4.40),
+    ("o3-mini", 1.10, 4.40),
+    ("o3", 2.00, 8.00),
+];
+
+/// Where a batch of tokens was spent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UsageScope {
+    /// The main chat (accumulated into the current turn).
+    Chat,
+    /// A live agent window, by id.
+    Window(usize),
+    /// A background recipe / daemon task, by name.
+    Recipe(String),
+}
+
+/// Accumulated token counts and cost for one bucket.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct UsageTotals {
+    #[serde(default)]
+    pub requests: u64,
+    #[serde(default)]
+    pub tokens: TokenUsage,
+    #[serde(default)]
+    pub cost_usd: f64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, usage: &TokenUsage, cost_usd: f64) {
+        self.requests += 1;
+        self.tokens.input_tokens += usage.input_tokens;
+        self.tokens.output_tokens += usage.output_tokens;
+        self.tokens.reasoning_tokens += usage.reasoning_tokens;
+        self.cost_usd += cost_usd;
+    }
+
+    /// One-line human summary (`12.3k tokens (in 10k / out 2.3k, reasoning 1.1k) · $0.0400`).
+    pub fn summary(&self) -> String {
+        let mut text = format!(
+            "{} tokens (in {} / out {}",
+            format_tokens(self.tokens.total()),
+            format_tokens(self.tokens.input_tokens),
+            format_tokens(self.tokens.output_tokens)
+        );
+        if self.tokens.reasoning_tokens > 0 {
+            text.push_str(&format!(
+                ", reasoning {}",
+                format_tokens(self.tokens.reasoning_tokens)
+            ));
+        }
+        text.push_str(&format!(
+            ") · {} · {} request(s)",
+            format_usd(self.cost_usd),
+            self.requests
+        ));
+        text
+    }
+}
+
+/// USD price per one million tokens for a model.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+impl ModelPrice {
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        (usage.input_tokens as f64 * self.input_per_mtok
+            + usage.output_tokens as f64 * self.output_per_mtok)
+            / 1_000_000.0
+    }
+}
+
+/// A daily spending cap.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "limit", rename_all = "lowercase")]
+pub enum Budget {
+    Tokens(u64),
+    Dollars(f64),
+}
+
+impl Budget {
+    /// Parse `50000`, `50k`, `2M tokens`, `$2.50`, `2.5usd`, or `3 dollars`.
+    pub fn parse(raw: &str) -> Option<Self> {
+        let text = raw.trim().to_ascii_lowercase().replace([',', '_'], "");
+        if text.is_empty() {
+            return None;
+        }
+
+        if let Some(amount) = text.strip_prefix('$') {
+            return parse_dollars(amount);
+        }
+        for suffix in ["usd", "dollars", "dollar"] {
+            if let Some(amount) = text.strip_suffix(suffix) {
+                return parse_dollars(amount);
+            }
+        }
+
+        let amount = ["tokens", "token", "tok"]
+            .iter()
+            .find_map(|suffix| text.strip_suffix(suffix))
+            .unwrap_or(&text)
+            .trim();
+        let (digits, multiplier) = match amount.chars().last() {
+            Some('k') => (&amount[..amount.len() - 1], 1_000.0),
+            Some('m') => (&amount[..amount.len() - 1], 1_000_000.0),
+            _ => (amount, 1.0),
+        };
+        let value = digits.trim().parse::<f64>().ok()?;
+        if value <= 0.0 || !value.is_finite() {
+            return None;
+        }
+        Some(Budget::Tokens((value * multiplier).round() as u64))
+    }
+
+    /// Whether `totals` has reached or passed this budget.
+    pub fn exceeded_by(&self, totals: &UsageTotals) -> bool {
+        match self {
+            Budget::Tokens(limit) => totals.tokens.total() >= *limit,
+            Budget::Dollars(limit) => totals.cost_usd >= *limit,
+        }
+    }
+
+    /// Fraction of the budget consumed by `totals` (0.0 ..).
+    pub fn fraction_used(&self, totals: &UsageTotals) -> f64 {
+        match self {
+            Budget::Tokens(limit) => totals.tokens.total() as f64 / (*limit).max(1) as f64,
+            Budget::Dollars(limit) => totals.cost_usd / limit.max(f64::EPSILON),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Budget::Tokens(limit) => format!("{} tokens/day", format_tokens(*limit)),
+            Budget::Dollars(limit) => format!("${limit:.2}/day"),
+        }
+    }
+}
+
+fn parse_dollars(raw: &str) -> Option<Budget> {
+    let value = raw.trim().parse::<f64>().ok()?;
+    if value <= 0.0 || !value.is_finite() {
+        return None;
+    }
+    Some(Budget::Dollars(value))
+}
+
+/// Budgets already spent today, shared with background runs.  Their
+/// OpenAI client checks it before every request (see
+/// [`request_gate`](Self::request_gate)), so a tool loop stops mid-run
+/// instead of only at the next start.  The app refreshes it from the
+/// ledger.
+#[derive(Clone, Default)]
+pub struct BudgetGate(Arc<Mutex<GateState>>);
+
+#[derive(Default)]
+struct GateState {
+    global_spent: bool,
+    /// Recipe budgets being watched, by recipe name.
+    budgets: HashMap<String, Budget>,
+    spent: HashSet<String>,
+}
+
+impl BudgetGate {
+    /// Enforce `budget` on runs of recipe `name`.
+    pub fn watch(&self, name: &str, budget: Budget) {
+        if let Ok(mut state) = self.0.lock() {
+            state.budgets.insert(name.to_string(), budget);
+        }
+    }
+
+    pub fn budget(&self, name: &str) -> Option<Budget> {
+        self.0.lock().ok()?.budgets.get(name).copied()
+    }
+
+    /// Recompute what is spent from the ledger.
+    pub fn refresh(&self, ledger: &UsageLedger) {
+        let Ok(mut state) = self.0.lock() else {
+            return;
+        };
+        state.global_spent = ledger.global_budget_exceeded();
+        let spent = state
+            .budgets
+            .iter()
+            .filter(|(name, budget)| budget.exceeded_by(&ledger.recipe_today(name)))
+            .map(|(name, _)| name.clone())
+            .collect();
+        state.spent = spent;
+    }
+
+    /// The request gate for a background run in `scope`: the global
+    /// budget always applies, a recipe's own budget to its runs.
+    pub fn request_gate(&self, scope: UsageScope) -> RequestGate {
+        let state = self.0.clone();
+        Arc::new(move || {
+            let state = state.lock().ok()?;
+            if state.global_spent {
+                return Some("global daily budget reached (see /usage)".to_string());
+            }
+            match &scope {
+                UsageScope::Recipe(name) if state.spent.contains(name) => {
+                    Some(format!("daily budget for '{name}' reached (see /usage)"))
+                }
+                _ => None,
+            }
+        })
+    }
+}
+
+/// Running usage totals, persisted (in part) to Rice.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct UsageLedger {
+    /// Totals per local day (`YYYY-MM-DD`).
+    #[serde(default)]
+    pub days: BTreeMap<String, UsageTotals>,
+    /// Totals per recipe name, then per day.
+    #[serde(default)]
+    pub recipes: BTreeMap<String, BTreeMap<String, UsageTotals>>,
+    /// Totals across all agent windows, per day.  Window ids restart every
+    /// session, so per-window totals (`windows`) are not persisted.
+    #[serde(default)]
+    pub window_days: BTreeMap<String, UsageTotals>,
+    /// User price overrides set with `/usage price`.
+    #[serde(default)]
+    pub prices: BTreeMap<String, ModelPrice>,
+    /// Global daily budget set with `/usage budget`.
+    #[serde(default)]
+    pub budget: Option<Budget>,
+    /// Per-window totals for this session.
+    #[serde(skip)]
+    pub windows: BTreeMap<usize, UsageTotals>,
+    /// Main-chat turn currently in flight.
+    #[serde(skip)]
+    pub current_turn: UsageTotals,
+    /// Most recently finished main-chat turn.
+    #[serde(skip)]
+    pub last_turn: UsageTotals,
+    /// Everything spent since launch.
+    #[serde(skip)]
+    pub session: UsageTotals,
+    /// Prices from `MEMINI_PRICES`.
+    #[serde(skip)]
+    env_prices: BTreeMap<String, ModelPrice>,
+    /// Budget from `MEMINI_BUDGET`.
+    #[serde(skip)]
+    env_budget: Option<Budget>,
+    /// Day on which the global-budget warning was last shown.
+    #[serde(skip)]
+    pub budget_warned_on: Option<String>,
+    /// Set when persistent fields changed since the last save.
+    #[serde(skip)]
+    pub dirty: bool,
+}
+
+impl UsageLedger {
+    /// Build a ledger from a persisted Rice value (if any) plus env overrides.
+    pub fn from_persisted(value: Option<serde_json::Value>) -> Self {
+        let mut ledger = value
+            .and_then(|value| serde_json::from_value::<UsageLedger>(value).ok())
+            .unwrap_or_default();
+        ledger.env_prices = env_first(&["MEMINI_PRICES", "MEMINI_PRICE_TABLE"])
+            .map(|raw| parse_price_table(&raw))
+            .unwrap_or_default();
+        ledger.env_budget = env_first(&["MEMINI_BUDGET", "MEMINI_DAILY_BUDGET"])
+            .and_then(|raw| Budget::parse(&raw));
+        ledger
+    }
+
+    /// Record one response's usage and return its cost in USD.
+    pub fn record(&mut self, scope: &UsageScope, model: &str, usage: &TokenUsage) -> f64 {
+        let cost = self.price_for(model).map(|p| p.cost(usage)).unwrap_or(0.0);
+        let day = today();
+
+        self.days.entry(day.clone()).or_default().add(usage, cost);
+        self.session.add(usage, cost);
+        match scope {
+            UsageScope::Chat => self.current_turn.add(usage, cost),
+            UsageScope::Window(id) => {
+                self.windows.entry(*id).or_default().add(usage, cost);
+                self.window_days.entry(day).or_default().add(usage, cost);
+            }
+            UsageScope::Recipe(name) => self
+                .recipes
+                .entry(name.clone())
+                .or_default()
+                .entry(day)
+                .or_default()
+                .add(usage, cost),
+        }
+        self.prune();
+        self.dirty = true;
+        cost
+    }
+
+    /// Close the in-flight chat turn.
+    pub fn finish_turn(&mut self) {
+        self.last_turn = std::mem::take(&mut self.current_turn);
+    }
+
+    pub fn today(&self) -> UsageTotals {
+        self.days.get(&today()).copied().unwrap_or_default()
+    }
+
+    pub fn recipe_today(&self, name: &str) -> UsageTotals {
+        self.recipes
+            .get(name)
+            .and_then(|days| days.get(&today()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn windows_today(&self) -> UsageTotals {
+        self.window_days.get(&today()).copied().unwrap_or_default()
+    }
+
+    /// The effective global budget (`/usage budget` wins over `MEMINI_BUDGET`).
+    pub fn global_budget(&self) -> Option<Budget> {
+        self.budget.or(self.env_budget)
+    }
+
+    pub fn global_budget_exceeded(&self) -> bool {
+        self.global_budget()
+            .is_some_and(|budget| budget.exceeded_by(&self.today()))
+    }
+
+    /// Resolve a price by longest matching model prefix across the price
+    /// table; on equal prefixes user overrides win over env, env over built-ins.
+    pub fn price_for(&self, model: &str) -> Option<ModelPrice> {
+        let model = model.trim().to_ascii_lowercase();
+        self.price_table()
+            .into_iter()
+            .filter(|(prefix, _, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _, _)| prefix.len())
+            .map(|(_, price, _)| price)
+    }
+
+    /// All known prices with their source, for display.
+    pub fn price_table(&self) -> Vec<(String, ModelPrice, &'static str)> {
+        let mut table: BTreeMap<String, (ModelPrice, &'static str)> = DEFAULT_PRICES
+            .iter()
+            .map(|(model, input, output)| {
+                (
+                    model.to_string(),
+                    (
+                        ModelPrice {
+                            input_per_mtok: *input,
+                            output_per_mtok: *output,
+                        },
+                        "default",
+                    ),
+                )
+            })
+            .collect();
+        for (model, price) in &self.env_prices {
+            table.insert(model.clone(), (*price, "env"));
+        }
+        for (model, price) in &self.prices {
+            table.insert(model.clone(), (*price, "custom"));
+        }
+        table
+            .into_iter()
+            .map(|(model, (price, source))| (model, price, source))
+            .collect()
+    }
+
+    fn prune(&mut self) {
+        while self.days.len() > USAGE_HISTORY_DAYS {
+            self.days.pop_first();
+        }
+        while self.window_days.len() > USAGE_HISTORY_DAYS {
+            self.window_days.pop_first();
+        }
+        for days in self.recipes.values_mut() {
+            while days.len() > USAGE_HISTORY_DAYS {
+                days.pop_first();
+            }
+        }
+    }
+}
+
+/// Parse `model=in/out,model2=in/out` (USD per 1M tokens).
+pub fn parse_price_table(raw: &str) -> BTreeMap<String, ModelPrice> {
+    raw.split(',')
+        .filter_map(|entry| {
+            let (model, prices) = entry.split_once('=')?;
+            let (input, output) = prices.split_once('/')?;
+            let model = model.trim().to_ascii_lowercase();
+            if model.is_empty() {
+                return None;
+            }
+            Some((
+                model,
+                ModelPrice {
+                    input_per_mtok: input.trim().parse().ok()?,
+                    output_per_mtok: output.trim().parse().ok()?,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Today's ledger key (`YYYY-MM-DD`, local time).
+pub fn today() -> String {
+    Local::now().format("%Y-%m-%d").to_string()
+}
+
+/// Compact token count (`950`, `12.3k`, `1.2M`).
+pub fn format_tokens(count: u64) -> String {
+    if count >= 1_000_000 {
+        format!("{:.1}M", count as f64 / 1_000_000.0)
+    } else if count >= 1_000 {
+        format!("{:.1}k", count as f64 / 1_000.0)
+    } else {
+        count.to_string()
+    }
+}
+
+pub fn format_usd(amount: f64) -> String {
+    if amount > 0.0 && amount < 0.01 {
+        format!("${amount:.4}")
+    } else {
+        format!("${amount:.2}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_token_and_dollar_budgets() {
+        assert_eq!(Budget::parse("50000"), Some(Budget::Tokens(50_000)));
+        assert_eq!(Budget::parse("50k"), Some(Budget::Tokens(50_000)));
+        assert_eq!(Budget::parse("2M tokens"), Some(Budget::Tokens(2_000_000)));
+        assert_eq!(Budget::parse("$2.50"), Some(Budget::Dollars(2.5)));
+        assert_eq!(Budget::parse("3 usd"), Some(Budget::Dollars(3.0)));
+        assert_eq!(Budget::parse("lots"), None);
+        assert_eq!(Budget::parse("$0"), None);
+    }
+
+    #[test]
+    fn records_cost_and_enforces_budget() {
+        let mut ledger = UsageLedger::default();
+        let usage = TokenUsage {
+            input_tokens: 1_000_000,
+            output_tokens: 100_000,
+            reasoning_tokens: 0,
+        };
+        let cost = ledger.record(
+            &UsageScope::Recipe("digest".to_string()),
+            "gpt-4o-mini-2024-07-18",
+            &usage,
+        );
+        assert!((cost - 0.21).abs() < 1e-9);
+        assert_eq!(ledger.recipe_today("digest").tokens.total(), 1_100_000);
+        assert!(Budget::Dollars(0.20).exceeded_by(&ledger.recipe_today("digest")));
+        assert!(!Budget::Tokens(2_000_000).exceeded_by(&ledger.today()));
+    }
+
+    #[test]
+    fn custom_prices_override_defaults() {
+        let ledger = UsageLedger {
+            prices: parse_price_table("gpt-4o=1/2"),
+            ..Default::default()
+        };
+        let price = ledger.price_for("gpt-4o-2024-08-06").expect("price");
+        assert_eq!(price.input_per_mtok, 1.0);
+        let mini = ledger.price_for("gpt-4o-mini").expect("price");
+        assert_eq!(mini.input_per_mtok, 0.15);
+    }
+
+    #[test]
+    fn window_totals_for_the_day_survive_a_restart() {
+        let mut ledger = UsageLedger::default();
+        let usage = TokenUsage {
+            input_tokens: 1_000,
+            output_tokens: 200,
+            reasoning_tokens: 0,
+        };
+        ledger.record(&UsageScope::Window(3), "gpt-4o-mini", &usage);
+        ledger.record(&UsageScope::Window(4), "gpt-4o-mini", &usage);
+
+        let saved = serde_json::to_value(&ledger).unwrap();
+        let restored = UsageLedger::from_persisted(Some(saved));
+        assert_eq!(restored.windows_today().requests, 2);
+        assert_eq!(restored.windows_today().tokens.total(), 2_400);
+        assert_eq!(restored.today().tokens.total(), 2_400);
+        // The per-window breakdown is session-only.
+        assert!(restored.windows.is_empty());
+        assert_eq!(restored.session.requests, 0);
+    }
+}
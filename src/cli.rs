@@ -0,0 +1,57 @@
+//! Headless subcommands, run instead of the TUI when `memini` is given
+//! arguments.
+//!
+//! | Subcommand                  | Purpose                                |
+//! | --------------------------- | -------------------------------------- |
+//! | `memini memory export …`    | Write memory to JSONL / Markdown       |
+//! | `memini memory import …`    | Load a JSONL export (dedupes, dry-run) |
+
+use anyhow::{Context, Result, bail};
+
+use crate::rice::{JoinMode, RiceStatus, RiceStore, TRANSFER_USAGE, TransferCommand};
+
+/// Run a headless subcommand.  Returns `None` when `args` is empty, so
+/// the caller starts the TUI.
+pub fn run(args: &[String]) -> Option<Result<()>> {
+    let (command, rest) = args.split_first()?;
+    Some(match command.as_str() {
+        "memory" => run_memory(rest),
+        "help" | "--help" | "-h" => {
+            print_usage();
+            Ok(())
+        }
+        other => {
+            print_usage();
+            Err(anyhow::anyhow!("unknown subcommand '{other}'"))
+        }
+    })
+}
+
+fn print_usage() {
+    println!("memini                  Start the TUI");
+    println!("memini {}", TRANSFER_USAGE.trim_start_matches("Usage: "));
+}
+
+fn run_memory(args: &[String]) -> Result<()> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let command = TransferCommand::parse(&args)?;
+
+    let runtime = tokio::runtime::Runtime::new().context("start tokio runtime")?;
+    let mut rice = runtime.block_on(RiceStore::connect());
+    if let RiceStatus::Disabled(reason) = &rice.status {
+        bail!("memory is unavailable: {reason}");
+    }
+    eprintln!("Memory backend: {}", rice.status_label());
+    if let Ok(workspace) = runtime.block_on(rice.load_shared_workspace())
+        && let Some(workspace) = workspace
+    {
+        runtime
+            .block_on(rice.open_workspace(&workspace, JoinMode::Rejoin, None, None))
+            .with_context(|| format!("rejoin shared workspace {workspace}"))?;
+    }
+
+    for line in runtime.block_on(rice.run_transfer(&command))? {
+        println!("{line}");
+    }
+    Ok(())
+}
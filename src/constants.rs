@@ -12,6 +12,10 @@ pub const OPENAI_KEY_VAR: &str = "openai_api_key";
 pub const OPENAI_MODEL_VAR: &str = "openai_model";
 /// Rice variable key for reasoning effort (`low|medium|high`).
 pub const OPENAI_REASONING_EFFORT_VAR: &str = "openai_reasoning_effort";
+/// Rice variable key for the global model fallback chain (comma list).
+pub const OPENAI_FALLBACK_MODELS_VAR: &str = "openai_fallback_models";
+/// Rice variable key for `previous_response_id` chaining (`on|off`).
+pub const OPENAI_CHAIN_RESPONSES_VAR: &str = "openai_chain_responses";
 /// Rice variable key for the last-used MCP server.
 pub const ACTIVE_MCP_VAR: &str = "active_mcp";
 
@@ -22,21 +26,87 @@ pub const DEFAULT_OPENAI_MODEL: &str = "gpt-4o-mini";
 /// Default OpenAI API base URL.
 pub const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
 
-/// Maximum number of tool-call round-trips per chat turn.
+/// How many times a failed OpenAI request (429/5xx/timeout) is retried.
+pub const OPENAI_MAX_RETRIES: u32 = 4;
+/// Default number of OpenAI requests allowed in flight at once.
+pub const DEFAULT_OPENAI_CONCURRENCY: usize = 4;
+/// Default per-request timeout for OpenAI HTTP calls; generous because
+/// reasoning models can think for minutes (`MEMINI_OPENAI_TIMEOUT_SECS`).
+pub const OPENAI_REQUEST_TIMEOUT_SECS: u64 = 600;
+/// Timeout for establishing the connection to the OpenAI API.
+pub const OPENAI_CONNECT_TIMEOUT_SECS: u64 = 15;
+
+/// Default number of tool-call round-trips per run (chat turn, recipe,
+/// agent window); personas, recipes and `spawn_agent` may override it.
 pub const MAX_TOOL_LOOPS: usize = 6;
+/// Upper bound for any configured tool-loop limit.
+pub const MAX_TOOL_LOOPS_CAP: usize = 50;
+/// Read-only tool calls run concurrently per loop iteration, up to this many.
+pub const TOOL_CALL_CONCURRENCY: usize = 4;
+/// Longest `collect_results` may wait for running agents.
+pub const COLLECT_RESULTS_MAX_WAIT_SECS: u64 = 300;
+/// How often a waiting `collect_results` re-reads its group manifest.
+pub const COLLECT_RESULTS_POLL_SECS: u64 = 2;
+/// Rice State connections shared by all stores (`MEMINI_RICE_POOL_SIZE`).
+pub const RICE_POOL_SIZE: usize = 4;
+/// Idle time after which a pooled Rice connection is probed before reuse.
+pub const RICE_HEALTH_CHECK_SECS: u64 = 30;
+/// How long a health probe may take before the connection is reopened.
+pub const RICE_HEALTH_TIMEOUT_SECS: u64 = 3;
+/// Wait after a failed connect before the same slot dials again.
+pub const RICE_RECONNECT_BACKOFF_SECS: u64 = 10;
+/// Seconds between attempts to replay the Rice outbox.
+pub const OUTBOX_RETRY_SECS: u64 = 15;
+/// Times Rice may reject a queued write before it is set aside.
+pub const OUTBOX_MAX_ATTEMPTS: u32 = 5;
 /// Default number of Rice memory traces to recall.
 pub const DEFAULT_MEMORY_LIMIT: u64 = 6;
 /// Maximum number of log entries kept in the activity panel.
 pub const MAX_LOGS: usize = 1000;
 
-/// Rice variable key for the conversation thread.
+/// Rice variable key for the conversation thread (the `main` thread;
+/// other threads append `:<name>`).
 pub const CONVERSATION_THREAD_VAR: &str = "conversation_thread";
+/// Rice variable key for the index of named conversation threads.
+pub const THREAD_INDEX_VAR: &str = "conversation_threads";
+/// Rice variable key for the `main` thread's last-used time, persona and
+/// message count (other threads append `:<name>`).
+pub const THREAD_META_VAR: &str = "conversation_thread_meta";
+/// Personal-scope Rice variable key for the thread the user last had
+/// active (a shared workspace appends `:<workspace>`).
+pub const ACTIVE_THREAD_VAR: &str = "memini_active_thread";
+/// Thread used until another one is created.
+pub const DEFAULT_THREAD_NAME: &str = "main";
+/// Rice variable key for the `main` thread's turn transcript (other
+/// threads append `:<name>`).
+pub const THREAD_TRANSCRIPT_VAR: &str = "conversation_transcript";
+/// Most recent turns kept in a thread's transcript.
+pub const THREAD_TRANSCRIPT_MAX_TURNS: usize = 200;
+/// Serialized size a thread's transcript is trimmed to (oldest turns go
+/// first), so each save -- and each copy queued while offline -- stays small.
+pub const THREAD_TRANSCRIPT_MAX_BYTES: usize = 512 * 1024;
+/// Longest tool output kept per call in a transcript.
+pub const TRANSCRIPT_TOOL_OUTPUT_MAX_CHARS: usize = 8_000;
 /// Rice variable key for the active agent name.
 pub const ACTIVE_AGENT_VAR: &str = "active_agent_name";
 /// Rice variable key for user-created agents.
 pub const CUSTOM_AGENTS_VAR: &str = "custom_agents";
-/// Maximum number of messages kept in the conversation thread.
-pub const MAX_THREAD_MESSAGES: usize = 30;
+/// Rice variable key for deleted custom agents (name → deletion time), so
+/// a stale persona file elsewhere does not bring them back.
+pub const CUSTOM_AGENT_TOMBSTONES_VAR: &str = "custom_agent_tombstones";
+/// Estimated token size of the conversation thread that triggers a rolling
+/// summary of the oldest turns.
+pub const THREAD_SUMMARY_TRIGGER_TOKENS: usize = 6_000;
+/// Estimated thread size past which the oldest turns are dropped when
+/// summaries keep failing (a last resort; failed summaries normally keep
+/// the turns and retry).
+pub const THREAD_HARD_CAP_TOKENS: usize = 32_000;
+/// Most turns skipped between summary retries after repeated failures.
+pub const THREAD_SUMMARY_MAX_RETRY_WAIT: u32 = 8;
+/// Most-recent thread messages always kept verbatim when summarizing.
+pub const THREAD_KEEP_RECENT_MESSAGES: usize = 8;
+/// Content prefix marking the pinned summary message in the thread.
+pub const THREAD_SUMMARY_PREFIX: &str = "Summary of earlier conversation:";
 
 // ── Daemon / autonomous agent constants ──────────────────────────────
 
@@ -52,3 +122,49 @@ pub const MAX_DAEMON_RESULTS: usize = 50;
 
 /// Rice variable key for the last-joined shared workspace.
 pub const SHARED_WORKSPACE_VAR: &str = "memini_shared_workspace";
+/// Rice variable key (in the workspace run) for members, roles and invites.
+pub const WORKSPACE_ACL_VAR: &str = "memini_workspace_acl";
+/// Hours an invite token stays valid.
+pub const WORKSPACE_INVITE_TTL_HOURS: i64 = 72;
+/// PBKDF2 rounds for deriving a workspace key from its shared secret.
+pub const WORKSPACE_KDF_ITERATIONS: u32 = 100_000;
+
+// ── Memory management constants ──────────────────────────────────────
+
+/// Rice variable key for pinned and forgotten memories.
+pub const MEMORY_OVERRIDES_VAR: &str = "memini_memory_overrides";
+/// Default number of traces shown by `/memory list`.
+pub const MEMORY_LIST_LIMIT: u64 = 20;
+/// How many recent traces are scanned when resolving a memory id.
+pub const MEMORY_SCAN_LIMIT: u64 = 500;
+/// Most traces read by `/memory export` and import dedupe.
+pub const MEMORY_EXPORT_LIMIT: u64 = 10_000;
+/// Word-overlap (Jaccard) similarity at which `/memory compact` treats
+/// two traces as near-duplicates.
+pub const MEMORY_COMPACT_SIMILARITY: f64 = 0.6;
+/// Most clusters merged by one compaction run, to bound model calls.
+pub const MEMORY_COMPACT_MAX_CLUSTERS: usize = 20;
+
+// ── Usage accounting constants ───────────────────────────────────────
+
+/// Rice variable key for the persisted token usage ledger.
+pub const USAGE_LEDGER_VAR: &str = "memini_usage";
+/// How many days of usage history are kept in the ledger.
+pub const USAGE_HISTORY_DAYS: usize = 31;
+/// Least time between two saves of the usage ledger to Rice; it is also
+/// saved on exit.
+pub const USAGE_SAVE_INTERVAL_SECS: u64 = 30;
+
+// ── Attachment constants ─────────────────────────────────────────────
+
+/// Characters per text chunk when a large file is attached.
+pub const ATTACHMENT_CHUNK_CHARS: usize = 12_000;
+/// Maximum characters of one text file sent with a chat turn.
+pub const ATTACHMENT_MAX_TEXT_CHARS: usize = 60_000;
+/// Maximum size of an attached image.
+pub const ATTACHMENT_MAX_IMAGE_BYTES: u64 = 10 * 1024 * 1024;
+/// Token cost assumed for an image whose size cannot be read: the
+/// high-detail worst case (768×2048, eight tiles).
+pub const ATTACHMENT_IMAGE_TOKEN_ESTIMATE: usize = 1_445;
+/// Characters of an attached file kept as an excerpt in the thread.
+pub const ATTACHMENT_EXCERPT_CHARS: usize = 300;
@@ -360,7 +360,7 @@ fn to_output(result: Result<Value>) -> String {
         .unwrap_or_else(|_| r#"{"error":"serialize failed"}"#.to_string())
 }
 
-fn resolve_workspace_path(raw: &str) -> Result<(PathBuf, PathBuf)> {
+pub(crate) fn resolve_workspace_path(raw: &str) -> Result<(PathBuf, PathBuf)> {
     let workspace_root = workspace_root();
     let target = if raw.trim().is_empty() {
         workspace_root.clone()
@@ -425,7 +425,7 @@ fn normalize_path(path: &Path) -> PathBuf {
     }
 }
 
-fn to_workspace_relative(path: &Path, workspace_root: &Path) -> String {
+pub(crate) fn to_workspace_relative(path: &Path, workspace_root: &Path) -> String {
     match path.strip_prefix(workspace_root) {
         Ok(rel) if rel.as_os_str().is_empty() => ".".to_string(),
         Ok(rel) => rel.display().to_string(),
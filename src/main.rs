@@ -2,9 +2,11 @@
 //! servers, backed by Rice for persistent memory.
 //!
 //! This binary sets up a full-screen terminal UI, delegates to [`app::App`]
-//! for all application logic, and tears the terminal down on exit.
+//! for all application logic, and tears the terminal down on exit.  With
+//! arguments it runs a headless subcommand instead (see [`cli`]).
 
 mod app;
+mod cli;
 mod constants;
 mod local_tools;
 mod mcp;
@@ -33,10 +35,16 @@ use crate::app::App;
 fn main() -> Result<()> {
     dotenvy::dotenv().ok();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(result) = cli::run(&args) {
+        return result;
+    }
+
     let mut terminal = setup_terminal()?;
     let mut app = App::new()?;
 
     let run_result = run_app(&mut terminal, &mut app);
+    app.flush();
 
     restore_terminal()?;
     run_result
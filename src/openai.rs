@@ -1,13 +1,38 @@
 //! OpenAI API client — chat responses, embeddings, and response helpers.
 
-use anyhow::{Context, Result, anyhow};
-use reqwest::Client as HttpClient;
-use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use reqwest::header::HeaderMap;
+use reqwest::{Client as HttpClient, StatusCode};
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
+use tokio::sync::Semaphore;
 
-use crate::constants::{DEFAULT_OPENAI_BASE_URL, DEFAULT_OPENAI_MODEL, MAX_TOOL_LOOPS};
+use crate::constants::{
+    DEFAULT_OPENAI_BASE_URL, DEFAULT_OPENAI_CONCURRENCY, DEFAULT_OPENAI_MODEL, MAX_TOOL_LOOPS,
+    MAX_TOOL_LOOPS_CAP, OPENAI_CONNECT_TIMEOUT_SECS, OPENAI_MAX_RETRIES,
+    OPENAI_REQUEST_TIMEOUT_SECS,
+};
 use crate::util::env_first;
 
+/// Base delay for the first retry; doubled per attempt.
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+/// Ceiling for computed backoff delays.
+const RETRY_MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// Ceiling for server-requested delays (`Retry-After`, `x-ratelimit-reset-*`).
+const RETRY_MAX_SERVER_DELAY: Duration = Duration::from_secs(120);
+
+/// Callback that receives a human-readable line before each retry.
+pub type RetryReporter = Arc<dyn Fn(String) + Send + Sync>;
+
+/// Check run before every request; `Some(reason)` refuses it (e.g. a
+/// spent daily budget).
+pub type RequestGate = Arc<dyn Fn() -> Option<String> + Send + Sync>;
+
 /// A single tool-call extracted from an OpenAI response.
 #[derive(Clone, Debug)]
 pub struct ToolCall {
@@ -16,13 +41,236 @@ pub struct ToolCall {
     pub call_id: String,
 }
 
+/// Token counts reported in the `usage` block of a `/responses` reply.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    /// Subset of `output_tokens` spent on hidden reasoning.
+    #[serde(default)]
+    pub reasoning_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn total(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+}
+
+/// A provider-hosted Responses API tool.  These run server-side, so they
+/// never show up as `function_call` items needing an output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HostedTool {
+    WebSearch,
+    CodeInterpreter,
+    FileSearch { vector_store_ids: Vec<String> },
+}
+
+impl HostedTool {
+    /// Parse `web_search`, `code_interpreter`, or
+    /// `file_search:<vector_store_id>[|<vector_store_id>...]`.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        let (name, arg) = spec.split_once(':').unwrap_or((spec, ""));
+        match name.trim().to_ascii_lowercase().as_str() {
+            "web_search" | "web" => Ok(HostedTool::WebSearch),
+            "code_interpreter" | "code" => Ok(HostedTool::CodeInterpreter),
+            "file_search" | "files" => {
+                let vector_store_ids: Vec<String> = arg
+                    .split('|')
+                    .map(str::trim)
+                    .filter(|id| !id.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                if vector_store_ids.is_empty() {
+                    bail!("file_search needs vector store ids (file_search:vs_123|vs_456)");
+                }
+                Ok(HostedTool::FileSearch { vector_store_ids })
+            }
+            other => bail!(
+                "unknown hosted tool '{other}' (expected web_search, code_interpreter, file_search:<ids>)"
+            ),
+        }
+    }
+
+    /// The spec string [`parse`](Self::parse) accepts.
+    pub fn spec(&self) -> String {
+        match self {
+            HostedTool::WebSearch => "web_search".to_string(),
+            HostedTool::CodeInterpreter => "code_interpreter".to_string(),
+            HostedTool::FileSearch { vector_store_ids } => {
+                format!("file_search:{}", vector_store_ids.join("|"))
+            }
+        }
+    }
+
+    /// The tool entry sent in `body["tools"]`.
+    pub fn definition(&self) -> Value {
+        match self {
+            HostedTool::WebSearch => json!({"type": "web_search"}),
+            HostedTool::CodeInterpreter => {
+                json!({"type": "code_interpreter", "container": {"type": "auto"}})
+            }
+            HostedTool::FileSearch { vector_store_ids } => {
+                json!({"type": "file_search", "vector_store_ids": vector_store_ids})
+            }
+        }
+    }
+}
+
+/// Parse a list of hosted tool specs, failing on the first invalid one.
+pub fn parse_hosted_tools(specs: &[String]) -> Result<Vec<HostedTool>> {
+    specs.iter().map(|spec| HostedTool::parse(spec)).collect()
+}
+
 /// Thin wrapper around the OpenAI HTTP API.
+///
+/// Clones share one concurrency limiter, so every chat turn, agent window,
+/// and daemon task draws from the same pool of in-flight requests.
 #[derive(Clone)]
 pub struct OpenAiClient {
     pub model: String,
     pub reasoning_effort: Option<String>,
+    /// Sampling temperature; `None` leaves the API default.
+    pub temperature: Option<f32>,
+    /// Models tried in order when `model` is unavailable, overloaded, or
+    /// the input exceeds its context window.
+    pub fallback_models: Vec<String>,
     pub base_url: String,
     http_client: HttpClient,
+    limiter: Arc<Semaphore>,
+    retry_reporter: Option<RetryReporter>,
+    request_gate: Option<RequestGate>,
+    /// Retry requests that time out (`MEMINI_OPENAI_RETRY_TIMEOUTS`, on by
+    /// default).  The server may have run, and billed, a timed-out request.
+    retry_timeouts: bool,
+    /// Responses API `text.format` block (structured output), if any.
+    text_format: Option<Value>,
+    /// Chain follow-up calls with `previous_response_id` + `store` instead
+    /// of resending the whole transcript (see [`ResponseChain`]).
+    pub chain_responses: bool,
+    /// Tool-call round-trips allowed per run before it is wrapped up.
+    pub max_tool_loops: usize,
+    /// Provider-hosted tools added to every request's `tools`.
+    pub hosted_tools: Vec<HostedTool>,
+}
+
+/// Tracks a multi-call exchange (tool loops, window continuations) so
+/// follow-up requests can send only new input items plus
+/// `previous_response_id`.
+///
+/// Callers keep the full `input` vector and append each response's output
+/// items to it, as the tool loops already do; it is resent in full when
+/// chaining is off or the provider rejects it.
+#[derive(Clone, Debug, Default)]
+pub struct ResponseChain {
+    previous_response_id: Option<String>,
+    /// Number of leading `input` items the server already has.
+    sent: usize,
+    /// Set once the provider rejected chaining; later calls send full input.
+    unsupported: bool,
+    /// Position in the client's model chain serving this exchange.  Once a
+    /// request falls back, later calls start from the fallback model rather
+    /// than trying the failed one again.
+    model: usize,
+}
+
+impl ResponseChain {
+    /// Continue from an earlier response; only `input[skip..]` is new.
+    pub fn resume(previous_response_id: Option<String>, skip: usize) -> Self {
+        ResponseChain {
+            sent: if previous_response_id.is_some() {
+                skip
+            } else {
+                0
+            },
+            previous_response_id,
+            unsupported: false,
+            model: 0,
+        }
+    }
+
+    /// Id of the latest stored response, for continuing later.
+    pub fn last_response_id(&self) -> Option<&str> {
+        self.previous_response_id.as_deref()
+    }
+
+    fn record(&mut self, response: &Value, input_len: usize, stored: bool) {
+        self.previous_response_id = if stored {
+            response
+                .get("id")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        } else {
+            None
+        };
+        self.sent = input_len + extract_output_items(response).len();
+    }
+}
+
+/// Why a single request attempt failed.
+enum AttemptError {
+    /// Worth retrying (429, 5xx, timeouts, or the request never reached
+    /// the server); carries any server-requested delay.
+    Retryable {
+        error: anyhow::Error,
+        reason: String,
+        server_delay: Option<Duration>,
+    },
+    Fatal(anyhow::Error),
+}
+
+/// A non-success reply from the API, kept structured so callers can act on
+/// the status and the error `code` rather than on the message text.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    /// `error.code` from the reply body, when present.
+    pub code: Option<String>,
+    pub body: Value,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, body: Value) -> Self {
+        let code = body
+            .get("error")
+            .and_then(|e| e.get("code"))
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        ApiError { status, code, body }
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OpenAI error {}: {}", self.status, self.body)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Why a model was skipped in favour of the next one in the chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FallbackReason {
+    Unavailable,
+    Overloaded,
+    ContextTooLong,
+}
+
+impl FallbackReason {
+    fn label(self) -> &'static str {
+        match self {
+            FallbackReason::Unavailable => "model unavailable",
+            FallbackReason::Overloaded => "overloaded",
+            FallbackReason::ContextTooLong => "context too long",
+        }
+    }
 }
 
 impl OpenAiClient {
@@ -39,75 +287,580 @@ impl OpenAiClient {
         .flatten();
         let base_url = env_first(&["OPENAI_BASE_URL", "OPENAI_API_BASE"])
             .unwrap_or_else(|| DEFAULT_OPENAI_BASE_URL.to_string());
+        let concurrency = env_first(&["MEMINI_OPENAI_CONCURRENCY"])
+            .and_then(|raw| raw.trim().parse::<usize>().ok())
+            .filter(|value| *value > 0)
+            .unwrap_or(DEFAULT_OPENAI_CONCURRENCY);
+        let chain_responses = env_first(&["MEMINI_CHAIN_RESPONSES"])
+            .map(|raw| parse_toggle(&raw).unwrap_or(false))
+            .unwrap_or(false);
+        let max_tool_loops = env_first(&["MEMINI_MAX_TOOL_LOOPS"])
+            .and_then(|raw| raw.trim().parse::<usize>().ok())
+            .map(clamp_tool_loops)
+            .unwrap_or(MAX_TOOL_LOOPS);
+        let fallback_models = env_first(&["OPENAI_FALLBACK_MODELS", "MEMINI_FALLBACK_MODELS"])
+            .map(|raw| parse_model_list(&raw))
+            .unwrap_or_default();
+        let retry_timeouts = env_first(&["MEMINI_OPENAI_RETRY_TIMEOUTS"])
+            .map(|raw| parse_toggle(&raw).unwrap_or(true))
+            .unwrap_or(true);
+        let timeout_secs = env_first(&["MEMINI_OPENAI_TIMEOUT_SECS"])
+            .and_then(|raw| raw.trim().parse::<u64>().ok())
+            .filter(|value| *value > 0)
+            .unwrap_or(OPENAI_REQUEST_TIMEOUT_SECS);
+        let http_client = HttpClient::builder()
+            .connect_timeout(Duration::from_secs(OPENAI_CONNECT_TIMEOUT_SECS))
+            .timeout(Duration::from_secs(timeout_secs))
+            .build()
+            .unwrap_or_else(|_| HttpClient::new());
         OpenAiClient {
             model,
             reasoning_effort,
+            temperature: None,
+            fallback_models,
             base_url: base_url.trim_end_matches('/').to_string(),
-            http_client: HttpClient::new(),
+            http_client,
+            limiter: Arc::new(Semaphore::new(concurrency)),
+            retry_reporter: None,
+            request_gate: None,
+            retry_timeouts,
+            text_format: None,
+            chain_responses,
+            max_tool_loops,
+            hosted_tools: Vec::new(),
         }
     }
 
+    /// Return a clone that reports retries through `reporter`.
+    pub fn with_retry_reporter(&self, reporter: impl Fn(String) + Send + Sync + 'static) -> Self {
+        let mut client = self.clone();
+        client.retry_reporter = Some(Arc::new(reporter));
+        client
+    }
+
+    /// Return a clone that asks `gate` before every request.
+    pub fn with_request_gate(&self, gate: RequestGate) -> Self {
+        let mut client = self.clone();
+        client.request_gate = Some(gate);
+        client
+    }
+
+    /// Return a clone with a different tool-loop limit (clamped to
+    /// `1..=MAX_TOOL_LOOPS_CAP`).
+    pub fn with_max_tool_loops(&self, limit: usize) -> Self {
+        let mut client = self.clone();
+        client.max_tool_loops = clamp_tool_loops(limit);
+        client
+    }
+
+    /// Return a clone that also offers the given hosted tools.
+    pub fn with_hosted_tools(&self, hosted_tools: Vec<HostedTool>) -> Self {
+        let mut client = self.clone();
+        client.hosted_tools = hosted_tools;
+        client
+    }
+
+    /// Returns `true` when a run has used up its tool-call round-trips.
+    pub fn tool_loop_limit_reached(&self, tool_loops: usize) -> bool {
+        tool_loops >= self.max_tool_loops
+    }
+
+    /// Return a clone that asks for output in the given `text.format`
+    /// (e.g. a `json_schema` structured-output block).
+    pub fn with_text_format(&self, format: Value) -> Self {
+        let mut client = self.clone();
+        client.text_format = Some(format);
+        client
+    }
+
+    /// Request a response, walking the fallback chain when the model is
+    /// unavailable, overloaded, or the input is too long for it.  The
+    /// returned JSON's `model` field names the model that answered.
     pub async fn response(
         &self,
         key: &str,
         input: &[Value],
         tools: Option<&[Value]>,
+    ) -> Result<Value> {
+        self.respond(key, input, tools, false, None, &mut 0).await
+    }
+
+    /// Like [`response`](Self::response), but chains onto the previous call
+    /// in `chain` when [`chain_responses`](Self::chain_responses) is on,
+    /// sending only the input items the server has not seen yet.
+    pub async fn response_chained(
+        &self,
+        key: &str,
+        input: &[Value],
+        tools: Option<&[Value]>,
+        chain: &mut ResponseChain,
+    ) -> Result<Value> {
+        let chaining = self.chain_responses && !chain.unsupported;
+        if chaining
+            && let Some(previous) = chain.previous_response_id.clone()
+            && chain.sent <= input.len()
+        {
+            match self
+                .respond(
+                    key,
+                    &input[chain.sent..],
+                    tools,
+                    true,
+                    Some(&previous),
+                    &mut chain.model,
+                )
+                .await
+            {
+                Ok(value) => {
+                    chain.record(&value, input.len(), true);
+                    return Ok(value);
+                }
+                Err(err) if chaining_rejected(&format!("{err:#}")) => {
+                    chain.unsupported = true;
+                    if let Some(report) = &self.retry_reporter {
+                        report(
+                            "↻ previous_response_id not accepted; resending full input".to_string(),
+                        );
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        let stored = self.chain_responses && !chain.unsupported;
+        let value = self
+            .respond(key, input, tools, stored, None, &mut chain.model)
+            .await?;
+        chain.record(&value, input.len(), stored);
+        Ok(value)
+    }
+
+    /// Request a response starting at `chain[*current]`; `current` is left
+    /// on the model that answered so the rest of the turn stays on it.
+    async fn respond(
+        &self,
+        key: &str,
+        input: &[Value],
+        tools: Option<&[Value]>,
+        store: bool,
+        previous_response_id: Option<&str>,
+        current: &mut usize,
+    ) -> Result<Value> {
+        if let Some(gate) = &self.request_gate
+            && let Some(reason) = gate()
+        {
+            bail!("Stopped: {reason}");
+        }
+        let chain = self.model_chain();
+        if *current >= chain.len() {
+            *current = 0;
+        }
+        loop {
+            let model = chain[*current].as_str();
+            let err = match self
+                .response_with_model(model, key, input, tools, store, previous_response_id)
+                .await
+            {
+                Ok(mut value) => {
+                    if value.get("model").and_then(Value::as_str).is_none()
+                        && let Some(obj) = value.as_object_mut()
+                    {
+                        obj.insert("model".to_string(), json!(model));
+                    }
+                    return Ok(value);
+                }
+                Err(err) => err,
+            };
+            let Some(reason) = fallback_reason(&err) else {
+                return Err(err);
+            };
+            let Some(next) = next_fallback(&chain, *current, reason) else {
+                return Err(err);
+            };
+            if let Some(report) = &self.retry_reporter {
+                report(format!(
+                    "↘ {model} failed ({}); falling back to {}",
+                    reason.label(),
+                    chain[next]
+                ));
+            }
+            *current = next;
+        }
+    }
+
+    /// Primary model followed by the de-duplicated fallbacks.
+    pub fn model_chain(&self) -> Vec<String> {
+        let mut chain = vec![self.model.clone()];
+        for model in &self.fallback_models {
+            if !chain.iter().any(|existing| existing == model) {
+                chain.push(model.clone());
+            }
+        }
+        chain
+    }
+
+    async fn response_with_model(
+        &self,
+        model: &str,
+        key: &str,
+        input: &[Value],
+        tools: Option<&[Value]>,
+        store: bool,
+        previous_response_id: Option<&str>,
     ) -> Result<Value> {
         let mut body = json!({
-            "model": self.model,
+            "model": model,
             "input": input,
         });
+        if store {
+            body["store"] = json!(true);
+        }
+        if let Some(previous) = previous_response_id {
+            body["previous_response_id"] = json!(previous);
+        }
         if let Some(effort) = &self.reasoning_effort {
-            body["reasoning"] = json!({ "effort": effort });
+            body["reasoning"] = json!({ "effort": effort, "summary": "auto" });
         }
-        if let Some(tools) = tools {
-            body["tools"] = Value::Array(tools.to_vec());
+        let mut all_tools = tools.map(<[Value]>::to_vec).unwrap_or_default();
+        all_tools.extend(self.hosted_tools.iter().map(HostedTool::definition));
+        if !all_tools.is_empty() {
+            body["tools"] = Value::Array(all_tools);
+        }
+        if let Some(format) = &self.text_format {
+            body["text"] = json!({ "format": format });
+        }
+        if let Some(temperature) = self.temperature {
+            body["temperature"] = json!(temperature);
         }
         match self.request(key, "responses", body.clone()).await {
             Ok(value) => Ok(value),
             Err(err) => {
-                let should_retry_without_reasoning = body.get("reasoning").is_some() && {
-                    let message = err.to_string().to_ascii_lowercase();
-                    message.contains("reasoning") || message.contains("effort")
-                };
-                if !should_retry_without_reasoning {
-                    return Err(err);
-                }
+                // Some models reject reasoning or sampling options; retry
+                // once without whichever one the error complains about.
+                let message = err.to_string().to_ascii_lowercase();
                 let mut fallback_body = body;
+                let mut removed = false;
                 if let Some(obj) = fallback_body.as_object_mut() {
-                    obj.remove("reasoning");
+                    // Prefer dropping just the summary request when that is
+                    // what the model objects to.
+                    if message.contains("summary")
+                        && let Some(Value::Object(reasoning)) = obj.get_mut("reasoning")
+                        && reasoning.remove("summary").is_some()
+                    {
+                        removed = true;
+                    } else if (message.contains("reasoning") || message.contains("effort"))
+                        && obj.remove("reasoning").is_some()
+                    {
+                        removed = true;
+                    }
+                    if message.contains("temperature") && obj.remove("temperature").is_some() {
+                        removed = true;
+                    }
+                    if hosted_tools_rejected(&message, &self.hosted_tools)
+                        && let Some(Value::Array(tools)) = obj.get_mut("tools")
+                    {
+                        tools.retain(|tool| tool.get("type") == Some(&json!("function")));
+                        if tools.is_empty() {
+                            obj.remove("tools");
+                        }
+                        removed = true;
+                        if let Some(report) = &self.retry_reporter {
+                            report(format!(
+                                "↘ {model} rejected hosted tools; retrying without them"
+                            ));
+                        }
+                    }
+                }
+                if !removed {
+                    return Err(err);
                 }
                 self.request(key, "responses", fallback_body).await
             }
         }
     }
 
+    /// POST to the API, retrying 429/5xx, timeouts and failed connections
+    /// with jittered exponential backoff (or the delay the server asks
+    /// for).  A response dropped for any other reason is not retried: the
+    /// server may already have acted on (and billed) it.
     async fn request(&self, key: &str, path: &str, body: Value) -> Result<Value> {
-        let client = self.http_client.clone();
-        let base_url = self.base_url.clone();
         let url = format!(
             "{}/{}",
-            base_url.trim_end_matches('/'),
+            self.base_url.trim_end_matches('/'),
             path.trim_start_matches('/')
         );
-        let payload = body;
-        let key = key.to_string();
 
-        let response = client
+        let mut attempt = 0u32;
+        loop {
+            let (error, reason, server_delay) = match self.attempt(&url, key, &body).await {
+                Ok(value) => return Ok(value),
+                Err(AttemptError::Fatal(error)) => return Err(error),
+                Err(AttemptError::Retryable {
+                    error,
+                    reason,
+                    server_delay,
+                }) => (error, reason, server_delay),
+            };
+            if attempt >= OPENAI_MAX_RETRIES {
+                return Err(error.context(format!(
+                    "OpenAI request failed after {} attempt(s)",
+                    attempt + 1
+                )));
+            }
+            let delay = retry_delay(attempt, server_delay);
+            attempt += 1;
+            if let Some(report) = &self.retry_reporter {
+                report(format!(
+                    "↻ OpenAI {reason}; retrying in {:.1}s (attempt {attempt}/{OPENAI_MAX_RETRIES})",
+                    delay.as_secs_f32()
+                ));
+            }
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// One HTTP round-trip, holding a concurrency slot for its duration.
+    async fn attempt(&self, url: &str, key: &str, body: &Value) -> Result<Value, AttemptError> {
+        let _permit = self
+            .limiter
+            .acquire()
+            .await
+            .map_err(|err| AttemptError::Fatal(anyhow!("OpenAI limiter closed: {err}")))?;
+
+        let response = match self
+            .http_client
             .post(url)
             .bearer_auth(key)
-            .json(&payload)
+            .json(body)
             .send()
             .await
-            .context("send OpenAI request")?;
+        {
+            Ok(response) => response,
+            Err(err) if err.is_connect() => {
+                return Err(AttemptError::Retryable {
+                    error: anyhow!(err).context("send OpenAI request"),
+                    reason: "connection failed".to_string(),
+                    server_delay: None,
+                });
+            }
+            Err(err) if err.is_timeout() => return Err(self.timed_out(err)),
+            Err(err) => {
+                return Err(AttemptError::Fatal(
+                    anyhow!(err).context("send OpenAI request"),
+                ));
+            }
+        };
+
         let status = response.status();
-        let text = response.text().await.context("read OpenAI response")?;
+        let server_delay = server_retry_delay(response.headers());
+        let text = match response.text().await {
+            Ok(text) => text,
+            Err(err) if err.is_timeout() => return Err(self.timed_out(err)),
+            Err(err) => {
+                return Err(AttemptError::Fatal(
+                    anyhow!(err).context("read OpenAI response"),
+                ));
+            }
+        };
         let json: Value = serde_json::from_str(&text).unwrap_or_else(|_| json!({"raw": text}));
-        if !status.is_success() {
-            return Err(anyhow!("OpenAI error {status}: {json}"));
+        if status.is_success() {
+            return Ok(json);
+        }
+
+        let api_error = ApiError::new(status, json);
+        let quota_exhausted = api_error.code.as_deref() == Some("insufficient_quota");
+        let error = anyhow!(api_error);
+        let retryable = !quota_exhausted && (status.as_u16() == 429 || status.is_server_error());
+        if retryable {
+            let reason = if status.as_u16() == 429 {
+                "rate limited (429)".to_string()
+            } else {
+                format!("returned {}", status.as_u16())
+            };
+            Err(AttemptError::Retryable {
+                error,
+                reason,
+                server_delay,
+            })
+        } else {
+            Err(AttemptError::Fatal(error))
+        }
+    }
+
+    fn timed_out(&self, err: reqwest::Error) -> AttemptError {
+        let error = anyhow!(err)
+            .context("OpenAI request timed out (raise MEMINI_OPENAI_TIMEOUT_SECS for slow models)");
+        if self.retry_timeouts {
+            AttemptError::Retryable {
+                error,
+                reason: "timed out".to_string(),
+                server_delay: None,
+            }
+        } else {
+            AttemptError::Fatal(error)
+        }
+    }
+}
+
+/// Classify an API error as one that another model might not hit, from
+/// its HTTP status and error `code`.  Anything that is not an [`ApiError`]
+/// (connection failures, timeouts) never falls back.
+fn fallback_reason(error: &anyhow::Error) -> Option<FallbackReason> {
+    let api = error.downcast_ref::<ApiError>()?;
+    match api.code.as_deref() {
+        Some("context_length_exceeded") => return Some(FallbackReason::ContextTooLong),
+        Some("model_not_found") => return Some(FallbackReason::Unavailable),
+        Some("server_is_overloaded" | "overloaded") => return Some(FallbackReason::Overloaded),
+        _ => {}
+    }
+    match api.status.as_u16() {
+        503 | 529 => Some(FallbackReason::Overloaded),
+        // Providers without error codes still answer an unknown model with
+        // 404; a missing previous response is not the model's fault.
+        404 if api.code.is_none() && !chaining_rejected(&api.to_string()) => {
+            Some(FallbackReason::Unavailable)
+        }
+        _ => None,
+    }
+}
+
+/// Whether an API error says the provider cannot chain responses (no
+/// `previous_response_id`/`store` support, or the stored response is gone).
+fn chaining_rejected(error: &str) -> bool {
+    let error = error.to_ascii_lowercase();
+    error.contains("previous_response_id")
+        || error.contains("previous response")
+        || (error.contains("store") && error.contains("unsupported"))
+        || error.contains("unrecognized request argument supplied: store")
+}
+
+/// Index of the next model to try after `chain[current]` failed.
+///
+/// For context-length failures, models whose known window is no larger
+/// than the failing model's are skipped.
+fn next_fallback(chain: &[String], current: usize, reason: FallbackReason) -> Option<usize> {
+    let failed_window = context_window(&chain[current]);
+    (current + 1..chain.len()).find(|&idx| {
+        if reason != FallbackReason::ContextTooLong {
+            return true;
+        }
+        match (failed_window, context_window(&chain[idx])) {
+            (Some(failed), Some(candidate)) => candidate > failed,
+            _ => true,
+        }
+    })
+}
+
+/// Approximate context window (tokens) for well-known model families.
+pub fn context_window(model: &str) -> Option<u64> {
+    let model = model.to_ascii_lowercase();
+    let windows: &[(&str, u64)] = &[
+        ("gpt-4.1", 1_047_576),
+        ("gpt-5", 400_000),
+        ("o4", 200_000),
+        ("o3", 200_000),
+        ("o1", 200_000),
+        ("gpt-4o", 128_000),
+        ("gpt-4-turbo", 128_000),
+        ("gpt-3.5-turbo", 16_385),
+        ("gpt-4", 8_192),
+    ];
+    windows
+        .iter()
+        .find(|(prefix, _)| model.starts_with(prefix))
+        .map(|(_, window)| *window)
+}
+
+/// Parse a comma-separated model list.
+pub fn parse_model_list(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|model| !model.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Delay before retry number `attempt + 1`: the server's request if any,
+/// otherwise exponential backoff with jitter in `[backoff/2, backoff]`.
+fn retry_delay(attempt: u32, server_delay: Option<Duration>) -> Duration {
+    if let Some(delay) = server_delay {
+        return delay.min(RETRY_MAX_SERVER_DELAY);
+    }
+    let backoff = RETRY_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(RETRY_MAX_BACKOFF);
+    backoff.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+}
+
+/// Read the delay the server asked for from `retry-after-ms`, `Retry-After`,
+/// or — when a rate-limit bucket is empty — `x-ratelimit-reset-*`.
+fn server_retry_delay(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    if let Some(ms) = header("retry-after-ms").and_then(|v| v.trim().parse::<f64>().ok()) {
+        return Some(Duration::from_secs_f64(ms.max(0.0) / 1000.0));
+    }
+    if let Some(delay) = header("retry-after").and_then(|v| parse_retry_after(v, Utc::now())) {
+        return Some(delay);
+    }
+
+    ["requests", "tokens"]
+        .iter()
+        .filter(|bucket| {
+            header(&format!("x-ratelimit-remaining-{bucket}"))
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                == Some(0)
+        })
+        .filter_map(|bucket| header(&format!("x-ratelimit-reset-{bucket}")))
+        .filter_map(parse_reset_duration)
+        .max()
+}
+
+/// `Retry-After` is either delay-seconds or an HTTP-date.
+fn parse_retry_after(raw: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let raw = raw.trim();
+    if let Ok(secs) = raw.parse::<f64>() {
+        return Some(Duration::from_secs_f64(secs.max(0.0)));
+    }
+    let at = DateTime::parse_from_rfc2822(raw).ok()?;
+    Some(
+        (at.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// Parse OpenAI reset durations such as `20ms`, `1.5s`, `6m0s`, or `1h2m3s`.
+fn parse_reset_duration(raw: &str) -> Option<Duration> {
+    let mut total = 0.0f64;
+    let mut number = String::new();
+    let mut chars = raw.trim().chars().peekable();
+    let mut matched = false;
+    while let Some(ch) = chars.next() {
+        if ch.is_ascii_digit() || ch == '.' {
+            number.push(ch);
+            continue;
         }
-        Ok(json)
+        let value: f64 = number.parse().ok()?;
+        number.clear();
+        let unit_secs = match ch {
+            'h' => 3600.0,
+            'm' if chars.peek() == Some(&'s') => {
+                chars.next();
+                0.001
+            }
+            'm' => 60.0,
+            's' => 1.0,
+            _ => return None,
+        };
+        total += value * unit_secs;
+        matched = true;
     }
+    if !number.is_empty() {
+        // Bare number: seconds.
+        total += number.parse::<f64>().ok()?;
+        matched = true;
+    }
+    matched.then(|| Duration::from_secs_f64(total))
 }
 
 /// Pull the top-level `output` array from an OpenAI response.
@@ -142,6 +895,53 @@ pub fn extract_output_text(output_items: &[Value]) -> String {
     parts.join("\n")
 }
 
+/// Collect the `summary_text` parts of `reasoning` output items, one entry
+/// per reasoning item.
+pub fn extract_reasoning_summaries(output_items: &[Value]) -> Vec<String> {
+    output_items
+        .iter()
+        .filter(|item| item.get("type").and_then(Value::as_str) == Some("reasoning"))
+        .filter_map(|item| {
+            let parts: Vec<&str> = item
+                .get("summary")
+                .and_then(Value::as_array)?
+                .iter()
+                .filter_map(|part| part.get("text").and_then(Value::as_str))
+                .map(str::trim)
+                .filter(|text| !text.is_empty())
+                .collect();
+            (!parts.is_empty()).then(|| parts.join("\n\n"))
+        })
+        .collect()
+}
+
+/// Read the `usage` block from an OpenAI response (zeroes when absent).
+pub fn extract_usage(response: &Value) -> TokenUsage {
+    let Some(usage) = response.get("usage") else {
+        return TokenUsage::default();
+    };
+    let count = |value: Option<&Value>| value.and_then(|v| v.as_u64()).unwrap_or(0);
+    TokenUsage {
+        input_tokens: count(usage.get("input_tokens")),
+        output_tokens: count(usage.get("output_tokens")),
+        reasoning_tokens: count(
+            usage
+                .get("output_tokens_details")
+                .and_then(|details| details.get("reasoning_tokens")),
+        ),
+    }
+}
+
+/// The model that actually served a response, falling back to `default`.
+pub fn response_model(response: &Value, default: &str) -> String {
+    response
+        .get("model")
+        .and_then(|v| v.as_str())
+        .filter(|model| !model.trim().is_empty())
+        .unwrap_or(default)
+        .to_string()
+}
+
 /// Collect all `function_call` items into structured [`ToolCall`] values.
 pub fn extract_tool_calls(output_items: &[Value]) -> Vec<ToolCall> {
     let mut calls = Vec::new();
@@ -172,9 +972,129 @@ pub fn extract_tool_calls(output_items: &[Value]) -> Vec<ToolCall> {
     calls
 }
 
-/// Returns `true` when the tool-call loop has hit the configured ceiling.
-pub fn tool_loop_limit_reached(tool_loops: usize) -> bool {
-    tool_loops >= MAX_TOOL_LOOPS
+/// Whether an error message (lowercased) complains about one of the
+/// hosted tools that were sent.
+fn hosted_tools_rejected(message: &str, hosted: &[HostedTool]) -> bool {
+    hosted.iter().any(|tool| {
+        let name = match tool {
+            HostedTool::WebSearch => "web_search",
+            HostedTool::CodeInterpreter => "code_interpreter",
+            HostedTool::FileSearch { .. } => "file_search",
+        };
+        message.contains(name)
+    }) || message.contains("vector_store")
+}
+
+/// One-line descriptions of hosted-tool activity in a response: searches,
+/// code runs, citations, and files the model generated.
+pub fn describe_hosted_output(output_items: &[Value]) -> Vec<String> {
+    fn text(value: Option<&Value>) -> &str {
+        value.and_then(Value::as_str).unwrap_or("").trim()
+    }
+    let mut lines = Vec::new();
+    let mut cited: Vec<String> = Vec::new();
+    for item in output_items {
+        match text(item.get("type")) {
+            "web_search_call" => {
+                let action = item.get("action");
+                let query = text(action.and_then(|a| a.get("query")));
+                let url = text(action.and_then(|a| a.get("url")));
+                lines.push(match (query, url) {
+                    ("", "") => "🔎 Web search".to_string(),
+                    ("", url) => format!("🔎 Opened {url}"),
+                    (query, _) => format!("🔎 Web search: {query}"),
+                });
+            }
+            "file_search_call" => {
+                let queries: Vec<&str> = item
+                    .get("queries")
+                    .and_then(Value::as_array)
+                    .map(|queries| queries.iter().filter_map(Value::as_str).collect())
+                    .unwrap_or_default();
+                let hits = item.get("results").and_then(Value::as_array).map(Vec::len);
+                let mut line = format!("📄 File search: {}", queries.join("; "));
+                if let Some(hits) = hits {
+                    line.push_str(&format!(" ({hits} result(s))"));
+                }
+                lines.push(line.trim_end_matches(": ").to_string());
+            }
+            "code_interpreter_call" => {
+                let code = text(item.get("code"));
+                let first = code.lines().next().unwrap_or("");
+                lines.push(format!(
+                    "⚙ Code interpreter ran {} line(s): {first}",
+                    code.lines().count()
+                ));
+                let outputs = item.get("outputs").and_then(Value::as_array);
+                for output in outputs.into_iter().flatten() {
+                    match text(output.get("type")) {
+                        "logs" => {
+                            if let Some(log) = text(output.get("logs")).lines().last() {
+                                lines.push(format!("  ↳ {log}"));
+                            }
+                        }
+                        "image" => lines.push(format!("  ↳ image: {}", text(output.get("url")))),
+                        _ => {}
+                    }
+                }
+            }
+            "message" => {
+                let annotations = item
+                    .get("content")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|block| block.get("annotations").and_then(Value::as_array))
+                    .flatten();
+                for annotation in annotations {
+                    let line = match text(annotation.get("type")) {
+                        "url_citation" => {
+                            let url = text(annotation.get("url"));
+                            match text(annotation.get("title")) {
+                                "" => format!("🔗 {url}"),
+                                title => format!("🔗 {title} — {url}"),
+                            }
+                        }
+                        "file_citation" => format!(
+                            "📄 Cited {}",
+                            [annotation.get("filename"), annotation.get("file_id")]
+                                .into_iter()
+                                .map(text)
+                                .find(|name| !name.is_empty())
+                                .unwrap_or("file")
+                        ),
+                        "container_file_citation" => format!(
+                            "📎 Generated file: {} ({}/{})",
+                            text(annotation.get("filename")),
+                            text(annotation.get("container_id")),
+                            text(annotation.get("file_id"))
+                        ),
+                        _ => continue,
+                    };
+                    if !cited.contains(&line) {
+                        cited.push(line);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    lines.extend(cited);
+    lines
+}
+
+/// Clamp a configured tool-loop limit to `1..=MAX_TOOL_LOOPS_CAP`.
+pub fn clamp_tool_loops(limit: usize) -> usize {
+    limit.clamp(1, MAX_TOOL_LOOPS_CAP)
+}
+
+/// Parse an on/off toggle (`on`, `true`, `1`, `yes` / `off`, `false`, ...).
+pub fn parse_toggle(raw: &str) -> Option<bool> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "on" | "true" | "1" | "yes" | "enable" | "enabled" => Some(true),
+        "off" | "false" | "0" | "no" | "disable" | "disabled" => Some(false),
+        _ => None,
+    }
 }
 
 /// Parse model "thinking" / reasoning setting from user or environment input.
@@ -198,3 +1118,347 @@ pub fn parse_reasoning_setting(raw: &str) -> Option<Option<String>> {
 pub fn format_json<T: Serialize>(value: T) -> String {
     serde_json::to_string_pretty(&value).unwrap_or_else(|_| "<unrenderable>".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rate_limit_reset_durations() {
+        assert_eq!(
+            parse_reset_duration("20ms"),
+            Some(Duration::from_millis(20))
+        );
+        assert_eq!(
+            parse_reset_duration("1.5s"),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(parse_reset_duration("6m0s"), Some(Duration::from_secs(360)));
+        assert_eq!(parse_reset_duration("2"), Some(Duration::from_secs(2)));
+        assert_eq!(parse_reset_duration("soon"), None);
+    }
+
+    #[test]
+    fn honors_server_delay_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining-tokens", "0".parse().unwrap());
+        headers.insert("x-ratelimit-reset-tokens", "7s".parse().unwrap());
+        headers.insert("x-ratelimit-remaining-requests", "12".parse().unwrap());
+        headers.insert("x-ratelimit-reset-requests", "1m".parse().unwrap());
+        assert_eq!(server_retry_delay(&headers), Some(Duration::from_secs(7)));
+
+        headers.insert("retry-after", "3".parse().unwrap());
+        assert_eq!(server_retry_delay(&headers), Some(Duration::from_secs(3)));
+
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:27:30Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let date = "Wed, 21 Oct 2015 07:28:00 GMT";
+        assert_eq!(parse_retry_after(date, now), Some(Duration::from_secs(30)));
+        let past = now + chrono::Duration::minutes(5);
+        assert_eq!(parse_retry_after(date, past), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn falls_back_along_the_chain() {
+        let chain: Vec<String> = ["gpt-4o", "gpt-4o-mini", "gpt-4.1"]
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+        let not_found = api_error(404, json!({"error": {"code": "model_not_found"}}));
+        assert_eq!(
+            fallback_reason(&not_found),
+            Some(FallbackReason::Unavailable)
+        );
+        assert_eq!(
+            next_fallback(&chain, 0, FallbackReason::Unavailable),
+            Some(1)
+        );
+
+        let too_long = api_error(400, json!({"error": {"code": "context_length_exceeded"}}));
+        let reason = fallback_reason(&too_long).expect("context error");
+        // gpt-4o-mini has the same window as gpt-4o, so go straight to gpt-4.1.
+        assert_eq!(next_fallback(&chain, 0, reason), Some(2));
+        assert_eq!(next_fallback(&chain, 2, reason), None);
+
+        assert_eq!(fallback_reason(&api_error(401, json!({}))), None);
+        // Only the status and code count, not words in the message.
+        let wordy = api_error(
+            400,
+            json!({"error": {"code": "invalid_value", "message": "model overloaded, context window"}}),
+        );
+        assert_eq!(fallback_reason(&wordy), None);
+        assert_eq!(
+            fallback_reason(&api_error(503, json!({}))),
+            Some(FallbackReason::Overloaded)
+        );
+        // Retries wrap the error in context; the classification survives.
+        let retried = api_error(529, json!({})).context("OpenAI request failed after 4 attempt(s)");
+        assert_eq!(fallback_reason(&retried), Some(FallbackReason::Overloaded));
+        assert_eq!(fallback_reason(&anyhow!("connection refused")), None);
+    }
+
+    #[test]
+    fn a_turn_stays_on_the_model_it_fell_back_to() {
+        let reply = |id: &str| json!({"id": id, "model": "gpt-4.1", "output": []});
+        let (base_url, server) = mock_responses_endpoint(vec![
+            (404, json!({"error": {"code": "model_not_found"}})),
+            (200, reply("resp_1")),
+            (200, reply("resp_2")),
+        ]);
+        let mut client = OpenAiClient::new();
+        client.base_url = base_url;
+        client.model = "gpt-5".to_string();
+        client.fallback_models = vec!["gpt-4.1".to_string()];
+        client.chain_responses = false;
+        let input = [json!({"role": "user", "content": "hi"})];
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("runtime");
+        let mut chain = ResponseChain::default();
+        runtime.block_on(async {
+            for _ in 0..2 {
+                client
+                    .response_chained("sk-test", &input, None, &mut chain)
+                    .await
+                    .expect("mock response");
+            }
+        });
+        let models: Vec<Value> = server
+            .join()
+            .expect("mock server")
+            .into_iter()
+            .map(|body| body["model"].clone())
+            .collect();
+        assert_eq!(
+            models,
+            vec![json!("gpt-5"), json!("gpt-4.1"), json!("gpt-4.1")]
+        );
+    }
+
+    fn api_error(status: u16, body: Value) -> anyhow::Error {
+        anyhow!(ApiError::new(
+            StatusCode::from_u16(status).expect("status"),
+            body
+        ))
+    }
+
+    /// Serve canned `/responses` replies on a local port, capturing each
+    /// request body.
+    fn mock_responses_endpoint(
+        replies: Vec<(u16, Value)>,
+    ) -> (String, std::thread::JoinHandle<Vec<Value>>) {
+        mock_delayed_endpoint(
+            replies
+                .into_iter()
+                .map(|(status, reply)| (Duration::ZERO, status, reply))
+                .collect(),
+        )
+    }
+
+    /// [`mock_responses_endpoint`] that waits before each reply.  A client
+    /// that gave up in the meantime just doesn't get it.
+    fn mock_delayed_endpoint(
+        replies: Vec<(Duration, u16, Value)>,
+    ) -> (String, std::thread::JoinHandle<Vec<Value>>) {
+        let server = tiny_http::Server::http("127.0.0.1:0").expect("bind mock endpoint");
+        let port = server.server_addr().to_ip().expect("tcp address").port();
+        let handle = std::thread::spawn(move || {
+            let mut bodies = Vec::new();
+            let mut pending = Vec::new();
+            for (delay, status, reply) in replies {
+                let mut request = server.recv().expect("mock request");
+                let mut raw = String::new();
+                request.as_reader().read_to_string(&mut raw).expect("body");
+                bodies.push(serde_json::from_str(&raw).expect("json body"));
+                // Reply on the side so a slow reply doesn't hold up the next request.
+                pending.push(std::thread::spawn(move || {
+                    std::thread::sleep(delay);
+                    let response = tiny_http::Response::from_string(reply.to_string())
+                        .with_status_code(status);
+                    let _ = request.respond(response);
+                }));
+            }
+            for reply in pending {
+                let _ = reply.join();
+            }
+            bodies
+        });
+        (format!("http://127.0.0.1:{port}/v1"), handle)
+    }
+
+    #[test]
+    fn timed_out_requests_are_retried_unless_disabled() {
+        let reply = json!({"id": "resp_1", "output": []});
+        let slow = Duration::from_millis(1500);
+        let (base_url, server) = mock_delayed_endpoint(vec![
+            (slow, 200, reply.clone()),
+            (Duration::ZERO, 200, reply.clone()),
+        ]);
+        let mut client = OpenAiClient::new();
+        client.base_url = base_url;
+        client.fallback_models.clear();
+        client.http_client = HttpClient::builder()
+            .timeout(Duration::from_millis(300))
+            .build()
+            .expect("http client");
+        client.retry_timeouts = true;
+        let input = [json!({"role": "user", "content": "hi"})];
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("runtime");
+        let response = runtime
+            .block_on(client.response("sk-test", &input, None))
+            .expect("retried after the timeout");
+        assert_eq!(response["id"], "resp_1");
+        assert_eq!(server.join().expect("mock server").len(), 2);
+
+        let (base_url, server) = mock_delayed_endpoint(vec![(slow, 200, reply)]);
+        client.base_url = base_url;
+        client.retry_timeouts = false;
+        let err = runtime
+            .block_on(client.response("sk-test", &input, None))
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("timed out"), "{err:#}");
+        assert_eq!(server.join().expect("mock server").len(), 1);
+    }
+
+    #[test]
+    fn hosted_tools_are_sent_and_their_output_described() {
+        let reply = json!({
+            "id": "resp_1",
+            "model": "gpt-4o",
+            "output": [
+                {"type": "web_search_call", "status": "completed",
+                 "action": {"type": "search", "query": "rust 2024 edition"}},
+                {"type": "code_interpreter_call", "code": "print(1 + 1)\nsave()",
+                 "outputs": [{"type": "logs", "logs": "2"}]},
+                {"type": "message", "content": [{
+                    "type": "output_text",
+                    "text": "Done.",
+                    "annotations": [
+                        {"type": "url_citation", "title": "Edition Guide",
+                         "url": "https://doc.rust-lang.org/edition-guide/"},
+                        {"type": "container_file_citation", "container_id": "cntr_1",
+                         "file_id": "file_9", "filename": "plot.png"}
+                    ]
+                }]}
+            ]
+        });
+        let (base_url, server) = mock_responses_endpoint(vec![(200, reply)]);
+
+        let mut client = OpenAiClient::new().with_hosted_tools(vec![
+            HostedTool::parse("web_search").expect("web"),
+            HostedTool::parse("file_search:vs_1|vs_2").expect("files"),
+        ]);
+        client.base_url = base_url;
+        client.fallback_models.clear();
+        let function = json!({"type": "function", "name": "rice_memories"});
+        let input = [json!({"role": "user", "content": "hi"})];
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("runtime");
+        let response = runtime
+            .block_on(client.response("sk-test", &input, Some(std::slice::from_ref(&function))))
+            .expect("mock response");
+
+        // A provider that rejects hosted tools gets the function tools only.
+        let bare = json!({"id": "resp_2", "output": []});
+        let (base_url, fallback_server) = mock_responses_endpoint(vec![
+            (
+                400,
+                json!({"error": {"message": "Tool 'web_search' is not supported"}}),
+            ),
+            (200, bare),
+        ]);
+        client.base_url = base_url;
+        runtime
+            .block_on(client.response("sk-test", &input, Some(std::slice::from_ref(&function))))
+            .expect("retry without hosted tools");
+
+        let bodies = server.join().expect("mock server");
+        assert_eq!(
+            bodies[0]["tools"],
+            json!([
+                function,
+                {"type": "web_search"},
+                {"type": "file_search", "vector_store_ids": ["vs_1", "vs_2"]}
+            ])
+        );
+        let retried = fallback_server.join().expect("fallback server");
+        assert_eq!(retried[1]["tools"], json!([function]));
+
+        assert_eq!(
+            describe_hosted_output(&extract_output_items(&response)),
+            vec![
+                "🔎 Web search: rust 2024 edition",
+                "⚙ Code interpreter ran 2 line(s): print(1 + 1)",
+                "  ↳ 2",
+                "🔗 Edition Guide — https://doc.rust-lang.org/edition-guide/",
+                "📎 Generated file: plot.png (cntr_1/file_9)",
+            ]
+        );
+        assert!(HostedTool::parse("file_search").is_err());
+    }
+
+    #[test]
+    fn extracts_reasoning_summaries() {
+        let items = vec![
+            json!({"type": "reasoning", "summary": [
+                {"type": "summary_text", "text": "Checking the config."},
+                {"type": "summary_text", "text": "Then the tests."}
+            ]}),
+            json!({"type": "reasoning", "summary": []}),
+            json!({"type": "message", "content": [{"type": "output_text", "text": "Done."}]}),
+        ];
+        assert_eq!(
+            extract_reasoning_summaries(&items),
+            vec!["Checking the config.\n\nThen the tests."]
+        );
+        assert_eq!(extract_output_text(&items), "Done.");
+    }
+
+    #[test]
+    fn chain_sends_only_new_items() {
+        let mut chain = ResponseChain::default();
+        let response = json!({
+            "id": "resp_1",
+            "output": [{"type": "function_call", "name": "x", "call_id": "c1", "arguments": "{}"}]
+        });
+        chain.record(&response, 3, true);
+        assert_eq!(chain.last_response_id(), Some("resp_1"));
+        // Three input items plus the one output item are already server-side.
+        assert_eq!(chain.sent, 4);
+
+        chain.record(&response, 5, false);
+        assert_eq!(chain.last_response_id(), None);
+
+        let resumed = ResponseChain::resume(Some("resp_9".to_string()), 2);
+        assert_eq!(resumed.sent, 2);
+        assert_eq!(ResponseChain::resume(None, 2).sent, 0);
+
+        assert!(chaining_rejected(
+            r#"OpenAI error 400: {"error":{"message":"Previous response with id 'resp_1' not found."}}"#
+        ));
+        let missing = api_error(
+            404,
+            json!({"error": {"message": "Previous response with id 'resp_1' not found."}}),
+        );
+        assert_eq!(fallback_reason(&missing), None);
+    }
+
+    #[test]
+    fn backoff_grows_and_stays_capped() {
+        let first = retry_delay(0, None);
+        assert!(first >= RETRY_BASE_DELAY / 2 && first <= RETRY_BASE_DELAY);
+        assert!(retry_delay(20, None) <= RETRY_MAX_BACKOFF);
+        assert_eq!(
+            retry_delay(0, Some(Duration::from_secs(600))),
+            RETRY_MAX_SERVER_DELAY
+        );
+    }
+}
@@ -19,6 +19,10 @@ const DAEMON_BRIEFING_PERSONA_MD: &str = include_str!("../prompts/daemon_briefin
 const DAEMON_BRIEFING_PROMPT_MD: &str = include_str!("../prompts/daemon_briefing_prompt.md");
 const DAEMON_DIGEST_PERSONA_MD: &str = include_str!("../prompts/daemon_digest_persona.md");
 const DAEMON_DIGEST_PROMPT_MD: &str = include_str!("../prompts/daemon_digest_prompt.md");
+const THREAD_SUMMARY_MD: &str = include_str!("../prompts/thread_summary.md");
+const MEMORY_COMPACT_MD: &str = include_str!("../prompts/memory_compact.md");
+const TOOL_LOOP_EXHAUSTED_MD: &str = include_str!("../prompts/tool_loop_exhausted.md");
+const STRUCTURED_ANSWER_MD: &str = include_str!("../prompts/structured_answer.md");
 
 fn memini_home() -> PathBuf {
     if let Ok(value) = env::var("MEMINI_HOME") {
@@ -79,6 +83,22 @@ pub fn daemon_digest_prompt() -> String {
     load_prompt("daemon_digest_prompt.md", DAEMON_DIGEST_PROMPT_MD)
 }
 
+pub fn thread_summary_prompt() -> String {
+    load_prompt("thread_summary.md", THREAD_SUMMARY_MD)
+}
+
+pub fn memory_compact_prompt() -> String {
+    load_prompt("memory_compact.md", MEMORY_COMPACT_MD)
+}
+
+pub fn tool_loop_exhausted_prompt() -> String {
+    load_prompt("tool_loop_exhausted.md", TOOL_LOOP_EXHAUSTED_MD)
+}
+
+pub fn structured_answer_prompt() -> String {
+    load_prompt("structured_answer.md", STRUCTURED_ANSWER_MD)
+}
+
 pub fn custom_persona(name: &str, description: &str) -> String {
     format!(
         "You are {name}, a specialized execution-first AI assistant. \
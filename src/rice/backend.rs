@@ -0,0 +1,269 @@
+//! Storage backends behind `RiceStore`.
+//!
+//! | Backend        | Used when                                   |
+//! | -------------- | ------------------------------------------- |
+//! | `RiceBackend`  | Rice State URL configured and reachable     |
+//! | `LocalBackend` | No Rice env — embedded files under `$MEMINI_HOME` |
+
+use std::future::Future;
+use std::pin::Pin;
+
+use anyhow::{Context, Result};
+use rice::rice_state::client::StateClient;
+use rice::rice_state::proto::Trace;
+
+use super::StateEvent;
+
+/// Boxed future returned by [`MemoryBackend`] methods so the trait stays
+/// object-safe.
+pub type BackendFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// A state variable as returned by [`MemoryBackend::list_variables`].
+#[derive(Clone, Debug)]
+pub struct StoredVariable {
+    pub name: String,
+    pub value_json: String,
+    pub source: String,
+}
+
+/// A committed trace plus the id and timestamp its backend knows it by.
+/// Rice keeps neither, so its ids are content hashes and `created_at` is
+/// empty.
+#[derive(Clone, Debug)]
+pub struct StoredTrace {
+    pub id: String,
+    pub created_at: String,
+    pub trace: Trace,
+}
+
+/// Short content hash used as the id of traces whose backend assigns none.
+pub fn trace_id(trace: &Trace) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in [&trace.agent_id, &trace.input, &trace.action, &trace.outcome] {
+        for byte in part.bytes().chain(std::iter::once(0)) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    format!("{:08x}", (hash >> 32) ^ (hash & 0xffff_ffff))
+}
+
+/// The subset of Rice State that Memini relies on: variables, traces,
+/// recall, and the event stream.
+pub trait MemoryBackend: Send + Sync {
+    fn set_variable<'a>(
+        &'a mut self,
+        run_id: String,
+        name: String,
+        value_json: String,
+        source: String,
+    ) -> BackendFuture<'a, ()>;
+
+    /// Returns `None` when the variable is unset.
+    fn get_variable<'a>(
+        &'a mut self,
+        run_id: String,
+        name: String,
+    ) -> BackendFuture<'a, Option<String>>;
+
+    fn list_variables<'a>(&'a mut self, run_id: String) -> BackendFuture<'a, Vec<StoredVariable>>;
+
+    fn delete_variable<'a>(&'a mut self, run_id: String, name: String) -> BackendFuture<'a, ()>;
+
+    fn focus<'a>(&'a mut self, run_id: String, content: String) -> BackendFuture<'a, ()>;
+
+    fn reminisce<'a>(
+        &'a mut self,
+        run_id: String,
+        embedding: Vec<f32>,
+        limit: u64,
+        query_text: String,
+    ) -> BackendFuture<'a, Vec<StoredTrace>>;
+
+    fn commit<'a>(&'a mut self, trace: Trace) -> BackendFuture<'a, ()>;
+
+    /// Up to `limit` traces for `run_id`, newest first where known.
+    fn list_traces<'a>(
+        &'a mut self,
+        run_id: String,
+        limit: u64,
+    ) -> BackendFuture<'a, Vec<StoredTrace>>;
+
+    /// Whether `list_traces` returns every trace (up to `limit`), so the
+    /// caller knows how many exist.
+    fn lists_every_trace(&self) -> bool {
+        true
+    }
+
+    /// Remove a trace.  `Ok(false)` means the backend cannot delete
+    /// traces and the caller should hide it instead.
+    fn delete_trace<'a>(&'a mut self, run_id: String, id: String) -> BackendFuture<'a, bool>;
+
+    /// Replace a trace in place.  `Ok(false)` as for `delete_trace`.
+    fn update_trace<'a>(
+        &'a mut self,
+        run_id: String,
+        id: String,
+        trace: Trace,
+    ) -> BackendFuture<'a, bool>;
+
+    /// Stream events for `run_id` into `on_event` until the source closes.
+    fn subscribe<'a>(
+        &'a mut self,
+        run_id: String,
+        event_types: Vec<String>,
+        on_event: &'a mut (dyn FnMut(StateEvent) + Send),
+    ) -> BackendFuture<'a, ()>;
+}
+
+// ── Rice State ───────────────────────────────────────────────────────
+
+/// Remote backend talking to the Rice State gRPC service.
+pub struct RiceBackend {
+    state: StateClient,
+}
+
+impl RiceBackend {
+    pub fn new(state: StateClient) -> Self {
+        RiceBackend { state }
+    }
+}
+
+impl MemoryBackend for RiceBackend {
+    fn set_variable<'a>(
+        &'a mut self,
+        run_id: String,
+        name: String,
+        value_json: String,
+        source: String,
+    ) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            self.state
+                .set_variable(run_id, name, value_json, source)
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn get_variable<'a>(
+        &'a mut self,
+        run_id: String,
+        name: String,
+    ) -> BackendFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let variable = self.state.get_variable(run_id, name).await?;
+            if variable.value_json.trim().is_empty() {
+                return Ok(None);
+            }
+            Ok(Some(variable.value_json))
+        })
+    }
+
+    fn list_variables<'a>(&'a mut self, run_id: String) -> BackendFuture<'a, Vec<StoredVariable>> {
+        Box::pin(async move {
+            let response = self.state.list_variables(run_id).await?;
+            Ok(response
+                .variables
+                .into_iter()
+                .map(|variable| StoredVariable {
+                    name: variable.name,
+                    value_json: variable.value_json,
+                    source: variable.source,
+                })
+                .collect())
+        })
+    }
+
+    fn delete_variable<'a>(&'a mut self, run_id: String, name: String) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            self.state.delete_variable(run_id, name).await?;
+            Ok(())
+        })
+    }
+
+    fn focus<'a>(&'a mut self, run_id: String, content: String) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            self.state.focus(content, run_id).await?;
+            Ok(())
+        })
+    }
+
+    fn reminisce<'a>(
+        &'a mut self,
+        run_id: String,
+        embedding: Vec<f32>,
+        limit: u64,
+        query_text: String,
+    ) -> BackendFuture<'a, Vec<StoredTrace>> {
+        Box::pin(async move {
+            let response = self
+                .state
+                .reminisce(embedding, limit, query_text, run_id)
+                .await?;
+            Ok(response.traces.into_iter().map(stored_rice_trace).collect())
+        })
+    }
+
+    fn commit<'a>(&'a mut self, trace: Trace) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            self.state.commit(trace).await?;
+            Ok(())
+        })
+    }
+
+    fn list_traces<'a>(
+        &'a mut self,
+        run_id: String,
+        limit: u64,
+    ) -> BackendFuture<'a, Vec<StoredTrace>> {
+        // Rice has no listing call; an unranked recall is the closest.
+        self.reminisce(run_id, Vec::new(), limit, String::new())
+    }
+
+    fn lists_every_trace(&self) -> bool {
+        // An unranked recall may return any subset, and Rice has neither
+        // paging nor a count to check it against.
+        false
+    }
+
+    fn delete_trace<'a>(&'a mut self, _run_id: String, _id: String) -> BackendFuture<'a, bool> {
+        Box::pin(async { Ok(false) })
+    }
+
+    fn update_trace<'a>(
+        &'a mut self,
+        _run_id: String,
+        _id: String,
+        _trace: Trace,
+    ) -> BackendFuture<'a, bool> {
+        Box::pin(async { Ok(false) })
+    }
+
+    fn subscribe<'a>(
+        &'a mut self,
+        run_id: String,
+        event_types: Vec<String>,
+        on_event: &'a mut (dyn FnMut(StateEvent) + Send),
+    ) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            let mut stream = self.state.subscribe(run_id, event_types).await?;
+            while let Some(event) = stream.message().await.context("read state event")? {
+                on_event(StateEvent {
+                    event_type: event.r#type,
+                    run_id: event.run_id,
+                    agent_id: event.agent_id,
+                    payload: event.payload,
+                });
+            }
+            Ok(())
+        })
+    }
+}
+
+fn stored_rice_trace(trace: Trace) -> StoredTrace {
+    StoredTrace {
+        id: trace_id(&trace),
+        created_at: String::new(),
+        trace,
+    }
+}
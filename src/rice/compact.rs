@@ -0,0 +1,250 @@
+//! Memory consolidation: cluster near-duplicate traces and replace each
+//! cluster with one consolidated fact.
+//!
+//! Clustering is lexical (word-set Jaccard similarity within one agent),
+//! so it works the same on Rice and on the local store.  Pinned memories
+//! are never merged.  Merged traces are retired with the same machinery
+//! as `/memory forget`: deleted where the backend can, hidden otherwise.
+
+use std::collections::BTreeSet;
+
+use anyhow::{Context, Result};
+use rice::rice_state::proto::Trace;
+
+use super::outbox::QueuedWrite;
+use super::{RiceStore, StoredTrace, provenance};
+use crate::constants::{MEMORY_COMPACT_SIMILARITY, MEMORY_SCAN_LIMIT};
+
+/// Action recorded on consolidated traces.
+pub const CONSOLIDATED_ACTION: &str = "consolidated";
+
+/// Near-duplicate memories of one agent, newest first.
+#[derive(Clone, Debug)]
+pub struct MemoryCluster {
+    pub agent_id: String,
+    pub members: Vec<StoredTrace>,
+}
+
+impl MemoryCluster {
+    /// The cluster as a numbered list, for the merge prompt.
+    pub fn describe(&self) -> String {
+        self.members
+            .iter()
+            .enumerate()
+            .map(|(idx, stored)| {
+                format!(
+                    "{}. [{}] Q: {}\n   A: {}",
+                    idx + 1,
+                    if stored.created_at.is_empty() {
+                        "undated"
+                    } else {
+                        stored.created_at.as_str()
+                    },
+                    stored.trace.input.trim(),
+                    stored.trace.outcome.trim()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl RiceStore {
+    /// Find clusters of near-duplicate memories among the most recent
+    /// traces.  Nothing is changed.
+    pub async fn plan_compaction(&mut self) -> Result<Vec<MemoryCluster>> {
+        let overrides = self.memory_overrides().await?;
+        let memories: Vec<StoredTrace> = self
+            .list_memories(None, None, None, MEMORY_SCAN_LIMIT)
+            .await?
+            .into_iter()
+            .filter(|stored| !overrides.is_pinned(&stored.id))
+            .collect();
+        Ok(cluster_memories(memories, MEMORY_COMPACT_SIMILARITY))
+    }
+
+    /// Commit `fact` as the consolidated memory for `cluster` and retire
+    /// its members.
+    pub async fn apply_compaction(&mut self, cluster: &MemoryCluster, fact: &str) -> Result<()> {
+        self.ensure_writable()?;
+        let rid = self.active_run_id();
+        let Some(newest) = cluster.members.first() else {
+            return Ok(());
+        };
+        let ids: Vec<&str> = cluster.members.iter().map(|m| m.id.as_str()).collect();
+        let mut trace = Trace {
+            input: newest.trace.input.clone(),
+            reasoning: format!("Merged from memories {}", ids.join(", ")),
+            action: CONSOLIDATED_ACTION.to_string(),
+            outcome: fact.trim().to_string(),
+            agent_id: cluster.agent_id.clone(),
+            embedding: vec![],
+            run_id: rid.clone(),
+        };
+        provenance::stamp_trace(&mut trace);
+        self.seal_trace(&mut trace)?;
+        self.write_through(QueuedWrite::commit(trace))
+            .await
+            .context("commit consolidated memory")?;
+
+        let backend = self.backend()?;
+        let mut hidden = Vec::new();
+        for stored in &cluster.members {
+            let deleted = backend
+                .delete_trace(rid.clone(), stored.id.clone())
+                .await
+                .context("retire merged memory")?;
+            if !deleted {
+                hidden.push(stored.id.clone());
+            }
+        }
+        if hidden.is_empty() {
+            return Ok(());
+        }
+        let mut overrides = self.memory_overrides().await?;
+        for id in hidden {
+            if !overrides.is_forgotten(&id) {
+                overrides.forgotten.push(id);
+            }
+        }
+        self.save_memory_overrides(&overrides).await
+    }
+}
+
+/// Greedily group memories (newest first) with the first cluster of the
+/// same agent whose seed is at least `threshold` similar.  Only clusters
+/// of two or more are returned.
+fn cluster_memories(memories: Vec<StoredTrace>, threshold: f64) -> Vec<MemoryCluster> {
+    let mut clusters: Vec<(BTreeSet<String>, MemoryCluster)> = Vec::new();
+    for stored in memories {
+        let words = word_set(&stored.trace);
+        if words.is_empty() {
+            continue;
+        }
+        let home = clusters.iter_mut().find(|(seed, cluster)| {
+            cluster.agent_id == stored.trace.agent_id && jaccard(seed, &words) >= threshold
+        });
+        match home {
+            Some((_, cluster)) => cluster.members.push(stored),
+            None => clusters.push((
+                words,
+                MemoryCluster {
+                    agent_id: stored.trace.agent_id.clone(),
+                    members: vec![stored],
+                },
+            )),
+        }
+    }
+    clusters
+        .into_iter()
+        .map(|(_, cluster)| cluster)
+        .filter(|cluster| cluster.members.len() > 1)
+        .collect()
+}
+
+/// Lowercased words of three or more characters from input and outcome.
+fn word_set(trace: &Trace) -> BTreeSet<String> {
+    format!("{} {}", trace.input, trace.outcome)
+        .split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn jaccard(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    let shared = a.intersection(b).count();
+    let total = a.len() + b.len() - shared;
+    if total == 0 {
+        return 0.0;
+    }
+    shared as f64 / total as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(id: &str, agent: &str, input: &str, outcome: &str) -> StoredTrace {
+        StoredTrace {
+            id: id.to_string(),
+            created_at: String::new(),
+            trace: Trace {
+                input: input.to_string(),
+                outcome: outcome.to_string(),
+                agent_id: agent.to_string(),
+                ..Trace::default()
+            },
+        }
+    }
+
+    #[test]
+    fn clusters_near_duplicates_per_agent() {
+        let memories = vec![
+            stored(
+                "a1",
+                "memini:coder",
+                "where do we deploy",
+                "We deploy the API to fly.io",
+            ),
+            stored("b1", "memini:coder", "what is lunch", "Pizza on Friday"),
+            stored(
+                "a2",
+                "memini:coder",
+                "where do we deploy?",
+                "We deploy the API to fly.io now",
+            ),
+            stored(
+                "c1",
+                "memini:writer",
+                "where do we deploy",
+                "We deploy the API to fly.io",
+            ),
+        ];
+        let clusters = cluster_memories(memories, MEMORY_COMPACT_SIMILARITY);
+        assert_eq!(clusters.len(), 1);
+        let ids: Vec<&str> = clusters[0].members.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, ["a1", "a2"]);
+    }
+
+    #[test]
+    fn compaction_replaces_a_cluster_with_one_fact() {
+        let dir = std::env::temp_dir().join(format!(
+            "memini-compact-{}-{:x}",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let mut store = RiceStore::local_at(&dir).unwrap();
+            for outcome in ["Deploys go to fly.io", "Deploys go to fly.io daily"] {
+                store
+                    .commit_trace("deploy target", outcome, "chat", "", vec![], "memini:coder")
+                    .await
+                    .unwrap();
+            }
+            store
+                .commit_trace("lunch", "Pizza", "chat", "", vec![], "memini:coder")
+                .await
+                .unwrap();
+
+            let clusters = store.plan_compaction().await.unwrap();
+            assert_eq!(clusters.len(), 1);
+            store
+                .apply_compaction(&clusters[0], "Deploys go to fly.io daily")
+                .await
+                .unwrap();
+
+            let memories = store.list_memories(None, None, None, 10).await.unwrap();
+            assert_eq!(memories.len(), 2);
+            assert!(
+                memories
+                    .iter()
+                    .any(|m| m.trace.action == CONSOLIDATED_ACTION)
+            );
+            assert!(store.plan_compaction().await.unwrap().is_empty());
+        });
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,771 @@
+//! Embedded memory backend used when Rice is not configured.
+//!
+//! Everything lives in one directory (default `$MEMINI_HOME/memory`):
+//!
+//! | File             | Contents                                          |
+//! | ---------------- | ------------------------------------------------- |
+//! | `variables.json` | `{run_id: {name: {value_json, source, updated_at}}}` |
+//! | `traces.jsonl`   | One committed trace per line                      |
+//!
+//! Recall ranks traces by cosine similarity.  Traces committed without an
+//! embedding are indexed with a hashed bag-of-words vector, so recall
+//! works fully offline.  Events (`VariableUpdate`, `Commit`, `Focus`) are
+//! broadcast to subscribers in the same process only.
+
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use anyhow::{Context, Result, anyhow};
+use directories::BaseDirs;
+use rice::rice_state::proto::Trace;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::broadcast;
+
+use super::StateEvent;
+use super::backend::{BackendFuture, MemoryBackend, StoredTrace, StoredVariable};
+
+const VARIABLES_FILE: &str = "variables.json";
+const TRACES_FILE: &str = "traces.jsonl";
+const VECTOR_DIMS: usize = 256;
+const EVENT_CAPACITY: usize = 256;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct LocalVariable {
+    value_json: String,
+    #[serde(default)]
+    source: String,
+    #[serde(default)]
+    updated_at: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct LocalTrace {
+    id: String,
+    run_id: String,
+    #[serde(default)]
+    agent_id: String,
+    #[serde(default)]
+    input: String,
+    #[serde(default)]
+    reasoning: String,
+    #[serde(default)]
+    action: String,
+    #[serde(default)]
+    outcome: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    embedding: Vec<f32>,
+    #[serde(default)]
+    created_at: String,
+}
+
+impl LocalTrace {
+    fn to_trace(&self) -> Trace {
+        Trace {
+            input: self.input.clone(),
+            reasoning: self.reasoning.clone(),
+            action: self.action.clone(),
+            outcome: self.outcome.clone(),
+            agent_id: self.agent_id.clone(),
+            embedding: self.embedding.clone(),
+            run_id: self.run_id.clone(),
+        }
+    }
+
+    fn to_stored(&self) -> StoredTrace {
+        StoredTrace {
+            id: self.id.clone(),
+            created_at: self.created_at.clone(),
+            trace: self.to_trace(),
+        }
+    }
+
+    fn text_vector(&self) -> Vec<f32> {
+        text_vector(&format!("{} {} {}", self.input, self.action, self.outcome))
+    }
+}
+
+#[derive(Default)]
+struct LocalData {
+    variables: BTreeMap<String, BTreeMap<String, LocalVariable>>,
+    traces: Vec<LocalTrace>,
+    /// Parallel to `traces`: the text vector each trace is ranked by.
+    index: Vec<Vec<f32>>,
+}
+
+/// State shared by every `LocalBackend` opened on the same directory, so
+/// the trigger listener sees events from the main store.
+struct Shared {
+    dir: PathBuf,
+    data: Mutex<LocalData>,
+    events: broadcast::Sender<StateEvent>,
+}
+
+/// Everything stored locally, flattened for migration into Rice.
+pub struct LocalSnapshot {
+    /// `(run_id, variable)` pairs.
+    pub variables: Vec<(String, StoredVariable)>,
+    pub traces: Vec<Trace>,
+}
+
+pub struct LocalBackend {
+    shared: Arc<Shared>,
+}
+
+fn open_stores() -> &'static Mutex<HashMap<PathBuf, Arc<Shared>>> {
+    static STORES: OnceLock<Mutex<HashMap<PathBuf, Arc<Shared>>>> = OnceLock::new();
+    STORES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+impl LocalBackend {
+    /// Open (or create) the store in `dir`.  Opening the same directory
+    /// twice in one process returns a handle to the same data.
+    pub fn open(dir: &Path) -> Result<Self> {
+        let mut stores = open_stores()
+            .lock()
+            .map_err(|_| anyhow!("local memory lock poisoned"))?;
+        if let Some(shared) = stores.get(dir) {
+            return Ok(LocalBackend {
+                shared: shared.clone(),
+            });
+        }
+        let shared = Arc::new(Shared::load(dir)?);
+        stores.insert(dir.to_path_buf(), shared.clone());
+        Ok(LocalBackend { shared })
+    }
+
+    pub fn default_dir() -> PathBuf {
+        memini_home().join("memory")
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.shared.dir
+    }
+
+    pub fn snapshot(&self) -> Result<LocalSnapshot> {
+        let data = self.shared.lock()?;
+        let variables = data
+            .variables
+            .iter()
+            .flat_map(|(run_id, vars)| {
+                vars.iter().map(move |(name, variable)| {
+                    (
+                        run_id.clone(),
+                        StoredVariable {
+                            name: name.clone(),
+                            value_json: variable.value_json.clone(),
+                            source: variable.source.clone(),
+                        },
+                    )
+                })
+            })
+            .collect();
+        let traces = data.traces.iter().map(LocalTrace::to_trace).collect();
+        Ok(LocalSnapshot { variables, traces })
+    }
+
+    /// Move the store aside (to `<dir>.migrated-<timestamp>`) after its
+    /// contents were copied into Rice, so a second migration can't
+    /// duplicate traces.
+    pub fn archive(self) -> Result<PathBuf> {
+        let mut stores = open_stores()
+            .lock()
+            .map_err(|_| anyhow!("local memory lock poisoned"))?;
+        stores.remove(&self.shared.dir);
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        let mut target = self.shared.dir.clone().into_os_string();
+        target.push(format!(".migrated-{stamp}"));
+        let target = PathBuf::from(target);
+        fs::rename(&self.shared.dir, &target)
+            .with_context(|| format!("archive {}", self.shared.dir.display()))?;
+        Ok(target)
+    }
+}
+
+impl Shared {
+    fn load(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+
+        let mut data = LocalData::default();
+        let variables_path = dir.join(VARIABLES_FILE);
+        if variables_path.exists() {
+            let raw = fs::read_to_string(&variables_path)
+                .with_context(|| format!("read {}", variables_path.display()))?;
+            if !raw.trim().is_empty() {
+                data.variables = serde_json::from_str(&raw)
+                    .with_context(|| format!("parse {}", variables_path.display()))?;
+            }
+        }
+
+        let traces_path = dir.join(TRACES_FILE);
+        if traces_path.exists() {
+            let raw = fs::read_to_string(&traces_path)
+                .with_context(|| format!("read {}", traces_path.display()))?;
+            // A torn final line (crash mid-append) is skipped rather than
+            // making the whole store unreadable.
+            for line in raw.lines().filter(|line| !line.trim().is_empty()) {
+                if let Ok(trace) = serde_json::from_str::<LocalTrace>(line) {
+                    data.index.push(trace.text_vector());
+                    data.traces.push(trace);
+                }
+            }
+        }
+
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        Ok(Shared {
+            dir: dir.to_path_buf(),
+            data: Mutex::new(data),
+            events,
+        })
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, LocalData>> {
+        self.data
+            .lock()
+            .map_err(|_| anyhow!("local memory lock poisoned"))
+    }
+
+    /// Rewrite `variables.json` atomically (temp file + rename).
+    fn write_variables(&self, data: &LocalData) -> Result<()> {
+        let path = self.dir.join(VARIABLES_FILE);
+        let tmp = self.dir.join(format!("{VARIABLES_FILE}.tmp"));
+        let raw = serde_json::to_string_pretty(&data.variables).context("serialize variables")?;
+        fs::write(&tmp, raw).with_context(|| format!("write {}", tmp.display()))?;
+        fs::rename(&tmp, &path).with_context(|| format!("replace {}", path.display()))?;
+        Ok(())
+    }
+
+    fn append_trace(&self, trace: &LocalTrace) -> Result<()> {
+        let path = self.dir.join(TRACES_FILE);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("open {}", path.display()))?;
+        let line = serde_json::to_string(trace).context("serialize trace")?;
+        writeln!(file, "{line}").with_context(|| format!("append {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Rewrite `traces.jsonl` after a delete or edit (temp file + rename).
+    fn write_traces(&self, data: &LocalData) -> Result<()> {
+        let path = self.dir.join(TRACES_FILE);
+        let tmp = self.dir.join(format!("{TRACES_FILE}.tmp"));
+        let mut raw = String::new();
+        for trace in &data.traces {
+            raw.push_str(&serde_json::to_string(trace).context("serialize trace")?);
+            raw.push('\n');
+        }
+        fs::write(&tmp, raw).with_context(|| format!("write {}", tmp.display()))?;
+        fs::rename(&tmp, &path).with_context(|| format!("replace {}", path.display()))?;
+        Ok(())
+    }
+
+    fn emit(&self, event_type: &str, run_id: &str, agent_id: &str, payload: String) {
+        // No receivers is the normal case when no listener is running.
+        let _ = self.events.send(StateEvent {
+            event_type: event_type.to_string(),
+            run_id: run_id.to_string(),
+            agent_id: agent_id.to_string(),
+            payload,
+        });
+    }
+}
+
+impl MemoryBackend for LocalBackend {
+    fn set_variable<'a>(
+        &'a mut self,
+        run_id: String,
+        name: String,
+        value_json: String,
+        source: String,
+    ) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            let shared = &self.shared;
+            {
+                let mut data = shared.lock()?;
+                data.variables.entry(run_id.clone()).or_default().insert(
+                    name.clone(),
+                    LocalVariable {
+                        value_json: value_json.clone(),
+                        source: source.clone(),
+                        updated_at: chrono::Local::now().to_rfc3339(),
+                    },
+                );
+                shared.write_variables(&data)?;
+            }
+            let payload = json!({ "name": name, "value_json": value_json, "source": source });
+            shared.emit("VariableUpdate", &run_id, "", payload.to_string());
+            Ok(())
+        })
+    }
+
+    fn get_variable<'a>(
+        &'a mut self,
+        run_id: String,
+        name: String,
+    ) -> BackendFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let data = self.shared.lock()?;
+            Ok(data
+                .variables
+                .get(&run_id)
+                .and_then(|vars| vars.get(&name))
+                .map(|variable| variable.value_json.clone())
+                .filter(|value| !value.trim().is_empty()))
+        })
+    }
+
+    fn list_variables<'a>(&'a mut self, run_id: String) -> BackendFuture<'a, Vec<StoredVariable>> {
+        Box::pin(async move {
+            let data = self.shared.lock()?;
+            Ok(data
+                .variables
+                .get(&run_id)
+                .map(|vars| {
+                    vars.iter()
+                        .map(|(name, variable)| StoredVariable {
+                            name: name.clone(),
+                            value_json: variable.value_json.clone(),
+                            source: variable.source.clone(),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default())
+        })
+    }
+
+    fn delete_variable<'a>(&'a mut self, run_id: String, name: String) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            let shared = &self.shared;
+            let removed = {
+                let mut data = shared.lock()?;
+                let removed = data
+                    .variables
+                    .get_mut(&run_id)
+                    .and_then(|vars| vars.remove(&name))
+                    .is_some();
+                if removed {
+                    shared.write_variables(&data)?;
+                }
+                removed
+            };
+            if removed {
+                let payload = json!({ "name": name, "deleted": true });
+                shared.emit("VariableUpdate", &run_id, "", payload.to_string());
+            }
+            Ok(())
+        })
+    }
+
+    fn focus<'a>(&'a mut self, run_id: String, content: String) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            let payload = json!({ "content": content });
+            self.shared.emit("Focus", &run_id, "", payload.to_string());
+            Ok(())
+        })
+    }
+
+    fn reminisce<'a>(
+        &'a mut self,
+        run_id: String,
+        embedding: Vec<f32>,
+        limit: u64,
+        query_text: String,
+    ) -> BackendFuture<'a, Vec<StoredTrace>> {
+        Box::pin(async move {
+            let data = self.shared.lock()?;
+            let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+            let in_run = data
+                .traces
+                .iter()
+                .enumerate()
+                .filter(|(_, trace)| trace.run_id == run_id);
+
+            if query_text.trim().is_empty() && embedding.is_empty() {
+                // Nothing to rank by: most recent first.
+                let mut recent: Vec<StoredTrace> =
+                    in_run.map(|(_, trace)| trace.to_stored()).collect();
+                recent.reverse();
+                recent.truncate(limit);
+                return Ok(recent);
+            }
+
+            let query_vector = text_vector(&query_text);
+            let mut scored: Vec<(f32, usize)> = in_run
+                .map(|(index, trace)| {
+                    let score = if !embedding.is_empty() && trace.embedding.len() == embedding.len()
+                    {
+                        cosine(&embedding, &trace.embedding)
+                    } else {
+                        cosine(&query_vector, &data.index[index])
+                    };
+                    (score, index)
+                })
+                .filter(|(score, _)| *score > 0.0)
+                .collect();
+            // Best match first; newer traces win ties.
+            scored.sort_by(|a, b| b.0.total_cmp(&a.0).then(b.1.cmp(&a.1)));
+            Ok(scored
+                .into_iter()
+                .take(limit)
+                .map(|(_, index)| data.traces[index].to_stored())
+                .collect())
+        })
+    }
+
+    fn commit<'a>(&'a mut self, trace: Trace) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            let shared = &self.shared;
+            let now = chrono::Local::now();
+            let mut data = shared.lock()?;
+            let local = LocalTrace {
+                id: new_trace_id(&data),
+                run_id: trace.run_id,
+                agent_id: trace.agent_id,
+                input: trace.input,
+                reasoning: trace.reasoning,
+                action: trace.action,
+                outcome: trace.outcome,
+                embedding: trace.embedding,
+                created_at: now.to_rfc3339(),
+            };
+            shared.append_trace(&local)?;
+            data.index.push(local.text_vector());
+            data.traces.push(local.clone());
+            drop(data);
+            let payload = serde_json::to_string(&local).context("serialize trace")?;
+            shared.emit("Commit", &local.run_id, &local.agent_id, payload);
+            Ok(())
+        })
+    }
+
+    fn list_traces<'a>(
+        &'a mut self,
+        run_id: String,
+        limit: u64,
+    ) -> BackendFuture<'a, Vec<StoredTrace>> {
+        Box::pin(async move {
+            let data = self.shared.lock()?;
+            let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+            Ok(data
+                .traces
+                .iter()
+                .rev()
+                .filter(|trace| trace.run_id == run_id)
+                .take(limit)
+                .map(LocalTrace::to_stored)
+                .collect())
+        })
+    }
+
+    fn delete_trace<'a>(&'a mut self, run_id: String, id: String) -> BackendFuture<'a, bool> {
+        Box::pin(async move {
+            let shared = &self.shared;
+            let mut data = shared.lock()?;
+            let Some(position) = data
+                .traces
+                .iter()
+                .position(|trace| trace.run_id == run_id && trace.id == id)
+            else {
+                return Err(anyhow!("no memory with id {id}"));
+            };
+            data.traces.remove(position);
+            data.index.remove(position);
+            shared.write_traces(&data)?;
+            Ok(true)
+        })
+    }
+
+    fn update_trace<'a>(
+        &'a mut self,
+        run_id: String,
+        id: String,
+        trace: Trace,
+    ) -> BackendFuture<'a, bool> {
+        Box::pin(async move {
+            let shared = &self.shared;
+            let mut data = shared.lock()?;
+            let Some(position) = data
+                .traces
+                .iter()
+                .position(|existing| existing.run_id == run_id && existing.id == id)
+            else {
+                return Err(anyhow!("no memory with id {id}"));
+            };
+            let existing = &mut data.traces[position];
+            existing.agent_id = trace.agent_id;
+            existing.input = trace.input;
+            existing.reasoning = trace.reasoning;
+            existing.action = trace.action;
+            existing.outcome = trace.outcome;
+            existing.embedding = trace.embedding;
+            let vector = existing.text_vector();
+            data.index[position] = vector;
+            shared.write_traces(&data)?;
+            Ok(true)
+        })
+    }
+
+    fn subscribe<'a>(
+        &'a mut self,
+        run_id: String,
+        event_types: Vec<String>,
+        on_event: &'a mut (dyn FnMut(StateEvent) + Send),
+    ) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            let mut events = self.shared.events.subscribe();
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        if event.run_id == run_id
+                            && (event_types.is_empty() || event_types.contains(&event.event_type))
+                        {
+                            on_event(event);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                }
+            }
+        })
+    }
+}
+
+pub(super) fn memini_home() -> PathBuf {
+    if let Ok(value) = env::var("MEMINI_HOME")
+        && !value.trim().is_empty()
+    {
+        return PathBuf::from(value);
+    }
+    if let Some(base_dirs) = BaseDirs::new() {
+        return base_dirs.home_dir().join("Memini");
+    }
+    PathBuf::from("Memini")
+}
+
+/// Eight hex digits, short enough to type after `/memory show`.
+fn new_trace_id(data: &LocalData) -> String {
+    loop {
+        let id = format!("{:08x}", rand::random::<u32>());
+        if !data.traces.iter().any(|trace| trace.id == id) {
+            return id;
+        }
+    }
+}
+
+// ── Vector index ─────────────────────────────────────────────────────
+
+/// Hashed bag-of-words vector (FNV-1a into `VECTOR_DIMS` buckets),
+/// L2-normalised.  Stable across runs, so nothing needs persisting.
+fn text_vector(text: &str) -> Vec<f32> {
+    let mut vector = vec![0.0f32; VECTOR_DIMS];
+    for token in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| token.chars().count() > 1)
+    {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in token.to_lowercase().bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        vector[(hash % VECTOR_DIMS as u64) as usize] += 1.0;
+    }
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for value in &mut vector {
+            *value /= norm;
+        }
+    }
+    vector
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|v| v * v).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rice::RiceStore;
+    use serde_json::Value;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "memini-local-{name}-{}-{:x}",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn variables_and_traces_round_trip_through_the_store() {
+        let dir = temp_dir("store");
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let mut store = RiceStore::local_at(&dir).unwrap();
+            store
+                .set_variable("greeting", Value::String("hi".into()), "test")
+                .await
+                .unwrap();
+            assert_eq!(
+                store.get_variable("greeting").await.unwrap(),
+                Some(Value::String("hi".into()))
+            );
+            store
+                .commit_trace(
+                    "what colour is the sky",
+                    "blue on clear days",
+                    "chat",
+                    "",
+                    vec![],
+                    "memini:test",
+                )
+                .await
+                .unwrap();
+            store
+                .commit_trace(
+                    "favourite pizza",
+                    "margherita",
+                    "chat",
+                    "",
+                    vec![],
+                    "memini:test",
+                )
+                .await
+                .unwrap();
+
+            let recalled = store.reminisce(vec![], 1, "sky colour").await.unwrap();
+            assert_eq!(recalled.len(), 1);
+            assert_eq!(recalled[0].outcome, "blue on clear days");
+
+            store.delete_variable("greeting").await.unwrap();
+            assert_eq!(store.get_variable("greeting").await.unwrap(), None);
+        });
+
+        // Reload from disk, bypassing the in-process cache.
+        let reloaded = Shared::load(&dir).unwrap();
+        let data = reloaded.lock().unwrap();
+        assert_eq!(data.traces.len(), 2);
+        assert!(data.variables.values().all(|vars| vars.is_empty()));
+        drop(data);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn memories_can_be_forgotten_pinned_and_edited() {
+        let dir = temp_dir("manage");
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let mut store = RiceStore::local_at(&dir).unwrap();
+            for (input, outcome) in [
+                ("deploy target", "staging runs on port 8080"),
+                ("deploy target", "prod runs on port 9090"),
+                ("lunch", "tacos on friday"),
+            ] {
+                store
+                    .commit_trace(input, outcome, "chat", "", vec![], "memini:coder")
+                    .await
+                    .unwrap();
+            }
+            let listed = store
+                .list_memories(Some("coder"), None, None, 10)
+                .await
+                .unwrap();
+            assert_eq!(listed.len(), 3);
+            assert_eq!(listed[0].trace.outcome, "tacos on friday");
+
+            let staging = store.find_memory(&listed[2].id[..6]).await.unwrap();
+            store.forget_memory(&staging).await.unwrap();
+            let recalled = store.recall(vec![], 5, "deploy port").await.unwrap();
+            assert!(recalled.iter().all(|stored| stored.id != staging.id));
+
+            let lunch = store.find_memory(&listed[0].id).await.unwrap();
+            assert!(store.pin_memory(&lunch).await.unwrap());
+            let recalled = store.recall(vec![], 5, "deploy port").await.unwrap();
+            assert_eq!(recalled[0].id, lunch.id);
+            assert_eq!(
+                recalled
+                    .iter()
+                    .filter(|stored| stored.id == lunch.id)
+                    .count(),
+                1
+            );
+
+            let edited = store.edit_memory(&lunch, "pizza on friday").await.unwrap();
+            assert_eq!(edited.id, lunch.id);
+            let recalled = store.reminisce(vec![], 1, "unrelated").await.unwrap();
+            assert_eq!(recalled[0].outcome, "pizza on friday");
+
+            // Hidden ids whose trace is gone are pruned on the next save.
+            let mut overrides = store.memory_overrides().await.unwrap();
+            overrides.forgotten = vec!["deadbeef".to_string(), listed[1].id.clone()];
+            store.save_memory_overrides(&overrides).await.unwrap();
+            assert_eq!(
+                store.memory_overrides().await.unwrap().forgotten,
+                [listed[1].id.clone()]
+            );
+        });
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn subscribers_receive_variable_updates() {
+        let dir = temp_dir("events");
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let mut listener = LocalBackend::open(&dir).unwrap();
+            let mut writer = LocalBackend::open(&dir).unwrap();
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            let listen = tokio::spawn(async move {
+                let mut forward = |event: StateEvent| {
+                    let _ = tx.send(event);
+                };
+                listener
+                    .subscribe(
+                        "run".to_string(),
+                        vec!["VariableUpdate".to_string()],
+                        &mut forward,
+                    )
+                    .await
+            });
+            tokio::task::yield_now().await;
+            writer
+                .set_variable("other".into(), "x".into(), "1".into(), "test".into())
+                .await
+                .unwrap();
+            writer
+                .set_variable("run".into(), "x".into(), "2".into(), "test".into())
+                .await
+                .unwrap();
+            let event = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(event.run_id, "run");
+            let payload: Value = serde_json::from_str(&event.payload).unwrap();
+            assert_eq!(payload["name"], "x");
+            listen.abort();
+        });
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
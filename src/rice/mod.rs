@@ -0,0 +1,852 @@
+//! Rice SDK integration — state variables, memory traces, and focus.
+//!
+//! `RiceStore` talks to a [`MemoryBackend`]: Rice State when configured,
+//! otherwise the embedded [`LocalBackend`] (see `local.rs`).  Rice
+//! connections are shared through [`RicePool`] (`pool.rs`); writes made
+//! while Rice is unreachable wait in the outbox (`outbox.rs`).  Writes are
+//! stamped with their author (`provenance.rs`).  Shared-workspace members,
+//! roles and encryption live in `workspace.rs`, named conversation threads
+//! in `threads.rs`, export and import in `transfer.rs`, consolidation in
+//! `compact.rs`.
+
+mod backend;
+mod compact;
+mod local;
+mod outbox;
+mod pool;
+mod provenance;
+mod threads;
+mod transfer;
+mod workspace;
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, FixedOffset};
+use rice::rice_core::config::{RiceConfig, StateConfig, StorageConfig};
+use rice::rice_state::proto::Trace;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub use backend::StoredTrace;
+use backend::{MemoryBackend, RiceBackend};
+pub use compact::MemoryCluster;
+use local::LocalBackend;
+pub use outbox::outbox_depth;
+use outbox::{Observed, Outbox, QueuedWrite};
+pub use pool::RicePool;
+pub use provenance::{Author, authored_by, strip_stamp, trace_author};
+pub use transfer::{TRANSFER_USAGE, TransferCommand};
+use workspace::WorkspaceAccess;
+pub use workspace::{JoinKind, JoinMode, Role};
+
+use crate::constants::{
+    ACTIVE_AGENT_VAR, APP_NAME, CUSTOM_AGENT_TOMBSTONES_VAR, CUSTOM_AGENTS_VAR, DEFAULT_RUN_ID,
+    MEMORY_OVERRIDES_VAR, MEMORY_SCAN_LIMIT, SHARED_WORKSPACE_VAR,
+};
+use crate::util::{env_first, normalize_url};
+
+/// Persistent store backed by Rice State, or by local files when Rice
+/// is not configured.
+pub struct RiceStore {
+    backend: Option<Box<dyn MemoryBackend>>,
+    pub status: RiceStatus,
+    run_id: String,
+    /// When set, all memory operations target this shared workspace
+    /// instead of the personal `run_id`.
+    pub shared_run_id: Option<String>,
+    /// Where writes go while Rice is unreachable; `None` without Rice.
+    outbox: Option<Arc<Outbox>>,
+    /// Role and key in the shared workspace, when joined through
+    /// [`open_workspace`](Self::open_workspace).
+    access: Option<WorkspaceAccess>,
+    /// Member keys and secrets for shared workspaces.
+    keys_file: PathBuf,
+}
+
+/// Connection state of the Rice backend.
+#[derive(Clone, Debug)]
+pub enum RiceStatus {
+    Connected,
+    /// No Rice env; memory is kept in the given local directory.
+    Local(String),
+    Disabled(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct StateEvent {
+    pub event_type: String,
+    pub run_id: String,
+    pub agent_id: String,
+    pub payload: String,
+}
+
+impl RiceStore {
+    /// A store on a pooled Rice connection, or on the local store when
+    /// Rice is not configured.
+    pub async fn connect() -> Self {
+        let Some(config) = rice_config_from_env() else {
+            return Self::connect_local();
+        };
+
+        match RicePool::shared().checkout(config).await {
+            Ok(state) => RiceStore {
+                backend: Some(Box::new(RiceBackend::new(state))),
+                status: RiceStatus::Connected,
+                run_id: rice_run_id(),
+                shared_run_id: None,
+                outbox: Outbox::shared(),
+                access: None,
+                keys_file: workspace::default_keys_file(),
+            },
+            // Rice is configured but down: queue writes until it is back.
+            Err(reason) => RiceStore {
+                outbox: Outbox::shared(),
+                ..Self::disabled(reason)
+            },
+        }
+    }
+
+    /// Offline fallback: the embedded store under `$MEMINI_HOME/memory`,
+    /// unless `MEMINI_LOCAL_MEMORY=off`.
+    fn connect_local() -> Self {
+        let enabled = env::var("MEMINI_LOCAL_MEMORY")
+            .map(|value| !matches!(value.trim(), "0" | "off" | "false" | "no"))
+            .unwrap_or(true);
+        if !enabled {
+            return Self::disabled("Rice env not configured".to_string());
+        }
+        match Self::local_at(&LocalBackend::default_dir()) {
+            Ok(store) => RiceStore {
+                keys_file: workspace::default_keys_file(),
+                ..store
+            },
+            Err(err) => Self::disabled(format!("Rice env not configured; local memory: {err:#}")),
+        }
+    }
+
+    /// A store backed by the local files in `dir` (workspace keys
+    /// included).
+    pub fn local_at(dir: &Path) -> Result<Self> {
+        let backend = LocalBackend::open(dir)?;
+        let label = backend.dir().display().to_string();
+        Ok(RiceStore {
+            backend: Some(Box::new(backend)),
+            status: RiceStatus::Local(label),
+            run_id: rice_run_id(),
+            shared_run_id: None,
+            outbox: None,
+            access: None,
+            keys_file: dir.join("workspace-keys.json"),
+        })
+    }
+
+    fn disabled(reason: String) -> Self {
+        RiceStore {
+            backend: None,
+            status: RiceStatus::Disabled(reason),
+            run_id: rice_run_id(),
+            shared_run_id: None,
+            outbox: None,
+            access: None,
+            keys_file: workspace::default_keys_file(),
+        }
+    }
+
+    fn backend(&mut self) -> Result<&mut Box<dyn MemoryBackend>> {
+        self.backend
+            .as_mut()
+            .ok_or_else(|| anyhow!("Rice not connected"))
+    }
+
+    pub fn status_label(&self) -> String {
+        match &self.status {
+            RiceStatus::Connected => "connected".to_string(),
+            RiceStatus::Local(_) => "local".to_string(),
+            RiceStatus::Disabled(reason) => format!("off ({reason})"),
+        }
+    }
+
+    /// The run-id currently in effect.  Returns the shared workspace id
+    /// when the user has joined one, otherwise their personal id.
+    pub fn active_run_id(&self) -> String {
+        self.shared_run_id
+            .clone()
+            .unwrap_or_else(|| self.run_id.clone())
+    }
+
+    /// Switch to a shared workspace.  All subsequent memory operations
+    /// (focus, recall, commit) will target this workspace so that every
+    /// user on the same Rice endpoint who joins the same name shares
+    /// the same memory pool.  Membership is not checked; the app joins
+    /// through [`open_workspace`](Self::open_workspace).
+    pub fn join_workspace(&mut self, name: &str) {
+        self.shared_run_id = Some(name.to_string());
+        self.access = None;
+    }
+
+    /// Connect a store for a background task (chat turn, agent window,
+    /// daemon) that works in the same workspace, with the same role and
+    /// key, as this one.
+    pub fn connect_alike(&self) -> impl Future<Output = Self> + Send + 'static {
+        let shared_run_id = self.shared_run_id.clone();
+        let access = self.access.clone();
+        async move {
+            RiceStore {
+                shared_run_id,
+                access,
+                ..Self::connect().await
+            }
+        }
+    }
+
+    /// Return to the personal (private) workspace.
+    pub fn leave_workspace(&mut self) {
+        self.shared_run_id = None;
+        self.access = None;
+    }
+
+    /// Persist the current shared workspace name into Rice (personal
+    /// scope) so it can be restored on next launch.
+    pub async fn save_shared_workspace(&mut self) -> Result<()> {
+        // Always the personal scope, never the shared run_id.
+        let run_id = self.run_id.clone();
+        match self.shared_run_id.clone() {
+            Some(name) => {
+                let value_json =
+                    serde_json::to_string(&Value::String(name)).context("serialize")?;
+                self.backend()?
+                    .set_variable(
+                        run_id,
+                        SHARED_WORKSPACE_VAR.to_string(),
+                        value_json,
+                        "share".to_string(),
+                    )
+                    .await
+                    .context("save shared workspace")?;
+            }
+            None => {
+                self.backend()?
+                    .delete_variable(run_id, SHARED_WORKSPACE_VAR.to_string())
+                    .await
+                    .context("clear shared workspace")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Load a previously-saved shared workspace from Rice.
+    pub async fn load_shared_workspace(&mut self) -> Result<Option<String>> {
+        let run_id = self.run_id.clone();
+        let value_json = self
+            .backend()?
+            .get_variable(run_id, SHARED_WORKSPACE_VAR.to_string())
+            .await
+            .context("load shared workspace")?;
+        match value_json.map(|raw| serde_json::from_str::<Value>(&raw)) {
+            Some(Ok(Value::String(name))) => Ok(Some(name)),
+            _ => Ok(None),
+        }
+    }
+
+    pub async fn set_variable(&mut self, name: &str, value: Value, source: &str) -> Result<()> {
+        self.ensure_writable()?;
+        let rid = self.active_run_id();
+        let value_json = serde_json::to_string(&value).context("serialize value")?;
+        self.write_through(QueuedWrite::SetVariable {
+            run_id: rid,
+            name: name.to_string(),
+            value_json,
+            source: provenance::stamp_source(source),
+        })
+        .await
+        .context("set variable")
+    }
+
+    pub async fn get_variable(&mut self, name: &str) -> Result<Option<Value>> {
+        let rid = self.active_run_id();
+        let value_json = self
+            .backend()?
+            .get_variable(rid.clone(), name.to_string())
+            .await
+            .context("get variable")?;
+        if let Some(outbox) = &self.outbox {
+            outbox.observe(&rid, name, Observed::of(value_json.as_deref()));
+        }
+        let Some(value_json) = value_json else {
+            return Ok(None);
+        };
+        let value = serde_json::from_str::<Value>(&value_json).context("parse value_json")?;
+        Ok(Some(value))
+    }
+
+    pub async fn delete_variable(&mut self, name: &str) -> Result<()> {
+        self.ensure_writable()?;
+        let rid = self.active_run_id();
+        self.write_through(QueuedWrite::DeleteVariable {
+            run_id: rid,
+            name: name.to_string(),
+        })
+        .await
+        .context("delete variable")
+    }
+
+    pub async fn focus(&mut self, content: &str) -> Result<()> {
+        let rid = self.active_run_id();
+        self.backend()?
+            .focus(rid, content.to_string())
+            .await
+            .context("focus")?;
+        Ok(())
+    }
+
+    /// Recall traces for injection: forgotten memories are dropped and
+    /// pinned ones are always included, ahead of the ranked results.
+    pub async fn reminisce(
+        &mut self,
+        embedding: Vec<f32>,
+        limit: u64,
+        query_text: &str,
+    ) -> Result<Vec<Trace>> {
+        Ok(self
+            .recall(embedding, limit, query_text)
+            .await?
+            .into_iter()
+            .map(|stored| stored.trace)
+            .collect())
+    }
+
+    /// Like [`recall`](Self::recall), keeping only memories written by
+    /// `author` (a user name or `user@host`).
+    pub async fn recall_by_author(
+        &mut self,
+        author: &str,
+        limit: u64,
+        query_text: &str,
+    ) -> Result<Vec<StoredTrace>> {
+        Ok(self
+            .recall(vec![], MEMORY_SCAN_LIMIT, query_text)
+            .await?
+            .into_iter()
+            .filter(|stored| authored_by(&stored.trace, author))
+            .take(usize::try_from(limit).unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// Like [`reminisce`](Self::reminisce), keeping each trace's id.
+    pub async fn recall(
+        &mut self,
+        embedding: Vec<f32>,
+        limit: u64,
+        query_text: &str,
+    ) -> Result<Vec<StoredTrace>> {
+        let rid = self.active_run_id();
+        let ranked = self
+            .backend()?
+            .reminisce(rid, embedding, limit, query_text.to_string())
+            .await
+            .context("reminisce")?;
+        let overrides = self.memory_overrides().await?;
+        let mut traces: Vec<StoredTrace> = overrides
+            .pinned
+            .iter()
+            .map(|pin| self.open_stored(pin.to_stored()))
+            .collect();
+        traces.extend(
+            ranked
+                .into_iter()
+                .filter(|stored| {
+                    !overrides.is_forgotten(&stored.id) && !overrides.is_pinned(&stored.id)
+                })
+                .map(|stored| self.open_stored(stored)),
+        );
+        Ok(traces)
+    }
+
+    pub async fn commit_trace(
+        &mut self,
+        input: &str,
+        outcome: &str,
+        action: &str,
+        reasoning: &str,
+        embedding: Vec<f32>,
+        agent_id: &str,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let rid = self.active_run_id();
+        let mut trace = Trace {
+            input: input.to_string(),
+            reasoning: reasoning.to_string(),
+            action: action.to_string(),
+            outcome: outcome.to_string(),
+            agent_id: agent_id.to_string(),
+            embedding,
+            run_id: rid,
+        };
+        provenance::stamp_trace(&mut trace);
+        self.seal_trace(&mut trace)?;
+        self.write_through(QueuedWrite::commit(trace))
+            .await
+            .context("commit trace")
+    }
+
+    pub async fn subscribe_events_with<F>(
+        &mut self,
+        run_id: String,
+        event_types: Vec<String>,
+        mut on_event: F,
+    ) -> Result<()>
+    where
+        F: FnMut(StateEvent) + Send,
+    {
+        self.backend()?
+            .subscribe(run_id, event_types, &mut on_event)
+            .await
+            .context("subscribe to state events")
+    }
+
+    /// Copy everything in the local store into the connected Rice
+    /// instance, then move the local directory aside.  Returns
+    /// `(variables, traces, archived_dir)`, or `None` when there is no
+    /// local store to migrate.
+    pub async fn migrate_local_memory(
+        &mut self,
+    ) -> Result<Option<(usize, usize, std::path::PathBuf)>> {
+        if !matches!(self.status, RiceStatus::Connected) {
+            return Err(anyhow!("Rice is not connected"));
+        }
+        let dir = LocalBackend::default_dir();
+        if !dir.exists() {
+            return Ok(None);
+        }
+        let local = LocalBackend::open(&dir)?;
+        let snapshot = local.snapshot()?;
+        let backend = self.backend()?;
+        for (run_id, variable) in &snapshot.variables {
+            backend
+                .set_variable(
+                    run_id.clone(),
+                    variable.name.clone(),
+                    variable.value_json.clone(),
+                    variable.source.clone(),
+                )
+                .await
+                .with_context(|| format!("migrate variable {}", variable.name))?;
+        }
+        for trace in &snapshot.traces {
+            backend
+                .commit(trace.clone())
+                .await
+                .context("migrate trace")?;
+        }
+        let archived = local.archive()?;
+        Ok(Some((
+            snapshot.variables.len(),
+            snapshot.traces.len(),
+            archived,
+        )))
+    }
+
+    // ── Memory management ────────────────────────────────────────────
+
+    /// Recent memories, newest first, optionally narrowed to one agent
+    /// (persona name or full `memini:<name>` id), one author and a start
+    /// time.  Traces with neither a backend timestamp nor an author stamp
+    /// never match `since`.
+    pub async fn list_memories(
+        &mut self,
+        agent: Option<&str>,
+        author: Option<&str>,
+        since: Option<DateTime<FixedOffset>>,
+        limit: u64,
+    ) -> Result<Vec<StoredTrace>> {
+        let rid = self.active_run_id();
+        let scan = if agent.is_some() || author.is_some() || since.is_some() {
+            MEMORY_SCAN_LIMIT
+        } else {
+            limit
+        };
+        let traces = self
+            .backend()?
+            .list_traces(rid, scan)
+            .await
+            .context("list memories")?;
+        let overrides = self.memory_overrides().await?;
+        Ok(traces
+            .into_iter()
+            .filter(|stored| !overrides.is_forgotten(&stored.id))
+            .filter(|stored| agent.is_none_or(|agent| agent_matches(&stored.trace.agent_id, agent)))
+            .filter(|stored| author.is_none_or(|author| authored_by(&stored.trace, author)))
+            .filter(|stored| {
+                since.is_none_or(|since| written_at(stored).is_some_and(|at| at >= since))
+            })
+            .take(usize::try_from(limit).unwrap_or(usize::MAX))
+            .map(|stored| self.open_stored(stored))
+            .collect())
+    }
+
+    /// Resolve a memory by id or unique id prefix, looking at pinned
+    /// memories and the most recent traces.
+    pub async fn find_memory(&mut self, id: &str) -> Result<StoredTrace> {
+        let id = id.trim().to_ascii_lowercase();
+        if id.is_empty() {
+            return Err(anyhow!("memory id is empty"));
+        }
+        let mut candidates: Vec<StoredTrace> = self
+            .memory_overrides()
+            .await?
+            .pinned
+            .iter()
+            .map(|pin| self.open_stored(pin.to_stored()))
+            .collect();
+        for stored in self
+            .list_memories(None, None, None, MEMORY_SCAN_LIMIT)
+            .await?
+        {
+            if !candidates.iter().any(|known| known.id == stored.id) {
+                candidates.push(stored);
+            }
+        }
+        let mut matches: Vec<StoredTrace> = candidates
+            .into_iter()
+            .filter(|stored| stored.id.starts_with(&id))
+            .collect();
+        match matches.len() {
+            0 => Err(anyhow!("no memory with id {id}")),
+            1 => Ok(matches.remove(0)),
+            n => Err(anyhow!(
+                "id {id} is ambiguous ({n} matches); type more of it"
+            )),
+        }
+    }
+
+    /// Memories whose input or outcome contains `query` (case-insensitive).
+    pub async fn find_memories_containing(&mut self, query: &str) -> Result<Vec<StoredTrace>> {
+        let needle = query.trim().to_lowercase();
+        if needle.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(self
+            .list_memories(None, None, None, MEMORY_SCAN_LIMIT)
+            .await?
+            .into_iter()
+            .filter(|stored| {
+                stored.trace.input.to_lowercase().contains(&needle)
+                    || stored.trace.outcome.to_lowercase().contains(&needle)
+            })
+            .collect())
+    }
+
+    /// Delete a memory (or hide it, where the backend can't delete) and
+    /// drop any pin on it.
+    pub async fn forget_memory(&mut self, stored: &StoredTrace) -> Result<()> {
+        self.ensure_writable()?;
+        let rid = self.active_run_id();
+        let deleted = match self.backend()?.delete_trace(rid, stored.id.clone()).await {
+            Ok(deleted) => deleted,
+            // Pinned memories may outlive the trace they came from.
+            Err(_) if self.memory_overrides().await?.is_pinned(&stored.id) => true,
+            Err(err) => return Err(err.context("forget memory")),
+        };
+        let mut overrides = self.memory_overrides().await?;
+        overrides.pinned.retain(|pin| pin.id != stored.id);
+        if !deleted && !overrides.is_forgotten(&stored.id) {
+            overrides.forgotten.push(stored.id.clone());
+        }
+        self.save_memory_overrides(&overrides).await
+    }
+
+    /// Pin a memory so it is injected on every recall.  Returns `false`
+    /// when it was already pinned.
+    pub async fn pin_memory(&mut self, stored: &StoredTrace) -> Result<bool> {
+        let mut overrides = self.memory_overrides().await?;
+        if overrides.is_pinned(&stored.id) {
+            return Ok(false);
+        }
+        let mut sealed = stored.clone();
+        self.seal_trace(&mut sealed.trace)?;
+        overrides.pinned.push(PinnedMemory::from_stored(&sealed));
+        self.save_memory_overrides(&overrides).await?;
+        Ok(true)
+    }
+
+    /// Returns `false` when the memory was not pinned.
+    pub async fn unpin_memory(&mut self, id: &str) -> Result<bool> {
+        let mut overrides = self.memory_overrides().await?;
+        let before = overrides.pinned.len();
+        overrides.pinned.retain(|pin| pin.id != id);
+        if overrides.pinned.len() == before {
+            return Ok(false);
+        }
+        self.save_memory_overrides(&overrides).await?;
+        Ok(true)
+    }
+
+    pub async fn is_memory_pinned(&mut self, id: &str) -> Result<bool> {
+        Ok(self.memory_overrides().await?.is_pinned(id))
+    }
+
+    /// Replace a memory's outcome.  Backends that can't edit in place get
+    /// the old trace hidden and a corrected one committed.  Pins follow
+    /// the edit.  Returns the memory as stored after the edit.
+    pub async fn edit_memory(
+        &mut self,
+        stored: &StoredTrace,
+        outcome: &str,
+    ) -> Result<StoredTrace> {
+        self.ensure_writable()?;
+        let rid = self.active_run_id();
+        let mut trace = stored.trace.clone();
+        trace.outcome = outcome.to_string();
+        trace.run_id = rid.clone();
+        provenance::stamp_trace(&mut trace);
+        self.seal_trace(&mut trace)?;
+        let mut overrides = self.memory_overrides().await?;
+        let was_pinned = overrides.is_pinned(&stored.id);
+        let updated = self
+            .backend()?
+            .update_trace(rid, stored.id.clone(), trace.clone())
+            .await;
+        let edited_id = match updated {
+            Ok(true) => stored.id.clone(),
+            Ok(false) => {
+                self.write_through(QueuedWrite::commit(trace.clone()))
+                    .await
+                    .context("commit edited memory")?;
+                if !overrides.is_forgotten(&stored.id) {
+                    overrides.forgotten.push(stored.id.clone());
+                }
+                backend::trace_id(&trace)
+            }
+            // Only the pin is left of this memory; edit that.
+            Err(_) if was_pinned => stored.id.clone(),
+            Err(err) => return Err(err.context("edit memory")),
+        };
+        let edited = StoredTrace {
+            id: edited_id,
+            created_at: stored.created_at.clone(),
+            trace,
+        };
+
+        if was_pinned {
+            overrides.pinned.retain(|pin| pin.id != stored.id);
+            overrides.pinned.push(PinnedMemory::from_stored(&edited));
+        }
+        self.save_memory_overrides(&overrides).await?;
+        Ok(self.open_stored(edited))
+    }
+
+    async fn memory_overrides(&mut self) -> Result<MemoryOverrides> {
+        match self.get_variable(MEMORY_OVERRIDES_VAR).await? {
+            Some(value) => serde_json::from_value(value).context("parse memory overrides"),
+            None => Ok(MemoryOverrides::default()),
+        }
+    }
+
+    /// Save the overrides, dropping forgotten ids whose trace is gone.
+    /// Only a backend that lists every trace can tell; Rice never deletes
+    /// a forgotten trace, so its id stays needed there.
+    async fn save_memory_overrides(&mut self, overrides: &MemoryOverrides) -> Result<()> {
+        let mut overrides = overrides.clone();
+        if !overrides.forgotten.is_empty() && self.backend()?.lists_every_trace() {
+            let rid = self.active_run_id();
+            let traces = self
+                .backend()?
+                .list_traces(rid, MEMORY_SCAN_LIMIT)
+                .await
+                .context("list memories")?;
+            // A full page may have cut off the rest.
+            if (traces.len() as u64) < MEMORY_SCAN_LIMIT {
+                overrides
+                    .forgotten
+                    .retain(|id| traces.iter().any(|stored| stored.id == *id));
+            }
+        }
+        let value = serde_json::to_value(&overrides).context("serialize memory overrides")?;
+        self.set_variable(MEMORY_OVERRIDES_VAR, value, "memory")
+            .await
+    }
+
+    // ── Agent persistence ────────────────────────────────────────────
+
+    pub async fn save_custom_agents(&mut self, agents_json: Value) -> Result<()> {
+        self.set_variable(CUSTOM_AGENTS_VAR, agents_json, "agent")
+            .await
+    }
+
+    pub async fn load_custom_agents(&mut self) -> Result<Option<Value>> {
+        self.get_variable(CUSTOM_AGENTS_VAR).await
+    }
+
+    pub async fn save_agent_tombstones(&mut self, tombstones: Value) -> Result<()> {
+        self.set_variable(CUSTOM_AGENT_TOMBSTONES_VAR, tombstones, "agent")
+            .await
+    }
+
+    pub async fn load_agent_tombstones(&mut self) -> Result<Option<Value>> {
+        self.get_variable(CUSTOM_AGENT_TOMBSTONES_VAR).await
+    }
+
+    pub async fn save_active_agent_name(&mut self, name: &str) -> Result<()> {
+        self.set_variable(ACTIVE_AGENT_VAR, Value::String(name.to_string()), "agent")
+            .await
+    }
+
+    pub async fn load_active_agent_name(&mut self) -> Result<Option<String>> {
+        match self.get_variable(ACTIVE_AGENT_VAR).await? {
+            Some(Value::String(name)) => Ok(Some(name)),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Pinned and hidden memories for one run, stored as a state variable so
+/// they work the same on every backend.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct MemoryOverrides {
+    #[serde(default)]
+    pinned: Vec<PinnedMemory>,
+    /// Ids of memories forgotten on a backend that cannot delete them.
+    #[serde(default)]
+    forgotten: Vec<String>,
+}
+
+impl MemoryOverrides {
+    fn is_pinned(&self, id: &str) -> bool {
+        self.pinned.iter().any(|pin| pin.id == id)
+    }
+
+    fn is_forgotten(&self, id: &str) -> bool {
+        self.forgotten.iter().any(|forgotten| forgotten == id)
+    }
+}
+
+/// A copy of a pinned trace, so pins survive the trace leaving recall.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PinnedMemory {
+    id: String,
+    #[serde(default)]
+    agent_id: String,
+    #[serde(default)]
+    input: String,
+    #[serde(default)]
+    action: String,
+    #[serde(default)]
+    outcome: String,
+    #[serde(default)]
+    created_at: String,
+}
+
+impl PinnedMemory {
+    fn from_stored(stored: &StoredTrace) -> Self {
+        PinnedMemory {
+            id: stored.id.clone(),
+            agent_id: stored.trace.agent_id.clone(),
+            input: stored.trace.input.clone(),
+            action: stored.trace.action.clone(),
+            outcome: stored.trace.outcome.clone(),
+            created_at: stored.created_at.clone(),
+        }
+    }
+
+    fn to_stored(&self) -> StoredTrace {
+        StoredTrace {
+            id: self.id.clone(),
+            created_at: self.created_at.clone(),
+            trace: Trace {
+                input: self.input.clone(),
+                reasoning: String::new(),
+                action: self.action.clone(),
+                outcome: self.outcome.clone(),
+                agent_id: self.agent_id.clone(),
+                embedding: Vec::new(),
+                run_id: String::new(),
+            },
+        }
+    }
+}
+
+/// When a memory was written: the backend's timestamp, else the author
+/// stamp (Rice keeps no trace timestamps of its own).
+pub fn written_at(stored: &StoredTrace) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(&stored.created_at)
+        .ok()
+        .or_else(|| trace_author(&stored.trace)?.written_at())
+}
+
+/// `agent` may be a persona name (`coder`) or a full agent id.
+fn agent_matches(agent_id: &str, agent: &str) -> bool {
+    agent_id.eq_ignore_ascii_case(agent) || agent_id.eq_ignore_ascii_case(&agent_id_for(agent))
+}
+
+fn rice_run_id() -> String {
+    env::var("MEMINI_RUN_ID").unwrap_or_else(|_| DEFAULT_RUN_ID.to_string())
+}
+
+fn rice_config_from_env() -> Option<RiceConfig> {
+    let state = env_first(&["RICE_STATE_URL", "STATE_INSTANCE_URL"]).map(|url| StateConfig {
+        enabled: true,
+        base_url: Some(normalize_url(&url)),
+        auth_token: env_first(&["RICE_STATE_TOKEN", "STATE_AUTH_TOKEN"]),
+        llm_mode: None,
+        flux: None,
+    });
+
+    let storage =
+        env_first(&["RICE_STORAGE_URL", "STORAGE_INSTANCE_URL"]).map(|url| StorageConfig {
+            enabled: true,
+            base_url: Some(normalize_url(&url)),
+            auth_token: env_first(&["RICE_STORAGE_TOKEN", "STORAGE_AUTH_TOKEN"]),
+            username: None,
+            password: None,
+        });
+
+    if state.is_none() && storage.is_none() {
+        return None;
+    }
+
+    Some(RiceConfig { state, storage })
+}
+
+pub fn format_memories(traces: &[Trace]) -> String {
+    if traces.is_empty() {
+        return String::new();
+    }
+    let mut lines = Vec::new();
+    lines.push("Relevant memory from Rice:".to_string());
+    for trace in traces {
+        let input = trace.input.trim();
+        let outcome = trace.outcome.trim();
+        if input.is_empty() && outcome.is_empty() {
+            continue;
+        }
+        let action = trace.action.trim();
+        let mut line = if action.is_empty() {
+            format!("- input: {input} | outcome: {outcome}")
+        } else {
+            format!("- input: {input} | action: {action} | outcome: {outcome}")
+        };
+        if let Some(author) = trace_author(trace) {
+            let when = author
+                .written_at()
+                .map(|at| format!(" on {}", at.format("%Y-%m-%d")))
+                .unwrap_or_default();
+            line.push_str(&format!(" | by: {}{when}", author.label()));
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+pub fn system_prompt(persona: &str, require_mcp: bool) -> String {
+    let now = chrono::Local::now()
+        .format("%A, %B %e, %Y at %H:%M")
+        .to_string();
+    crate::prompts::main_chat_system_prompt(persona, &now, require_mcp)
+}
+
+pub fn agent_id_for(agent_name: &str) -> String {
+    format!("{APP_NAME}:{agent_name}")
+}
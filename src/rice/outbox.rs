@@ -0,0 +1,740 @@
+//! Durable write-behind queue for Rice.
+//!
+//! When Rice can't be reached, `set_variable`, `delete_variable` and
+//! `commit_trace` append the write to `$MEMINI_HOME/outbox.jsonl` instead
+//! of failing.  The TUI replays the queue in order once Rice answers
+//! again (see [`RiceStore::replay_outbox`]).
+//!
+//! Variable conflicts are settled per key.  Each queued write remembers
+//! the value this machine last saw for its key (read or written through
+//! Rice), and replay compares that with what Rice holds now, so no clock
+//! has to agree with another:
+//!
+//! | Situation                                        | Result                 |
+//! | ------------------------------------------------ | ---------------------- |
+//! | A later write to the same key is queued          | It replaces the older  |
+//! | This machine wrote the key directly since        | Queued one is dropped  |
+//! | Rice already holds the queued value              | Nothing to send        |
+//! | Rice no longer holds the value last seen         | Rice value is kept     |
+//! | The key was never seen before it was queued      | Queued one is sent     |
+//!
+//! Queued writes are appended to the file; it is rewritten only once
+//! replaced lines pile up, or when entries leave the queue.
+//!
+//! Writes Rice rejects outright are retried [`OUTBOX_MAX_ATTEMPTS`] times
+//! and then moved to `outbox.failed.jsonl`.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use anyhow::{Context, Result, anyhow};
+use rice::rice_state::proto::Trace;
+use serde::{Deserialize, Serialize};
+
+use super::backend::{MemoryBackend, StoredVariable};
+use super::local::memini_home;
+use super::pool::is_connectivity_error;
+use super::{RiceStatus, RiceStore};
+use crate::constants::OUTBOX_MAX_ATTEMPTS;
+
+const OUTBOX_FILE: &str = "outbox.jsonl";
+const FAILED_FILE: &str = "outbox.failed.jsonl";
+/// Replaced lines tolerated in the file before it is rewritten.
+const OUTBOX_STALE_LINES: usize = 64;
+
+/// One write that could not reach Rice.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub(super) enum QueuedWrite {
+    SetVariable {
+        run_id: String,
+        name: String,
+        value_json: String,
+        #[serde(default)]
+        source: String,
+    },
+    DeleteVariable {
+        run_id: String,
+        name: String,
+    },
+    Commit {
+        run_id: String,
+        #[serde(default)]
+        agent_id: String,
+        #[serde(default)]
+        input: String,
+        #[serde(default)]
+        reasoning: String,
+        #[serde(default)]
+        action: String,
+        #[serde(default)]
+        outcome: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        embedding: Vec<f32>,
+    },
+}
+
+impl QueuedWrite {
+    pub(super) fn commit(trace: Trace) -> Self {
+        QueuedWrite::Commit {
+            run_id: trace.run_id,
+            agent_id: trace.agent_id,
+            input: trace.input,
+            reasoning: trace.reasoning,
+            action: trace.action,
+            outcome: trace.outcome,
+            embedding: trace.embedding,
+        }
+    }
+
+    /// `(run_id, name)` for variable writes.
+    fn variable_key(&self) -> Option<(&str, &str)> {
+        match self {
+            QueuedWrite::SetVariable { run_id, name, .. }
+            | QueuedWrite::DeleteVariable { run_id, name } => Some((run_id, name)),
+            QueuedWrite::Commit { .. } => None,
+        }
+    }
+
+    pub(super) async fn apply(&self, backend: &mut dyn MemoryBackend) -> Result<()> {
+        match self.clone() {
+            QueuedWrite::SetVariable {
+                run_id,
+                name,
+                value_json,
+                source,
+            } => backend.set_variable(run_id, name, value_json, source).await,
+            QueuedWrite::DeleteVariable { run_id, name } => {
+                backend.delete_variable(run_id, name).await
+            }
+            QueuedWrite::Commit {
+                run_id,
+                agent_id,
+                input,
+                reasoning,
+                action,
+                outcome,
+                embedding,
+            } => {
+                backend
+                    .commit(Trace {
+                        input,
+                        reasoning,
+                        action,
+                        outcome,
+                        agent_id,
+                        embedding,
+                        run_id,
+                    })
+                    .await
+            }
+        }
+    }
+}
+
+/// A variable as this machine last saw it in Rice.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub(super) enum Observed {
+    Absent,
+    Value { value_json: String },
+}
+
+impl Observed {
+    pub(super) fn of(value_json: Option<&str>) -> Self {
+        match value_json {
+            Some(value_json) => Observed::Value {
+                value_json: value_json.to_string(),
+            },
+            None => Observed::Absent,
+        }
+    }
+
+    /// Whether Rice's current value still matches this observation.
+    fn matches(&self, current: Option<&StoredVariable>) -> bool {
+        match (self, current) {
+            (Observed::Absent, None) => true,
+            (Observed::Value { value_json }, Some(current)) => current.value_json == *value_json,
+            _ => false,
+        }
+    }
+}
+
+type VariableKey = (String, String);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct OutboxEntry {
+    seq: u64,
+    queued_at: String,
+    #[serde(default)]
+    attempts: u32,
+    /// The key's value before the first write still queued for it, if
+    /// this machine had seen it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    base: Option<Observed>,
+    #[serde(flatten)]
+    write: QueuedWrite,
+}
+
+/// The queue file plus an in-memory copy of its entries.
+pub(super) struct Outbox {
+    dir: PathBuf,
+    entries: Mutex<Vec<OutboxEntry>>,
+    /// Lines in the file that a later write to the same key replaced.
+    stale_lines: AtomicUsize,
+    /// Variables as last read from or written to Rice by this process.
+    observed: Mutex<HashMap<VariableKey, Observed>>,
+    next_seq: AtomicU64,
+    replaying: tokio::sync::Mutex<()>,
+}
+
+impl Outbox {
+    /// The process-wide outbox under `$MEMINI_HOME`, or `None` if it
+    /// can't be opened.
+    pub(super) fn shared() -> Option<Arc<Outbox>> {
+        static OUTBOX: OnceLock<Option<Arc<Outbox>>> = OnceLock::new();
+        OUTBOX
+            .get_or_init(|| Outbox::open(&memini_home()).ok().map(Arc::new))
+            .clone()
+    }
+
+    pub(super) fn open(dir: &Path) -> Result<Self> {
+        let path = dir.join(OUTBOX_FILE);
+        let lines: Vec<OutboxEntry> = match fs::read_to_string(&path) {
+            Ok(raw) => raw
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err).with_context(|| format!("read {}", path.display())),
+        };
+        let next_seq = lines.iter().map(|entry| entry.seq + 1).max().unwrap_or(0);
+        let line_count = lines.len();
+        let mut entries: Vec<OutboxEntry> = Vec::with_capacity(line_count);
+        for entry in lines {
+            coalesce(&mut entries, entry);
+        }
+        Ok(Outbox {
+            dir: dir.to_path_buf(),
+            stale_lines: AtomicUsize::new(line_count - entries.len()),
+            entries: Mutex::new(entries),
+            observed: Mutex::new(HashMap::new()),
+            next_seq: AtomicU64::new(next_seq),
+            replaying: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Note what Rice holds for a variable, as the base for writes queued
+    /// to it later.
+    pub(super) fn observe(&self, run_id: &str, name: &str, seen: Observed) {
+        if let Ok(mut observed) = self.observed.lock() {
+            observed.insert((run_id.to_string(), name.to_string()), seen);
+        }
+    }
+
+    /// Note the value a write that reached Rice left behind.
+    fn observe_written(&self, write: &QueuedWrite) {
+        match write {
+            QueuedWrite::SetVariable {
+                run_id,
+                name,
+                value_json,
+                ..
+            } => self.observe(run_id, name, Observed::of(Some(value_json))),
+            QueuedWrite::DeleteVariable { run_id, name } => {
+                self.observe(run_id, name, Observed::Absent)
+            }
+            QueuedWrite::Commit { .. } => {}
+        }
+    }
+
+    fn observed(&self, run_id: &str, name: &str) -> Option<Observed> {
+        self.observed
+            .lock()
+            .ok()?
+            .get(&(run_id.to_string(), name.to_string()))
+            .cloned()
+    }
+
+    pub(super) fn depth(&self) -> usize {
+        self.entries
+            .lock()
+            .map(|entries| entries.len())
+            .unwrap_or(0)
+    }
+
+    /// Queue `write`, replacing any write already queued for its key.
+    pub(super) fn push(&self, write: QueuedWrite) -> Result<()> {
+        let base = write
+            .variable_key()
+            .and_then(|(run_id, name)| self.observed(run_id, name));
+        let entry = OutboxEntry {
+            seq: self.next_seq.fetch_add(1, Ordering::Relaxed),
+            queued_at: chrono::Local::now().to_rfc3339(),
+            attempts: 0,
+            base,
+            write,
+        };
+        let mut entries = self.lock()?;
+        if !coalesce(&mut entries, entry) {
+            return self.append(entries.last().expect("entry was just queued"));
+        }
+        let stale = self.stale_lines.fetch_add(1, Ordering::Relaxed) + 1;
+        if stale > OUTBOX_STALE_LINES.max(entries.len()) {
+            self.persist(&entries)
+        } else {
+            self.append(entries.last().expect("entry was just queued"))
+        }
+    }
+
+    /// Drop queued writes to a variable this machine has since written
+    /// directly.
+    pub(super) fn supersede(&self, write: &QueuedWrite) -> Result<()> {
+        let Some(key) = write.variable_key() else {
+            return Ok(());
+        };
+        let mut entries = self.lock()?;
+        let before = entries.len();
+        entries.retain(|entry| entry.write.variable_key() != Some(key));
+        if entries.len() == before {
+            return Ok(());
+        }
+        self.persist(&entries)
+    }
+
+    fn snapshot(&self) -> Result<Vec<OutboxEntry>> {
+        Ok(self.lock()?.clone())
+    }
+
+    fn remove(&self, seqs: &HashSet<u64>) -> Result<()> {
+        if seqs.is_empty() {
+            return Ok(());
+        }
+        let mut entries = self.lock()?;
+        entries.retain(|entry| !seqs.contains(&entry.seq));
+        self.persist(&entries)
+    }
+
+    /// Count a rejected attempt.  Returns the new attempt count.
+    fn record_attempt(&self, seq: u64) -> Result<u32> {
+        let mut entries = self.lock()?;
+        let Some(entry) = entries.iter_mut().find(|entry| entry.seq == seq) else {
+            return Ok(0);
+        };
+        entry.attempts += 1;
+        let attempts = entry.attempts;
+        self.persist(&entries)?;
+        Ok(attempts)
+    }
+
+    fn move_to_failed(&self, entry: &OutboxEntry) -> Result<()> {
+        let path = self.dir.join(FAILED_FILE);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("open {}", path.display()))?;
+        let line = serde_json::to_string(entry).context("serialize queued write")?;
+        writeln!(file, "{line}").with_context(|| format!("append {}", path.display()))?;
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Vec<OutboxEntry>>> {
+        self.entries
+            .lock()
+            .map_err(|_| anyhow!("outbox lock poisoned"))
+    }
+
+    fn append(&self, entry: &OutboxEntry) -> Result<()> {
+        fs::create_dir_all(&self.dir).with_context(|| format!("create {}", self.dir.display()))?;
+        let path = self.dir.join(OUTBOX_FILE);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("open {}", path.display()))?;
+        let line = serde_json::to_string(entry).context("serialize queued write")?;
+        writeln!(file, "{line}").with_context(|| format!("append {}", path.display()))
+    }
+
+    fn persist(&self, entries: &[OutboxEntry]) -> Result<()> {
+        self.stale_lines.store(0, Ordering::Relaxed);
+        let path = self.dir.join(OUTBOX_FILE);
+        if entries.is_empty() {
+            return match fs::remove_file(&path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    Err(err).with_context(|| format!("remove {}", path.display()))
+                }
+                _ => Ok(()),
+            };
+        }
+        fs::create_dir_all(&self.dir).with_context(|| format!("create {}", self.dir.display()))?;
+        let tmp = self.dir.join(format!("{OUTBOX_FILE}.tmp"));
+        let mut raw = String::new();
+        for entry in entries {
+            raw.push_str(&serde_json::to_string(entry).context("serialize queued write")?);
+            raw.push('\n');
+        }
+        fs::write(&tmp, raw).with_context(|| format!("write {}", tmp.display()))?;
+        fs::rename(&tmp, &path).with_context(|| format!("replace {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Add `entry` at the end of the queue.  A write already queued for the
+/// same variable is replaced, and its base carried over so replay still
+/// checks against what Rice held before either was queued.  Returns
+/// whether one was replaced.
+fn coalesce(entries: &mut Vec<OutboxEntry>, mut entry: OutboxEntry) -> bool {
+    let replaced = entry.write.variable_key().and_then(|key| {
+        entries
+            .iter()
+            .position(|queued| queued.write.variable_key() == Some(key))
+    });
+    if let Some(idx) = replaced {
+        let queued = entries.remove(idx);
+        entry.base = queued.base.or(entry.base);
+    }
+    entries.push(entry);
+    replaced.is_some()
+}
+
+/// Writes waiting in the outbox, for the status bar.
+pub fn outbox_depth() -> usize {
+    Outbox::shared().map_or(0, |outbox| outbox.depth())
+}
+
+/// What one replay did.
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+    pub replayed: usize,
+    pub skipped: usize,
+    /// Variables where a newer Rice value was kept over the queued one.
+    pub conflicts: Vec<String>,
+    pub failed: usize,
+    pub remaining: usize,
+    /// Why replay stopped early, if it did.
+    pub error: Option<String>,
+}
+
+impl ReplayReport {
+    pub fn is_empty(&self) -> bool {
+        self.replayed == 0 && self.skipped == 0 && self.failed == 0 && self.conflicts.is_empty()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "Replayed {} queued Rice write(s); {} already superseded, {} still queued.",
+            self.replayed, self.skipped, self.remaining
+        )];
+        if !self.conflicts.is_empty() {
+            lines.push(format!(
+                "Kept newer Rice values for: {}",
+                self.conflicts.join(", ")
+            ));
+        }
+        if self.failed > 0 {
+            lines.push(format!(
+                "{} write(s) were rejected {OUTBOX_MAX_ATTEMPTS} times and moved to {FAILED_FILE}.",
+                self.failed
+            ));
+        }
+        if let Some(error) = &self.error {
+            lines.push(format!("Replay paused: {error}"));
+        }
+        lines
+    }
+}
+
+impl RiceStore {
+    /// Apply a variable, delete or commit, queueing it in the outbox when
+    /// Rice can't be reached.
+    pub(super) async fn write_through(&mut self, write: QueuedWrite) -> Result<()> {
+        let result = match self.backend.as_mut() {
+            Some(backend) => write.apply(backend.as_mut()).await,
+            None => Err(anyhow!("Rice not connected")),
+        };
+        let Some(outbox) = self.outbox.clone() else {
+            return result;
+        };
+        match result {
+            Ok(()) => {
+                outbox.supersede(&write)?;
+                outbox.observe_written(&write);
+                Ok(())
+            }
+            Err(err) if self.backend.is_none() || is_connectivity_error(&err) => outbox
+                .push(write)
+                .with_context(|| format!("{err:#}; queueing it in the outbox failed")),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Send queued writes to Rice, oldest first.  Stops at the first
+    /// connectivity error, leaving the rest queued.
+    pub async fn replay_outbox(&mut self) -> Result<ReplayReport> {
+        let Some(outbox) = self.outbox.clone() else {
+            return Ok(ReplayReport::default());
+        };
+        if !matches!(self.status, RiceStatus::Connected) {
+            return Ok(ReplayReport {
+                remaining: outbox.depth(),
+                ..ReplayReport::default()
+            });
+        }
+        // Another store is already replaying.
+        let Ok(_replaying) = outbox.replaying.try_lock() else {
+            return Ok(ReplayReport {
+                remaining: outbox.depth(),
+                ..ReplayReport::default()
+            });
+        };
+
+        let entries = outbox.snapshot()?;
+        let mut last_write: HashMap<(&str, &str), u64> = HashMap::new();
+        for entry in &entries {
+            if let Some(key) = entry.write.variable_key() {
+                last_write.insert(key, entry.seq);
+            }
+        }
+
+        let mut report = ReplayReport::default();
+        let mut done = HashSet::new();
+        let mut remote: HashMap<String, HashMap<String, StoredVariable>> = HashMap::new();
+        for entry in &entries {
+            if let Some(key) = entry.write.variable_key() {
+                if last_write.get(&key) != Some(&entry.seq) {
+                    report.skipped += 1;
+                    done.insert(entry.seq);
+                    continue;
+                }
+                let (run_id, name) = key;
+                if !remote.contains_key(run_id) {
+                    match self.backend()?.list_variables(run_id.to_string()).await {
+                        Ok(variables) => {
+                            let by_name = variables
+                                .into_iter()
+                                .map(|variable| (variable.name.clone(), variable))
+                                .collect();
+                            remote.insert(run_id.to_string(), by_name);
+                        }
+                        Err(err) => {
+                            report.error = Some(format!("{err:#}"));
+                            break;
+                        }
+                    }
+                }
+                let current = remote.get(run_id).and_then(|vars| vars.get(name));
+                let already_applied = match (&entry.write, current) {
+                    (QueuedWrite::SetVariable { value_json, .. }, Some(current)) => {
+                        current.value_json == *value_json
+                    }
+                    (QueuedWrite::DeleteVariable { .. }, None) => true,
+                    _ => false,
+                };
+                if already_applied {
+                    report.skipped += 1;
+                    done.insert(entry.seq);
+                    continue;
+                }
+                // Someone else wrote the key since this machine last saw it.
+                if let Some(base) = &entry.base
+                    && !base.matches(current)
+                {
+                    report.conflicts.push(name.to_string());
+                    outbox.observe(
+                        run_id,
+                        name,
+                        Observed::of(current.map(|c| c.value_json.as_str())),
+                    );
+                    done.insert(entry.seq);
+                    continue;
+                }
+            }
+
+            match entry.write.apply(self.backend()?.as_mut()).await {
+                Ok(()) => {
+                    report.replayed += 1;
+                    done.insert(entry.seq);
+                    outbox.observe_written(&entry.write);
+                }
+                Err(err) if is_connectivity_error(&err) => {
+                    report.error = Some(format!("{err:#}"));
+                    break;
+                }
+                Err(_) => {
+                    if outbox.record_attempt(entry.seq)? >= OUTBOX_MAX_ATTEMPTS {
+                        outbox.move_to_failed(entry)?;
+                        report.failed += 1;
+                        done.insert(entry.seq);
+                    }
+                }
+            }
+        }
+
+        outbox.remove(&done)?;
+        report.remaining = outbox.depth();
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rice::local::LocalBackend;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "memini-outbox-{name}-{}-{:x}",
+            std::process::id(),
+            rand::random::<u32>()
+        ))
+    }
+
+    fn set(name: &str, value: &str) -> QueuedWrite {
+        QueuedWrite::SetVariable {
+            run_id: "run".to_string(),
+            name: name.to_string(),
+            value_json: value.to_string(),
+            source: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn replay_applies_in_order_and_settles_conflicts() {
+        let dir = temp_dir("replay");
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let outbox = Arc::new(Outbox::open(&dir).unwrap());
+            // `theme` and `font` were last seen unset; `model` never seen.
+            outbox.observe("run", "theme", Observed::Absent);
+            outbox.observe("run", "font", Observed::Absent);
+            outbox.push(set("model", "\"gpt-4\"")).unwrap();
+            outbox.push(set("model", "\"gpt-5\"")).unwrap();
+            outbox.push(set("theme", "\"dark\"")).unwrap();
+            outbox.push(set("font", "\"mono\"")).unwrap();
+            outbox
+                .push(QueuedWrite::commit(Trace {
+                    input: "q".to_string(),
+                    outcome: "a".to_string(),
+                    run_id: "run".to_string(),
+                    ..Trace::default()
+                }))
+                .unwrap();
+            // The second `model` write replaced the first, on disk too.
+            assert_eq!(outbox.depth(), 4);
+            let reopened = Outbox::open(&dir).unwrap();
+            assert_eq!(reopened.depth(), 4);
+            let queued = reopened.snapshot().unwrap();
+            assert!(matches!(&queued[0].write,
+                QueuedWrite::SetVariable { value_json, .. } if value_json == "\"gpt-5\""));
+            assert_eq!(queued[1].base, Some(Observed::Absent));
+
+            let mut backend = LocalBackend::open(&dir.join("rice")).unwrap();
+            // Someone else set `theme` after it was queued.  Only what Rice
+            // holds matters, not when it says it was written.
+            backend
+                .set_variable(
+                    "run".to_string(),
+                    "theme".to_string(),
+                    "\"light\"".to_string(),
+                    "peer".to_string(),
+                )
+                .await
+                .unwrap();
+            let mut store = RiceStore {
+                backend: Some(Box::new(backend)),
+                status: RiceStatus::Connected,
+                run_id: "run".to_string(),
+                shared_run_id: None,
+                access: None,
+                keys_file: PathBuf::new(),
+                outbox: Some(outbox.clone()),
+            };
+
+            let report = store.replay_outbox().await.unwrap();
+            assert_eq!(report.replayed, 3, "{report:?}");
+            assert_eq!(report.skipped, 0);
+            assert_eq!(report.conflicts, ["theme"]);
+            assert_eq!(report.remaining, 0);
+            assert_eq!(
+                store.get_variable("model").await.unwrap(),
+                Some(serde_json::json!("gpt-5"))
+            );
+            assert_eq!(
+                store.get_variable("theme").await.unwrap(),
+                Some(serde_json::json!("light"))
+            );
+            assert_eq!(
+                store.get_variable("font").await.unwrap(),
+                Some(serde_json::json!("mono"))
+            );
+            assert_eq!(
+                store
+                    .list_memories(None, None, None, 10)
+                    .await
+                    .unwrap()
+                    .len(),
+                1
+            );
+            assert!(!dir.join(OUTBOX_FILE).exists());
+        });
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn writes_queue_while_rice_is_unreachable() {
+        let dir = temp_dir("queue");
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let outbox = Arc::new(Outbox::open(&dir).unwrap());
+            let mut store = RiceStore {
+                backend: None,
+                status: RiceStatus::Disabled("Client init failed".to_string()),
+                run_id: "run".to_string(),
+                shared_run_id: None,
+                access: None,
+                keys_file: PathBuf::new(),
+                outbox: Some(outbox.clone()),
+            };
+            store
+                .set_variable("model", serde_json::json!("gpt-5"), "test")
+                .await
+                .unwrap();
+            store
+                .commit_trace("q", "a", "chat", "", vec![], "memini:coder")
+                .await
+                .unwrap();
+            assert_eq!(store.outbox.as_ref().unwrap().depth(), 2);
+
+            // A direct write supersedes the queued one for that key.
+            store.backend = Some(Box::new(LocalBackend::open(&dir.join("rice")).unwrap()));
+            store
+                .set_variable("model", serde_json::json!("gpt-5-mini"), "test")
+                .await
+                .unwrap();
+            assert_eq!(store.outbox.as_ref().unwrap().depth(), 1);
+
+            // Going offline again, the queued write remembers that value.
+            store.backend = None;
+            store
+                .set_variable("model", serde_json::json!("gpt-5"), "test")
+                .await
+                .unwrap();
+            let queued = outbox.snapshot().unwrap();
+            assert_eq!(
+                queued.last().unwrap().base,
+                Some(Observed::of(Some("\"gpt-5-mini\"")))
+            );
+        });
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,211 @@
+//! Shared Rice State connections.
+//!
+//! Every chat turn, agent window and task run gets its own `RiceStore`,
+//! but they no longer open their own gRPC client: stores check a
+//! `StateClient` out of the process-wide [`RicePool`].  A `StateClient` is
+//! a cheap handle onto one HTTP/2 channel, so all checkouts of a slot
+//! multiplex over the same connection.  Slots connect lazily, are probed
+//! when they have been idle, and reconnect when the probe fails.
+
+use std::env;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use rice::rice_state::client::StateClient;
+use rice::{Client, RiceConfig, RiceError};
+use tokio::sync::Mutex;
+
+use crate::constants::{
+    DEFAULT_RUN_ID, RICE_HEALTH_CHECK_SECS, RICE_HEALTH_TIMEOUT_SECS, RICE_POOL_SIZE,
+    RICE_RECONNECT_BACKOFF_SECS, SHARED_WORKSPACE_VAR,
+};
+
+/// gRPC `UNAVAILABLE`: the server could not be reached.
+const GRPC_UNAVAILABLE: i32 = 14;
+
+/// Cloneable handle to the shared pool of Rice connections.
+#[derive(Clone)]
+pub struct RicePool {
+    inner: Arc<PoolInner>,
+}
+
+struct PoolInner {
+    slots: Vec<Mutex<Slot>>,
+    next: AtomicUsize,
+    open: AtomicUsize,
+}
+
+#[derive(Default)]
+struct Slot {
+    client: Option<StateClient>,
+    checked_at: Option<Instant>,
+    /// Last failed connect, so a down server isn't redialled per turn.
+    failed: Option<(Instant, String)>,
+}
+
+impl RicePool {
+    /// The process-wide pool.  Its size comes from `MEMINI_RICE_POOL_SIZE`.
+    pub fn shared() -> RicePool {
+        static POOL: OnceLock<RicePool> = OnceLock::new();
+        POOL.get_or_init(|| {
+            let size = env::var("MEMINI_RICE_POOL_SIZE")
+                .ok()
+                .and_then(|value| value.trim().parse::<usize>().ok())
+                .filter(|size| *size > 0)
+                .unwrap_or(RICE_POOL_SIZE);
+            RicePool::new(size)
+        })
+        .clone()
+    }
+
+    fn new(size: usize) -> Self {
+        RicePool {
+            inner: Arc::new(PoolInner {
+                slots: (0..size).map(|_| Mutex::new(Slot::default())).collect(),
+                next: AtomicUsize::new(0),
+                open: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// A client on the next slot, round-robin.  Errors carry the reason
+    /// shown as the store's `Disabled` status.
+    pub async fn checkout(&self, config: RiceConfig) -> Result<StateClient, String> {
+        let slots = &self.inner.slots;
+        let idx = self.inner.next.fetch_add(1, Ordering::Relaxed) % slots.len();
+        let mut guard = slots[idx].lock().await;
+        let slot = &mut *guard;
+
+        if let Some(client) = slot.client.as_mut() {
+            let fresh = slot
+                .checked_at
+                .is_some_and(|at| at.elapsed() < Duration::from_secs(RICE_HEALTH_CHECK_SECS));
+            if fresh || probe(client).await {
+                let client = client.clone();
+                slot.checked_at = Some(Instant::now());
+                return Ok(client);
+            }
+            slot.client = None;
+            self.inner.open.fetch_sub(1, Ordering::Relaxed);
+        }
+
+        if let Some((at, reason)) = &slot.failed
+            && at.elapsed() < Duration::from_secs(RICE_RECONNECT_BACKOFF_SECS)
+        {
+            return Err(reason.clone());
+        }
+
+        let result = match Client::new(config).await {
+            Ok(client) => client
+                .state
+                .ok_or_else(|| "Rice state module not enabled".to_string()),
+            Err(err) => Err(format!("Client init failed: {err}")),
+        };
+        match result {
+            Ok(client) => {
+                slot.client = Some(client.clone());
+                slot.checked_at = Some(Instant::now());
+                slot.failed = None;
+                self.inner.open.fetch_add(1, Ordering::Relaxed);
+                Ok(client)
+            }
+            Err(reason) => {
+                slot.failed = Some((Instant::now(), reason.clone()));
+                Err(reason)
+            }
+        }
+    }
+
+    /// Drop every connection, e.g. after the Rice settings changed.
+    /// Stores already holding a client keep it until they finish.
+    pub async fn reset(&self) {
+        for slot in &self.inner.slots {
+            let mut slot = slot.lock().await;
+            if slot.client.take().is_some() {
+                self.inner.open.fetch_sub(1, Ordering::Relaxed);
+            }
+            *slot = Slot::default();
+        }
+    }
+
+    /// Open connections and pool size, for `/rice`.
+    pub fn stats(&self) -> (usize, usize) {
+        (
+            self.inner.open.load(Ordering::Relaxed),
+            self.inner.slots.len(),
+        )
+    }
+}
+
+/// Whether `err` means Rice could not be reached (as opposed to Rice
+/// answering with an error).
+pub(super) fn is_connectivity_error(err: &anyhow::Error) -> bool {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<RiceError>())
+        .is_some_and(|rice_err| match rice_err {
+            RiceError::Grpc(status) => status.code() as i32 == GRPC_UNAVAILABLE,
+            RiceError::Transport(_) | RiceError::Io(_) => true,
+            _ => false,
+        })
+}
+
+/// Cheap round trip to check an idle connection.  Any answer from the
+/// server counts, including gRPC errors other than `UNAVAILABLE`.
+async fn probe(client: &mut StateClient) -> bool {
+    let request = client.get_variable(DEFAULT_RUN_ID.to_string(), SHARED_WORKSPACE_VAR.to_string());
+    match tokio::time::timeout(Duration::from_secs(RICE_HEALTH_TIMEOUT_SECS), request).await {
+        Ok(Ok(_)) => true,
+        Ok(Err(RiceError::Grpc(status))) => status.code() as i32 != GRPC_UNAVAILABLE,
+        Ok(Err(_)) | Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rice::rice_core::config::StateConfig;
+
+    use super::*;
+
+    fn refused(result: Result<StateClient, String>) -> String {
+        match result {
+            Ok(_) => panic!("unexpectedly connected"),
+            Err(reason) => reason,
+        }
+    }
+
+    #[test]
+    fn failed_connects_back_off_instead_of_redialling() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let pool = RicePool::new(1);
+        let unreachable = RiceConfig {
+            state: Some(StateConfig {
+                enabled: true,
+                base_url: Some("http://127.0.0.1:1".to_string()),
+                auth_token: None,
+                llm_mode: None,
+                flux: None,
+            }),
+            storage: None,
+        };
+        let no_state = RiceConfig {
+            state: None,
+            storage: None,
+        };
+
+        let first = refused(runtime.block_on(pool.checkout(unreachable)));
+        assert!(first.starts_with("Client init failed"), "{first}");
+        // Within the backoff window the slot reports the last failure
+        // rather than dialling again with the new config.
+        let second = refused(runtime.block_on(pool.checkout(no_state.clone())));
+        assert_eq!(second, first);
+        assert_eq!(pool.stats(), (0, 1));
+
+        runtime.block_on(pool.reset());
+        let third = refused(runtime.block_on(pool.checkout(no_state)));
+        assert_eq!(third, "Rice state module not enabled");
+    }
+}
@@ -0,0 +1,193 @@
+//! Who wrote a memory: author attribution for traces and state writes.
+//!
+//! Rice traces have no metadata field, so the author is recorded as the
+//! first line of `reasoning`:
+//!
+//! ```text
+//! [author alice@laptop 2026-10-18T09:30:00+02:00]
+//! ```
+//!
+//! Variable writes carry the same stamp in their `source`.  The user
+//! name comes from `MEMINI_USER` (falling back to the login name), the
+//! host from `MEMINI_HOST` or the machine's host name.  Edits, imports
+//! and migrations keep an existing stamp, so a memory stays attributed
+//! to whoever first taught it.
+
+use std::env;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, FixedOffset, Local};
+use rice::rice_state::proto::Trace;
+
+const STAMP_PREFIX: &str = "[author ";
+
+/// The user and host a write came from, and when.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Author {
+    pub user: String,
+    pub host: String,
+    /// RFC 3339 write time.
+    pub at: String,
+}
+
+impl Author {
+    /// This process's identity, stamped with the current time.
+    pub fn current() -> Self {
+        let (user, host) = identity();
+        Author {
+            user: user.clone(),
+            host: host.clone(),
+            at: Local::now().to_rfc3339(),
+        }
+    }
+
+    /// `user@host`.
+    pub fn label(&self) -> String {
+        format!("{}@{}", self.user, self.host)
+    }
+
+    /// Write time, if it parses.
+    pub fn written_at(&self) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(&self.at).ok()
+    }
+
+    /// Whether `filter` names this author: a user name, or `user@host`.
+    pub fn matches(&self, filter: &str) -> bool {
+        let filter = filter.trim();
+        match filter.split_once('@') {
+            Some((user, host)) => {
+                self.user.eq_ignore_ascii_case(user) && self.host.eq_ignore_ascii_case(host)
+            }
+            None => self.user.eq_ignore_ascii_case(filter),
+        }
+    }
+
+    fn stamp(&self) -> String {
+        format!("{STAMP_PREFIX}{} {}]", self.label(), self.at)
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let body = line.trim().strip_prefix(STAMP_PREFIX)?.strip_suffix(']')?;
+        let (who, at) = body.split_once(' ')?;
+        let (user, host) = who.split_once('@')?;
+        Some(Author {
+            user: user.to_string(),
+            host: host.to_string(),
+            at: at.trim().to_string(),
+        })
+    }
+}
+
+/// `user` and `host` for this process, resolved once.
+fn identity() -> &'static (String, String) {
+    static IDENTITY: OnceLock<(String, String)> = OnceLock::new();
+    IDENTITY.get_or_init(|| {
+        let user = first_env(&["MEMINI_USER", "USER", "USERNAME"])
+            .unwrap_or_else(|| "unknown".to_string());
+        let host = first_env(&["MEMINI_HOST", "HOSTNAME", "COMPUTERNAME"])
+            .or_else(|| {
+                std::fs::read_to_string("/etc/hostname")
+                    .ok()
+                    .map(|name| name.trim().to_string())
+                    .filter(|name| !name.is_empty())
+            })
+            .unwrap_or_else(|| "unknown".to_string());
+        (sanitize(&user), sanitize(&host))
+    })
+}
+
+fn first_env(keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .filter_map(|key| env::var(key).ok())
+        .map(|value| value.trim().to_string())
+        .find(|value| !value.is_empty())
+}
+
+/// Stamps are space- and `@`-delimited, so neither may appear in a part.
+fn sanitize(part: &str) -> String {
+    part.trim()
+        .chars()
+        .map(|ch| {
+            if ch.is_whitespace() || ch == '@' {
+                '-'
+            } else {
+                ch
+            }
+        })
+        .collect()
+}
+
+/// The author recorded on `trace`, if any.
+pub fn trace_author(trace: &Trace) -> Option<Author> {
+    Author::parse(trace.reasoning.lines().next()?)
+}
+
+/// `reasoning` without its author line.
+pub fn strip_stamp(reasoning: &str) -> &str {
+    match reasoning.split_once('\n') {
+        Some((first, rest)) if Author::parse(first).is_some() => rest,
+        None if Author::parse(reasoning).is_some() => "",
+        _ => reasoning,
+    }
+}
+
+/// Record the current author on `trace` unless it already has one.
+pub(super) fn stamp_trace(trace: &mut Trace) {
+    if trace_author(trace).is_some() {
+        return;
+    }
+    let stamp = Author::current().stamp();
+    trace.reasoning = if trace.reasoning.trim().is_empty() {
+        stamp
+    } else {
+        format!("{stamp}\n{}", trace.reasoning)
+    };
+}
+
+/// A variable write's `source`, tagged with the current author.
+pub(super) fn stamp_source(source: &str) -> String {
+    let author = Author::current();
+    format!("{source} by {} at {}", author.label(), author.at)
+}
+
+/// Whether `trace` was written by `filter` (see [`Author::matches`]).
+pub fn authored_by(trace: &Trace, filter: &str) -> bool {
+    trace_author(trace).is_some_and(|author| author.matches(filter))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stamps_round_trip_and_are_not_replaced() {
+        let mut trace = Trace {
+            reasoning: "Looked it up in the runbook.".to_string(),
+            ..Trace::default()
+        };
+        stamp_trace(&mut trace);
+        let author = trace_author(&trace).unwrap();
+        assert_eq!((&author.user, &author.host), (&identity().0, &identity().1));
+        assert!(author.written_at().is_some());
+        assert_eq!(
+            strip_stamp(&trace.reasoning),
+            "Looked it up in the runbook."
+        );
+        assert!(authored_by(&trace, &author.user));
+        assert!(authored_by(&trace, &author.label()));
+        assert!(!authored_by(&trace, "someone-else"));
+
+        // Edits and imports keep the original author.
+        let first = trace.reasoning.clone();
+        stamp_trace(&mut trace);
+        assert_eq!(trace.reasoning, first);
+
+        let old = Trace {
+            reasoning: "[author bob@ci 2026-01-02T03:04:05+00:00]".to_string(),
+            ..Trace::default()
+        };
+        assert_eq!(trace_author(&old).unwrap().label(), "bob@ci");
+        assert_eq!(strip_stamp(&old.reasoning), "");
+        assert_eq!(trace_author(&Trace::default()), None);
+    }
+}
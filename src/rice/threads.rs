@@ -0,0 +1,454 @@
+//! Named conversation threads.
+//!
+//! Each thread's messages live in their own variable; the `main` thread
+//! keeps the original `conversation_thread` key, so threads saved before
+//! naming existed still load.  The `conversation_threads` variable indexes
+//! them per run-id (a shared workspace has its own set of threads) and is
+//! only written when a thread is created, renamed or deleted.  What changes
+//! every turn -- last use, persona, message count -- goes in the thread's
+//! own `conversation_thread_meta[:<name>]`, and which thread is active is
+//! remembered per user, in their personal scope, so members of a shared
+//! workspace do not overwrite each other.  Each thread also has a turn
+//! transcript (`conversation_transcript[:<name>]`) that `/thread export`
+//! reads; it moves and goes with its thread.
+
+use anyhow::{Context, Result, bail};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::RiceStore;
+use crate::constants::{
+    ACTIVE_THREAD_VAR, CONVERSATION_THREAD_VAR, DEFAULT_THREAD_NAME, THREAD_INDEX_VAR,
+    THREAD_META_VAR, THREAD_TRANSCRIPT_VAR,
+};
+
+/// Longest accepted thread name.
+const MAX_THREAD_NAME_LEN: usize = 48;
+
+/// Index entry for one thread.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ThreadMeta {
+    pub name: String,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub last_used: String,
+    /// Persona the thread was last used with.
+    #[serde(default)]
+    pub persona: String,
+    #[serde(default)]
+    pub messages: usize,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ThreadIndex {
+    /// Where the active thread was kept before it became per-user; still
+    /// read as a fallback, never written.
+    #[serde(default, rename = "active", skip_serializing)]
+    legacy_active: String,
+    #[serde(default)]
+    pub threads: Vec<ThreadMeta>,
+}
+
+impl ThreadIndex {
+    pub fn get(&self, name: &str) -> Option<&ThreadMeta> {
+        self.threads.iter().find(|meta| meta.name == name)
+    }
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut ThreadMeta> {
+        self.threads.iter_mut().find(|meta| meta.name == name)
+    }
+
+    /// The entry for `name`, added if missing.
+    fn entry(&mut self, name: &str) -> &mut ThreadMeta {
+        if self.get(name).is_none() {
+            let now = Local::now().to_rfc3339();
+            self.threads.push(ThreadMeta {
+                name: name.to_string(),
+                created_at: now.clone(),
+                last_used: now,
+                ..ThreadMeta::default()
+            });
+        }
+        self.get_mut(name).expect("entry was just added")
+    }
+
+    /// Threads, most recently used first.
+    pub fn by_last_used(&self) -> Vec<&ThreadMeta> {
+        let mut threads: Vec<&ThreadMeta> = self.threads.iter().collect();
+        threads.sort_by(|a, b| b.last_used.cmp(&a.last_used));
+        threads
+    }
+}
+
+/// Variable holding the messages of thread `name`.
+pub fn thread_var(name: &str) -> String {
+    scoped_var(CONVERSATION_THREAD_VAR, name)
+}
+
+/// Variable holding the per-turn metadata of thread `name`.
+fn meta_var(name: &str) -> String {
+    scoped_var(THREAD_META_VAR, name)
+}
+
+/// Variable holding the turn transcript of thread `name`.
+pub fn transcript_var(name: &str) -> String {
+    scoped_var(THREAD_TRANSCRIPT_VAR, name)
+}
+
+fn scoped_var(base: &str, name: &str) -> String {
+    if name == DEFAULT_THREAD_NAME {
+        base.to_string()
+    } else {
+        format!("{base}:{name}")
+    }
+}
+
+/// Reject names that would be awkward to type or to use in a key.
+pub fn validate_thread_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > MAX_THREAD_NAME_LEN {
+        bail!("thread names must be 1-{MAX_THREAD_NAME_LEN} characters");
+    }
+    if !name
+        .chars()
+        .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.'))
+    {
+        bail!("thread names may only use letters, digits, '-', '_' and '.'");
+    }
+    Ok(())
+}
+
+impl RiceStore {
+    /// The thread index for the current run-id, with each thread's latest
+    /// metadata.  Before any thread is named this is just `main`, the
+    /// legacy single thread.
+    pub async fn thread_index(&mut self) -> Result<ThreadIndex> {
+        let mut index: ThreadIndex = match self.get_variable(THREAD_INDEX_VAR).await? {
+            Some(value) => serde_json::from_value(value).context("parse thread index")?,
+            None => ThreadIndex::default(),
+        };
+        if index.threads.is_empty() {
+            index.entry(DEFAULT_THREAD_NAME);
+        }
+        for meta in &mut index.threads {
+            // Entries written before metadata moved out keep their own.
+            if let Some(latest) = self.thread_meta(&meta.name).await? {
+                meta.last_used = latest.last_used;
+                meta.persona = latest.persona;
+                meta.messages = latest.messages;
+            }
+        }
+        Ok(index)
+    }
+
+    async fn save_thread_index(&mut self, index: &ThreadIndex) -> Result<()> {
+        let value = serde_json::to_value(index).context("serialize thread index")?;
+        self.set_variable(THREAD_INDEX_VAR, value, "chat").await
+    }
+
+    async fn thread_meta(&mut self, name: &str) -> Result<Option<ThreadMeta>> {
+        match self.get_variable(&meta_var(name)).await? {
+            Some(value) => Ok(serde_json::from_value(value).ok()),
+            None => Ok(None),
+        }
+    }
+
+    async fn save_thread_meta(&mut self, meta: &ThreadMeta) -> Result<()> {
+        let value = serde_json::to_value(meta).context("serialize thread metadata")?;
+        self.set_variable(&meta_var(&meta.name), value, "chat")
+            .await
+    }
+
+    /// Personal-scope key for the active thread of the current workspace.
+    fn active_thread_key(&self) -> String {
+        match &self.shared_run_id {
+            Some(workspace) => format!("{ACTIVE_THREAD_VAR}:{workspace}"),
+            None => ACTIVE_THREAD_VAR.to_string(),
+        }
+    }
+
+    /// Remember `name` as this user's active thread.  Kept in the personal
+    /// scope (like the joined workspace), so it works for viewers too.
+    async fn save_active_thread(&mut self, name: &str) -> Result<()> {
+        let run_id = self.run_id.clone();
+        let key = self.active_thread_key();
+        let value_json =
+            serde_json::to_string(&Value::String(name.to_string())).context("serialize")?;
+        self.backend()?
+            .set_variable(run_id, key, value_json, "chat".to_string())
+            .await
+            .context("save active thread")
+    }
+
+    async fn saved_active_thread(&mut self) -> Result<Option<String>> {
+        let run_id = self.run_id.clone();
+        let key = self.active_thread_key();
+        let value_json = self
+            .backend()?
+            .get_variable(run_id, key)
+            .await
+            .context("load active thread")?;
+        match value_json.map(|raw| serde_json::from_str::<Value>(&raw)) {
+            Some(Ok(Value::String(name))) => Ok(Some(name)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Name of the thread this user last had active in the current
+    /// workspace, falling back to the most recently used one.
+    pub async fn active_thread_name(&mut self) -> Result<String> {
+        let index = self.thread_index().await?;
+        let saved = self.saved_active_thread().await?;
+        Ok(saved
+            .into_iter()
+            .chain([index.legacy_active.clone()])
+            .find(|name| index.get(name).is_some())
+            .unwrap_or_else(|| index.by_last_used()[0].name.clone()))
+    }
+
+    /// Persist `messages` as thread `name`, last used with `persona`.
+    /// The shared index is left alone.
+    pub async fn save_thread(
+        &mut self,
+        name: &str,
+        messages: &[Value],
+        persona: &str,
+    ) -> Result<()> {
+        self.set_variable(&thread_var(name), Value::Array(messages.to_vec()), "chat")
+            .await?;
+        self.save_thread_meta(&ThreadMeta {
+            name: name.to_string(),
+            last_used: Local::now().to_rfc3339(),
+            persona: persona.to_string(),
+            messages: messages.len(),
+            ..ThreadMeta::default()
+        })
+        .await
+    }
+
+    pub async fn load_thread(&mut self, name: &str) -> Result<Vec<Value>> {
+        match self.get_variable(&thread_var(name)).await? {
+            Some(Value::Array(messages)) => Ok(messages),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    pub async fn save_transcript(&mut self, name: &str, turns: &[Value]) -> Result<()> {
+        self.set_variable(&transcript_var(name), Value::Array(turns.to_vec()), "chat")
+            .await
+    }
+
+    pub async fn load_transcript(&mut self, name: &str) -> Result<Vec<Value>> {
+        match self.get_variable(&transcript_var(name)).await? {
+            Some(Value::Array(turns)) => Ok(turns),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Empty thread `name` and its transcript, keeping its index entry.
+    pub async fn clear_thread(&mut self, name: &str) -> Result<()> {
+        self.delete_variable(&thread_var(name)).await?;
+        self.delete_variable(&transcript_var(name)).await?;
+        let index = self.thread_index().await?;
+        if let Some(meta) = index.get(name) {
+            let meta = ThreadMeta {
+                messages: 0,
+                ..meta.clone()
+            };
+            self.save_thread_meta(&meta).await?;
+        }
+        Ok(())
+    }
+
+    /// Add an empty thread `name` and make it the active one.
+    pub async fn create_thread(&mut self, name: &str, persona: &str) -> Result<()> {
+        validate_thread_name(name)?;
+        let mut index = self.thread_index().await?;
+        if index.get(name).is_some() {
+            bail!("thread '{name}' already exists");
+        }
+        index.entry(name).persona = persona.to_string();
+        self.save_thread_index(&index).await?;
+        self.save_active_thread(name).await
+    }
+
+    /// Make `name` the active thread and return its index entry.
+    pub async fn switch_thread(&mut self, name: &str) -> Result<ThreadMeta> {
+        let index = self.thread_index().await?;
+        let Some(meta) = index.get(name) else {
+            bail!("no thread named '{name}' (see /thread list)");
+        };
+        let meta = ThreadMeta {
+            last_used: Local::now().to_rfc3339(),
+            ..meta.clone()
+        };
+        self.save_thread_meta(&meta).await?;
+        self.save_active_thread(name).await?;
+        Ok(meta)
+    }
+
+    /// Rename thread `from` to `to`, moving its messages.
+    pub async fn rename_thread(&mut self, from: &str, to: &str) -> Result<()> {
+        validate_thread_name(to)?;
+        let mut index = self.thread_index().await?;
+        if index.get(from).is_none() {
+            bail!("no thread named '{from}' (see /thread list)");
+        }
+        if index.get(to).is_some() {
+            bail!("thread '{to}' already exists");
+        }
+        let active = self.active_thread_name().await?;
+        let messages = self.load_thread(from).await?;
+        self.set_variable(&thread_var(to), Value::Array(messages), "chat")
+            .await?;
+        self.delete_variable(&thread_var(from)).await?;
+        let turns = self.load_transcript(from).await?;
+        if !turns.is_empty() {
+            self.save_transcript(to, &turns).await?;
+            self.delete_variable(&transcript_var(from)).await?;
+        }
+        if let Some(meta) = index.get_mut(from) {
+            meta.name = to.to_string();
+            let meta = meta.clone();
+            self.save_thread_meta(&meta).await?;
+            self.delete_variable(&meta_var(from)).await?;
+        }
+        self.save_thread_index(&index).await?;
+        if active == from {
+            self.save_active_thread(to).await?;
+        }
+        Ok(())
+    }
+
+    /// Delete thread `name` and its messages.  The active thread cannot
+    /// be deleted.
+    pub async fn delete_thread(&mut self, name: &str) -> Result<()> {
+        let mut index = self.thread_index().await?;
+        if index.get(name).is_none() {
+            bail!("no thread named '{name}' (see /thread list)");
+        }
+        if self.active_thread_name().await? == name {
+            bail!("'{name}' is the active thread; switch to another one first");
+        }
+        self.delete_variable(&thread_var(name)).await?;
+        self.delete_variable(&transcript_var(name)).await?;
+        self.delete_variable(&meta_var(name)).await?;
+        index.threads.retain(|meta| meta.name != name);
+        self.save_thread_index(&index).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn threads_are_saved_switched_renamed_and_deleted() {
+        let dir = std::env::temp_dir().join(format!(
+            "memini-threads-{}-{:x}",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let mut rice = RiceStore::local_at(&dir).unwrap();
+            // A thread saved before naming existed is `main`.
+            let legacy = vec![json!({"role": "user", "content": "hi"})];
+            rice.set_variable(
+                CONVERSATION_THREAD_VAR,
+                Value::Array(legacy.clone()),
+                "chat",
+            )
+            .await
+            .unwrap();
+            assert_eq!(rice.active_thread_name().await.unwrap(), "main");
+            assert_eq!(rice.load_thread("main").await.unwrap(), legacy);
+
+            rice.create_thread("release-1.4", "coder").await.unwrap();
+            assert!(rice.create_thread("release-1.4", "coder").await.is_err());
+            assert!(rice.create_thread("no spaces", "coder").await.is_err());
+            let turn = vec![json!({"role": "user", "content": "cut the branch"})];
+            rice.save_thread("release-1.4", &turn, "coder")
+                .await
+                .unwrap();
+            assert_eq!(rice.active_thread_name().await.unwrap(), "release-1.4");
+            assert_eq!(rice.load_thread("main").await.unwrap(), legacy);
+
+            let meta = rice.switch_thread("main").await.unwrap();
+            assert_eq!(meta.name, "main");
+            assert!(rice.switch_thread("missing").await.is_err());
+
+            let record = vec![json!({"user": "cut the branch", "assistant": "done"})];
+            rice.save_transcript("release-1.4", &record).await.unwrap();
+            rice.rename_thread("release-1.4", "release").await.unwrap();
+            assert_eq!(rice.load_thread("release").await.unwrap(), turn);
+            assert_eq!(rice.load_transcript("release").await.unwrap(), record);
+            assert!(rice.load_thread("release-1.4").await.unwrap().is_empty());
+            let index = rice.thread_index().await.unwrap();
+            assert_eq!(index.get("release").unwrap().persona, "coder");
+            assert_eq!(index.get("release").unwrap().messages, 1);
+
+            assert!(rice.delete_thread("main").await.is_err());
+            rice.delete_thread("release").await.unwrap();
+            assert!(rice.load_transcript("release").await.unwrap().is_empty());
+            assert!(rice.thread_index().await.unwrap().get("release").is_none());
+        });
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn workspace_members_keep_their_own_active_thread() {
+        let dir = std::env::temp_dir().join(format!(
+            "memini-threads-shared-{}-{:x}",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let mut alice = RiceStore::local_at(&dir).unwrap();
+            let mut bob = RiceStore::local_at(&dir).unwrap();
+            bob.run_id = "bob".to_string();
+            alice.join_workspace("team");
+            bob.join_workspace("team");
+
+            alice.create_thread("alpha", "coder").await.unwrap();
+            bob.create_thread("beta", "writer").await.unwrap();
+            let turn = vec![json!({"role": "user", "content": "hi"})];
+            alice.save_thread("alpha", &turn, "coder").await.unwrap();
+            bob.save_thread("beta", &turn, "writer").await.unwrap();
+            assert_eq!(alice.active_thread_name().await.unwrap(), "alpha");
+            assert_eq!(bob.active_thread_name().await.unwrap(), "beta");
+            let index = alice.thread_index().await.unwrap();
+            assert_eq!(index.get("alpha").unwrap().messages, 1);
+            assert_eq!(index.get("beta").unwrap().persona, "writer");
+            assert!(bob.delete_thread("beta").await.is_err());
+
+            // A turn updates the thread's own metadata, not the index.
+            let stored = alice.get_variable(THREAD_INDEX_VAR).await.unwrap();
+            let two = vec![
+                turn[0].clone(),
+                json!({"role": "assistant", "content": "hello"}),
+            ];
+            alice.save_thread("alpha", &two, "coder").await.unwrap();
+            assert_eq!(alice.get_variable(THREAD_INDEX_VAR).await.unwrap(), stored);
+            assert_eq!(
+                bob.thread_index()
+                    .await
+                    .unwrap()
+                    .get("alpha")
+                    .unwrap()
+                    .messages,
+                2
+            );
+        });
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
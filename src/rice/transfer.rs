@@ -0,0 +1,688 @@
+//! Memory export and import — `/memory export|import` in the TUI and the
+//! headless `memini memory export|import` subcommand.
+//!
+//! The JSONL format is one record per line, tagged by `kind`:
+//!
+//! | `kind`     | Fields                                                       |
+//! | ---------- | ------------------------------------------------------------ |
+//! | `header`   | `version`, `run_id`, `exported_at`                           |
+//! | `variable` | `name`, `value`, `source`                                    |
+//! | `trace`    | `id`, `created_at`, `agent_id`, `input`, `action`, `outcome`, `reasoning`, `embedding` |
+//!
+//! Markdown exports are for reading only; import takes JSONL.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use rice::rice_state::proto::Trace;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{MemoryOverrides, RiceStore};
+use crate::constants::{
+    MEMORY_EXPORT_LIMIT, MEMORY_OVERRIDES_VAR, OPENAI_KEY_VAR, WORKSPACE_ACL_VAR,
+};
+
+const EXPORT_VERSION: u32 = 1;
+
+pub const TRANSFER_USAGE: &str = "Usage: memory export <path> [--workspace <name>] [--format jsonl|md] \
+     [--include-secrets] | memory import <path> [--workspace <name>] [--dry-run] [--overwrite]";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Jsonl,
+    Markdown,
+}
+
+#[derive(Clone, Debug)]
+pub enum TransferCommand {
+    Export {
+        path: PathBuf,
+        workspace: Option<String>,
+        format: ExportFormat,
+        include_secrets: bool,
+    },
+    Import {
+        path: PathBuf,
+        workspace: Option<String>,
+        dry_run: bool,
+        overwrite: bool,
+    },
+}
+
+impl TransferCommand {
+    /// Parse `export …` / `import …` arguments (the verb included).
+    pub fn parse(args: &[&str]) -> Result<Self> {
+        let Some((&verb, rest)) = args.split_first() else {
+            bail!("{TRANSFER_USAGE}");
+        };
+        let mut path = None;
+        let mut workspace = None;
+        let mut format = None;
+        let mut include_secrets = false;
+        let mut dry_run = false;
+        let mut overwrite = false;
+        let mut iter = rest.iter();
+        while let Some(&arg) = iter.next() {
+            match arg {
+                "--workspace" | "-w" => {
+                    let name = iter
+                        .next()
+                        .ok_or_else(|| anyhow!("--workspace needs a name"))?;
+                    workspace = Some(name.to_string());
+                }
+                "--format" | "-f" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| anyhow!("--format needs a value"))?;
+                    format = Some(match *value {
+                        "jsonl" | "json" => ExportFormat::Jsonl,
+                        "md" | "markdown" => ExportFormat::Markdown,
+                        other => bail!("unknown format '{other}' (use jsonl or md)"),
+                    });
+                }
+                "--include-secrets" => include_secrets = true,
+                "--dry-run" | "-n" => dry_run = true,
+                "--overwrite" => overwrite = true,
+                flag if flag.starts_with("--") => bail!("unknown option {flag}\n{TRANSFER_USAGE}"),
+                value if path.is_none() => path = Some(PathBuf::from(value)),
+                value => bail!("unexpected argument '{value}'\n{TRANSFER_USAGE}"),
+            }
+        }
+        let path = path.ok_or_else(|| anyhow!("{TRANSFER_USAGE}"))?;
+
+        match verb {
+            "export" => {
+                if dry_run || overwrite {
+                    bail!("--dry-run and --overwrite only apply to import");
+                }
+                // Default the format from the file extension.
+                let format =
+                    format.unwrap_or_else(|| match path.extension().and_then(|ext| ext.to_str()) {
+                        Some("md" | "markdown") => ExportFormat::Markdown,
+                        _ => ExportFormat::Jsonl,
+                    });
+                Ok(TransferCommand::Export {
+                    path,
+                    workspace,
+                    format,
+                    include_secrets,
+                })
+            }
+            "import" => {
+                if format.is_some() || include_secrets {
+                    bail!("--format and --include-secrets only apply to export");
+                }
+                Ok(TransferCommand::Import {
+                    path,
+                    workspace,
+                    dry_run,
+                    overwrite,
+                })
+            }
+            other => bail!("unknown memory command '{other}'\n{TRANSFER_USAGE}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Record {
+    Header {
+        version: u32,
+        run_id: String,
+        exported_at: String,
+    },
+    Variable {
+        name: String,
+        value: Value,
+        #[serde(default)]
+        source: String,
+    },
+    Trace {
+        #[serde(default)]
+        id: String,
+        #[serde(default)]
+        created_at: String,
+        #[serde(default)]
+        agent_id: String,
+        #[serde(default)]
+        input: String,
+        #[serde(default)]
+        action: String,
+        #[serde(default)]
+        outcome: String,
+        #[serde(default)]
+        reasoning: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        embedding: Vec<f32>,
+    },
+}
+
+/// API keys and MCP tokens stay out of exports unless asked for.
+fn is_secret_variable(name: &str) -> bool {
+    name == OPENAI_KEY_VAR || name.starts_with("mcp_token_")
+}
+
+/// Traces that are the same memory, whatever their backend id.
+fn trace_key(agent_id: &str, input: &str, action: &str, outcome: &str) -> String {
+    [agent_id, input, action, outcome]
+        .map(str::trim)
+        .join("\u{1f}")
+}
+
+impl RiceStore {
+    /// Run an export or import, returning summary lines for display.
+    ///
+    /// The target run is checked like a join: a shared workspace with
+    /// members needs membership, imports need write access, and traces
+    /// are decrypted on export and sealed on import.
+    pub async fn run_transfer(&mut self, command: &TransferCommand) -> Result<Vec<String>> {
+        let workspace = match command {
+            TransferCommand::Export { workspace, .. }
+            | TransferCommand::Import { workspace, .. } => workspace.clone(),
+        };
+        let run_id = workspace.unwrap_or_else(|| self.active_run_id());
+        let access = self.transfer_access(&run_id).await?;
+        let joined = std::mem::replace(&mut self.access, access);
+        let result = match command {
+            TransferCommand::Export {
+                path,
+                format,
+                include_secrets,
+                ..
+            } => {
+                self.export_memory(path, &run_id, *format, *include_secrets)
+                    .await
+            }
+            TransferCommand::Import {
+                path,
+                dry_run,
+                overwrite,
+                ..
+            } => {
+                self.import_memory(path, &run_id, *dry_run, *overwrite)
+                    .await
+            }
+        };
+        self.access = joined;
+        result
+    }
+
+    async fn export_memory(
+        &mut self,
+        path: &Path,
+        run_id: &str,
+        format: ExportFormat,
+        include_secrets: bool,
+    ) -> Result<Vec<String>> {
+        let backend = self.backend()?;
+        let mut variables = backend
+            .list_variables(run_id.to_string())
+            .await
+            .context("list variables")?;
+        let traces = backend
+            .list_traces(run_id.to_string(), MEMORY_EXPORT_LIMIT)
+            .await
+            .context("list traces")?;
+        let complete_listing = backend.lists_every_trace();
+        let listed = traces.len();
+
+        let forgotten: HashSet<String> = variables
+            .iter()
+            .find(|variable| variable.name == MEMORY_OVERRIDES_VAR)
+            .and_then(|variable| serde_json::from_str::<MemoryOverrides>(&variable.value_json).ok())
+            .map(|overrides| overrides.forgotten.into_iter().collect())
+            .unwrap_or_default();
+        let secrets = variables
+            .iter()
+            .filter(|variable| is_secret_variable(&variable.name))
+            .count();
+        if !include_secrets {
+            variables.retain(|variable| !is_secret_variable(&variable.name));
+        }
+        variables.retain(|variable| variable.name != WORKSPACE_ACL_VAR);
+        variables.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut records = vec![Record::Header {
+            version: EXPORT_VERSION,
+            run_id: run_id.to_string(),
+            exported_at: chrono::Local::now().to_rfc3339(),
+        }];
+        records.extend(variables.into_iter().map(|variable| {
+            Record::Variable {
+                value: serde_json::from_str(&variable.value_json)
+                    .unwrap_or(Value::String(variable.value_json)),
+                name: variable.name,
+                source: variable.source,
+            }
+        }));
+        // Oldest first, so an import replays memories in their original order.
+        records.extend(
+            traces
+                .into_iter()
+                .rev()
+                .filter(|stored| !forgotten.contains(&stored.id))
+                .map(|stored| self.open_stored(stored))
+                .map(|stored| Record::Trace {
+                    id: stored.id,
+                    created_at: stored.created_at,
+                    agent_id: stored.trace.agent_id,
+                    input: stored.trace.input,
+                    action: stored.trace.action,
+                    outcome: stored.trace.outcome,
+                    reasoning: stored.trace.reasoning,
+                    embedding: stored.trace.embedding,
+                }),
+        );
+
+        let body = match format {
+            ExportFormat::Jsonl => render_jsonl(&records)?,
+            ExportFormat::Markdown => render_markdown(&records),
+        };
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent).with_context(|| format!("create {}", parent.display()))?;
+        }
+        fs::write(path, body).with_context(|| format!("write {}", path.display()))?;
+
+        let trace_count = records
+            .iter()
+            .filter(|record| matches!(record, Record::Trace { .. }))
+            .count();
+        let variable_count = records
+            .iter()
+            .filter(|record| matches!(record, Record::Variable { .. }))
+            .count();
+        let mut lines = vec![format!(
+            "Exported {trace_count} trace(s) and {variable_count} variable(s) from '{run_id}' to {}.",
+            path.display()
+        )];
+        if listed > trace_count {
+            lines.push(format!(
+                "Left out {} forgotten memory trace(s).",
+                listed - trace_count
+            ));
+        }
+        if listed as u64 >= MEMORY_EXPORT_LIMIT {
+            lines.push(format!(
+                "Stopped at the {MEMORY_EXPORT_LIMIT}-trace export limit; older traces may be missing."
+            ));
+        } else if !complete_listing {
+            lines.push(format!(
+                "Rice returned {listed} trace(s) but cannot list a run in full or count it, \
+                 so the export may be missing some."
+            ));
+        }
+        if secrets > 0 && !include_secrets {
+            lines.push(format!(
+                "Left out {secrets} secret variable(s) (API keys, MCP tokens); add --include-secrets to keep them."
+            ));
+        }
+        Ok(lines)
+    }
+
+    async fn import_memory(
+        &mut self,
+        path: &Path,
+        run_id: &str,
+        dry_run: bool,
+        overwrite: bool,
+    ) -> Result<Vec<String>> {
+        if matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("md" | "markdown")
+        ) {
+            bail!("Markdown exports are for reading; import the JSONL export instead");
+        }
+        let raw = fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+        let records = parse_jsonl(&raw).with_context(|| format!("parse {}", path.display()))?;
+
+        if !dry_run {
+            self.ensure_writable()?;
+        }
+
+        // Compare plaintext: existing traces may be sealed.
+        let mut existing_traces: HashSet<String> = self
+            .backend()?
+            .list_traces(run_id.to_string(), MEMORY_EXPORT_LIMIT)
+            .await
+            .context("list traces")?
+            .into_iter()
+            .map(|stored| {
+                let trace = self.open_stored(stored).trace;
+                trace_key(&trace.agent_id, &trace.input, &trace.action, &trace.outcome)
+            })
+            .collect();
+        let existing_variables: HashMap<String, String> = self
+            .backend()?
+            .list_variables(run_id.to_string())
+            .await
+            .context("list variables")?
+            .into_iter()
+            .map(|variable| (variable.name, variable.value_json))
+            .collect();
+
+        let (mut traces_added, mut traces_skipped) = (0usize, 0usize);
+        let (mut variables_set, mut variables_unchanged, mut variables_kept) = (0usize, 0, 0);
+        let mut variables_protected = 0usize;
+        for record in records {
+            match record {
+                Record::Header { .. } => {}
+                Record::Variable {
+                    name,
+                    value,
+                    source,
+                } => {
+                    // Membership is managed with /share, never imported.
+                    if name == WORKSPACE_ACL_VAR {
+                        variables_protected += 1;
+                        continue;
+                    }
+                    if let Some(current) = existing_variables.get(&name) {
+                        let current = serde_json::from_str::<Value>(current)
+                            .unwrap_or_else(|_| Value::String(current.clone()));
+                        if current == value {
+                            variables_unchanged += 1;
+                            continue;
+                        }
+                        if !overwrite {
+                            variables_kept += 1;
+                            continue;
+                        }
+                    }
+                    variables_set += 1;
+                    if !dry_run {
+                        let value_json =
+                            serde_json::to_string(&value).context("serialize value")?;
+                        let source = if source.is_empty() {
+                            "import".to_string()
+                        } else {
+                            source
+                        };
+                        self.backend()?
+                            .set_variable(run_id.to_string(), name.clone(), value_json, source)
+                            .await
+                            .with_context(|| format!("import variable {name}"))?;
+                    }
+                }
+                Record::Trace {
+                    agent_id,
+                    input,
+                    action,
+                    outcome,
+                    reasoning,
+                    embedding,
+                    ..
+                } => {
+                    // Dedupes against the target and within the file.
+                    if !existing_traces.insert(trace_key(&agent_id, &input, &action, &outcome)) {
+                        traces_skipped += 1;
+                        continue;
+                    }
+                    traces_added += 1;
+                    if !dry_run {
+                        let mut trace = Trace {
+                            input,
+                            reasoning,
+                            action,
+                            outcome,
+                            agent_id,
+                            embedding,
+                            run_id: run_id.to_string(),
+                        };
+                        self.seal_trace(&mut trace)?;
+                        self.backend()?
+                            .commit(trace)
+                            .await
+                            .context("import trace")?;
+                    }
+                }
+            }
+        }
+
+        let (prefix, verb) = if dry_run {
+            ("Dry run: ", "would import")
+        } else {
+            ("", "Imported")
+        };
+        let mut lines = vec![format!(
+            "{prefix}{verb} {traces_added} trace(s) and {variables_set} variable(s) into '{run_id}'."
+        )];
+        if traces_skipped > 0 || variables_unchanged > 0 {
+            lines.push(format!(
+                "Skipped {traces_skipped} duplicate trace(s) and {variables_unchanged} unchanged variable(s)."
+            ));
+        }
+        if variables_protected > 0 {
+            lines.push(
+                "Left out the workspace member list; manage members with /share instead."
+                    .to_string(),
+            );
+        }
+        if variables_kept > 0 {
+            lines.push(format!(
+                "Kept {variables_kept} existing variable(s) with different values; add --overwrite to replace them."
+            ));
+        }
+        Ok(lines)
+    }
+}
+
+fn render_jsonl(records: &[Record]) -> Result<String> {
+    let mut body = String::new();
+    for record in records {
+        body.push_str(&serde_json::to_string(record).context("serialize record")?);
+        body.push('\n');
+    }
+    Ok(body)
+}
+
+fn parse_jsonl(raw: &str) -> Result<Vec<Record>> {
+    raw.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).with_context(|| format!("line {}", index + 1))
+        })
+        .collect()
+}
+
+fn render_markdown(records: &[Record]) -> String {
+    let mut traces = Vec::new();
+    let mut variables = Vec::new();
+    let mut header = Vec::new();
+    for record in records {
+        match record {
+            Record::Header {
+                run_id,
+                exported_at,
+                ..
+            } => {
+                header.push(format!("- Workspace: `{run_id}`"));
+                header.push(format!("- Exported: {exported_at}"));
+            }
+            Record::Variable {
+                name,
+                value,
+                source,
+            } => {
+                let pretty = serde_json::to_string_pretty(value).unwrap_or_default();
+                let source = if source.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({source})")
+                };
+                variables.push(format!("### `{name}`{source}\n\n```json\n{pretty}\n```\n"));
+            }
+            Record::Trace {
+                id,
+                created_at,
+                agent_id,
+                input,
+                action,
+                outcome,
+                reasoning,
+                ..
+            } => {
+                let mut title = vec![id.as_str()];
+                for part in [agent_id, created_at] {
+                    if !part.is_empty() {
+                        title.push(part);
+                    }
+                }
+                let mut entry = format!("### {}\n\n", title.join(" · "));
+                for (label, value) in [
+                    ("Input", input),
+                    ("Action", action),
+                    ("Outcome", outcome),
+                    ("Reasoning", reasoning),
+                ] {
+                    if !value.trim().is_empty() {
+                        entry.push_str(&format!("**{label}:** {}\n\n", value.trim()));
+                    }
+                }
+                traces.push(entry);
+            }
+        }
+    }
+    header.push(format!(
+        "- Traces: {} · Variables: {}",
+        traces.len(),
+        variables.len()
+    ));
+
+    let mut body = format!("# Memini memory export\n\n{}\n", header.join("\n"));
+    body.push_str("\n## Traces\n\n");
+    body.push_str(&traces.join("\n"));
+    body.push_str("\n## Variables\n\n");
+    body.push_str(&variables.join("\n"));
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "memini-transfer-{name}-{}-{:x}",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn parses_transfer_arguments() {
+        let export = TransferCommand::parse(&["export", "team.md", "--workspace", "team"]).unwrap();
+        assert!(matches!(
+            export,
+            TransferCommand::Export {
+                format: ExportFormat::Markdown,
+                workspace: Some(ref name),
+                include_secrets: false,
+                ..
+            } if name == "team"
+        ));
+        let import = TransferCommand::parse(&["import", "team.jsonl", "--dry-run"]).unwrap();
+        assert!(matches!(
+            import,
+            TransferCommand::Import {
+                dry_run: true,
+                overwrite: false,
+                ..
+            }
+        ));
+        assert!(TransferCommand::parse(&["import", "x.jsonl", "--format", "md"]).is_err());
+        assert!(TransferCommand::parse(&["export"]).is_err());
+    }
+
+    #[test]
+    fn export_then_import_dedupes_and_honours_dry_run() {
+        let dir = temp_dir("roundtrip");
+        let file = dir.join("export.jsonl");
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let mut store = RiceStore::local_at(&dir.join("store")).unwrap();
+            store.join_workspace("source");
+            store
+                .set_variable("notes", serde_json::json!({"a": 1}), "test")
+                .await
+                .unwrap();
+            store
+                .set_variable(OPENAI_KEY_VAR, Value::String("sk-secret".into()), "test")
+                .await
+                .unwrap();
+            for outcome in ["first fact", "second fact"] {
+                store
+                    .commit_trace("q", outcome, "chat", "", vec![], "memini:coder")
+                    .await
+                    .unwrap();
+            }
+
+            let export = TransferCommand::parse(&["export", file.to_str().unwrap()]).unwrap();
+            let lines = store.run_transfer(&export).await.unwrap();
+            assert!(
+                lines[0].contains("2 trace(s) and 1 variable(s)"),
+                "{lines:?}"
+            );
+            let raw = fs::read_to_string(&file).unwrap();
+            assert!(!raw.contains("sk-secret"));
+
+            let target = file.to_str().unwrap();
+            let dry =
+                TransferCommand::parse(&["import", target, "-w", "team", "--dry-run"]).unwrap();
+            let lines = store.run_transfer(&dry).await.unwrap();
+            assert!(
+                lines[0].starts_with("Dry run: would import 2 trace(s)"),
+                "{lines:?}"
+            );
+
+            store.join_workspace("team");
+            assert!(
+                store
+                    .list_memories(None, None, None, 10)
+                    .await
+                    .unwrap()
+                    .is_empty()
+            );
+
+            let import = TransferCommand::parse(&["import", target, "-w", "team"]).unwrap();
+            store.run_transfer(&import).await.unwrap();
+            assert_eq!(
+                store
+                    .list_memories(None, None, None, 10)
+                    .await
+                    .unwrap()
+                    .len(),
+                2
+            );
+            assert_eq!(
+                store.get_variable("notes").await.unwrap(),
+                Some(serde_json::json!({"a": 1}))
+            );
+
+            let lines = store.run_transfer(&import).await.unwrap();
+            assert!(
+                lines[0].contains("Imported 0 trace(s) and 0 variable(s)"),
+                "{lines:?}"
+            );
+            assert_eq!(
+                store
+                    .list_memories(None, None, None, 10)
+                    .await
+                    .unwrap()
+                    .len(),
+                2
+            );
+        });
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
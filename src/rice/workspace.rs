@@ -0,0 +1,863 @@
+//! Shared-workspace membership, roles and encryption.
+//!
+//! Each shared workspace keeps a manifest in its own run
+//! (`memini_workspace_acl`) listing members with a role, plus outstanding
+//! invite tokens.  A manifest is only written by an explicit create
+//! (`/share create`), whose user becomes admin; anyone else needs an
+//! invite.  Workspaces from before membership have no manifest: joining
+//! one by name is refused, and rejoining it at startup opens it without
+//! membership checks until someone creates its manifest.
+//!
+//! A member is not just a user name (`MEMINI_USER` is whatever the caller
+//! sets): creating a workspace or redeeming an invite issues a random
+//! member key that stays in `$MEMINI_HOME/workspace-keys.json`, and the
+//! manifest keeps only its SHA-256 hash (as it does for invite tokens).
+//! Joining, and every admin action, checks the local key against it.
+//!
+//! Roles are enforced by this client: readers can recall but
+//! `set_variable`, `delete_variable` and `commit_trace` are refused.
+//! Anyone with direct Rice credentials can still read and rewrite the run
+//! (the manifest included), which is what the optional shared secret is
+//! for: when a workspace is created with one, trace input, outcome and
+//! reasoning (and pinned copies) are sealed with ChaCha20-Poly1305 under a
+//! PBKDF2-derived key before they leave the machine.  Secrets are
+//! remembered per workspace next to the member key.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{DateTime, Duration, Local};
+use rice::rice_state::proto::Trace;
+use ring::aead::{Aad, CHACHA20_POLY1305, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::local::memini_home;
+use super::{Author, RiceStore, StoredTrace};
+use crate::constants::{WORKSPACE_ACL_VAR, WORKSPACE_INVITE_TTL_HOURS, WORKSPACE_KDF_ITERATIONS};
+
+/// Prefix of sealed trace fields.
+const SEALED_PREFIX: &str = "enc:v1:";
+/// Shown in place of a field this client cannot decrypt.
+const SEALED_PLACEHOLDER: &str = "[encrypted]";
+/// Known plaintext sealed into the manifest to verify a secret.
+const KEY_CHECK: &str = "memini-workspace-key-check";
+const KEYS_FILE: &str = "workspace-keys.json";
+
+/// What a member may do in a workspace.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Reader,
+    Writer,
+    Admin,
+}
+
+impl Role {
+    pub fn parse(raw: &str) -> Option<Role> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "reader" | "read" => Some(Role::Reader),
+            "writer" | "write" => Some(Role::Writer),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Role::Reader => "reader",
+            Role::Writer => "writer",
+            Role::Admin => "admin",
+        }
+    }
+
+    pub fn can_write(self) -> bool {
+        self >= Role::Writer
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WorkspaceMember {
+    pub user: String,
+    pub role: Role,
+    #[serde(default)]
+    pub added_by: String,
+    #[serde(default)]
+    pub added_at: String,
+    /// SHA-256 of the member key issued when this member joined.
+    #[serde(default)]
+    key_hash: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct WorkspaceInvite {
+    token_hash: String,
+    role: Role,
+    created_by: String,
+    expires_at: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Encryption {
+    /// Base64 PBKDF2 salt.
+    salt: String,
+    /// [`KEY_CHECK`] sealed under the workspace key.
+    check: String,
+}
+
+/// Members, invites and encryption settings of one workspace.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WorkspaceManifest {
+    pub name: String,
+    #[serde(default)]
+    pub created_by: String,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub members: Vec<WorkspaceMember>,
+    #[serde(default)]
+    invites: Vec<WorkspaceInvite>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encryption: Option<Encryption>,
+}
+
+impl WorkspaceManifest {
+    pub fn member(&self, user: &str) -> Option<&WorkspaceMember> {
+        self.members
+            .iter()
+            .find(|member| member.user.eq_ignore_ascii_case(user))
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.encryption.is_some()
+    }
+
+    /// Invites that have not expired yet.
+    pub fn pending_invites(&self) -> usize {
+        let now = Local::now();
+        self.invites
+            .iter()
+            .filter(|invite| !is_expired(&invite.expires_at, now))
+            .count()
+    }
+
+    /// The current user's entry, if this machine holds its member key.
+    fn verified_member(&self, keys: &SavedKeys) -> Option<&WorkspaceMember> {
+        let user = Author::current().user;
+        let key = keys.member_key.as_deref()?;
+        self.member(&user).filter(|member| {
+            !member.key_hash.is_empty()
+                && keys.user.eq_ignore_ascii_case(&user)
+                && member.key_hash == token_hash(key)
+        })
+    }
+
+    fn admins(&self) -> usize {
+        self.members
+            .iter()
+            .filter(|member| member.role == Role::Admin)
+            .count()
+    }
+}
+
+/// What the caller of `open_workspace` asked for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JoinMode {
+    /// `/share create`: write a new manifest with the user as admin.
+    Create,
+    /// `/share join`: the workspace must already have a manifest.
+    Join,
+    /// Startup and headless restore of the last joined workspace; a
+    /// workspace without a manifest opens without membership checks.
+    Rejoin,
+}
+
+/// How `open_workspace` let the user in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JoinKind {
+    /// No manifest yet: the workspace was created with the user as admin.
+    Created,
+    /// A workspace from before membership, opened without checks.
+    Unmanaged,
+    /// The user was already a member.
+    Member,
+    /// An invite token added the user.
+    Invited,
+}
+
+/// Outcome of [`RiceStore::open_workspace`].
+#[derive(Clone, Debug)]
+pub struct WorkspaceJoin {
+    pub kind: JoinKind,
+    pub role: Role,
+    pub encrypted: bool,
+}
+
+/// The current user's standing in a workspace, checked against its
+/// manifest.  Cloned into background stores (see
+/// [`RiceStore::connect_alike`]).
+#[derive(Clone)]
+pub(super) struct WorkspaceAccess {
+    name: String,
+    role: Role,
+    cipher: Option<Cipher>,
+}
+
+#[derive(Clone)]
+struct Cipher(LessSafeKey);
+
+impl Cipher {
+    fn derive(secret: &str, salt: &[u8]) -> Result<Self> {
+        let mut key = [0u8; 32];
+        let rounds = NonZeroU32::new(WORKSPACE_KDF_ITERATIONS).context("kdf rounds")?;
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            rounds,
+            salt,
+            secret.as_bytes(),
+            &mut key,
+        );
+        let key = UnboundKey::new(&CHACHA20_POLY1305, &key)
+            .map_err(|_| anyhow!("derive workspace key"))?;
+        Ok(Cipher(LessSafeKey::new(key)))
+    }
+
+    fn seal(&self, plaintext: &str) -> Result<String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| anyhow!("generate nonce"))?;
+        let mut sealed = plaintext.as_bytes().to_vec();
+        self.0
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut sealed,
+            )
+            .map_err(|_| anyhow!("encrypt trace"))?;
+        let mut payload = nonce.to_vec();
+        payload.extend(sealed);
+        Ok(format!("{SEALED_PREFIX}{}", STANDARD.encode(payload)))
+    }
+
+    fn open(&self, sealed: &str) -> Option<String> {
+        let payload = STANDARD.decode(sealed.strip_prefix(SEALED_PREFIX)?).ok()?;
+        if payload.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, body) = payload.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+        let mut body = body.to_vec();
+        let plain = self.0.open_in_place(nonce, Aad::empty(), &mut body).ok()?;
+        String::from_utf8(plain.to_vec()).ok()
+    }
+}
+
+impl RiceStore {
+    /// Join `name` as the current user, checking membership and the
+    /// shared secret.  [`JoinMode::Create`] creates the workspace (with the
+    /// user as admin and, given a secret, encryption on); it must not have
+    /// a manifest yet.
+    pub async fn open_workspace(
+        &mut self,
+        name: &str,
+        mode: JoinMode,
+        invite: Option<&str>,
+        secret: Option<&str>,
+    ) -> Result<WorkspaceJoin> {
+        let user = Author::current().user;
+        let mut keys = load_keys(&self.keys_file).remove(name).unwrap_or_default();
+        if let Some(secret) = secret.filter(|secret| !secret.is_empty()) {
+            keys.secret = Some(secret.to_string());
+        } else if keys.secret.is_none() {
+            keys.secret = env_secret(name);
+        }
+
+        let (manifest, kind) = match (self.load_manifest(name).await?, mode) {
+            (None, JoinMode::Rejoin) => {
+                self.join_workspace(name);
+                return Ok(WorkspaceJoin {
+                    kind: JoinKind::Unmanaged,
+                    role: Role::Writer,
+                    encrypted: false,
+                });
+            }
+            (None, JoinMode::Join) => bail!(
+                "workspace {name} has no member list. Check the name, or create it with \
+                 /share create {name} (you become its admin)"
+            ),
+            (Some(_), JoinMode::Create) => bail!(
+                "workspace {name} already exists; join it with /share join {name} <invite-token>"
+            ),
+            (None, JoinMode::Create) => {
+                let encryption = match &keys.secret {
+                    Some(secret) => Some(new_encryption(secret)?),
+                    None => None,
+                };
+                let now = Local::now().to_rfc3339();
+                let manifest = WorkspaceManifest {
+                    name: name.to_string(),
+                    created_by: user.clone(),
+                    created_at: now.clone(),
+                    members: vec![WorkspaceMember {
+                        user: user.clone(),
+                        role: Role::Admin,
+                        added_by: user.clone(),
+                        added_at: now,
+                        key_hash: keys.issue_member_key(&user)?,
+                    }],
+                    invites: Vec::new(),
+                    encryption,
+                };
+                (manifest, JoinKind::Created)
+            }
+            (Some(manifest), _) if manifest.verified_member(&keys).is_some() => {
+                (manifest, JoinKind::Member)
+            }
+            (Some(mut manifest), _) => {
+                let Some(token) = invite else {
+                    bail!(
+                        "you ({user}) are not a member of workspace {name}; ask an admin for an \
+                         invite (/share invite) and join with /share join {name} <token>"
+                    );
+                };
+                if manifest.member(&user).is_some() {
+                    // The name is taken and this machine can't prove it is
+                    // that member; redeeming would let anyone claim it.
+                    bail!(
+                        "{user} is already a member of {name}, but this machine holds no member \
+                         key for it. Copy workspace-keys.json from the machine you joined on, \
+                         or ask an admin to /share remove {user} and invite you again"
+                    );
+                }
+                let now = Local::now();
+                let hash = token_hash(token);
+                let Some(idx) = manifest.invites.iter().position(|invite| {
+                    invite.token_hash == hash && !is_expired(&invite.expires_at, now)
+                }) else {
+                    bail!("invite token is invalid or has expired");
+                };
+                let invite = manifest.invites.remove(idx);
+                manifest.members.push(WorkspaceMember {
+                    user: user.clone(),
+                    role: invite.role,
+                    added_by: invite.created_by,
+                    added_at: now.to_rfc3339(),
+                    key_hash: keys.issue_member_key(&user)?,
+                });
+                (manifest, JoinKind::Invited)
+            }
+        };
+
+        let access = authorize(&manifest, &keys)?;
+        if kind != JoinKind::Member {
+            self.save_manifest(&manifest).await?;
+        }
+        if access.cipher.is_none() {
+            keys.secret = None;
+        }
+        save_keys(&self.keys_file, name, keys)?;
+
+        let join = WorkspaceJoin {
+            kind,
+            role: access.role,
+            encrypted: access.cipher.is_some(),
+        };
+        self.join_workspace(name);
+        self.access = Some(access);
+        Ok(join)
+    }
+
+    /// Access to `run_id` for an export or import, without joining it.
+    /// Runs without a manifest (personal runs, workspaces from before
+    /// membership) need none; a workspace with one needs membership and,
+    /// if encrypted, its secret.
+    pub(super) async fn transfer_access(
+        &mut self,
+        run_id: &str,
+    ) -> Result<Option<WorkspaceAccess>> {
+        if run_id == self.active_run_id() {
+            return Ok(self.access.clone());
+        }
+        if run_id == self.run_id {
+            return Ok(None);
+        }
+        let Some(manifest) = self.load_manifest(run_id).await? else {
+            return Ok(None);
+        };
+        let mut keys = load_keys(&self.keys_file)
+            .remove(run_id)
+            .unwrap_or_default();
+        if manifest.verified_member(&keys).is_none() {
+            bail!("you are not a member of workspace {run_id}");
+        }
+        if keys.secret.is_none() {
+            keys.secret = env_secret(run_id);
+        }
+        authorize(&manifest, &keys).map(Some)
+    }
+
+    /// The joined workspace's manifest, if it has one.
+    pub async fn workspace_manifest(&mut self) -> Result<Option<WorkspaceManifest>> {
+        match self.shared_run_id.clone() {
+            Some(name) => self.load_manifest(&name).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Your role in the joined workspace (`None` when private, or joined
+    /// without membership checks).
+    pub fn workspace_role(&self) -> Option<Role> {
+        self.access.as_ref().map(|access| access.role)
+    }
+
+    /// Whether trace content in the joined workspace is encrypted.
+    pub fn workspace_encrypted(&self) -> bool {
+        self.cipher().is_some()
+    }
+
+    /// Create a single-use invite token for `role`.  Admins only.
+    pub async fn create_invite(&mut self, role: Role) -> Result<String> {
+        let mut manifest = self.admin_manifest().await?;
+        let mut bytes = [0u8; 16];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .map_err(|_| anyhow!("generate invite token"))?;
+        let token: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+        let now = Local::now();
+        manifest
+            .invites
+            .retain(|invite| !is_expired(&invite.expires_at, now));
+        manifest.invites.push(WorkspaceInvite {
+            token_hash: token_hash(&token),
+            role,
+            created_by: Author::current().user,
+            expires_at: (now + Duration::hours(WORKSPACE_INVITE_TTL_HOURS)).to_rfc3339(),
+        });
+        self.save_manifest(&manifest).await?;
+        Ok(token)
+    }
+
+    /// Change a member's role.  Admins only; the last admin stays admin.
+    pub async fn set_member_role(&mut self, user: &str, role: Role) -> Result<()> {
+        let mut manifest = self.admin_manifest().await?;
+        let admins = manifest.admins();
+        let Some(member) = manifest
+            .members
+            .iter_mut()
+            .find(|member| member.user.eq_ignore_ascii_case(user))
+        else {
+            bail!("{user} is not a member of this workspace");
+        };
+        if member.role == Role::Admin && role != Role::Admin && admins == 1 {
+            bail!("{user} is the only admin; make someone else admin first");
+        }
+        member.role = role;
+        self.save_manifest(&manifest).await
+    }
+
+    /// Remove a member.  Admins only; the last admin can't be removed.
+    pub async fn remove_member(&mut self, user: &str) -> Result<()> {
+        let mut manifest = self.admin_manifest().await?;
+        let Some(member) = manifest.member(user) else {
+            bail!("{user} is not a member of this workspace");
+        };
+        if member.role == Role::Admin && manifest.admins() == 1 {
+            bail!("{user} is the only admin and can't be removed");
+        }
+        manifest
+            .members
+            .retain(|member| !member.user.eq_ignore_ascii_case(user));
+        self.save_manifest(&manifest).await
+    }
+
+    /// Refuse writes from readers of the joined workspace.
+    pub(super) fn ensure_writable(&self) -> Result<()> {
+        match &self.access {
+            Some(access) if !access.role.can_write() => Err(anyhow!(
+                "read-only: you are a {} in workspace {}",
+                access.role.label(),
+                access.name
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Encrypt a trace's content for an encrypted workspace.
+    pub(super) fn seal_trace(&self, trace: &mut Trace) -> Result<()> {
+        let Some(cipher) = self.cipher() else {
+            return Ok(());
+        };
+        for field in [&mut trace.input, &mut trace.outcome, &mut trace.reasoning] {
+            if !field.is_empty() && !field.starts_with(SEALED_PREFIX) {
+                *field = cipher.seal(field)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Decrypt a trace read back from the backend.  Fields this client
+    /// can't decrypt read as `[encrypted]`.
+    pub(super) fn open_trace(&self, trace: &mut Trace) {
+        let cipher = self.cipher();
+        for field in [&mut trace.input, &mut trace.outcome, &mut trace.reasoning] {
+            if field.starts_with(SEALED_PREFIX) {
+                *field = cipher
+                    .and_then(|cipher| cipher.open(field))
+                    .unwrap_or_else(|| SEALED_PLACEHOLDER.to_string());
+            }
+        }
+    }
+
+    pub(super) fn open_stored(&self, mut stored: StoredTrace) -> StoredTrace {
+        self.open_trace(&mut stored.trace);
+        stored
+    }
+
+    fn cipher(&self) -> Option<&Cipher> {
+        self.access.as_ref()?.cipher.as_ref()
+    }
+
+    async fn admin_manifest(&mut self) -> Result<WorkspaceManifest> {
+        let Some(manifest) = self.workspace_manifest().await? else {
+            bail!("join a shared workspace first (/share join <name>)");
+        };
+        let keys = load_keys(&self.keys_file)
+            .remove(&manifest.name)
+            .unwrap_or_default();
+        match manifest.verified_member(&keys) {
+            Some(member) if member.role == Role::Admin => Ok(manifest),
+            _ => bail!("only workspace admins can do that"),
+        }
+    }
+
+    async fn load_manifest(&mut self, name: &str) -> Result<Option<WorkspaceManifest>> {
+        let raw = self
+            .backend()?
+            .get_variable(name.to_string(), WORKSPACE_ACL_VAR.to_string())
+            .await
+            .context("load workspace members")?;
+        raw.map(|raw| serde_json::from_str(&raw).context("parse workspace members"))
+            .transpose()
+    }
+
+    async fn save_manifest(&mut self, manifest: &WorkspaceManifest) -> Result<()> {
+        let value_json = serde_json::to_string(manifest).context("serialize workspace")?;
+        self.backend()?
+            .set_variable(
+                manifest.name.clone(),
+                WORKSPACE_ACL_VAR.to_string(),
+                value_json,
+                "share".to_string(),
+            )
+            .await
+            .context("save workspace members")
+    }
+}
+
+/// The verified member's access to `manifest`, unlocking encryption with
+/// the secret in `keys`.
+fn authorize(manifest: &WorkspaceManifest, keys: &SavedKeys) -> Result<WorkspaceAccess> {
+    let name = &manifest.name;
+    let member = manifest
+        .verified_member(keys)
+        .ok_or_else(|| anyhow!("you are not a member of workspace {name}"))?;
+    let cipher = match (&manifest.encryption, &keys.secret) {
+        (None, _) => None,
+        (Some(_), None) => bail!(
+            "workspace {name} is encrypted; join with --secret <shared secret> \
+             or set {}",
+            secret_env_var(name)
+        ),
+        (Some(encryption), Some(secret)) => Some(unlock(encryption, secret)?),
+    };
+    Ok(WorkspaceAccess {
+        name: name.clone(),
+        role: member.role,
+        cipher,
+    })
+}
+
+fn new_encryption(secret: &str) -> Result<Encryption> {
+    let mut salt = [0u8; 16];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .map_err(|_| anyhow!("generate salt"))?;
+    let cipher = Cipher::derive(secret, &salt)?;
+    Ok(Encryption {
+        salt: STANDARD.encode(salt),
+        check: cipher.seal(KEY_CHECK)?,
+    })
+}
+
+fn unlock(encryption: &Encryption, secret: &str) -> Result<Cipher> {
+    let salt = STANDARD
+        .decode(&encryption.salt)
+        .context("parse workspace salt")?;
+    let cipher = Cipher::derive(secret, &salt)?;
+    match cipher.open(&encryption.check) {
+        Some(check) if check == KEY_CHECK => Ok(cipher),
+        _ => bail!("wrong secret for this workspace"),
+    }
+}
+
+fn token_hash(token: &str) -> String {
+    Sha256::digest(token.trim().as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn is_expired(expires_at: &str, now: DateTime<Local>) -> bool {
+    DateTime::parse_from_rfc3339(expires_at)
+        .ok()
+        .is_none_or(|at| at < now)
+}
+
+/// Default location of the saved member keys and secrets.
+pub(super) fn default_keys_file() -> PathBuf {
+    memini_home().join(KEYS_FILE)
+}
+
+/// What this machine holds for one workspace.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct SavedKeys {
+    /// User the member key was issued to.
+    #[serde(default)]
+    user: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    member_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secret: Option<String>,
+}
+
+impl SavedKeys {
+    /// Generate a member key for `user`, returning its hash for the
+    /// manifest.
+    fn issue_member_key(&mut self, user: &str) -> Result<String> {
+        let mut bytes = [0u8; 32];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .map_err(|_| anyhow!("generate member key"))?;
+        let key: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+        let hash = token_hash(&key);
+        self.user = user.to_string();
+        self.member_key = Some(key);
+        Ok(hash)
+    }
+}
+
+fn load_keys(path: &Path) -> BTreeMap<String, SavedKeys> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_keys(path: &Path, name: &str, entry: SavedKeys) -> Result<()> {
+    let mut keys = load_keys(path);
+    let unchanged = keys.get(name).is_some_and(|saved| {
+        saved.user == entry.user
+            && saved.member_key == entry.member_key
+            && saved.secret == entry.secret
+    });
+    if unchanged {
+        return Ok(());
+    }
+    keys.insert(name.to_string(), entry);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("create {}", parent.display()))?;
+    }
+    fs::write(path, serde_json::to_string_pretty(&keys)?)
+        .with_context(|| format!("write {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(path, fs::Permissions::from_mode(0o600));
+    }
+    Ok(())
+}
+
+/// `MEMINI_WORKSPACE_SECRET_<NAME>`: the name upper-cased, anything but
+/// letters and digits as `_`.
+fn secret_env_var(name: &str) -> String {
+    let suffix: String = name
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() {
+                ch.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("MEMINI_WORKSPACE_SECRET_{suffix}")
+}
+
+fn env_secret(name: &str) -> Option<String> {
+    std::env::var(secret_env_var(name))
+        .ok()
+        .filter(|secret| !secret.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_fields_round_trip_only_with_the_right_secret() {
+        let encryption = new_encryption("hunter2").unwrap();
+        let cipher = unlock(&encryption, "hunter2").unwrap();
+        assert!(unlock(&encryption, "wrong").is_err());
+
+        let sealed = cipher.seal("Deploys go to fly.io").unwrap();
+        assert!(sealed.starts_with(SEALED_PREFIX));
+        assert!(!sealed.contains("fly.io"));
+        assert_eq!(
+            cipher.open(&sealed).as_deref(),
+            Some("Deploys go to fly.io")
+        );
+
+        let other = Cipher::derive("hunter2", b"another salt").unwrap();
+        assert_eq!(other.open(&sealed), None);
+    }
+
+    #[test]
+    fn workspaces_without_a_manifest_are_never_claimed_implicitly() {
+        let dir = std::env::temp_dir().join(format!(
+            "memini-workspace-{}-{:x}",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            // A team workspace used before membership existed.
+            let mut old = RiceStore::local_at(&dir).unwrap();
+            old.join_workspace("legacy");
+            old.set_variable("notes", serde_json::json!("shared"), "test")
+                .await
+                .unwrap();
+
+            let mut store = RiceStore::local_at(&dir).unwrap();
+            let err = store
+                .open_workspace("legacy", JoinMode::Join, None, None)
+                .await
+                .unwrap_err();
+            assert!(format!("{err:#}").contains("/share create"), "{err:#}");
+            let join = store
+                .open_workspace("legacy", JoinMode::Rejoin, None, None)
+                .await
+                .unwrap();
+            assert_eq!((join.kind, join.role), (JoinKind::Unmanaged, Role::Writer));
+            assert!(store.workspace_manifest().await.unwrap().is_none());
+            assert_eq!(store.workspace_role(), None);
+
+            let join = store
+                .open_workspace("legacy", JoinMode::Create, None, None)
+                .await
+                .unwrap();
+            assert_eq!((join.kind, join.role), (JoinKind::Created, Role::Admin));
+        });
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn readers_cannot_write_and_invites_are_single_use() {
+        let dir = std::env::temp_dir().join(format!(
+            "memini-workspace-{}-{:x}",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let mut admin = RiceStore::local_at(&dir).unwrap();
+            assert!(
+                admin
+                    .open_workspace("team", JoinMode::Join, None, None)
+                    .await
+                    .is_err()
+            );
+            let join = admin
+                .open_workspace("team", JoinMode::Create, None, None)
+                .await
+                .unwrap();
+            assert_eq!((join.kind, join.role), (JoinKind::Created, Role::Admin));
+            let token = admin.create_invite(Role::Reader).await.unwrap();
+
+            // Same user name, but without the admin's member key.
+            let mut store = RiceStore::local_at(&dir).unwrap();
+            store.keys_file = dir.join("other-keys.json");
+            assert!(
+                store
+                    .open_workspace("team", JoinMode::Join, None, None)
+                    .await
+                    .is_err()
+            );
+            assert!(
+                store
+                    .open_workspace("team", JoinMode::Create, None, None)
+                    .await
+                    .is_err()
+            );
+            let err = store
+                .open_workspace("team", JoinMode::Join, Some(&token), None)
+                .await
+                .unwrap_err();
+            assert!(format!("{err:#}").contains("member key"), "{err:#}");
+            assert_eq!(
+                admin
+                    .workspace_manifest()
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .pending_invites(),
+                1
+            );
+
+            // Pretend to be someone else: the name is free again.
+            let mut manifest = admin.workspace_manifest().await.unwrap().unwrap();
+            manifest.members[0].user = "someone-else".to_string();
+            admin.save_manifest(&manifest).await.unwrap();
+
+            let join = store
+                .open_workspace("team", JoinMode::Join, Some(&token), None)
+                .await
+                .unwrap();
+            assert_eq!((join.kind, join.role), (JoinKind::Invited, Role::Reader));
+            assert_eq!(
+                store
+                    .workspace_manifest()
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .pending_invites(),
+                0
+            );
+
+            let err = store
+                .commit_trace("q", "a", "chat", "", vec![], "memini:coder")
+                .await
+                .unwrap_err();
+            assert!(format!("{err:#}").contains("read-only"), "{err:#}");
+            assert!(
+                store
+                    .set_variable("k", serde_json::json!(1), "test")
+                    .await
+                    .is_err()
+            );
+            assert!(store.create_invite(Role::Admin).await.is_err());
+        });
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
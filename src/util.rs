@@ -1,6 +1,9 @@
 //! Small utility helpers shared across the crate.
 
 use std::env;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Poll;
 
 /// Return the first non-empty environment variable from `keys`, or `None`.
 pub fn env_first(keys: &[&str]) -> Option<String> {
@@ -26,3 +29,89 @@ pub fn normalize_url(raw: &str) -> String {
     };
     format!("{scheme}://{raw}")
 }
+
+/// Drive `futures` with at most `limit` in flight, returning their outputs
+/// in input order (not completion order).
+pub async fn join_ordered<F: Future>(futures: Vec<F>, limit: usize) -> Vec<F::Output> {
+    let limit = limit.max(1);
+    let total = futures.len();
+    let mut slots: Vec<Option<Pin<Box<F>>>> =
+        futures.into_iter().map(|f| Some(Box::pin(f))).collect();
+    let mut results: Vec<Option<F::Output>> = (0..total).map(|_| None).collect();
+    let mut in_flight: Vec<usize> = Vec::new();
+    let mut next = 0usize;
+    let mut finished = 0usize;
+
+    std::future::poll_fn(|cx| {
+        loop {
+            while in_flight.len() < limit && next < total {
+                in_flight.push(next);
+                next += 1;
+            }
+            let before = finished;
+            in_flight.retain(|&idx| {
+                let Some(future) = slots[idx].as_mut() else {
+                    return false;
+                };
+                match future.as_mut().poll(cx) {
+                    Poll::Ready(output) => {
+                        results[idx] = Some(output);
+                        slots[idx] = None;
+                        finished += 1;
+                        false
+                    }
+                    Poll::Pending => true,
+                }
+            });
+            if finished == total {
+                return Poll::Ready(());
+            }
+            // Nothing completed: every in-flight future has registered a wake-up.
+            if finished == before {
+                return Poll::Pending;
+            }
+        }
+    })
+    .await;
+
+    results.into_iter().map(Option::unwrap).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use super::join_ordered;
+
+    #[test]
+    fn join_ordered_keeps_order_and_caps_concurrency() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .expect("runtime");
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let futures: Vec<_> = [30u64, 5, 20, 1, 10]
+            .into_iter()
+            .enumerate()
+            .map(|(idx, delay)| {
+                let active = Arc::clone(&active);
+                let peak = Arc::clone(&peak);
+                async move {
+                    let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    active.fetch_sub(1, Ordering::SeqCst);
+                    idx
+                }
+            })
+            .collect();
+
+        let outputs = runtime.block_on(join_ordered(futures, 2));
+        assert_eq!(outputs, vec![0, 1, 2, 3, 4]);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+}